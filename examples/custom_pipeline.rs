@@ -20,6 +20,8 @@ fn main() {
                     // new rendering pipeline
                     RenderingPipelineBuilder::new()
                         .with_graphic_pipeline(id("default"), default_phong_pipeline())
+                        // cast shadows from the directionnal light
+                        .with_shadows(ShadowSettings::default().with_cascade_count(3))
                         // finish the construction of the rendering pipeline (set it to a buildable state)
                         .with_final_rt(FinalRenderTargetBuilder::default())
                 )
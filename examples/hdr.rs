@@ -0,0 +1,90 @@
+use foundry::{create_entity, AsAny, System, Updatable};
+use propellant::*;
+
+fn main() {
+
+    let mut resources = PropellantResources::default();
+    resources.meshes_mut().register_mesh(id("cube"), MeshType::cube(1.0));
+    resources.meshes_mut().register_mesh(id("quad"), MeshType::flat_quad(10.0));
+
+    let window = PropellantWindow::builder()
+        .with_title("HDR".to_string())
+        .with_renderer(
+            DefaultVulkanRendererBuilder::default()
+                .with_pipeline(
+                    RenderingPipelineBuilder::new()
+                        .with_graphic_pipeline(id("default"), default_phong_pipeline())
+                        // the scene is drawn in a hdr target, and tonemapped in the swapchain.
+                        // on hdr displays, the swapchain is created in a hdr color space.
+                        .with_hdr(TonemapSettings::default().with_operator(Tonemapping::Agx).with_hdr_output(true))
+                )
+        );
+
+    let mut engine = PropellantEngine::builder()
+        .with_window(window)
+        .with_resources(resources);
+
+    let _cam = create_entity!(engine.world_mut();
+        Transform::origin().translated(glam::vec3(0., -2., -6.)),
+        Camera::main_perspective(800., 450., 0.1, 100., 1.2)
+    );
+    // a sun way brighter than the white of the display, that would clip without the tonemapping
+    engine.world_mut().add_singleton(DirectionnalLight::new(
+        glam::vec3(0.2, 0.2, 0.25),
+        glam::vec3(6., 5.6, 5.),
+        glam::vec3(-1., -1., -1.).normalize()
+    ));
+    let _quad = create_entity!(engine.world_mut();
+        Transform::origin().translated(glam::vec3(0., -0.5, 0.)),
+        InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+            id("quad"),
+            PhongMaterial::default().colored(glam::vec3(0.5, 0.5, 0.5))
+        )
+    );
+    let _cube = create_entity!(engine.world_mut();
+        Transform::origin().rotated(glam::Quat::from_rotation_y(0.6)),
+        InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+            id("cube"),
+            PhongMaterial::default().colored(glam::vec3(0.9, 0.2, 0.05))
+        )
+    );
+
+    // the tonemapping pass reads its settings every frame.
+    engine.world_mut().add_singleton(TonemapSettings::default().with_operator(Tonemapping::Agx));
+    engine.world_mut().register_system(ExposureCycler::new(), 11);
+
+    engine.main_loop().unwrap();
+}
+
+/// Sweep the exposure, and switch the operator at each sweep.
+#[derive(AsAny)]
+struct ExposureCycler {
+    timer: f32,
+}
+
+impl ExposureCycler {
+    pub fn new() -> System {
+        System::new(ExposureCycler { timer: 0. }, foundry::UpdateFrequency::PerFrame)
+    }
+}
+
+impl Updatable for ExposureCycler {
+    fn update(&mut self, components: &mut foundry::ComponentTable, delta: f32) {
+        self.timer += delta;
+        let Some(settings) = components.get_singleton_mut::<TonemapSettings>() else {
+            return;
+        };
+        // from -2 to +2 stops in 4 seconds.
+        settings.set_exposure((self.timer % 4.) - 2.);
+        if self.timer >= 4. {
+            self.timer = 0.;
+            let operator = match settings.operator() {
+                Tonemapping::Reinhard => Tonemapping::Aces,
+                Tonemapping::Aces => Tonemapping::Agx,
+                Tonemapping::Agx => Tonemapping::Reinhard,
+            };
+            settings.set_operator(operator);
+            println!("Tonemapping operator: {operator:?}");
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use foundry::{create_entity, World};
+use propellant::*;
+
+/// Render a frame without any window, and save it as a png.
+/// This also runs on software drivers, such as lavapipe.
+fn main() {
+    let device_prefs: Box<dyn PhysicalDevicePreferences> = Box::new(DefaultPhysicalDevicePreferences);
+    let mut vk_interface = VulkanInterface::create_headless(&device_prefs, "Propellant offscreen".to_string()).unwrap();
+    let mut renderer = RenderingPipelineBuilder::default()
+        .with_clear_color((0.1, 0.1, 0.1))
+        .with_msaa_samples(4)
+        .build_offscreen(&vk_interface, 320, 180)
+        .unwrap();
+
+    let mut resources = PropellantResources::default();
+    resources.meshes_mut().register_mesh(id("cube"), MeshType::cube(1.0));
+
+    let mut world = World::default();
+    world.add_singleton(resources);
+    world.add_singleton(ClusteredLights::default());
+    world.add_singleton(DirectionnalLight::new(
+        glam::vec3(0.2, 0.2, 0.2),
+        glam::vec3(1., 1., 1.),
+        glam::vec3(-1., -1., -1.)
+    ));
+    let _cam = create_entity!(world;
+        Transform::origin().translated(glam::vec3(0., -1., -4.)),
+        Camera::main_perspective(180., 320., 0.1, 100., 1.2)
+    );
+    let _cube = create_entity!(world;
+        Transform::origin().rotated(glam::Quat::from_rotation_y(0.6)),
+        InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+            id("cube"),
+            PhongMaterial::default().colored(glam::vec3(0.6, 0., 0.))
+        )
+    );
+
+    let frame = renderer.render(&mut vk_interface, &mut world).unwrap();
+    frame.save("offscreen.png").unwrap();
+
+    // the vulkan objects are destroyed before the interface is dropped.
+    vk_interface.wait_idle().unwrap();
+    if let Some(mut resources) = world.remove_singleton::<PropellantResources>() {
+        resources.destroy(&vk_interface.device);
+    }
+    renderer.destroy(&vk_interface.device);
+}
@@ -0,0 +1,108 @@
+use foundry::{create_entity, AsAny, System, Updatable};
+use propellant::*;
+
+fn main() {
+
+    let mut resources = PropellantResources::default();
+    resources.meshes_mut().register_mesh(id("cube"), MeshType::cube(1.0));
+    resources.meshes_mut().register_mesh(id("quad"), MeshType::flat_quad(10.0));
+
+    let window = PropellantWindow::builder()
+        .with_title("Post Processing".to_string())
+        .with_renderer(
+            DefaultVulkanRendererBuilder::default()
+                .with_pipeline(
+                    RenderingPipelineBuilder::new()
+                        .with_graphic_pipeline(id("default"), default_phong_pipeline())
+                        // the scene is drawn in a hdr target, read by the first post process pipeline
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::hdr_scene())
+                        // ambient occlusion from the depth of the scene
+                        .with_compute_pipeline(ssao_pipeline(SsaoSettings::default()), id("ssao"))
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::hdr_color())
+                        // glow around the brightest parts
+                        .with_compute_pipeline(bloom_pipeline(BloomSettings::default().with_threshold(1.2)), id("bloom"))
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::hdr_color())
+                        // grading on the hdr colors, in a compute shader
+                        .with_compute_pipeline(color_grading_pipeline(ColorGrading {
+                            exposure: 0.5,
+                            contrast: 1.1,
+                            saturation: 1.2,
+                            gain: glam::vec3(1.0, 0.95, 0.9),
+                        }), id("color-grading"))
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::hdr_color())
+                        .with_compute_pipeline(tonemap_pipeline(Tonemapping::Aces), id("tonemap"))
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::ldr_color())
+                        .with_compute_pipeline(fxaa_pipeline(), id("fxaa"))
+                        .with_intermediate_rt(IntermediateRenderTargetBuilder::ldr_color())
+                        // the last pipeline writes the swapchain
+                        .with_compute_pipeline(vignette_pipeline(0.4, 0.6), id("vignette"))
+                        .with_final_rt(FinalRenderTargetBuilder::default())
+                )
+        );
+
+    let mut engine = PropellantEngine::builder()
+        .with_window(window)
+        .with_resources(resources);
+
+    let _cam = create_entity!(engine.world_mut();
+        Transform::origin().translated(glam::vec3(0., -2., -6.)),
+        Camera::main_perspective(800., 450., 0.1, 100., 1.2)
+    );
+    // bright sun, the tonemapping brings it back to the displayable range
+    engine.world_mut().add_singleton(DirectionnalLight::new(
+        glam::vec3(0.2, 0.2, 0.25),
+        glam::vec3(3., 2.8, 2.5),
+        glam::vec3(-1., -1., -1.).normalize()
+    ));
+    let _quad = create_entity!(engine.world_mut();
+        Transform::origin().translated(glam::vec3(0., -0.5, 0.)),
+        InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+            id("quad"),
+            PhongMaterial::default().colored(glam::vec3(0.5, 0.5, 0.5))
+        )
+    );
+    let _cube = create_entity!(engine.world_mut();
+        Transform::origin().rotated(glam::Quat::from_rotation_y(0.6)),
+        InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+            id("cube"),
+            PhongMaterial::default().colored(glam::vec3(0.8, 0.1, 0.05))
+        )
+    );
+
+    // the effects read their settings every frame, the toggler switches them on and off.
+    engine.world_mut().add_singleton(SsaoSettings::default());
+    engine.world_mut().add_singleton(BloomSettings::default().with_threshold(1.2));
+    engine.world_mut().register_system(EffectToggler::new(), 11);
+
+    engine.main_loop().unwrap();
+}
+
+/// Toggle the bloom and the ambient occlusion every few seconds.
+#[derive(AsAny)]
+struct EffectToggler {
+    timer: f32,
+}
+
+impl EffectToggler {
+    pub fn new() -> System {
+        System::new(EffectToggler { timer: 0. }, foundry::UpdateFrequency::PerFrame)
+    }
+}
+
+impl Updatable for EffectToggler {
+    fn update(&mut self, components: &mut foundry::ComponentTable, delta: f32) {
+        self.timer += delta;
+        if self.timer < 3. {
+            return;
+        }
+        self.timer = 0.;
+        if let Some(bloom) = components.get_singleton_mut::<BloomSettings>() {
+            bloom.set_enabled(!bloom.enabled());
+            println!("Bloom enabled: {}", bloom.enabled());
+        }
+        if let Some(ssao) = components.get_singleton_mut::<SsaoSettings>() {
+            ssao.set_enabled(!ssao.enabled());
+            println!("Ambient occlusion enabled: {}", ssao.enabled());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Derive macros for the propellant engine.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields};
+
+/// Implement `VulkanVertex` for a `#[repr(C)]` struct.
+/// Each field becomes a vertex attribute, with locations following the field order.
+/// Formats come from the `VertexAttribute` implementation of the field types
+/// (`f32`, `u32`, `i32`, glam vectors and arrays of up to four of them).
+/// The format of a field can be overriden with `#[vertex(format = "A2B10G10R10_SNORM_PACK32")]`,
+/// for example to store packed normals in a `u32`.
+/// If the vertex has a `position` field of a type that can be a position, like `Vec3`,
+/// it is used to compute the bounds of the meshes.
+#[proc_macro_derive(VulkanVertex, attributes(vertex))]
+pub fn derive_vulkan_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match vulkan_vertex_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn vulkan_vertex_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let is_repr_c = input.attrs.iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .any(|attr| {
+            let mut repr_c = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                repr_c |= meta.path.is_ident("C");
+                Ok(())
+            });
+            repr_c
+        });
+    if !is_repr_c {
+        return Err(syn::Error::new_spanned(name, "VulkanVertex can only be derived for #[repr(C)] structs, so the field offsets are stable"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "VulkanVertex can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "VulkanVertex can only be derived for structs")),
+    };
+
+    let mut attributes = Vec::with_capacity(fields.len());
+    let mut position = None;
+    for (location, field) in fields.iter().enumerate() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+        let overriden = format_override(field)?;
+        let format = match &overriden {
+            Some(format) => {
+                let format = syn::Ident::new(format, Span::call_site());
+                quote! { ::propellant::vulkanalia::vk::Format::#format }
+            },
+            // the compiler resolves the type, so aliases and qualified paths work.
+            None => quote_spanned! { field_type.span()=> <#field_type as ::propellant::VertexAttribute>::FORMAT },
+        };
+        let location = location as u32;
+        attributes.push(quote! {
+            ::propellant::vulkanalia::vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(#location)
+                .format(#format)
+                .offset({
+                    let vertex = ::core::mem::MaybeUninit::<#name>::uninit();
+                    let base = vertex.as_ptr();
+                    // SAFETY: we only compute the address of the field, without reading it.
+                    let field = unsafe { ::core::ptr::addr_of!((*base).#field_name) };
+                    (field as usize - base as usize) as u32
+                })
+                .build()
+        });
+        if field_name == "position" && overriden.is_none() {
+            position = Some(quote! {
+                fn vertex_position(&self) -> Option<::propellant::glam::Vec3> {
+                    ::propellant::VertexAttribute::as_position(&self.position)
+                }
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl ::propellant::VulkanVertex for #name {
+            fn binding_description() -> ::propellant::vulkanalia::vk::VertexInputBindingDescription {
+                use ::propellant::vulkanalia::vk::HasBuilder;
+                ::propellant::vulkanalia::vk::VertexInputBindingDescription::builder()
+                    .binding(0)
+                    .stride(::core::mem::size_of::<#name>() as u32)
+                    .input_rate(::propellant::vulkanalia::vk::VertexInputRate::VERTEX)
+                    .build()
+            }
+
+            fn attribute_description() -> Vec<::propellant::vulkanalia::vk::VertexInputAttributeDescription> {
+                use ::propellant::vulkanalia::vk::HasBuilder;
+                vec![#(#attributes),*]
+            }
+
+            #position
+        }
+    })
+}
+
+/// Read the `#[vertex(format = "...")]` attribute of a field.
+fn format_override(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut format = None;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("format") {
+                let value: syn::LitStr = meta.value()?.parse()?;
+                format = Some(value.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown vertex attribute, expected `format`"))
+            }
+        })?;
+    }
+    Ok(format)
+}
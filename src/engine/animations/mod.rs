@@ -1 +1,4 @@
-// ideally, animations can both move skeletons and trigger any comp event ?
\ No newline at end of file
+// ideally, animations can both move skeletons and trigger any comp event ?
+
+pub(crate) mod skeleton;
+pub(crate) mod skinning_system;
@@ -0,0 +1,172 @@
+use crate::engine::consts::PROPELLANT_MAX_SKELETON_JOINTS;
+use crate::engine::errors::PResult;
+use crate::engine::errors::loading_errors::LoadingError;
+
+/// A single joint (or bone) of a skeleton.
+#[derive(Debug, Clone)]
+pub struct Joint {
+    /// Index of the parent joint in the skeleton, if any.
+    parent: Option<usize>,
+    /// Matrix that brings a vertex from the mesh space to the joint space, in the bind pose.
+    inverse_bind_matrix: glam::Mat4,
+    /// Transform of the joint relative to its parent in the bind pose.
+    bind_local_transform: glam::Mat4,
+}
+
+impl Joint {
+    pub fn new(parent: Option<usize>, inverse_bind_matrix: glam::Mat4) -> Joint {
+        Joint {
+            parent,
+            inverse_bind_matrix,
+            bind_local_transform: glam::Mat4::IDENTITY,
+        }
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    pub fn inverse_bind_matrix(&self) -> glam::Mat4 {
+        self.inverse_bind_matrix
+    }
+
+    pub fn bind_local_transform(&self) -> glam::Mat4 {
+        self.bind_local_transform
+    }
+}
+
+/// Joint hierarchy of a skinned mesh.
+/// Skeletons are shared between all the skinned mesh renderers using them,
+/// which only store their own pose.
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    /// Joints of the skeleton. A joint parent always comes before the joint itself.
+    joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    /// Creates a skeleton from the list of its joints.
+    /// Joints must be ordered so that parents come before their children,
+    /// which allows to compute the joints world transforms in a single pass.
+    /// Fails if a joint comes before its parent, or if there are more joints than the skinning shaders can render.
+    pub fn new(mut joints: Vec<Joint>) -> PResult<Skeleton> {
+        if joints.len() > PROPELLANT_MAX_SKELETON_JOINTS {
+            return Err(LoadingError::TooManySkeletonJoints(joints.len()).into());
+        }
+        for (index, joint) in joints.iter().enumerate() {
+            match joint.parent {
+                Some(parent) if parent >= index => return Err(LoadingError::SkeletonJointOrder(index, parent).into()),
+                _ => {},
+            }
+        }
+        // compute the bind pose local transforms from the inverse bind matrices
+        for index in 0..joints.len() {
+            let bind_transform = joints[index].inverse_bind_matrix.inverse();
+            joints[index].bind_local_transform = match joints[index].parent {
+                Some(parent) => joints[parent].inverse_bind_matrix * bind_transform,
+                None => bind_transform,
+            };
+        }
+        Ok(Skeleton { joints })
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// Local transforms of all joints in the bind pose.
+    pub fn bind_pose(&self) -> Vec<glam::Mat4> {
+        self.joints.iter().map(|joint| joint.bind_local_transform).collect()
+    }
+
+    /// Compute the skinning matrices of the joints, from their local transforms.
+    /// Each resulting matrix brings a vertex from the mesh space in bind pose to the world space in the given pose.
+    pub fn compute_palette(&self, pose: &[glam::Mat4], world_transform: glam::Mat4, palette: &mut Vec<glam::Mat4>) {
+        palette.clear();
+        // first pass: joints global transforms. Parents are always computed before their children.
+        for (joint, local_transform) in self.joints.iter().zip(pose.iter()) {
+            let parent_transform = match joint.parent {
+                Some(parent) => palette[parent],
+                None => world_transform,
+            };
+            palette.push(parent_transform * *local_transform);
+        }
+        // second pass: apply the inverse bind matrices.
+        for (joint, global_transform) in self.joints.iter().zip(palette.iter_mut()) {
+            *global_transform = *global_transform * joint.inverse_bind_matrix;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Joint, Skeleton};
+    use crate::engine::consts::PROPELLANT_MAX_SKELETON_JOINTS;
+    use crate::engine::errors::PropellantError;
+    use crate::engine::errors::loading_errors::LoadingError;
+
+    /// A root joint at the origin, and a child joint one unit above it.
+    fn arm() -> Skeleton {
+        Skeleton::new(vec![
+            Joint::new(None, glam::Mat4::IDENTITY),
+            Joint::new(Some(0), glam::Mat4::from_translation(glam::Vec3::NEG_Y)),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn bind_pose_gives_identity_palette() {
+        let skeleton = arm();
+        let bind_pose = skeleton.bind_pose();
+        assert!(bind_pose[1].abs_diff_eq(glam::Mat4::from_translation(glam::Vec3::Y), 1e-6));
+
+        let mut palette = Vec::new();
+        skeleton.compute_palette(&bind_pose, glam::Mat4::IDENTITY, &mut palette);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.iter().all(|matrix| matrix.abs_diff_eq(glam::Mat4::IDENTITY, 1e-6)));
+    }
+
+    #[test]
+    fn palette_composes_parents_and_world_transform() {
+        let skeleton = arm();
+        let mut pose = skeleton.bind_pose();
+        pose[0] = glam::Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let world = glam::Mat4::from_translation(glam::vec3(10., 0., 0.));
+
+        let mut palette = Vec::new();
+        skeleton.compute_palette(&pose, world, &mut palette);
+        // a vertex at the tip of the child joint follows the rotation of the root, then the world transform.
+        let tip = palette[1].transform_point3(glam::vec3(0., 2., 0.));
+        assert!(tip.abs_diff_eq(glam::vec3(8., 0., 0.), 1e-5), "tip {tip:?}");
+        let root = palette[0].transform_point3(glam::Vec3::ZERO);
+        assert!(root.abs_diff_eq(glam::vec3(10., 0., 0.), 1e-5), "root {root:?}");
+    }
+
+    #[test]
+    fn parent_after_child_is_an_error() {
+        let result = Skeleton::new(vec![
+            Joint::new(Some(1), glam::Mat4::IDENTITY),
+            Joint::new(None, glam::Mat4::IDENTITY),
+        ]);
+        assert!(matches!(result, Err(PropellantError::Loading(LoadingError::SkeletonJointOrder(0, 1)))));
+
+        let result = Skeleton::new(vec![Joint::new(Some(0), glam::Mat4::IDENTITY)]);
+        assert!(matches!(result, Err(PropellantError::Loading(LoadingError::SkeletonJointOrder(0, 0)))));
+    }
+
+    #[test]
+    fn too_many_joints_is_an_error() {
+        let joints = |count: usize| (0..count)
+            .map(|index| Joint::new(index.checked_sub(1), glam::Mat4::IDENTITY))
+            .collect::<Vec<_>>();
+        assert!(Skeleton::new(joints(PROPELLANT_MAX_SKELETON_JOINTS)).is_ok());
+        let result = Skeleton::new(joints(PROPELLANT_MAX_SKELETON_JOINTS + 1));
+        assert!(matches!(
+            result,
+            Err(PropellantError::Loading(LoadingError::TooManySkeletonJoints(count))) if count == PROPELLANT_MAX_SKELETON_JOINTS + 1
+        ));
+    }
+}
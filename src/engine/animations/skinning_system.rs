@@ -0,0 +1,28 @@
+use foundry::*;
+
+use crate::{
+    Transform,
+    engine::mesh::mesh_renderer::skinned_mesh_renderer::SkinnedMeshRenderer,
+};
+
+/// System that computes the joints palettes of all the skinned meshes.
+/// It should run after any system that animates the skeletons, so that the palettes are up to date when rendering.
+#[derive(AsAny)]
+pub struct SkinningSystem {}
+
+impl SkinningSystem {
+    pub fn new() -> System {
+        System::new(
+            SkinningSystem {},
+            UpdateFrequency::PerFrame,
+        )
+    }
+}
+
+impl Updatable for SkinningSystem {
+    fn update(&mut self, components: &mut ComponentTable, _delta: f32) {
+        for (_, tf, skinned_mesh) in components.query2d_mut::<Transform, SkinnedMeshRenderer>() {
+            skinned_mesh.update_palette(tf.world_pos());
+        }
+    }
+}
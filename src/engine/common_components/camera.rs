@@ -105,6 +105,38 @@ impl Camera {
         self.is_main
     }
 
+    /// World position of the main camera of the scene, if there is one.
+    /// The transform of the camera is its view matrix, so the position is the translation of its inverse.
+    pub fn main_camera_position(components: &foundry::ComponentTable) -> Option<glam::Vec3> {
+        for (_, tf, cam) in components.query2d::<crate::Transform, Camera>() {
+            if cam.is_main() {
+                return Some(tf.world_pos().inverse().w_axis.truncate());
+            }
+        }
+        None
+    }
+
+    /// Distance of the near clipping plane.
+    pub fn z_near(&self) -> f32 {
+        match self.properties {
+            CameraTypeProperty::Perspective{z_near, ..} => z_near,
+            CameraTypeProperty::Orthographic{z_near, ..} => z_near,
+        }
+    }
+
+    /// Distance of the far clipping plane.
+    pub fn z_far(&self) -> f32 {
+        match self.properties {
+            CameraTypeProperty::Perspective{z_far, ..} => z_far,
+            CameraTypeProperty::Orthographic{z_far, ..} => z_far,
+        }
+    }
+
+    /// Whether the camera has an orthographic projection, otherwise it is a perspective one.
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self.properties, CameraTypeProperty::Orthographic{..})
+    }
+
     /// Get the projection matrix of this camera.
     pub fn projection_matrix(&self) -> glam::Mat4 {
         self.projection_matrix
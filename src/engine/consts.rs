@@ -1,4 +1,6 @@
 pub const ENGINE_VERSION: (u32, u32, u32) = (0, 1, 0);
 pub const PROPELLANT_DEBUG_FEATURES: bool = cfg!(feature = "debug-features");
 #[allow(unused)]
-pub const PROPELLANT_MAX_LOADED_TEXTURE_COUNT: u32 = 4096;
\ No newline at end of file
+pub const PROPELLANT_MAX_LOADED_TEXTURE_COUNT: u32 = 4096;
+/// Maximum number of joints a skeleton can have to be rendered by the skinning pipelines.
+pub const PROPELLANT_MAX_SKELETON_JOINTS: usize = 64;
@@ -110,6 +110,9 @@ impl PropellantEngineBuilder {
         #[cfg(feature = "resources")]
         world.add_singleton(self.resources);
 
+        #[cfg(feature = "vulkan-renderer")]
+        world.add_singleton(crate::ClusteredLights::default());
+
         #[cfg(feature = "inputs")]
         let (
             input_handler,
@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 use image::ImageError;
 
+use crate::engine::consts::PROPELLANT_MAX_SKELETON_JOINTS;
 use crate::engine::mesh::loader::MeshLoadingError;
 
 
@@ -15,8 +16,22 @@ pub enum LoadingError {
     TextureCreation(ImageError),
     /// Error while transitionning a texture to a new layout.
     TextureLayoutTransitionMissing,
+    /// The images given for a cubemap can't be made into a cubemap.
+    InvalidCubemap(String),
     /// Unable to load a mesh.
     MeshLoading(MeshLoadingError),
+    /// Unable to read a material file.
+    MaterialFile(String, std::io::Error),
+    /// The material file is not a valid material description.
+    MaterialParsing(String, String),
+    /// A material references a texture by id, but no texture was registered with this id.
+    MissingTexture(String),
+    /// A material references a texture by path, but the file could not be read.
+    MissingTextureFile(String, std::io::Error),
+    /// A skeleton joint (first) comes before its parent (second), parents must come before their children.
+    SkeletonJointOrder(usize, usize),
+    /// A skeleton has more joints than the skinning shaders can render.
+    TooManySkeletonJoints(usize),
 }
 
 impl Display for LoadingError {
@@ -25,7 +40,14 @@ impl Display for LoadingError {
             LoadingError::VulkanLibrary(e) => write!(f, "Unable to load Vulkan library: {}", e),
             LoadingError::TextureCreation(e) => write!(f, "Texture error: {}", e),
             LoadingError::TextureLayoutTransitionMissing => write!(f, "Texture layout transition missing"),
+            LoadingError::InvalidCubemap(e) => write!(f, "Invalid cubemap: {}", e),
             LoadingError::MeshLoading(e) => write!(f, "Mesh loading error: {:?}", e),
+            LoadingError::MaterialFile(path, e) => write!(f, "Unable to read material file '{}': {}", path, e),
+            LoadingError::MaterialParsing(path, e) => write!(f, "Invalid material file '{}': {}", path, e),
+            LoadingError::MissingTexture(texture) => write!(f, "Material references the texture '{}', but no texture is registered with this id", texture),
+            LoadingError::MissingTextureFile(path, e) => write!(f, "Material references the texture file '{}', but it could not be read: {}", path, e),
+            LoadingError::SkeletonJointOrder(joint, parent) => write!(f, "Skeleton joint {} has parent {}, but parents must come before their children", joint, parent),
+            LoadingError::TooManySkeletonJoints(count) => write!(f, "Skeleton has {} joints, but at most {} can be rendered", count, PROPELLANT_MAX_SKELETON_JOINTS),
         }
     }
 }
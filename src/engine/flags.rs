@@ -1,7 +1,7 @@
-use crate::{PropellantEngine, PropellantResources, UiTextRenderer};
+use crate::{PropellantEngine, PropellantResources};
 use self::resource_loading::RequireResourcesLoadingFlag;
 
-use super::{errors::PResult, ui::{ui_resolution::UiResolution, ui_transform::UiTransform}, consts::PROPELLANT_DEBUG_FEATURES};
+use super::{errors::PResult, ui::ui_resolution::UiResolution};
 
 pub(crate) mod resource_loading;
 
@@ -44,28 +44,7 @@ impl PropellantEngine {
                 }
             },
             #[cfg(feature = "ui")]
-            PropellantFlag::UiRequireResolution => {
-                let resolution = match self.world.get_singleton::<UiResolution>() {
-                    Some(res) => *res,
-                    None => {
-                        if PROPELLANT_DEBUG_FEATURES {
-                            println!("[PROPELLANT DEBUG] [UI] Ui require screen resolution flag set, but no existing screen resolution.");
-                        }
-                        UiResolution::default()
-                    }
-                };
-                for (_, ui_tf) in self.world.query1d_mut::<UiTransform>() {
-                    ui_tf.set_ui_resolution(resolution);
-                }
-                // temp: rebuild text here
-                let fonts = self.world.get_singleton::<PropellantResources>().unwrap().fonts().clone();
-                let ui_res = self.world.get_singleton::<UiResolution>().unwrap().clone();
-
-                for (_, tf, tr) in self.world.query2d_mut::<UiTransform, UiTextRenderer>() {
-                    let font = fonts.font(tr.font()).unwrap();
-                    tr.rebuild_text(tf, &font, ui_res);
-                }
-            }
+            PropellantFlag::UiRequireResolution => UiResolution::propagate(&mut self.world),
         }
 
         Ok(())
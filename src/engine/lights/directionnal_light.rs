@@ -7,6 +7,8 @@ pub struct DirectionnalLight {
     pub direction: glam::Vec3,
     pub ambiant_color: glam::Vec3,
     pub direct_color: glam::Vec3,
+    /// Scale of the ambient light coming from the environment cubemap, added to the ambiant color.
+    pub environment_intensity: f32,
 }
 
 impl DirectionnalLight {
@@ -15,14 +17,21 @@ impl DirectionnalLight {
             direction,
             ambiant_color: ambiant,
             direct_color: direct,
+            environment_intensity: 1.0,
         }
     }
 
+    pub fn with_environment_intensity(mut self, environment_intensity: f32) -> Self {
+        self.environment_intensity = environment_intensity;
+        self
+    }
+
     pub fn black() -> Self {
         DirectionnalLight {
             direction: glam::Vec3::NEG_Y,
             ambiant_color: glam::Vec3::ZERO,
             direct_color: glam::Vec3::ZERO,
+            environment_intensity: 0.0,
         }
     }
 }
\ No newline at end of file
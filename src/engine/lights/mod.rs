@@ -1 +1,3 @@
-pub(crate) mod directionnal_light;
\ No newline at end of file
+pub(crate) mod directionnal_light;
+pub(crate) mod point_light;
+pub(crate) mod spot_light;
@@ -0,0 +1,19 @@
+
+/// A light emitting in all directions from the position of its entity transform.
+/// The intensity decreases with the square of the distance, and smoothly reaches zero at the range.
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    pub color: glam::Vec3,
+    pub intensity: f32,
+    pub range: f32,
+}
+
+impl PointLight {
+    pub fn new(color: glam::Vec3, intensity: f32, range: f32) -> Self {
+        PointLight {
+            color,
+            intensity,
+            range,
+        }
+    }
+}
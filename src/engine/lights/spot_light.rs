@@ -0,0 +1,24 @@
+
+/// A light emitting in a cone, from the position of its entity transform along its local -Z axis.
+/// The light is full inside the inner cone, and fades out until the outer cone. Angles are in radians, from the cone axis.
+/// As point lights, the intensity decreases with the square of the distance, and smoothly reaches zero at the range.
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+    pub color: glam::Vec3,
+    pub intensity: f32,
+    pub range: f32,
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
+}
+
+impl SpotLight {
+    pub fn new(color: glam::Vec3, intensity: f32, range: f32, inner_cone_angle: f32, outer_cone_angle: f32) -> Self {
+        SpotLight {
+            color,
+            intensity,
+            range,
+            inner_cone_angle,
+            outer_cone_angle,
+        }
+    }
+}
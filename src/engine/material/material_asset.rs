@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    engine::{
+        errors::{PResult, loading_errors::LoadingError},
+        resources::texture_library::{TextureLibrary, TextureColorSpace},
+    },
+    id,
+    PhongMaterial,
+    PbrMaterial,
+    UnlitMaterial,
+};
+
+/// Reference to a texture in a material file.
+#[derive(Debug, Clone, Deserialize)]
+pub enum TextureSource {
+    /// Path of an image file. Relative paths are relative to the material file.
+    /// The texture is registered with the id given by `texture_file_id`, from its path and color space,
+    /// so materials sharing a file share the texture, and a file used both as a color and a data map is registered twice.
+    Path(String),
+    /// Name of a texture already registered in the texture library, with the id `id(name)`.
+    Id(String),
+}
+
+/// Phong material description. Missing fields take the default value of the material.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhongMaterialAsset {
+    color: [f32; 3],
+    texture: Option<TextureSource>,
+    normal_texture: Option<TextureSource>,
+    normal_strength: f32,
+}
+
+impl Default for PhongMaterialAsset {
+    fn default() -> Self {
+        PhongMaterialAsset {
+            color: [1.0; 3],
+            texture: None,
+            normal_texture: None,
+            normal_strength: 1.0,
+        }
+    }
+}
+
+/// Metallic-roughness material description. Missing fields take the default value of the material.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PbrMaterialAsset {
+    base_color: [f32; 4],
+    base_color_texture: Option<TextureSource>,
+    metallic: f32,
+    roughness: f32,
+    metallic_roughness_texture: Option<TextureSource>,
+    normal_texture: Option<TextureSource>,
+    normal_scale: f32,
+    occlusion_texture: Option<TextureSource>,
+    occlusion_strength: f32,
+    emissive: [f32; 3],
+    emissive_texture: Option<TextureSource>,
+    alpha_cutoff: f32,
+}
+
+impl Default for PbrMaterialAsset {
+    fn default() -> Self {
+        PbrMaterialAsset {
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: [0.0; 3],
+            emissive_texture: None,
+            alpha_cutoff: 0.0,
+        }
+    }
+}
+
+/// Unlit material description. Missing fields take the default value of the material.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UnlitMaterialAsset {
+    color: [f32; 4],
+    texture: Option<TextureSource>,
+    alpha_cutoff: f32,
+}
+
+impl Default for UnlitMaterialAsset {
+    fn default() -> Self {
+        UnlitMaterialAsset {
+            color: [1.0; 4],
+            texture: None,
+            alpha_cutoff: 0.0,
+        }
+    }
+}
+
+/// Material description, as written in a RON material file:
+/// ```ron
+/// Pbr(
+///     base_color: (1.0, 0.9, 0.8, 1.0),
+///     base_color_texture: Some(Path("textures/bricks.png")),
+///     roughness: 0.8,
+///     normal_texture: Some(Id("bricks-normal")),
+/// )
+/// ```
+/// Materials are resolved against the texture library when loaded, so they can be tweaked without recompiling.
+#[derive(Debug, Clone, Deserialize)]
+pub enum MaterialAsset {
+    Phong(PhongMaterialAsset),
+    Pbr(PbrMaterialAsset),
+    Unlit(UnlitMaterialAsset),
+}
+
+/// Material component built from a material asset.
+#[derive(Debug, Clone)]
+pub enum LoadedMaterial {
+    Phong(PhongMaterial),
+    Pbr(PbrMaterial),
+    Unlit(UnlitMaterial),
+}
+
+impl LoadedMaterial {
+    pub fn phong(self) -> Option<PhongMaterial> {
+        match self {
+            LoadedMaterial::Phong(material) => Some(material),
+            _ => None,
+        }
+    }
+
+    pub fn pbr(self) -> Option<PbrMaterial> {
+        match self {
+            LoadedMaterial::Pbr(material) => Some(material),
+            _ => None,
+        }
+    }
+
+    pub fn unlit(self) -> Option<UnlitMaterial> {
+        match self {
+            LoadedMaterial::Unlit(material) => Some(material),
+            _ => None,
+        }
+    }
+}
+
+impl MaterialAsset {
+    /// Parse a material description. `name` is only used to report errors.
+    /// Relative texture paths are kept as is, relative to the working directory.
+    pub fn parse(name: &str, source: &str) -> PResult<MaterialAsset> {
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::UNWRAP_VARIANT_NEWTYPES)
+            .from_str(source)
+            .map_err(|e| LoadingError::MaterialParsing(name.to_string(), e.to_string()).into())
+    }
+
+    /// Read and parse a material file. Relative texture paths are made relative to the directory of the file.
+    pub fn load<P: AsRef<Path>>(path: P) -> PResult<MaterialAsset> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LoadingError::MaterialFile(path.display().to_string(), e))?;
+        let mut material = Self::parse(&path.display().to_string(), &source)?;
+        if let Some(directory) = path.parent() {
+            for texture in material.textures_mut() {
+                if let TextureSource::Path(texture_path) = texture {
+                    if Path::new(texture_path.as_str()).is_relative() {
+                        *texture_path = directory.join(texture_path.as_str()).display().to_string();
+                    }
+                }
+            }
+        }
+        Ok(material)
+    }
+
+    /// Build the material component, with the texture indices of the texture library.
+    /// Textures referenced by path that are not in the library yet are read and registered,
+    /// in srgb for color maps and linear for data maps.
+    pub fn resolve(&self, textures: &mut TextureLibrary) -> PResult<LoadedMaterial> {
+        Ok(match self {
+            MaterialAsset::Phong(asset) => {
+                let mut material = PhongMaterial::default().colored(glam::Vec3::from(asset.color));
+                if let Some(texture) = &asset.texture {
+                    material = material.textured(resolve_texture(texture, textures, TextureColorSpace::Srgb)?);
+                }
+                if let Some(texture) = &asset.normal_texture {
+                    material = material.normal_mapped(resolve_texture(texture, textures, TextureColorSpace::Linear)?, asset.normal_strength);
+                }
+                LoadedMaterial::Phong(material)
+            },
+            MaterialAsset::Pbr(asset) => {
+                let mut material = PbrMaterial::default()
+                    .base_color(glam::Vec4::from(asset.base_color))
+                    .metallic(asset.metallic)
+                    .roughness(asset.roughness)
+                    .emissive(glam::Vec3::from(asset.emissive))
+                    .alpha_cutoff(asset.alpha_cutoff);
+                if let Some(texture) = &asset.base_color_texture {
+                    material = material.base_color_texture(resolve_texture(texture, textures, TextureColorSpace::Srgb)?);
+                }
+                if let Some(texture) = &asset.metallic_roughness_texture {
+                    material = material.metallic_roughness_texture(resolve_texture(texture, textures, TextureColorSpace::Linear)?);
+                }
+                if let Some(texture) = &asset.normal_texture {
+                    material = material.normal_texture(resolve_texture(texture, textures, TextureColorSpace::Linear)?, asset.normal_scale);
+                }
+                if let Some(texture) = &asset.occlusion_texture {
+                    material = material.occlusion_texture(resolve_texture(texture, textures, TextureColorSpace::Linear)?, asset.occlusion_strength);
+                }
+                if let Some(texture) = &asset.emissive_texture {
+                    material = material.emissive_texture(resolve_texture(texture, textures, TextureColorSpace::Srgb)?);
+                }
+                LoadedMaterial::Pbr(material)
+            },
+            MaterialAsset::Unlit(asset) => {
+                let mut material = UnlitMaterial::new(glam::Vec4::from(asset.color))
+                    .alpha_cutoff(asset.alpha_cutoff);
+                if let Some(texture) = &asset.texture {
+                    material = material.texture(resolve_texture(texture, textures, TextureColorSpace::Srgb)?);
+                }
+                LoadedMaterial::Unlit(material)
+            },
+        })
+    }
+
+    fn textures_mut(&mut self) -> Vec<&mut TextureSource> {
+        match self {
+            MaterialAsset::Phong(asset) => [&mut asset.texture, &mut asset.normal_texture]
+                .into_iter().filter_map(|t| t.as_mut()).collect(),
+            MaterialAsset::Pbr(asset) => [
+                &mut asset.base_color_texture,
+                &mut asset.metallic_roughness_texture,
+                &mut asset.normal_texture,
+                &mut asset.occlusion_texture,
+                &mut asset.emissive_texture,
+            ].into_iter().filter_map(|t| t.as_mut()).collect(),
+            MaterialAsset::Unlit(asset) => [&mut asset.texture]
+                .into_iter().filter_map(|t| t.as_mut()).collect(),
+        }
+    }
+}
+
+/// Get the index of a texture, registering it from its file if needed.
+fn resolve_texture(texture: &TextureSource, textures: &mut TextureLibrary, color_space: TextureColorSpace) -> PResult<u32> {
+    match texture {
+        TextureSource::Id(name) => textures.texture_index(id(name))
+            .ok_or_else(|| LoadingError::MissingTexture(name.clone()).into()),
+        TextureSource::Path(path) => {
+            let texture_id = texture_file_id(path, color_space);
+            match textures.texture_index(texture_id) {
+                Some(index) => Ok(index),
+                None => {
+                    let bytes = std::fs::read(path)
+                        .map_err(|e| LoadingError::MissingTextureFile(path.clone(), e))?;
+                    match color_space {
+                        TextureColorSpace::Srgb => textures.register_texture(texture_id, &bytes),
+                        TextureColorSpace::Linear => textures.register_linear_texture(texture_id, &bytes),
+                    }
+                }
+            }
+        },
+    }
+}
+
+/// Id of a texture file registered by the material assets.
+/// The color space is part of the id: srgb textures use the id of their path, linear ones the id of their path followed by `#linear`.
+pub fn texture_file_id(path: &str, color_space: TextureColorSpace) -> u64 {
+    match color_space {
+        TextureColorSpace::Srgb => id(path),
+        TextureColorSpace::Linear => id(&format!("{path}#linear")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::{
+            errors::{PropellantError, loading_errors::LoadingError},
+            resources::texture_library::{TextureLibrary, TextureColorSpace},
+        },
+        id,
+        PhongMaterial,
+    };
+
+    use super::{MaterialAsset, LoadedMaterial, TextureSource, texture_file_id};
+
+    /// Write a 1x1 png in a fresh directory of the temp dir, and return its path.
+    fn texture_file(name: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("propellant-material-asset-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("texture.png");
+        image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 128, 0, 255])).save(&path).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn parse_pbr_material() {
+        let asset = MaterialAsset::parse("test", r#"
+            Pbr(
+                base_color: (1.0, 0.5, 0.25, 1.0),
+                base_color_texture: Some(Path("bricks.png")),
+                roughness: 0.8,
+                normal_texture: Some(Id("bricks-normal")),
+            )
+        "#).unwrap();
+        let MaterialAsset::Pbr(asset) = asset else {
+            panic!("expected a pbr material");
+        };
+        assert_eq!(asset.base_color, [1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(asset.roughness, 0.8);
+        // missing fields keep the defaults of the material.
+        assert_eq!(asset.metallic, 0.0);
+        assert_eq!(asset.normal_scale, 1.0);
+        assert!(matches!(&asset.base_color_texture, Some(TextureSource::Path(path)) if path == "bricks.png"));
+        assert!(matches!(&asset.normal_texture, Some(TextureSource::Id(name)) if name == "bricks-normal"));
+        assert!(asset.occlusion_texture.is_none());
+    }
+
+    #[test]
+    fn parse_errors_name_the_material() {
+        let error = MaterialAsset::parse("broken", "Phong(colour: (1.0, 1.0, 1.0))").unwrap_err();
+        assert!(matches!(&error, PropellantError::Loading(LoadingError::MaterialParsing(name, _)) if name == "broken"), "{error}");
+        assert!(MaterialAsset::parse("broken", "Toon()").is_err());
+    }
+
+    #[test]
+    fn resolve_registered_textures() {
+        let mut textures = TextureLibrary::new();
+        let normal = textures.register_built_texture_with_color_space(id("bricks-normal"), image::RgbaImage::new(1, 1), TextureColorSpace::Linear).unwrap();
+        let asset = MaterialAsset::parse("test", r#"Phong(color: (1.0, 0.0, 0.0), normal_texture: Some(Id("bricks-normal")))"#).unwrap();
+        let material = asset.resolve(&mut textures).unwrap().phong().unwrap();
+        let expected = PhongMaterial::default().colored(glam::vec3(1.0, 0.0, 0.0)).normal_mapped(normal, 1.0);
+        assert_eq!(format!("{material:?}"), format!("{expected:?}"));
+    }
+
+    #[test]
+    fn resolve_missing_texture_id() {
+        let mut textures = TextureLibrary::new();
+        let asset = MaterialAsset::parse("test", r#"Unlit(texture: Some(Id("missing")))"#).unwrap();
+        let error = asset.resolve(&mut textures).unwrap_err();
+        assert!(matches!(&error, PropellantError::Loading(LoadingError::MissingTexture(name)) if name == "missing"), "{error}");
+    }
+
+    #[test]
+    fn resolve_missing_texture_file() {
+        let mut textures = TextureLibrary::new();
+        let asset = MaterialAsset::parse("test", r#"Unlit(texture: Some(Path("/no/such/texture.png")))"#).unwrap();
+        let error = asset.resolve(&mut textures).unwrap_err();
+        assert!(matches!(&error, PropellantError::Loading(LoadingError::MissingTextureFile(path, _)) if path == "/no/such/texture.png"), "{error}");
+    }
+
+    #[test]
+    fn texture_files_are_registered_per_color_space() {
+        let path = texture_file("color-spaces");
+        let mut textures = TextureLibrary::new();
+        let asset = MaterialAsset::parse("test", &format!(r#"
+            Pbr(
+                base_color_texture: Some(Path("{path}")),
+                metallic_roughness_texture: Some(Path("{path}")),
+                emissive_texture: Some(Path("{path}")),
+            )
+        "#)).unwrap();
+        asset.resolve(&mut textures).unwrap();
+        let srgb = textures.texture_index(texture_file_id(&path, TextureColorSpace::Srgb)).unwrap();
+        let linear = textures.texture_index(texture_file_id(&path, TextureColorSpace::Linear)).unwrap();
+        // the same file is a color map and a data map: it is loaded once in each color space.
+        assert_ne!(srgb, linear);
+        // resolving again reuses the registered textures.
+        let LoadedMaterial::Pbr(_) = asset.resolve(&mut textures).unwrap() else {
+            panic!("expected a pbr material");
+        };
+        assert_eq!(textures.texture_index(texture_file_id(&path, TextureColorSpace::Srgb)), Some(srgb));
+        assert_eq!(textures.texture_index(texture_file_id(&path, TextureColorSpace::Linear)), Some(linear));
+        std::fs::remove_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
+    }
+}
@@ -1,3 +1,11 @@
+pub(crate) mod pbr_material;
 pub(crate) mod phong_material;
 pub(crate) mod colored_texture;
+#[cfg(feature = "material-assets")]
+pub(crate) mod material_asset;
+pub(crate) mod skybox_material;
+pub(crate) mod tint_material;
+pub(crate) mod transparent;
 pub(crate) mod ui_material;
+pub(crate) mod unlit_material;
+pub(crate) mod vertex_color_material;
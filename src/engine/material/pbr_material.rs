@@ -0,0 +1,203 @@
+use foundry::AsAny;
+
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer
+};
+
+/// Physically based material, using the metallic-roughness model.
+/// The fields follow the glTF 2.0 material definition, so imported materials map onto it one to one:
+/// each map is multiplied by its factor, and the texture index 0 (white 1x1) stands for no texture.
+/// The metallic-roughness map stores the roughness in its green channel and the metalness in its blue channel,
+/// the occlusion map stores the occlusion in its red channel.
+/// These data maps, as well as the normal map, should be registered as linear textures.
+/// Fragments with an alpha under the alpha cutoff are discarded, which gives hard edged transparency without sorting.
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, Clone, AsAny)]
+pub struct PbrMaterial {
+    base_color: glam::Vec4,
+    emissive: glam::Vec3,
+    base_color_texture: u32,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    metallic_roughness_texture: u32,
+    normal_texture: u32,
+    occlusion_texture: u32,
+    emissive_texture: u32,
+    alpha_cutoff: f32,
+    _padding: [f32; 3],
+}
+
+impl PbrMaterial {
+    pub fn base_color(mut self, color: glam::Vec4) -> Self {
+        self.base_color = color;
+        self
+    }
+
+    pub fn base_color_texture(mut self, texture_index: u32) -> Self {
+        self.base_color_texture = texture_index;
+        self
+    }
+
+    pub fn metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn metallic_roughness_texture(mut self, texture_index: u32) -> Self {
+        self.metallic_roughness_texture = texture_index;
+        self
+    }
+
+    /// Tangent space normal map. The scale multiplies the x and y components of the sampled normals.
+    pub fn normal_texture(mut self, texture_index: u32, scale: f32) -> Self {
+        self.normal_texture = texture_index;
+        self.normal_scale = scale;
+        self
+    }
+
+    /// Ambient occlusion map. The strength blends between no occlusion (0) and the full occlusion of the map (1).
+    pub fn occlusion_texture(mut self, texture_index: u32, strength: f32) -> Self {
+        self.occlusion_texture = texture_index;
+        self.occlusion_strength = strength;
+        self
+    }
+
+    pub fn emissive(mut self, emissive: glam::Vec3) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    pub fn emissive_texture(mut self, texture_index: u32) -> Self {
+        self.emissive_texture = texture_index;
+        self
+    }
+
+    /// Discard the fragments whose alpha is under the cutoff, for foliage or fences.
+    /// A cutoff of 0 (the default) disables the cutout.
+    pub fn alpha_cutoff(mut self, cutoff: f32) -> Self {
+        self.alpha_cutoff = cutoff;
+        self
+    }
+}
+
+impl RenderableComponent for PbrMaterial {
+    type FromComponent<Mesh> = InstancedMeshRenderer<PbrMaterial, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
+
+impl Default for PbrMaterial {
+    /// White dielectric material, without any maps.
+    fn default() -> Self {
+        PbrMaterial {
+            base_color: glam::Vec4::ONE,
+            emissive: glam::Vec3::ZERO,
+            base_color_texture: 0,
+            metallic: 0.0,
+            roughness: 0.5,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            metallic_roughness_texture: 0,
+            normal_texture: 0,
+            occlusion_texture: 0,
+            emissive_texture: 0,
+            alpha_cutoff: 0.0,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{offset_of, size_of};
+
+    use super::PbrMaterial;
+
+    const PBR_FRAGMENT_SHADER: &str = include_str!("../../shaders/pbr.frag");
+
+    /// Members of the `PbrMaterial` struct of the pbr shader, with their std430 offsets.
+    fn shader_layout() -> (Vec<(String, usize)>, usize) {
+        let start = PBR_FRAGMENT_SHADER.find("struct PbrMaterial {").expect("no PbrMaterial struct in pbr.frag");
+        let end = start + PBR_FRAGMENT_SHADER[start..].find("};").unwrap();
+        let mut offset: usize = 0;
+        let members = PBR_FRAGMENT_SHADER[start..end].lines().skip(1).filter_map(|line| {
+            let mut words = line.trim().trim_end_matches(';').split_whitespace();
+            let (ty, name) = (words.next()?, words.next()?);
+            let (size, align) = match ty {
+                "vec4" => (16, 16),
+                "vec3" => (12, 16),
+                "float" | "uint" => (4, 4),
+                _ => panic!("unexpected member type {ty} in the pbr material"),
+            };
+            offset = offset.next_multiple_of(align);
+            let member = (name.to_string(), offset);
+            offset += size;
+            Some(member)
+        }).collect();
+        // structs are aligned to their largest member, a vec4.
+        (members, offset.next_multiple_of(16))
+    }
+
+    #[test]
+    fn uniform_layout_matches_the_shader() {
+        let padding = offset_of!(PbrMaterial, _padding);
+        let expected = [
+            ("baseColor", offset_of!(PbrMaterial, base_color)),
+            ("emissive", offset_of!(PbrMaterial, emissive)),
+            ("baseColorTexture", offset_of!(PbrMaterial, base_color_texture)),
+            ("metallic", offset_of!(PbrMaterial, metallic)),
+            ("roughness", offset_of!(PbrMaterial, roughness)),
+            ("normalScale", offset_of!(PbrMaterial, normal_scale)),
+            ("occlusionStrength", offset_of!(PbrMaterial, occlusion_strength)),
+            ("metallicRoughnessTexture", offset_of!(PbrMaterial, metallic_roughness_texture)),
+            ("normalTexture", offset_of!(PbrMaterial, normal_texture)),
+            ("occlusionTexture", offset_of!(PbrMaterial, occlusion_texture)),
+            ("emissiveTexture", offset_of!(PbrMaterial, emissive_texture)),
+            ("alphaCutoff", offset_of!(PbrMaterial, alpha_cutoff)),
+            ("padding0", padding),
+            ("padding1", padding + 4),
+            ("padding2", padding + 8),
+        ].map(|(name, offset)| (name.to_string(), offset));
+        let (members, size) = shader_layout();
+        assert_eq!(members, expected);
+        assert_eq!(size_of::<PbrMaterial>(), size);
+        assert_eq!(size_of::<PbrMaterial>(), 80);
+    }
+
+    #[test]
+    fn metallic_roughness_channels_match_the_documentation() {
+        // the metalness is in the blue channel, and the roughness in the green one, like in glTF.
+        assert!(PBR_FRAGMENT_SHADER.contains("metallicRoughness.b * material.metallic"));
+        assert!(PBR_FRAGMENT_SHADER.contains("metallicRoughness.g * material.roughness"));
+        assert!(PBR_FRAGMENT_SHADER.contains("sampleTexture(material.occlusionTexture).r"));
+    }
+}
@@ -13,6 +13,7 @@ use super::colored_texture::ColoredTexture;
 pub struct PhongMaterial {
     albedo: ColoredTexture, // default color
     metalic: ColoredTexture, // sininess color ?
+    normal: ColoredTexture, // normal map, the color scales the sampled normal
 }
 
 impl PhongMaterial {
@@ -25,6 +26,14 @@ impl PhongMaterial {
         self.albedo.set_texture(texture_index);
         self
     }
+
+    /// Use a tangent space normal map. The strength scales the bumps of the map, 0 being a flat surface.
+    /// The normal map should be registered as a linear texture.
+    pub fn normal_mapped(mut self, texture_index: u32, strength: f32) -> Self {
+        self.normal.set_texture(texture_index);
+        self.normal.set_color(glam::Vec3::new(strength, strength, 1.));
+        self
+    }
 }
 
 impl RenderableComponent for PhongMaterial {
@@ -58,6 +67,7 @@ impl Default for PhongMaterial {
         PhongMaterial {
             albedo: ColoredTexture::color(glam::Vec3::ONE),
             metalic: ColoredTexture::color(glam::Vec3::ZERO),
+            normal: ColoredTexture::color(glam::Vec3::ONE),
         }
     }
 }
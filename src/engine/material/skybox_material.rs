@@ -0,0 +1,77 @@
+use foundry::AsAny;
+
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer
+};
+
+/// Material drawing the environment cubemap of the texture library around the camera.
+/// It goes on a cube mesh, that always stays centered on the camera and behind the rest of the scene.
+/// The blur ranges from 0 (sharp) to 1 (the roughest level of the prefiltered environment).
+#[repr(C)]
+#[derive(Debug, Clone, AsAny)]
+pub struct SkyboxMaterial {
+    tint: glam::Vec3,
+    intensity: f32,
+    blur: f32,
+    _padding: [f32; 3],
+}
+
+impl SkyboxMaterial {
+    pub fn new() -> SkyboxMaterial {
+        SkyboxMaterial::default()
+    }
+
+    pub fn tint(mut self, tint: glam::Vec3) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn blur(mut self, blur: f32) -> Self {
+        self.blur = blur;
+        self
+    }
+}
+
+impl RenderableComponent for SkyboxMaterial {
+    type FromComponent<Mesh> = InstancedMeshRenderer<SkyboxMaterial, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
+
+impl Default for SkyboxMaterial {
+    /// The environment as is, without tint nor blur.
+    fn default() -> Self {
+        SkyboxMaterial {
+            tint: glam::Vec3::ONE,
+            intensity: 1.0,
+            blur: 0.0,
+            _padding: [0.0; 3],
+        }
+    }
+}
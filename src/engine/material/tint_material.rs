@@ -0,0 +1,59 @@
+use foundry::AsAny;
+
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer
+};
+
+/// Material of line and point meshes.
+/// The color of each vertex is multiplied by the tint.
+#[repr(C)]
+#[derive(Debug, Clone, AsAny)]
+pub struct TintMaterial {
+    tint: glam::Vec4,
+}
+
+impl TintMaterial {
+    pub fn new(tint: glam::Vec4) -> TintMaterial {
+        TintMaterial { tint }
+    }
+
+    pub fn tinted(mut self, tint: glam::Vec4) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+impl RenderableComponent for TintMaterial {
+    type FromComponent<Mesh> = InstancedMeshRenderer<TintMaterial, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
+
+impl Default for TintMaterial {
+    fn default() -> Self {
+        TintMaterial {
+            tint: glam::Vec4::ONE,
+        }
+    }
+}
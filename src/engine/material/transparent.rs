@@ -0,0 +1,58 @@
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer,
+    PbrMaterial,
+    UnlitMaterial,
+};
+
+/// Wraps a material to draw it with the transparent pipelines.
+/// The wrapped material is sent to the shaders as is, but the entity is now queried by the pipelines
+/// using `Transparent<M>`, that are drawn after the opaque ones and sort their instances back to front.
+#[repr(transparent)]
+#[derive(Debug, Clone)]
+pub struct Transparent<M>(pub M);
+
+/// Pbr material drawn by the transparent pbr pipeline.
+pub type TransparentPbrMaterial = Transparent<PbrMaterial>;
+/// Unlit material drawn by the transparent unlit pipeline.
+pub type TransparentUnlitMaterial = Transparent<UnlitMaterial>;
+
+impl<M> Transparent<M> {
+    pub fn new(material: M) -> Transparent<M> {
+        Transparent(material)
+    }
+
+    pub fn material(&self) -> &M {
+        &self.0
+    }
+
+    pub fn material_mut(&mut self) -> &mut M {
+        &mut self.0
+    }
+}
+
+impl<M: Clone> RenderableComponent for Transparent<M> {
+    type FromComponent<Mesh> = InstancedMeshRenderer<Transparent<M>, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
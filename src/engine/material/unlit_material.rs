@@ -0,0 +1,82 @@
+use foundry::AsAny;
+
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer
+};
+
+/// Material that ignores the lights: the output color is the texture multiplied by the color.
+/// The alpha of the color is kept, so it can be blended by transparent pipelines,
+/// and fragments with an alpha under the alpha cutoff are discarded.
+#[repr(C)]
+#[derive(Debug, Clone, AsAny)]
+pub struct UnlitMaterial {
+    color: glam::Vec4,
+    texture: u32,
+    alpha_cutoff: f32,
+    _padding: [f32; 2],
+}
+
+impl UnlitMaterial {
+    pub fn new(color: glam::Vec4) -> UnlitMaterial {
+        UnlitMaterial {
+            color,
+            ..Default::default()
+        }
+    }
+
+    pub fn color(mut self, color: glam::Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn texture(mut self, texture_index: u32) -> Self {
+        self.texture = texture_index;
+        self
+    }
+
+    /// Discard the fragments whose alpha is under the cutoff.
+    /// A cutoff of 0 (the default) disables the cutout.
+    pub fn alpha_cutoff(mut self, cutoff: f32) -> Self {
+        self.alpha_cutoff = cutoff;
+        self
+    }
+}
+
+impl RenderableComponent for UnlitMaterial {
+    type FromComponent<Mesh> = InstancedMeshRenderer<UnlitMaterial, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
+
+impl Default for UnlitMaterial {
+    /// Opaque white, without texture.
+    fn default() -> Self {
+        UnlitMaterial {
+            color: glam::Vec4::ONE,
+            texture: 0,
+            alpha_cutoff: 0.0,
+            _padding: [0.0; 2],
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use foundry::AsAny;
+
+use crate::{
+    engine::renderer::graphic_pipeline::renderable_component::RenderableComponent,
+    InstancedMeshRenderer
+};
+
+/// Unlit material of color meshes.
+/// The color of each vertex is multiplied by the tint.
+#[repr(C)]
+#[derive(Debug, Clone, AsAny)]
+pub struct VertexColorMaterial {
+    tint: glam::Vec4,
+}
+
+impl VertexColorMaterial {
+    pub fn new(tint: glam::Vec4) -> VertexColorMaterial {
+        VertexColorMaterial { tint }
+    }
+
+    pub fn tinted(mut self, tint: glam::Vec4) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+impl RenderableComponent for VertexColorMaterial {
+    type FromComponent<Mesh> = InstancedMeshRenderer<VertexColorMaterial, Mesh>;
+
+    fn set_uniform<Mesh>(component: &Self::FromComponent<Mesh>, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        for i in 0..instance_count {
+            write_to_buf(&[component.material().clone()], i);
+        }
+    }
+
+    fn mesh_id<Mesh>(component: &Self::FromComponent<Mesh>) -> u64 {
+        component.mesh_id()
+    }
+
+    fn set_uniform_buffer_index<Mesh>(component: &mut Self::FromComponent<Mesh>, index: usize) {
+        component.set_uniform_buffer_offset(index);
+    }
+
+    fn uniform_buffer_index<Mesh>(component: &Self::FromComponent<Mesh>) -> usize {
+        component.uniform_buffer_offset()
+    }
+
+    fn instance_count<Mesh>(_component: &Self::FromComponent<Mesh>) -> usize {
+        1
+    }
+}
+
+impl Default for VertexColorMaterial {
+    fn default() -> Self {
+        VertexColorMaterial {
+            tint: glam::Vec4::ONE,
+        }
+    }
+}
@@ -1,12 +1,23 @@
-pub(crate) use self::vertex::StaticVertex;
-use self::vertex::VulkanVertex;
+pub use self::vertex::StaticVertex;
+pub use self::vertex::SkeletalVertex;
+pub use self::vertex::LineVertex;
+pub use self::vertex::PointVertex;
+pub use self::vertex::ColorVertex;
+pub use self::vertex::VulkanVertex;
+pub use self::vertex::VertexAttribute;
+pub use self::custom_mesh::CustomMesh;
+use self::vertex::GeometryVertex;
 
-use super::errors::PResult;
+use super::errors::{PResult, PropellantError};
 
 
 pub(crate) mod cube;
+pub(crate) mod custom_mesh;
+pub(crate) mod grid;
 pub(crate) mod loader;
+pub(crate) mod lod;
 pub(crate) mod mesh_renderer;
+pub(crate) mod processing;
 pub(crate) mod quad;
 pub(crate) mod sphere;
 #[cfg(feature = "ui")]
@@ -18,33 +29,208 @@ pub(crate) type StaticMeshVertexType = StaticVertex;
 pub(crate) type StaticMeshTriangleType = u32;
 
 pub type StaticMesh = Mesh<StaticMeshVertexType, StaticMeshTriangleType>;
+/// Static mesh with 16 bits indices, for meshes that have less than 65536 vertices.
+pub type CompactStaticMesh = Mesh<StaticMeshVertexType, u16>;
+/// Mesh that can be deformed by a skeleton.
+pub type SkinnedMesh = Mesh<SkeletalVertex, u32>;
+/// Mesh made of lines, where each pair of indices is a line.
+pub type LineMesh = Mesh<LineVertex, u32>;
+/// Mesh made of points, where each index is a point.
+pub type PointMesh = Mesh<PointVertex, u32>;
+/// Triangle mesh with a color per vertex, stored as a custom mesh.
+pub type ColorMesh = Mesh<ColorVertex, u32>;
 
 #[derive(Debug, Clone)]
 pub enum MeshType {
     Static(StaticMesh),
-    // Skinned(),
+    CompactStatic(CompactStaticMesh),
+    Skinned(SkinnedMesh),
+    /// Mesh with a user defined vertex layout.
+    Custom(CustomMesh),
+    /// Lines, drawn by pipelines with a line list topology.
+    Lines(LineMesh),
+    /// Points, drawn by pipelines with a point list topology.
+    Points(PointMesh),
 }
 
 impl MeshType {
+    /// Creates a static mesh. Tangents are generated if the vertices do not have them.
     pub fn static_mesh(vertices: Vec<StaticMeshVertexType>, triangles: Vec<StaticMeshTriangleType>) -> MeshType {
-        MeshType::Static(Mesh::new(vertices, triangles))
+        let mut mesh = Mesh::new(vertices, triangles);
+        mesh.generate_missing_tangents();
+        MeshType::Static(mesh)
     }
 
+    pub fn skinned_mesh(vertices: Vec<SkeletalVertex>, triangles: Vec<u32>) -> MeshType {
+        MeshType::Skinned(Mesh::new(vertices, triangles))
+    }
+
+    /// Creates a mesh with a custom vertex layout, that can be drawn by pipelines using the `Mesh<V, u32>` mesh type.
+    pub fn custom_mesh<V: VulkanVertex>(vertices: Vec<V>, triangles: Vec<u32>) -> MeshType {
+        MeshType::Custom(CustomMesh::new(Mesh::new(vertices, triangles)))
+    }
+
+    /// Creates a triangle mesh with a color per vertex, that can be drawn by the vertex color pipeline.
+    pub fn color_mesh(vertices: Vec<ColorVertex>, triangles: Vec<u32>) -> MeshType {
+        MeshType::custom_mesh(vertices, triangles)
+    }
+
+    /// Creates a line mesh, where each pair of indices is a line.
+    pub fn lines(vertices: Vec<LineVertex>, indices: Vec<u32>) -> MeshType {
+        MeshType::Lines(Mesh::new(vertices, indices))
+    }
+
+    /// Creates a line mesh where each vertex is linked to the next one, like a trajectory.
+    /// The polyline is stored as a line list, with each inner vertex indexed twice,
+    /// so it can be drawn by the same pipelines as other lines.
+    pub fn polyline(vertices: Vec<LineVertex>) -> MeshType {
+        let indices = (1..vertices.len() as u32).flat_map(|i| [i - 1, i]).collect();
+        MeshType::Lines(Mesh::new(vertices, indices))
+    }
+
+    /// Creates a point mesh, where each vertex is drawn as a point.
+    pub fn points(vertices: Vec<PointVertex>) -> MeshType {
+        let indices = (0..vertices.len() as u32).collect();
+        MeshType::Points(Mesh::new(vertices, indices))
+    }
+
+    /// Loads a static mesh from bytes, and generates its tangents.
     pub fn load_static_mesh(bytes: &[u8]) -> PResult<MeshType> {
-        Ok(MeshType::Static(Mesh::from_bytes(bytes)?))
+        let mut mesh: StaticMesh = Mesh::from_bytes(bytes)?;
+        mesh.generate_tangents();
+        Ok(MeshType::Static(mesh))
+    }
+
+    /// Converts the mesh to 16 bits indices if it has few enough vertices.
+    /// Otherwise, the mesh is returned as is.
+    pub fn narrow_indices(self) -> MeshType {
+        match self {
+            MeshType::Static(mesh) => match mesh.try_narrow_indices() {
+                Ok(compact) => MeshType::CompactStatic(compact),
+                Err(mesh) => MeshType::Static(mesh),
+            },
+            other => other,
+        }
     }
 
     pub fn buffer_size(&self) -> usize {
+        self.vertex_data().len() + self.index_data().len()
+    }
+
+    /// Raw bytes of the vertex data, as it will be sent to the gpu.
+    pub fn vertex_data(&self) -> &[u8] {
+        match self {
+            MeshType::Static(mesh) => as_bytes(mesh.vertices()),
+            MeshType::CompactStatic(mesh) => as_bytes(mesh.vertices()),
+            MeshType::Skinned(mesh) => as_bytes(mesh.vertices()),
+            MeshType::Custom(mesh) => mesh.vertex_data(),
+            MeshType::Lines(mesh) => as_bytes(mesh.vertices()),
+            MeshType::Points(mesh) => as_bytes(mesh.vertices()),
+        }
+    }
+
+    /// Raw bytes of the index data, as it will be sent to the gpu.
+    pub fn index_data(&self) -> &[u8] {
         match self {
-            MeshType::Static(mesh) => mesh.vertices().len() * std::mem::size_of::<StaticMeshVertexType>() + mesh.triangles().len() * std::mem::size_of::<StaticMeshTriangleType>(),
+            MeshType::Static(mesh) => as_bytes(mesh.triangles()),
+            MeshType::CompactStatic(mesh) => as_bytes(mesh.triangles()),
+            MeshType::Skinned(mesh) => as_bytes(mesh.triangles()),
+            MeshType::Custom(mesh) => as_bytes(mesh.triangles()),
+            MeshType::Lines(mesh) => as_bytes(mesh.triangles()),
+            MeshType::Points(mesh) => as_bytes(mesh.triangles()),
+        }
+    }
+
+    /// Radius of the smallest sphere centered on the mesh origin that contains all the vertices.
+    pub fn bounding_radius(&self) -> f32 {
+        fn radius<V: GeometryVertex>(vertices: &[V]) -> f32 {
+            vertices.iter().map(|v| v.position().length()).fold(0.0, f32::max)
+        }
+        fn primitives_radius<V: VulkanVertex>(vertices: &[V]) -> f32 {
+            vertices.iter().filter_map(|v| v.vertex_position()).map(|p| p.length()).fold(0.0, f32::max)
+        }
+        match self {
+            MeshType::Static(mesh) => radius(mesh.vertices()),
+            MeshType::CompactStatic(mesh) => radius(mesh.vertices()),
+            MeshType::Skinned(mesh) => radius(mesh.vertices()),
+            MeshType::Custom(mesh) => mesh.bounding_radius(),
+            MeshType::Lines(mesh) => primitives_radius(mesh.vertices()),
+            MeshType::Points(mesh) => primitives_radius(mesh.vertices()),
+        }
+    }
+
+    /// Creates a simplified version of the mesh, with approximately `target_ratio` of its triangles.
+    /// Only static and skinned meshes can be simplified: custom vertices have no known geometry,
+    /// and lines and points are not made of triangles, so they are an error.
+    pub fn simplified(&self, target_ratio: f32) -> PResult<MeshType> {
+        match self {
+            MeshType::Static(mesh) => Ok(MeshType::Static(mesh.simplify(target_ratio))),
+            MeshType::CompactStatic(mesh) => Ok(MeshType::CompactStatic(mesh.simplify(target_ratio))),
+            MeshType::Skinned(mesh) => Ok(MeshType::Skinned(mesh.simplify(target_ratio))),
+            MeshType::Custom(_) => Err(PropellantError::Custom("Custom meshes can not be simplified, their vertices have no known geometry.".to_string())),
+            MeshType::Lines(_) => Err(PropellantError::Custom("Line meshes can not be simplified, they are not made of triangles.".to_string())),
+            MeshType::Points(_) => Err(PropellantError::Custom("Point meshes can not be simplified, they are not made of triangles.".to_string())),
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        match self {
+            MeshType::Static(mesh) => mesh.vertices().len(),
+            MeshType::CompactStatic(mesh) => mesh.vertices().len(),
+            MeshType::Skinned(mesh) => mesh.vertices().len(),
+            MeshType::Custom(mesh) => mesh.vertex_count(),
+            MeshType::Lines(mesh) => mesh.vertices().len(),
+            MeshType::Points(mesh) => mesh.vertices().len(),
+        }
+    }
+
+    pub fn index_count(&self) -> usize {
+        match self {
+            MeshType::Static(mesh) => mesh.triangles().len(),
+            MeshType::CompactStatic(mesh) => mesh.triangles().len(),
+            MeshType::Skinned(mesh) => mesh.triangles().len(),
+            MeshType::Custom(mesh) => mesh.triangles().len(),
+            MeshType::Lines(mesh) => mesh.triangles().len(),
+            MeshType::Points(mesh) => mesh.triangles().len(),
         }
     }
 
     pub fn index_type(&self) -> vulkanalia::vk::IndexType {
         match self {
             MeshType::Static(_) => StaticMeshTriangleType::INDEX_TYPE,
+            MeshType::CompactStatic(_) => u16::INDEX_TYPE,
+            MeshType::Skinned(_) => u32::INDEX_TYPE,
+            MeshType::Custom(_) | MeshType::Lines(_) | MeshType::Points(_) => u32::INDEX_TYPE,
         }
     }
+
+    /// Size of a single vertex, in bytes.
+    pub fn vertex_size(&self) -> usize {
+        match self {
+            MeshType::Static(_) => std::mem::size_of::<StaticMeshVertexType>(),
+            MeshType::CompactStatic(_) => std::mem::size_of::<StaticMeshVertexType>(),
+            MeshType::Skinned(_) => std::mem::size_of::<SkeletalVertex>(),
+            MeshType::Custom(mesh) => mesh.vertex_size(),
+            MeshType::Lines(_) => std::mem::size_of::<LineVertex>(),
+            MeshType::Points(_) => std::mem::size_of::<PointVertex>(),
+        }
+    }
+
+    /// Size of a single index, in bytes.
+    pub fn index_size(&self) -> usize {
+        match self {
+            MeshType::Static(_) => std::mem::size_of::<StaticMeshTriangleType>(),
+            MeshType::CompactStatic(_) => std::mem::size_of::<u16>(),
+            MeshType::Skinned(_) => std::mem::size_of::<u32>(),
+            MeshType::Custom(_) | MeshType::Lines(_) | MeshType::Points(_) => std::mem::size_of::<u32>(),
+        }
+    }
+}
+
+/// Reinterpret a slice of plain data as bytes.
+fn as_bytes<T>(data: &[T]) -> &[u8] {
+    // SAFETY: any initialized memory can be read as bytes, and the length is computed from the slice itself.
+    unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * std::mem::size_of::<T>()) }
 }
 
 #[derive(Debug, Clone)]
@@ -65,17 +251,78 @@ impl<V: VulkanVertex, T: ToVulkanIntSize> Mesh<V, T> {
     pub fn triangles(&self) -> &Vec<T> {
         &self.triangles
     }
+
+    /// Vertex binding description of this mesh type, used when creating pipelines.
+    pub fn vertex_binding_description() -> vulkanalia::vk::VertexInputBindingDescription {
+        V::binding_description()
+    }
+
+    /// Vertex attributes description of this mesh type, used when creating pipelines.
+    pub fn vertex_attribute_description() -> Vec<vulkanalia::vk::VertexInputAttributeDescription> {
+        V::attribute_description()
+    }
 }
 
 
-pub trait ToVulkanIntSize {
+pub trait ToVulkanIntSize: Copy {
     const INDEX_TYPE: vulkanalia::vk::IndexType;
+    /// Convert the index to a usize, to index the vertex array.
+    fn to_index(self) -> usize;
+    /// Create the index from a usize. The value is expected to fit in the int size.
+    fn from_index(index: usize) -> Self;
 }
 
 impl ToVulkanIntSize for u16 {
     const INDEX_TYPE: vulkanalia::vk::IndexType = vulkanalia::vk::IndexType::UINT16;
+    fn to_index(self) -> usize {
+        self as usize
+    }
+    fn from_index(index: usize) -> Self {
+        debug_assert!(index <= u16::MAX as usize);
+        index as u16
+    }
 }
 
 impl ToVulkanIntSize for u32 {
     const INDEX_TYPE: vulkanalia::vk::IndexType = vulkanalia::vk::IndexType::UINT32;
-}
\ No newline at end of file
+    fn to_index(self) -> usize {
+        self as usize
+    }
+    fn from_index(index: usize) -> Self {
+        debug_assert!(index <= u32::MAX as usize);
+        index as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MeshType, LineVertex, PointVertex};
+
+    #[test]
+    fn simplifying_a_sphere_lowers_the_triangle_count() {
+        let sphere = MeshType::uv_sphere(1.0, 16);
+        let simplified = sphere.simplified(0.5).unwrap();
+        assert!(simplified.index_count() < sphere.index_count());
+        assert!((simplified.bounding_radius() - sphere.bounding_radius()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn polyline_links_each_vertex_to_the_next() {
+        let vertices = (0..4).map(|i| LineVertex::new(glam::vec3(i as f32, 0., 0.), glam::Vec4::ONE)).collect();
+        let MeshType::Lines(polyline) = MeshType::polyline(vertices) else {
+            panic!("polylines should be line meshes");
+        };
+        assert_eq!(polyline.triangles(), &vec![0, 1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn simplifying_lines_and_points_is_an_error() {
+        let lines = MeshType::lines(
+            vec![LineVertex::new(glam::Vec3::ZERO, glam::Vec4::ONE), LineVertex::new(glam::Vec3::X, glam::Vec4::ONE)],
+            vec![0, 1],
+        );
+        assert!(lines.simplified(0.5).is_err());
+        let points = MeshType::points(vec![PointVertex::new(glam::Vec3::ZERO, glam::Vec4::ONE, 1.0)]);
+        assert!(points.simplified(0.5).is_err());
+    }
+}
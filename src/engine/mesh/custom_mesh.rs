@@ -0,0 +1,56 @@
+use super::{Mesh, vertex::VulkanVertex};
+
+/// Mesh with a user defined vertex layout.
+/// The vertices are stored as raw bytes, as they will be sent to the gpu.
+/// The pipelines drawing it are created with the mesh type `Mesh<V, u32>`, where `V` is the vertex layout.
+#[derive(Debug, Clone)]
+pub struct CustomMesh {
+    vertex_data: Vec<u8>,
+    vertex_size: usize,
+    vertex_count: usize,
+    triangles: Vec<u32>,
+    bounding_radius: f32,
+}
+
+impl CustomMesh {
+    /// Creates a custom mesh from a mesh of any vertex layout.
+    /// If the vertices have no position, the mesh is considered infinitely big, and will always use its most detailed level.
+    pub fn new<V: VulkanVertex>(mesh: Mesh<V, u32>) -> CustomMesh {
+        let bounding_radius = if mesh.vertices().iter().all(|v| v.vertex_position().is_some()) {
+            mesh.vertices().iter()
+                .filter_map(|v| v.vertex_position())
+                .map(|position| position.length())
+                .fold(0.0, f32::max)
+        } else {
+            f32::INFINITY
+        };
+        let vertex_data = super::as_bytes(mesh.vertices()).to_vec();
+        CustomMesh {
+            vertex_data,
+            vertex_size: std::mem::size_of::<V>(),
+            vertex_count: mesh.vertices().len(),
+            triangles: mesh.triangles,
+            bounding_radius,
+        }
+    }
+
+    pub fn vertex_data(&self) -> &[u8] {
+        &self.vertex_data
+    }
+
+    pub fn vertex_size(&self) -> usize {
+        self.vertex_size
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    pub fn triangles(&self) -> &Vec<u32> {
+        &self.triangles
+    }
+
+    pub fn bounding_radius(&self) -> f32 {
+        self.bounding_radius
+    }
+}
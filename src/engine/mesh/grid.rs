@@ -0,0 +1,24 @@
+use super::{vertex::LineVertex, MeshType};
+
+
+impl MeshType {
+    /// Flat grid of lines on the XZ plane, centered on the origin.
+    /// The grid is `size` wide and has `divisions` cells along each axis.
+    pub fn grid(size: f32, divisions: u32, color: glam::Vec4) -> MeshType {
+        let divisions = divisions.max(1);
+        let half_size = size * 0.5;
+        let step = size / divisions as f32;
+        let mut vertices = Vec::with_capacity(4 * (divisions as usize + 1));
+        for i in 0..=divisions {
+            let offset = -half_size + step * i as f32;
+            // line along the z axis
+            vertices.push(LineVertex::new(glam::Vec3::new(offset, 0., -half_size), color));
+            vertices.push(LineVertex::new(glam::Vec3::new(offset, 0., half_size), color));
+            // line along the x axis
+            vertices.push(LineVertex::new(glam::Vec3::new(-half_size, 0., offset), color));
+            vertices.push(LineVertex::new(glam::Vec3::new(half_size, 0., offset), color));
+        }
+        let indices = (0..vertices.len() as u32).collect();
+        MeshType::lines(vertices, indices)
+    }
+}
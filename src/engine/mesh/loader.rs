@@ -16,10 +16,14 @@ pub enum MeshLoadingError {
 }
 
 pub trait Loadable {
+    /// Size of the serialized data, in bytes. It can differ from the in memory size.
+    const LOADED_SIZE: usize;
     fn load_from(data: &[u8]) -> Result<Self, TryFromSliceError> where Self: Sized;
 }
 
 impl Loadable for StaticVertex {
+    // tangents are not serialized, they are generated after loading.
+    const LOADED_SIZE: usize = 8 * std::mem::size_of::<f32>();
     fn load_from(data: &[u8]) -> Result<StaticVertex, TryFromSliceError> {
         let size = std::mem::size_of::<f32>();
         Ok(StaticVertex::new(
@@ -36,12 +40,14 @@ impl Loadable for StaticVertex {
 }
 
 impl Loadable for u16 {
+    const LOADED_SIZE: usize = std::mem::size_of::<u16>();
     fn load_from(data: &[u8]) -> Result<u16, TryFromSliceError> {
         Ok(u16::from_ne_bytes(data.try_into()?))
     }
 }
 
 impl Loadable for u32 {
+    const LOADED_SIZE: usize = std::mem::size_of::<u32>();
     fn load_from(data: &[u8]) -> Result<u32, TryFromSliceError> {
         Ok(u32::from_ne_bytes(data.try_into()?))
     }
@@ -83,7 +89,7 @@ impl<V: Loadable + VulkanVertex, T: Loadable + ToVulkanIntSize> Mesh<V, T> {
     
         let mut vertices = Vec::with_capacity(vertex_count as usize);
         for _ in 0..vertex_count {
-            match V::load_from(read_buffer(std::mem::size_of::<V>())) {
+            match V::load_from(read_buffer(V::LOADED_SIZE)) {
                 Ok(v) => vertices.push(v),
                 Err(e) => return Err(LoadingError::from(MeshLoadingError::InvalidData(e)).into()),
             }
@@ -91,7 +97,7 @@ impl<V: Loadable + VulkanVertex, T: Loadable + ToVulkanIntSize> Mesh<V, T> {
     
         let mut triangles = Vec::with_capacity(3 * triangle_count as usize);
         for _ in 0..vertex_count {
-            match T::load_from(read_buffer(std::mem::size_of::<T>())) {
+            match T::load_from(read_buffer(T::LOADED_SIZE)) {
                 Ok(t) => triangles.push(t),
                 Err(e) => return Err(LoadingError::from(MeshLoadingError::InvalidData(e)).into()),
             }
@@ -0,0 +1,121 @@
+use foundry::ComponentTable;
+
+use crate::{
+    Camera,
+    Transform,
+    PropellantResources,
+    engine::resources::mesh_library::MeshLibrary,
+};
+
+/// A mesh with several levels of detail.
+/// Each level is an actual mesh in the library, and is used when the mesh screen size is above its threshold.
+/// The first level is the mesh registered with the group id, the others are stored apart, keyed by the mesh id and their level.
+#[derive(Debug, Clone)]
+pub struct MeshLodGroup {
+    /// Radius of the sphere containing the mesh, centered on the mesh origin.
+    bounding_radius: f32,
+    /// Level of each mesh, and the minimum screen size to use it, from the most to the least detailed.
+    levels: Vec<(usize, f32)>,
+}
+
+impl MeshLodGroup {
+    pub fn new(bounding_radius: f32, mut levels: Vec<(usize, f32)>) -> MeshLodGroup {
+        // keep the levels sorted from the biggest screen size to the smallest one
+        levels.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        MeshLodGroup {
+            bounding_radius,
+            levels,
+        }
+    }
+
+    pub fn bounding_radius(&self) -> f32 {
+        self.bounding_radius
+    }
+
+    pub fn levels(&self) -> &[(usize, f32)] {
+        &self.levels
+    }
+
+    /// Get the level to use for the given screen size.
+    /// The least detailed level is used for anything smaller than all thresholds.
+    pub fn level_for(&self, screen_size: f32) -> Option<usize> {
+        self.levels.iter()
+            .find(|(_, threshold)| screen_size >= *threshold)
+            .or(self.levels.last())
+            .map(|(level, _)| *level)
+    }
+}
+
+/// Chooses the level of detail of meshes, relative to the main camera.
+pub struct LodSelector<'a> {
+    meshes: Option<&'a MeshLibrary>,
+    /// Position of the main camera in world space, if the projection is a perspective one.
+    camera_position: Option<glam::Vec3>,
+    /// Vertical scale of the projection.
+    projection_scale: f32,
+}
+
+impl<'a> LodSelector<'a> {
+    /// Creates a selector from the main camera of the scene.
+    /// If there is no main camera or no meshes with levels of detail, the selector always gives the most detailed mesh.
+    pub fn new(components: &'a ComponentTable) -> LodSelector<'a> {
+        let meshes = components.get_singleton::<PropellantResources>()
+            .map(|resources| resources.meshes())
+            .filter(|meshes| meshes.has_lods());
+        let mut result = LodSelector {
+            meshes,
+            camera_position: None,
+            projection_scale: 0.0,
+        };
+        if result.meshes.is_none() {
+            return result;
+        }
+        for (_, tf, cam) in components.query2d::<Transform, Camera>() {
+            if cam.is_main() {
+                let projection = cam.projection_matrix();
+                result.projection_scale = projection.y_axis.y.abs();
+                // perspective projections have a zero in the bottom right, orthographic ones don't.
+                if projection.w_axis.w == 0.0 {
+                    // the camera transform is used as the view matrix
+                    result.camera_position = Some(tf.world_pos().inverse().w_axis.truncate());
+                }
+                else {
+                    result.camera_position = None;
+                }
+                break;
+            }
+        }
+        result
+    }
+
+    /// Whether the selector will actually do any selection.
+    pub fn has_lods(&self) -> bool {
+        self.meshes.is_some()
+    }
+
+    /// Proportion of the screen height covered by a sphere of the given radius, with the given transform.
+    pub fn screen_size(&self, world_transform: glam::Mat4, bounding_radius: f32) -> f32 {
+        let (scale, _, translation) = world_transform.to_scale_rotation_translation();
+        let radius = bounding_radius * scale.abs().max_element();
+        match self.camera_position {
+            Some(camera_position) => {
+                let distance = camera_position.distance(translation).max(f32::EPSILON);
+                radius * self.projection_scale / distance
+            },
+            // orthographic projection, screen size does not depend on the distance.
+            None => radius * self.projection_scale,
+        }
+    }
+
+    /// Get the mesh id and the level of detail to draw for the given mesh and world transform.
+    pub fn select(&self, mesh_id: u64, world_transform: Option<glam::Mat4>) -> (u64, usize) {
+        let (meshes, world_transform) = match (self.meshes, world_transform) {
+            (Some(meshes), Some(world_transform)) => (meshes, world_transform),
+            _ => return (mesh_id, 0),
+        };
+        match meshes.lod_group(mesh_id) {
+            Some(group) => (mesh_id, group.level_for(self.screen_size(world_transform, group.bounding_radius())).unwrap_or(0)),
+            None => (mesh_id, 0),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod skinned_mesh_renderer;
 #[cfg(feature = "ui")]
 pub(crate) mod ui_mesh_renderer;
 
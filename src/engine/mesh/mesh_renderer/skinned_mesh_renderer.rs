@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::engine::animations::skeleton::Skeleton;
+
+/// Component holding the pose of a skinned mesh.
+/// It goes along a `InstancedMeshRenderer<Material, SkinnedMesh>` that tells which mesh and material to draw,
+/// while this component tells how the mesh is deformed.
+/// The joints palette is computed by the skinning system, that needs to be registered in the world.
+pub struct SkinnedMeshRenderer {
+    /// Shared skeleton of the mesh.
+    skeleton: Arc<Skeleton>,
+    /// Local transforms of each joint.
+    pose: Vec<glam::Mat4>,
+    /// Skinning matrices of the joints, as sent to the gpu.
+    palette: Vec<glam::Mat4>,
+}
+
+impl SkinnedMeshRenderer {
+    /// Creates a new skinned mesh renderer, in the bind pose of the skeleton.
+    pub fn new(skeleton: Arc<Skeleton>) -> SkinnedMeshRenderer {
+        let pose = skeleton.bind_pose();
+        let palette = vec![glam::Mat4::IDENTITY; skeleton.joint_count()];
+        SkinnedMeshRenderer {
+            skeleton,
+            pose,
+            palette,
+        }
+    }
+
+    pub fn skeleton(&self) -> &Arc<Skeleton> {
+        &self.skeleton
+    }
+
+    /// Local transforms of the joints, relative to their parents.
+    pub fn pose(&self) -> &[glam::Mat4] {
+        &self.pose
+    }
+
+    /// Sets the local transform of a joint, relative to its parent.
+    pub fn set_joint_transform(&mut self, joint: usize, transform: glam::Mat4) {
+        if let Some(local_transform) = self.pose.get_mut(joint) {
+            *local_transform = transform;
+        }
+    }
+
+    /// Resets the pose to the bind pose of the skeleton.
+    pub fn reset_pose(&mut self) {
+        self.pose = self.skeleton.bind_pose();
+    }
+
+    /// Skinning matrices of the joints, computed at the last skinning system update.
+    pub fn palette(&self) -> &[glam::Mat4] {
+        &self.palette
+    }
+
+    /// Recompute the joints palette, with the world transform of the entity.
+    pub fn update_palette(&mut self, world_transform: glam::Mat4) {
+        self.skeleton.compute_palette(&self.pose, world_transform, &mut self.palette);
+    }
+}
@@ -0,0 +1,14 @@
+//! Cpu side utilities to work on meshes before sending them to the gpu.
+//! Useful for meshes built in code, where normals, tangents and indices would otherwise be computed by hand.
+
+pub(crate) mod index_optimization;
+pub(crate) mod normals;
+pub(crate) mod simplification;
+pub(crate) mod tangents;
+pub(crate) mod welding;
+
+/// Unnormalized face normal of a triangle. Its length is twice the triangle area.
+/// Triangles are wound clockwise in the engine, which gives the order of the cross product.
+pub(crate) fn face_normal(a: glam::Vec3, b: glam::Vec3, c: glam::Vec3) -> glam::Vec3 {
+    (c - a).cross(b - a)
+}
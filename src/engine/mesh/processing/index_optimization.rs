@@ -0,0 +1,274 @@
+use crate::engine::mesh::{
+    Mesh,
+    ToVulkanIntSize,
+    vertex::VulkanVertex,
+};
+
+/// Size of the simulated post transform vertex cache.
+const VERTEX_CACHE_SIZE: usize = 32;
+/// Score bonus for the vertices of the last added triangle.
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+/// Power of the score decay with the position in the cache.
+const CACHE_DECAY_POWER: f32 = 1.5;
+/// Score bonus for vertices that have few triangles left to draw.
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+
+impl<V: VulkanVertex, T: ToVulkanIntSize> Mesh<V, T> {
+    /// Reorder the triangles to make a better use of the gpu post transform vertex cache.
+    /// This uses Tom Forsyth's linear speed vertex cache optimization:
+    /// triangles are greedily added, choosing the one whose vertices are the most likely to still be in cache.
+    /// The vertices are not changed, and the rendered result is the same.
+    pub fn optimize_vertex_cache(&mut self) {
+        let triangle_count = self.triangles.len() / 3;
+        if triangle_count == 0 {
+            return;
+        }
+        let vertex_count = self.vertices.len();
+
+        // triangles using each vertex
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+        for (triangle, indices) in self.triangles.chunks_exact(3).enumerate() {
+            for index in indices {
+                vertex_triangles[index.to_index()].push(triangle);
+            }
+        }
+        let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+        let mut vertex_scores: Vec<f32> = vertex_triangles.iter()
+            .map(|triangles| vertex_score(None, triangles.len()))
+            .collect();
+        let mut triangle_added = vec![false; triangle_count];
+        let mut triangle_scores: Vec<f32> = self.triangles.chunks_exact(3)
+            .map(|indices| indices.iter().map(|i| vertex_scores[i.to_index()]).sum())
+            .collect();
+
+        let mut result = Vec::with_capacity(triangle_count * 3);
+        let mut cache: Vec<usize> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+        // index from which we look for the best triangle when nothing in cache can be drawn.
+        let mut scan_start = 0;
+        let mut best_triangle = None;
+
+        for _ in 0..triangle_count {
+            let triangle = match best_triangle {
+                Some(triangle) => triangle,
+                None => {
+                    // no candidates in the cache, find the best remaining triangle
+                    while triangle_added[scan_start] {
+                        scan_start += 1;
+                    }
+                    (scan_start..triangle_count)
+                        .filter(|t| !triangle_added[*t])
+                        .max_by(|a, b| triangle_scores[*a].total_cmp(&triangle_scores[*b]))
+                        .unwrap_or(scan_start)
+                }
+            };
+
+            triangle_added[triangle] = true;
+            let indices = [
+                self.triangles[triangle * 3].to_index(),
+                self.triangles[triangle * 3 + 1].to_index(),
+                self.triangles[triangle * 3 + 2].to_index(),
+            ];
+            for index in indices {
+                result.push(T::from_index(index));
+                vertex_triangles[index].retain(|t| *t != triangle);
+            }
+
+            // move the triangle vertices to the front of the cache
+            cache.retain(|v| !indices.contains(v));
+            let mut new_cache = Vec::with_capacity(cache.len() + 3);
+            new_cache.extend_from_slice(&indices);
+            new_cache.append(&mut cache);
+            cache = new_cache;
+
+            // update the scores of the vertices in the cache, and the ones that got out of it.
+            for (position, vertex) in cache.iter().enumerate() {
+                cache_positions[*vertex] = if position < VERTEX_CACHE_SIZE { Some(position) } else { None };
+                vertex_scores[*vertex] = vertex_score(cache_positions[*vertex], vertex_triangles[*vertex].len());
+            }
+            cache.truncate(VERTEX_CACHE_SIZE);
+
+            // the candidates for the next triangle are the ones using vertices in the cache
+            best_triangle = None;
+            let mut best_score = f32::NEG_INFINITY;
+            for vertex in cache.iter() {
+                for candidate in vertex_triangles[*vertex].iter() {
+                    let score = self.triangles[candidate * 3..candidate * 3 + 3].iter()
+                        .map(|i| vertex_scores[i.to_index()])
+                        .sum();
+                    triangle_scores[*candidate] = score;
+                    if score > best_score {
+                        best_score = score;
+                        best_triangle = Some(*candidate);
+                    }
+                }
+            }
+        }
+
+        self.triangles = result;
+    }
+
+    /// Reorder the vertices in the order they are first used by the triangles.
+    /// This improves the memory access pattern of the vertex fetching, and is best used after the vertex cache optimization.
+    /// Vertices that are not used by any triangle are removed.
+    pub fn optimize_vertex_fetch(&mut self) where V: Clone {
+        let mut remap: Vec<Option<usize>> = vec![None; self.vertices.len()];
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        for index in self.triangles.iter_mut() {
+            let new_index = match remap[index.to_index()] {
+                Some(new_index) => new_index,
+                None => {
+                    let new_index = vertices.len();
+                    vertices.push(self.vertices[index.to_index()].clone());
+                    remap[index.to_index()] = Some(new_index);
+                    new_index
+                }
+            };
+            *index = T::from_index(new_index);
+        }
+        self.vertices = vertices;
+    }
+}
+
+impl<V: VulkanVertex> Mesh<V, u32> {
+    /// Convert the mesh to 16 bits indices, halving the index buffer size.
+    /// This is only possible if the mesh has at most 65536 vertices, otherwise the mesh is given back as is.
+    pub fn try_narrow_indices(self) -> Result<Mesh<V, u16>, Mesh<V, u32>> {
+        if self.vertices.len() > u16::MAX as usize + 1 {
+            return Err(self);
+        }
+        let triangles = self.triangles.iter().map(|i| *i as u16).collect();
+        Ok(Mesh::new(self.vertices, triangles))
+    }
+}
+
+/// Score of a vertex, given its position in the cache and the number of triangles left that use it.
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        // no triangles left to draw with this vertex
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        // the last triangle vertices get a fixed score, to avoid favoring re-use of the exact same edge
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scaler = 1.0 / (VERTEX_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scaler).powf(CACHE_DECAY_POWER)
+        }
+    };
+    let valence_boost = VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_boost
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::mesh::{Mesh, vertex::{GeometryVertex, StaticVertex}};
+
+    /// A grid of quads, with the triangles in a shuffled order.
+    fn shuffled_grid(size: usize) -> Mesh<StaticVertex, u32> {
+        let vertices = (0..(size + 1) * (size + 1))
+            .map(|i| StaticVertex::new((i % (size + 1)) as f32, 0., (i / (size + 1)) as f32, 0., 1., 0., 0., 0.))
+            .collect();
+        let mut triangles: Vec<[u32; 3]> = Vec::new();
+        for z in 0..size {
+            for x in 0..size {
+                let corner = (z * (size + 1) + x) as u32;
+                let row = (size + 1) as u32;
+                triangles.push([corner, corner + 1, corner + row + 1]);
+                triangles.push([corner + row + 1, corner + row, corner]);
+            }
+        }
+        // deterministic shuffle, interleaving the triangles from both ends.
+        let mut shuffled = Vec::with_capacity(triangles.len());
+        while !triangles.is_empty() {
+            shuffled.push(triangles.remove(0));
+            if let Some(last) = triangles.pop() {
+                shuffled.push(last);
+            }
+        }
+        Mesh::new(vertices, shuffled.into_iter().flatten().collect())
+    }
+
+    fn sorted_triangles(mesh: &Mesh<StaticVertex, u32>) -> Vec<[u32; 3]> {
+        let mut triangles = mesh.triangles.chunks_exact(3)
+            .map(|t| {
+                let positions = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].position());
+                // compare the triangles by their positions, as the vertices can be reordered.
+                positions.map(|p| (p.x as u32) + (p.z as u32) * 1000)
+            })
+            .map(|mut t| {
+                // keep the winding, starting from the smallest index.
+                let start = (0..3).min_by_key(|i| t[*i]).unwrap();
+                t.rotate_left(start);
+                t
+            })
+            .collect::<Vec<_>>();
+        triangles.sort();
+        triangles
+    }
+
+    /// Average number of vertices transformed per triangle, with a fifo cache of the given size.
+    fn average_cache_miss_ratio(triangles: &[u32], cache_size: usize) -> f32 {
+        let mut cache = std::collections::VecDeque::with_capacity(cache_size);
+        let mut misses = 0;
+        for index in triangles {
+            if !cache.contains(index) {
+                misses += 1;
+                if cache.len() == cache_size {
+                    cache.pop_front();
+                }
+                cache.push_back(*index);
+            }
+        }
+        misses as f32 / (triangles.len() / 3) as f32
+    }
+
+    #[test]
+    fn vertex_cache_optimization_keeps_the_triangles() {
+        let mut mesh = shuffled_grid(8);
+        let before = sorted_triangles(&mesh);
+        mesh.optimize_vertex_cache();
+        assert_eq!(sorted_triangles(&mesh), before);
+    }
+
+    #[test]
+    fn vertex_cache_optimization_lowers_the_cache_misses() {
+        let mut mesh = shuffled_grid(16);
+        let before = average_cache_miss_ratio(&mesh.triangles, 16);
+        mesh.optimize_vertex_cache();
+        let after = average_cache_miss_ratio(&mesh.triangles, 16);
+        assert!(after < before, "cache miss ratio went from {before} to {after}");
+    }
+
+    #[test]
+    fn vertex_fetch_optimization_orders_and_removes_vertices() {
+        let mut mesh = shuffled_grid(4);
+        // a vertex no triangle uses.
+        mesh.vertices.push(StaticVertex::new(10., 0., 10., 0., 1., 0., 0., 0.));
+        let before = sorted_triangles(&mesh);
+        mesh.optimize_vertex_fetch();
+        assert_eq!(mesh.vertices.len(), 25);
+        assert_eq!(sorted_triangles(&mesh), before);
+        // each new vertex is used for the first time right after the previous one.
+        let mut next = 0;
+        for index in mesh.triangles.iter() {
+            assert!(*index <= next);
+            if *index == next {
+                next += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn narrowing_indices_depends_on_the_vertex_count() {
+        let mesh = shuffled_grid(2);
+        let triangles = mesh.triangles.clone();
+        let narrowed = mesh.try_narrow_indices().unwrap();
+        assert_eq!(narrowed.triangles, triangles.iter().map(|i| *i as u16).collect::<Vec<_>>());
+
+        let vertices = vec![StaticVertex::new(0., 0., 0., 0., 1., 0., 0., 0.); u16::MAX as usize + 2];
+        assert!(Mesh::new(vertices, vec![0u32, 1, 2]).try_narrow_indices().is_err());
+    }
+}
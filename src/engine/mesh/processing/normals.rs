@@ -0,0 +1,93 @@
+use crate::engine::mesh::{
+    Mesh,
+    ToVulkanIntSize,
+    vertex::{VulkanVertex, GeometryVertex},
+};
+
+use super::face_normal;
+
+
+impl<V: VulkanVertex + GeometryVertex, T: ToVulkanIntSize> Mesh<V, T> {
+    /// Recompute the normals of the mesh, averaging the normals of the faces around each vertex.
+    /// Faces normals are weighted by their area, so small triangles have little influence.
+    /// Vertices that are duplicated (uv seams, etc) will not be smoothed together: weld them first if needed.
+    pub fn compute_smooth_normals(&mut self) {
+        let mut normals = vec![glam::Vec3::ZERO; self.vertices.len()];
+        for triangle in self.triangles.chunks_exact(3) {
+            let (a, b, c) = (triangle[0].to_index(), triangle[1].to_index(), triangle[2].to_index());
+            let normal = face_normal(
+                self.vertices[a].position(),
+                self.vertices[b].position(),
+                self.vertices[c].position(),
+            );
+            normals[a] += normal;
+            normals[b] += normal;
+            normals[c] += normal;
+        }
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals.into_iter()) {
+            vertex.set_normal(normal.normalize_or_zero());
+        }
+    }
+
+    /// Recompute the normals of the mesh so that each face is flat.
+    /// This requires each triangle to have its own vertices, so the vertices are duplicated
+    /// and the mesh ends up with exactly one vertex per index.
+    pub fn compute_flat_normals(&mut self) {
+        let mut vertices = Vec::with_capacity(self.triangles.len());
+        for triangle in self.triangles.chunks_exact(3) {
+            let normal = face_normal(
+                self.vertices[triangle[0].to_index()].position(),
+                self.vertices[triangle[1].to_index()].position(),
+                self.vertices[triangle[2].to_index()].position(),
+            ).normalize_or_zero();
+            for index in triangle {
+                let mut vertex = self.vertices[index.to_index()].clone();
+                vertex.set_normal(normal);
+                vertices.push(vertex);
+            }
+        }
+        self.triangles = (0..vertices.len()).map(T::from_index).collect();
+        self.vertices = vertices;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::mesh::{Mesh, vertex::{GeometryVertex, StaticVertex}};
+
+    /// Two triangles folded at a right angle along the x axis, sharing their edge vertices, with wrong normals.
+    fn folded_quad() -> Mesh<StaticVertex, u32> {
+        Mesh::new(
+            vec![
+                StaticVertex::new(0., 0., 0., 0., 0., 0., 0., 0.),
+                StaticVertex::new(1., 0., 0., 0., 0., 0., 0., 0.),
+                // floor triangle, facing up
+                StaticVertex::new(1., 0., 1., 0., 0., 0., 0., 0.),
+                // wall triangle, facing -z
+                StaticVertex::new(1., 1., 0., 0., 0., 0., 0., 0.),
+            ],
+            vec![0, 1, 2, 0, 1, 3],
+        )
+    }
+
+    #[test]
+    fn smooth_normals_average_the_faces() {
+        let mut mesh = folded_quad();
+        mesh.compute_smooth_normals();
+        let shared = glam::vec3(0., 1., -1.).normalize();
+        assert!(mesh.vertices[0].normal().abs_diff_eq(shared, 1e-5), "{:?}", mesh.vertices[0].normal());
+        assert!(mesh.vertices[1].normal().abs_diff_eq(shared, 1e-5), "{:?}", mesh.vertices[1].normal());
+        assert!(mesh.vertices[2].normal().abs_diff_eq(glam::Vec3::Y, 1e-5), "{:?}", mesh.vertices[2].normal());
+        assert!(mesh.vertices[3].normal().abs_diff_eq(glam::Vec3::NEG_Z, 1e-5), "{:?}", mesh.vertices[3].normal());
+    }
+
+    #[test]
+    fn flat_normals_split_the_vertices() {
+        let mut mesh = folded_quad();
+        mesh.compute_flat_normals();
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.triangles, vec![0, 1, 2, 3, 4, 5]);
+        assert!(mesh.vertices[..3].iter().all(|vertex| vertex.normal().abs_diff_eq(glam::Vec3::Y, 1e-5)));
+        assert!(mesh.vertices[3..].iter().all(|vertex| vertex.normal().abs_diff_eq(glam::Vec3::NEG_Z, 1e-5)));
+    }
+}
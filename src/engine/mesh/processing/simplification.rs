@@ -0,0 +1,347 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering;
+
+use crate::engine::mesh::{
+    Mesh,
+    ToVulkanIntSize,
+    vertex::{VulkanVertex, GeometryVertex},
+};
+
+
+impl<V: VulkanVertex + GeometryVertex, T: ToVulkanIntSize> Mesh<V, T> {
+    /// Create a simplified version of this mesh, with approximately `target_ratio` of its triangles.
+    /// 
+    /// This uses edge collapses ordered by quadric error (Garland and Heckbert).
+    /// Vertices are collapsed onto one of their neighbours, so no new vertices are created and attributes stay valid.
+    /// Vertices on the mesh borders, and vertices that are split for attribute seams (same position, different vertices),
+    /// are never moved to preserve the mesh silhouette and textures.
+    /// Collapses that would flip a triangle are rejected, so the result may have more triangles than asked.
+    /// 
+    /// Unused vertices are removed from the simplified mesh.
+    pub fn simplify(&self, target_ratio: f32) -> Mesh<V, T> {
+        let triangle_count = self.triangles.len() / 3;
+        let target_count = (triangle_count as f32 * target_ratio.clamp(0.0, 1.0)) as usize;
+
+        let positions: Vec<glam::DVec3> = self.vertices.iter().map(|v| v.position().as_dvec3()).collect();
+        let mut triangles: Vec<[usize; 3]> = self.triangles.chunks_exact(3)
+            .map(|t| [t[0].to_index(), t[1].to_index(), t[2].to_index()])
+            .collect();
+        let mut triangle_alive = vec![true; triangles.len()];
+        let mut alive_count = triangle_count;
+
+        // triangles around each vertex
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+        for (index, triangle) in triangles.iter().enumerate() {
+            for vertex in triangle {
+                vertex_triangles[*vertex].push(index);
+            }
+        }
+
+        // quadrics of each vertex, sum of the planes of the triangles around it.
+        let mut quadrics = vec![Quadric::default(); self.vertices.len()];
+        for triangle in triangles.iter() {
+            let normal = plane_normal(positions[triangle[0]], positions[triangle[1]], positions[triangle[2]]);
+            let area = normal.length() * 0.5;
+            let normal = normal.normalize_or_zero();
+            let quadric = Quadric::from_plane(normal, -normal.dot(positions[triangle[0]])).scaled(area);
+            for vertex in triangle {
+                quadrics[*vertex].add(&quadric);
+            }
+        }
+
+        let locked = locked_vertices(&triangles, &positions);
+
+        let mut versions = vec![0u32; self.vertices.len()];
+        let mut heap = BinaryHeap::new();
+        let push_collapses = |vertex: usize, heap: &mut BinaryHeap<Collapse>, vertex_triangles: &Vec<Vec<usize>>, triangles: &Vec<[usize; 3]>, quadrics: &Vec<Quadric>, versions: &Vec<u32>| {
+            for triangle in vertex_triangles[vertex].iter() {
+                for other in triangles[*triangle] {
+                    if other == vertex {
+                        continue;
+                    }
+                    // collapse from the vertex to its neighbour, and the other way around
+                    for (from, to) in [(vertex, other), (other, vertex)] {
+                        if locked[from] {
+                            continue;
+                        }
+                        let mut quadric = quadrics[from];
+                        quadric.add(&quadrics[to]);
+                        heap.push(Collapse {
+                            cost: quadric.error(positions[to]),
+                            from,
+                            to,
+                            from_version: versions[from],
+                            to_version: versions[to],
+                        });
+                    }
+                }
+            }
+        };
+        for vertex in 0..self.vertices.len() {
+            push_collapses(vertex, &mut heap, &vertex_triangles, &triangles, &quadrics, &versions);
+        }
+
+        // vertex each vertex was collapsed to, if any.
+        let mut collapsed_to: Vec<Option<usize>> = vec![None; self.vertices.len()];
+
+        while alive_count > target_count {
+            let collapse = match heap.pop() {
+                Some(collapse) => collapse,
+                None => break,
+            };
+            let (from, to) = (collapse.from, collapse.to);
+            if collapsed_to[from].is_some() || collapsed_to[to].is_some()
+                || versions[from] != collapse.from_version || versions[to] != collapse.to_version {
+                // outdated collapse
+                continue;
+            }
+            if !collapse_keeps_orientation(from, to, &vertex_triangles[from], &triangles, &positions) {
+                continue;
+            }
+
+            // perform the collapse: all triangles around "from" now use "to"
+            for triangle in std::mem::take(&mut vertex_triangles[from]) {
+                if !triangle_alive[triangle] {
+                    continue;
+                }
+                if triangles[triangle].contains(&to) {
+                    // the triangle is on the collapsed edge, it disappears
+                    triangle_alive[triangle] = false;
+                    alive_count -= 1;
+                    for vertex in triangles[triangle] {
+                        if vertex != from {
+                            vertex_triangles[vertex].retain(|t| *t != triangle);
+                        }
+                    }
+                } else {
+                    for vertex in triangles[triangle].iter_mut() {
+                        if *vertex == from {
+                            *vertex = to;
+                        }
+                    }
+                    vertex_triangles[to].push(triangle);
+                }
+            }
+            collapsed_to[from] = Some(to);
+            let quadric = quadrics[from];
+            quadrics[to].add(&quadric);
+            // invalidate the collapses around the updated vertex, and push the new ones
+            versions[to] += 1;
+            push_collapses(to, &mut heap, &vertex_triangles, &triangles, &quadrics, &versions);
+        }
+
+        // rebuild the mesh with the remaining triangles and vertices
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::with_capacity(alive_count * 3);
+        for (triangle, alive) in triangles.iter().zip(triangle_alive.iter()) {
+            if !alive {
+                continue;
+            }
+            for vertex in triangle {
+                let index = *remap.entry(*vertex).or_insert_with(|| {
+                    vertices.push(self.vertices[*vertex].clone());
+                    vertices.len() - 1
+                });
+                indices.push(T::from_index(index));
+            }
+        }
+
+        Mesh::new(vertices, indices)
+    }
+}
+
+/// Same as the face normal, in double precision.
+fn plane_normal(a: glam::DVec3, b: glam::DVec3, c: glam::DVec3) -> glam::DVec3 {
+    (c - a).cross(b - a)
+}
+
+/// Find the vertices that can't be moved: the ones on borders, and the ones on attribute seams.
+fn locked_vertices(triangles: &[[usize; 3]], positions: &[glam::DVec3]) -> Vec<bool> {
+    let mut locked = vec![false; positions.len()];
+    // border edges are the ones used by a single triangle
+    let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+    for triangle in triangles.iter() {
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+    for ((a, b), count) in edges.into_iter() {
+        if count == 1 {
+            locked[a] = true;
+            locked[b] = true;
+        }
+    }
+    // seams are vertices that share the same position
+    let mut seen: HashMap<[u64; 3], usize> = HashMap::new();
+    for (vertex, position) in positions.iter().enumerate() {
+        let key = [position.x.to_bits(), position.y.to_bits(), position.z.to_bits()];
+        if let Some(other) = seen.insert(key, vertex) {
+            locked[other] = true;
+            locked[vertex] = true;
+        }
+    }
+    locked
+}
+
+/// Check that moving a vertex does not flip any of the triangles around it.
+fn collapse_keeps_orientation(from: usize, to: usize, around: &[usize], triangles: &[[usize; 3]], positions: &[glam::DVec3]) -> bool {
+    around.iter().all(|triangle| {
+        let vertices = triangles[*triangle];
+        if vertices.contains(&to) {
+            // will be removed by the collapse
+            return true;
+        }
+        let before = plane_normal(positions[vertices[0]], positions[vertices[1]], positions[vertices[2]]);
+        let moved = vertices.map(|v| if v == from { positions[to] } else { positions[v] });
+        let after = plane_normal(moved[0], moved[1], moved[2]);
+        before.dot(after) > 0.0
+    })
+}
+
+/// Symmetric 4x4 matrix representing the sum of squared distances to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    // upper triangle of the matrix
+    a: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(normal: glam::DVec3, d: f64) -> Quadric {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        Quadric {
+            a: [
+                a * a, a * b, a * c, a * d,
+                b * b, b * c, b * d,
+                c * c, c * d,
+                d * d,
+            ],
+        }
+    }
+
+    fn scaled(mut self, factor: f64) -> Quadric {
+        self.a.iter_mut().for_each(|v| *v *= factor);
+        self
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.a.iter_mut().zip(other.a.iter()) {
+            *a += *b;
+        }
+    }
+
+    /// Error of placing a vertex at the given position.
+    fn error(&self, p: glam::DVec3) -> f64 {
+        let q = &self.a;
+        q[0] * p.x * p.x + 2.0 * q[1] * p.x * p.y + 2.0 * q[2] * p.x * p.z + 2.0 * q[3] * p.x
+            + q[4] * p.y * p.y + 2.0 * q[5] * p.y * p.z + 2.0 * q[6] * p.y
+            + q[7] * p.z * p.z + 2.0 * q[8] * p.z
+            + q[9]
+    }
+}
+
+/// A potential edge collapse, ordered by lowest cost first in the heap.
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, so the binary heap gives the lowest cost first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::mesh::{Mesh, vertex::{GeometryVertex, StaticVertex}};
+
+    /// Closed uv sphere, with shared poles and no seam, so none of its vertices are locked.
+    fn sphere(radius: f32, rings: u32, segments: u32) -> Mesh<StaticVertex, u32> {
+        let mut vertices = vec![StaticVertex::new(0., radius, 0., 0., 1., 0., 0., 0.)];
+        for ring in 1..rings {
+            let phi = std::f32::consts::PI * ring as f32 / rings as f32;
+            for segment in 0..segments {
+                let theta = std::f32::consts::TAU * segment as f32 / segments as f32;
+                let normal = glam::vec3(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+                let position = normal * radius;
+                vertices.push(StaticVertex::new(position.x, position.y, position.z, normal.x, normal.y, normal.z, 0., 0.));
+            }
+        }
+        vertices.push(StaticVertex::new(0., -radius, 0., 0., -1., 0., 0., 0.));
+        let bottom = vertices.len() as u32 - 1;
+
+        let ring_vertex = |ring: u32, segment: u32| 1 + (ring - 1) * segments + segment % segments;
+        let mut triangles = Vec::new();
+        for segment in 0..segments {
+            triangles.extend([0, ring_vertex(1, segment + 1), ring_vertex(1, segment)]);
+            triangles.extend([bottom, ring_vertex(rings - 1, segment), ring_vertex(rings - 1, segment + 1)]);
+        }
+        for ring in 1..rings - 1 {
+            for segment in 0..segments {
+                let (a, b) = (ring_vertex(ring, segment), ring_vertex(ring, segment + 1));
+                let (c, d) = (ring_vertex(ring + 1, segment), ring_vertex(ring + 1, segment + 1));
+                triangles.extend([a, b, d, a, d, c]);
+            }
+        }
+        Mesh::new(vertices, triangles)
+    }
+
+    fn bounds(mesh: &Mesh<StaticVertex, u32>) -> (glam::Vec3, glam::Vec3) {
+        mesh.vertices.iter().fold(
+            (glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN)),
+            |(min, max), v| (min.min(v.position()), max.max(v.position())),
+        )
+    }
+
+    #[test]
+    fn simplification_lowers_the_triangle_count() {
+        let mesh = sphere(1.0, 16, 32);
+        let simplified = mesh.simplify(0.25);
+        let (before, after) = (mesh.triangles.len() / 3, simplified.triangles.len() / 3);
+        assert!(after < before, "{after} triangles, from {before}");
+        // flipped collapses are rejected, but a smooth sphere should get close to the target.
+        assert!(after <= before / 2, "{after} triangles, from {before}");
+        assert!(simplified.vertices.len() < mesh.vertices.len());
+        assert!(simplified.triangles.iter().all(|i| (*i as usize) < simplified.vertices.len()));
+    }
+
+    #[test]
+    fn simplification_keeps_the_bounds() {
+        let mesh = sphere(1.0, 16, 32);
+        let simplified = mesh.simplify(0.25);
+        let (min, max) = bounds(&mesh);
+        let (simplified_min, simplified_max) = bounds(&simplified);
+        // vertices are collapsed onto existing ones: the bounds can only shrink, and not by much.
+        assert!(simplified_min.cmpge(min).all() && simplified_max.cmple(max).all());
+        assert!(simplified_min.abs_diff_eq(min, 0.1), "{simplified_min:?} from {min:?}");
+        assert!(simplified_max.abs_diff_eq(max, 0.1), "{simplified_max:?} from {max:?}");
+    }
+
+    #[test]
+    fn full_ratio_keeps_the_mesh() {
+        let mesh = sphere(1.0, 8, 16);
+        let simplified = mesh.simplify(1.0);
+        assert_eq!(simplified.triangles.len(), mesh.triangles.len());
+        assert_eq!(simplified.vertices.len(), mesh.vertices.len());
+    }
+}
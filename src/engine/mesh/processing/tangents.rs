@@ -0,0 +1,301 @@
+use crate::engine::mesh::{
+    Mesh,
+    ToVulkanIntSize,
+    vertex::{VulkanVertex, GeometryVertex, StaticVertex},
+};
+
+
+/// Tangent frame of a triangle, from the derivatives of its positions along its uvs.
+struct TriangleFrame {
+    /// Normalized direction of growing u.
+    tangent: glam::Vec3,
+    /// Sign of the bitangent relative to `normal.cross(tangent)`, negative when the uvs are mirrored.
+    handedness: f32,
+}
+
+impl TriangleFrame {
+    /// The frame of a triangle, or none when its uvs have no area and it can not orient a tangent.
+    fn new(positions: [glam::Vec3; 3], uvs: [glam::Vec2; 3]) -> Option<TriangleFrame> {
+        let edge_1 = positions[1] - positions[0];
+        let edge_2 = positions[2] - positions[0];
+        let delta_uv_1 = uvs[1] - uvs[0];
+        let delta_uv_2 = uvs[2] - uvs[0];
+
+        // twice the signed area of the triangle in uv space.
+        let det = delta_uv_1.x * delta_uv_2.y - delta_uv_2.x * delta_uv_1.y;
+        if det.abs() <= f32::EPSILON {
+            return None;
+        }
+        let tangent = ((edge_1 * delta_uv_2.y - edge_2 * delta_uv_1.y) / det).try_normalize()?;
+        // MikkTSpace preserves the orientation when the signed uv area is positive, for counter clockwise triangles.
+        // Triangles are wound clockwise in the engine, which flips it.
+        let handedness = if det > 0.0 { -1.0 } else { 1.0 };
+        Some(TriangleFrame { tangent, handedness })
+    }
+}
+
+impl<V: VulkanVertex + GeometryVertex, T: ToVulkanIntSize> Mesh<V, T> {
+    fn triangle_frame(&self, indices: [usize; 3]) -> Option<TriangleFrame> {
+        TriangleFrame::new(
+            indices.map(|i| self.vertices[i].position()),
+            indices.map(|i| self.vertices[i].uv()),
+        )
+    }
+
+    /// Duplicate the vertices shared by triangles of opposite handedness, such as the vertices of mirrored uv seams,
+    /// so each vertex gets a single tangent frame as in MikkTSpace.
+    /// Triangles with no uv area can use any frame, and never split a vertex.
+    /// Returns the number of vertices that were added.
+    pub fn split_mirrored_vertices(&mut self) -> usize {
+        let vertex_count = self.vertices.len();
+        // handedness of the first triangle using each vertex, and the duplicate used by the triangles of the other one.
+        let mut handedness: Vec<Option<f32>> = vec![None; vertex_count];
+        let mut mirrored: Vec<Option<usize>> = vec![None; vertex_count];
+
+        for first_corner in (0..self.triangles.len() / 3).map(|triangle| triangle * 3) {
+            let indices = [0, 1, 2].map(|corner| self.triangles[first_corner + corner].to_index());
+            let frame = match self.triangle_frame(indices) {
+                Some(frame) => frame,
+                None => continue,
+            };
+            for (corner, index) in indices.into_iter().enumerate() {
+                match handedness[index] {
+                    None => handedness[index] = Some(frame.handedness),
+                    Some(vertex_handedness) if vertex_handedness == frame.handedness => {},
+                    Some(_) => {
+                        let duplicate = match mirrored[index] {
+                            Some(duplicate) => duplicate,
+                            None => {
+                                let vertex = self.vertices[index].clone();
+                                self.vertices.push(vertex);
+                                mirrored[index] = Some(self.vertices.len() - 1);
+                                self.vertices.len() - 1
+                            }
+                        };
+                        self.triangles[first_corner + corner] = T::from_index(duplicate);
+                    }
+                }
+            }
+        }
+
+        self.vertices.len() - vertex_count
+    }
+
+    /// Generate per vertex tangents following the MikkTSpace rules, so normal maps baked by most tools are shaded as expected:
+    /// the tangent of each triangle is projected on the tangent plane of the vertex normal,
+    /// weighted by the angle of the triangle at the vertex measured in that plane, and the sum is normalized.
+    /// The handedness of the tangent frame is stored in w, and the bitangent can be reconstructed with `w * normal.cross(tangent.xyz)`.
+    ///
+    /// A vertex shared by triangles of opposite handedness keeps the frame of the first one:
+    /// such vertices should be split with `split_mirrored_vertices` first, as `generate_tangents` does.
+    /// The mesh needs valid normals and uvs. Vertices with no uv gradient get an arbitrary tangent orthogonal to the normal.
+    pub fn compute_tangents(&self) -> Vec<glam::Vec4> {
+        let mut tangents = vec![glam::Vec3::ZERO; self.vertices.len()];
+        let mut handedness: Vec<Option<f32>> = vec![None; self.vertices.len()];
+
+        for triangle in self.triangles.chunks_exact(3) {
+            let indices = [triangle[0].to_index(), triangle[1].to_index(), triangle[2].to_index()];
+            let frame = match self.triangle_frame(indices) {
+                Some(frame) => frame,
+                // degenerate uv mapping, this triangle can't contribute
+                None => continue,
+            };
+            let positions = indices.map(|i| self.vertices[i].position());
+
+            for corner in 0..3 {
+                let index = indices[corner];
+                match handedness[index] {
+                    None => handedness[index] = Some(frame.handedness),
+                    Some(vertex_handedness) if vertex_handedness != frame.handedness => continue,
+                    Some(_) => {},
+                }
+                let normal = self.vertices[index].normal();
+                let project = |vector: glam::Vec3| (vector - normal * normal.dot(vector)).try_normalize();
+                // weight by the angle of the triangle at this corner, in the tangent plane of the vertex
+                let angle = match (project(positions[(corner + 1) % 3] - positions[corner]), project(positions[(corner + 2) % 3] - positions[corner])) {
+                    (Some(to_next), Some(to_prev)) => to_next.dot(to_prev).clamp(-1.0, 1.0).acos(),
+                    _ => 0.0,
+                };
+                if let Some(tangent) = project(frame.tangent) {
+                    tangents[index] += tangent * angle;
+                }
+            }
+        }
+
+        self.vertices.iter().zip(tangents.into_iter().zip(handedness)).map(|(vertex, (tangent, handedness))| {
+            let normal = vertex.normal();
+            let tangent = (tangent - normal * normal.dot(tangent)).try_normalize()
+                .unwrap_or_else(|| normal.any_orthonormal_vector());
+            tangent.extend(handedness.unwrap_or(1.0))
+        }).collect()
+    }
+}
+
+impl<T: ToVulkanIntSize> Mesh<StaticVertex, T> {
+    /// Compute the tangents of the mesh and store them in the vertices, for normal mapping.
+    /// The vertices shared by triangles of opposite handedness are split first, so the mesh can get more vertices.
+    pub fn generate_tangents(&mut self) {
+        self.split_mirrored_vertices();
+        let tangents = self.compute_tangents();
+        for (vertex, tangent) in self.vertices.iter_mut().zip(tangents.into_iter()) {
+            vertex.set_tangent(tangent);
+        }
+    }
+
+    /// Compute the tangents of the mesh if some vertices do not have one.
+    pub fn generate_missing_tangents(&mut self) {
+        if self.vertices.iter().any(|vertex| !vertex.has_tangent()) {
+            self.generate_tangents();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::mesh::{Mesh, vertex::{GeometryVertex, StaticVertex}};
+
+    fn quad() -> Mesh<StaticVertex, u32> {
+        Mesh::new(
+            vec![
+                StaticVertex::new(-0.5, 0., -0.5, 0., 1., 0., 0., 0.),
+                StaticVertex::new(0.5, 0., -0.5, 0., 1., 0., 1., 0.),
+                StaticVertex::new(0.5, 0., 0.5, 0., 1., 0., 1., 1.),
+                StaticVertex::new(-0.5, 0., 0.5, 0., 1., 0., 0., 1.),
+            ],
+            vec![0, 1, 2, 2, 3, 0],
+        )
+    }
+
+    #[test]
+    fn quad_tangents_follow_the_u_axis() {
+        let tangents = quad().compute_tangents();
+        assert_eq!(tangents.len(), 4);
+        for tangent in tangents {
+            // u grows along x, v along z: with a y up normal, the frame is left handed.
+            assert!(tangent.truncate().abs_diff_eq(glam::Vec3::X, 1e-5), "tangent {tangent:?}");
+            assert_eq!(tangent.w, -1.0);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_handedness() {
+        let mut mesh = quad();
+        for vertex in mesh.vertices.iter_mut() {
+            let position = vertex.position();
+            // v grows against z.
+            *vertex = StaticVertex::new(position.x, position.y, position.z, 0., 1., 0., position.x + 0.5, 0.5 - position.z);
+        }
+        for tangent in mesh.compute_tangents() {
+            assert!(tangent.truncate().abs_diff_eq(glam::Vec3::X, 1e-5), "tangent {tangent:?}");
+            assert_eq!(tangent.w, 1.0);
+        }
+    }
+
+    #[test]
+    fn generated_tangents_are_stored_in_the_vertices() {
+        let mut mesh = quad();
+        assert!(mesh.vertices.iter().all(|vertex| !vertex.has_tangent()));
+        mesh.generate_tangents();
+        // the uvs are not mirrored, no vertex is split.
+        assert_eq!(mesh.vertices.len(), 4);
+        for vertex in mesh.vertices.iter() {
+            assert!(vertex.has_tangent());
+            assert!(vertex.tangent().truncate().abs_diff_eq(glam::Vec3::X, 1e-5), "tangent {:?}", vertex.tangent());
+        }
+    }
+
+    #[test]
+    fn missing_tangents_keep_the_existing_ones() {
+        let mut mesh = quad();
+        for vertex in mesh.vertices.iter_mut() {
+            vertex.set_tangent(glam::Vec4::new(0., 0., 1., 1.));
+        }
+        mesh.generate_missing_tangents();
+        assert!(mesh.vertices.iter().all(|vertex| vertex.tangent() == glam::Vec4::new(0., 0., 1., 1.)));
+
+        mesh.vertices[0].set_tangent(glam::Vec4::ZERO);
+        mesh.generate_missing_tangents();
+        assert!(mesh.vertices.iter().all(|vertex| vertex.tangent().truncate().abs_diff_eq(glam::Vec3::X, 1e-5)));
+    }
+
+    /// Two quads side by side along x, sharing the edge at x = 0, with the uvs of the right one mirrored on u.
+    fn mirrored_quads() -> Mesh<StaticVertex, u32> {
+        Mesh::new(
+            vec![
+                StaticVertex::new(-1., 0., -1., 0., 1., 0., 0., 0.),
+                StaticVertex::new(0., 0., -1., 0., 1., 0., 1., 0.),
+                StaticVertex::new(0., 0., 1., 0., 1., 0., 1., 1.),
+                StaticVertex::new(-1., 0., 1., 0., 1., 0., 0., 1.),
+                StaticVertex::new(1., 0., -1., 0., 1., 0., 0., 0.),
+                StaticVertex::new(1., 0., 1., 0., 1., 0., 0., 1.),
+            ],
+            vec![0, 1, 2, 2, 3, 0, 1, 4, 5, 5, 2, 1],
+        )
+    }
+
+    #[test]
+    fn mirrored_seams_are_split() {
+        let mut mesh = mirrored_quads();
+        assert_eq!(mesh.split_mirrored_vertices(), 2);
+        assert_eq!(mesh.vertices.len(), 8);
+        // the left quad keeps the shared vertices, the right one uses their duplicates.
+        assert_eq!(&mesh.triangles[..6], &[0, 1, 2, 2, 3, 0]);
+        assert_eq!(&mesh.triangles[6..], &[6, 4, 5, 5, 7, 6]);
+        assert_eq!(mesh.vertices[6].position(), mesh.vertices[1].position());
+        assert_eq!(mesh.vertices[7].position(), mesh.vertices[2].position());
+        // splitting again changes nothing.
+        assert_eq!(mesh.split_mirrored_vertices(), 0);
+    }
+
+    #[test]
+    fn mirrored_seams_get_a_tangent_frame_per_side() {
+        let mut mesh = mirrored_quads();
+        mesh.generate_tangents();
+        for (triangle, (expected_tangent, expected_handedness)) in mesh.triangles.chunks_exact(3).zip([
+            (glam::Vec3::X, -1.0),
+            (glam::Vec3::X, -1.0),
+            (glam::Vec3::NEG_X, 1.0),
+            (glam::Vec3::NEG_X, 1.0),
+        ]) {
+            for index in triangle {
+                let tangent = mesh.vertices[*index as usize].tangent();
+                assert!(tangent.truncate().abs_diff_eq(expected_tangent, 1e-5), "tangent {tangent:?}");
+                assert_eq!(tangent.w, expected_handedness);
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_are_projected_on_the_vertex_normals() {
+        let mut mesh = quad();
+        let normal = glam::vec3(0.3, 1., 0.).normalize();
+        for vertex in mesh.vertices.iter_mut() {
+            vertex.set_normal(normal);
+        }
+        for tangent in mesh.compute_tangents() {
+            assert!((tangent.truncate().length() - 1.0).abs() < 1e-5);
+            assert!(tangent.truncate().dot(normal).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn degenerate_uvs_give_an_orthogonal_tangent() {
+        let mut mesh = quad();
+        for vertex in mesh.vertices.iter_mut() {
+            let position = vertex.position();
+            *vertex = StaticVertex::new(position.x, position.y, position.z, 0., 1., 0., 0., 0.);
+        }
+        for tangent in mesh.compute_tangents() {
+            assert!((tangent.truncate().length() - 1.0).abs() < 1e-5);
+            assert!(tangent.truncate().dot(glam::Vec3::Y).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn generate_missing_tangents_fills_the_vertices() {
+        let mut mesh = quad();
+        assert!(mesh.vertices.iter().all(|vertex| !vertex.has_tangent()));
+        mesh.generate_missing_tangents();
+        assert!(mesh.vertices.iter().all(|vertex| vertex.has_tangent()));
+    }
+}
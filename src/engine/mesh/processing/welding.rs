@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use crate::engine::mesh::{
+    Mesh,
+    ToVulkanIntSize,
+    vertex::{VulkanVertex, GeometryVertex},
+};
+
+use super::face_normal;
+
+
+impl<V: VulkanVertex + GeometryVertex, T: ToVulkanIntSize> Mesh<V, T> {
+    /// Merge vertices that are closer than the given tolerance.
+    /// Vertices are merged only if their positions, normals and uvs are all within the tolerance,
+    /// so hard edges and uv seams are preserved. The first vertex of each group is kept.
+    /// Returns the number of vertices that were removed.
+    pub fn weld_vertices(&mut self, tolerance: f32) -> usize {
+        let tolerance = tolerance.max(0.0);
+        // spatial hashing of the positions, with cells the size of the tolerance.
+        // Any vertex to merge with is in the same cell or in one of the neighbouring ones.
+        let cell_size = tolerance.max(f32::EPSILON);
+        let cell_of = |position: glam::Vec3| -> (i64, i64, i64) {
+            let cell = (position / cell_size).floor();
+            (cell.x as i64, cell.y as i64, cell.z as i64)
+        };
+        let is_close = |a: &V, b: &V| {
+            a.position().distance(b.position()) <= tolerance
+                && a.normal().distance(b.normal()) <= tolerance
+                && a.uv().distance(b.uv()) <= tolerance
+        };
+
+        let mut cells: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        let mut vertices: Vec<V> = Vec::with_capacity(self.vertices.len());
+        let mut remap = Vec::with_capacity(self.vertices.len());
+
+        for vertex in self.vertices.iter() {
+            let (x, y, z) = cell_of(vertex.position());
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        if let Some(candidates) = cells.get(&(x + dx, y + dy, z + dz)) {
+                            if let Some(welded) = candidates.iter().find(|i| is_close(&vertices[**i], vertex)) {
+                                found = Some(*welded);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+            match found {
+                Some(index) => remap.push(index),
+                None => {
+                    cells.entry((x, y, z)).or_insert_with(Vec::new).push(vertices.len());
+                    remap.push(vertices.len());
+                    vertices.push(vertex.clone());
+                }
+            }
+        }
+
+        let removed = self.vertices.len() - vertices.len();
+        for index in self.triangles.iter_mut() {
+            *index = T::from_index(remap[index.to_index()]);
+        }
+        self.vertices = vertices;
+        removed
+    }
+
+    /// Remove triangles that have no area: triangles that use the same vertex twice,
+    /// or whose vertices are aligned.
+    /// Vertices are left untouched, even if they are not used anymore.
+    /// Returns the number of triangles that were removed.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let triangle_count = self.triangles.len() / 3;
+        let vertices = &self.vertices;
+        let triangles: Vec<T> = self.triangles.chunks_exact(3).filter(|triangle| {
+            let (a, b, c) = (triangle[0].to_index(), triangle[1].to_index(), triangle[2].to_index());
+            if a == b || b == c || c == a {
+                return false;
+            }
+            let normal = face_normal(vertices[a].position(), vertices[b].position(), vertices[c].position());
+            normal.length_squared() > f32::EPSILON * f32::EPSILON
+        }).flatten().copied().collect();
+        let removed = triangle_count - triangles.len() / 3;
+        self.triangles = triangles;
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::mesh::{Mesh, vertex::{GeometryVertex, StaticVertex}};
+
+    /// A quad where each triangle has its own vertices, as exported by most tools.
+    fn unwelded_quad() -> Mesh<StaticVertex, u32> {
+        let corner = |x: f32, z: f32| StaticVertex::new(x, 0., z, 0., 1., 0., x, z);
+        Mesh::new(
+            vec![
+                corner(0., 0.), corner(1., 0.), corner(1., 1.),
+                corner(1., 1.), corner(0., 1.), corner(0., 0.),
+            ],
+            vec![0, 1, 2, 3, 4, 5],
+        )
+    }
+
+    #[test]
+    fn duplicated_vertices_are_merged() {
+        let mut mesh = unwelded_quad();
+        assert_eq!(mesh.weld_vertices(1e-4), 2);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangles, vec![0, 1, 2, 2, 3, 0]);
+    }
+
+    #[test]
+    fn uv_seams_are_kept() {
+        let mut mesh = unwelded_quad();
+        let last = mesh.vertices[5].position();
+        mesh.vertices[5] = StaticVertex::new(last.x, last.y, last.z, 0., 1., 0., 0.5, 0.5);
+        assert_eq!(mesh.weld_vertices(1e-4), 1);
+        assert_eq!(mesh.vertices.len(), 5);
+    }
+
+    #[test]
+    fn degenerate_triangles_are_removed() {
+        let mut mesh = unwelded_quad();
+        // a triangle using a vertex twice, and a triangle with aligned vertices.
+        mesh.vertices.push(StaticVertex::new(2., 0., 0., 0., 1., 0., 0., 0.));
+        mesh.triangles.extend_from_slice(&[0, 0, 1, 0, 1, 6]);
+        assert_eq!(mesh.remove_degenerate_triangles(), 2);
+        assert_eq!(mesh.triangles, vec![0, 1, 2, 3, 4, 5]);
+    }
+}
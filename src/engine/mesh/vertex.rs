@@ -1,10 +1,174 @@
 pub(crate) mod static_vertex;
 pub(crate) mod skeletal_vertex;
+pub(crate) mod line_vertex;
+pub(crate) mod point_vertex;
+pub(crate) mod color_vertex;
 
-pub(crate) use self::static_vertex::StaticVertex;
+pub use self::static_vertex::StaticVertex;
+pub use self::skeletal_vertex::SkeletalVertex;
+pub use self::line_vertex::LineVertex;
+pub use self::point_vertex::PointVertex;
+pub use self::color_vertex::ColorVertex;
 
 
+/// Vertex layout that can be sent to the gpu.
+/// This can be implemented with `#[derive(VulkanVertex)]` on `#[repr(C)]` structs.
 pub trait VulkanVertex {
     fn binding_description() -> vulkanalia::vk::VertexInputBindingDescription;
     fn attribute_description() -> Vec<vulkanalia::vk::VertexInputAttributeDescription>;
-}
\ No newline at end of file
+    /// Position of the vertex, if the layout have one. Used to compute the bounds of the meshes.
+    fn vertex_position(&self) -> Option<glam::Vec3> {
+        None
+    }
+}
+
+/// Type of a vertex field, that can be read by the vertex shaders.
+/// `#[derive(VulkanVertex)]` takes the format of each field from this trait,
+/// so type aliases and qualified paths are resolved by the compiler.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no known vertex attribute format",
+    note = "the format of the field can be given with `#[vertex(format = \"...\")]`"
+)]
+pub trait VertexAttribute {
+    const FORMAT: vulkanalia::vk::Format;
+    /// This attribute as a vertex position, for the types that can be one.
+    fn as_position(&self) -> Option<glam::Vec3> {
+        None
+    }
+}
+
+macro_rules! vertex_attributes {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexAttribute for $ty {
+                const FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::$format;
+            }
+        )*
+    };
+}
+
+vertex_attributes!(
+    f32 => R32_SFLOAT,
+    u32 => R32_UINT,
+    i32 => R32_SINT,
+    [f32; 1] => R32_SFLOAT,
+    [f32; 2] => R32G32_SFLOAT,
+    [f32; 3] => R32G32B32_SFLOAT,
+    [f32; 4] => R32G32B32A32_SFLOAT,
+    [u32; 1] => R32_UINT,
+    [u32; 2] => R32G32_UINT,
+    [u32; 3] => R32G32B32_UINT,
+    [u32; 4] => R32G32B32A32_UINT,
+    [i32; 1] => R32_SINT,
+    [i32; 2] => R32G32_SINT,
+    [i32; 3] => R32G32B32_SINT,
+    [i32; 4] => R32G32B32A32_SINT,
+    [u8; 4] => R8G8B8A8_UNORM,
+    [i8; 4] => R8G8B8A8_SNORM,
+    glam::Vec2 => R32G32_SFLOAT,
+    glam::Vec3A => R32G32B32_SFLOAT,
+    glam::Vec4 => R32G32B32A32_SFLOAT,
+    glam::Quat => R32G32B32A32_SFLOAT,
+    glam::UVec2 => R32G32_UINT,
+    glam::UVec3 => R32G32B32_UINT,
+    glam::UVec4 => R32G32B32A32_UINT,
+    glam::IVec2 => R32G32_SINT,
+    glam::IVec3 => R32G32B32_SINT,
+    glam::IVec4 => R32G32B32A32_SINT,
+);
+
+impl VertexAttribute for glam::Vec3 {
+    const FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::R32G32B32_SFLOAT;
+    fn as_position(&self) -> Option<glam::Vec3> {
+        Some(*self)
+    }
+}
+
+/// Access to the geometric attributes of a vertex.
+/// This is what the mesh processing utilities rely on, whatever the actual vertex layout is.
+pub trait GeometryVertex: Clone {
+    fn position(&self) -> glam::Vec3;
+    fn normal(&self) -> glam::Vec3;
+    fn uv(&self) -> glam::Vec2;
+    fn set_normal(&mut self, normal: glam::Vec3);
+}
+
+#[cfg(test)]
+mod tests {
+    use vulkanalia::vk::HasBuilder;
+    use propellant_derive::VulkanVertex;
+
+    use super::{VulkanVertex, StaticVertex, SkeletalVertex};
+
+    /// Attribute descriptions as (location, binding, format, offset), to compare them.
+    fn layout<V: VulkanVertex>() -> Vec<(u32, u32, vulkanalia::vk::Format, u32)> {
+        V::attribute_description().iter().map(|a| (a.location, a.binding, a.format, a.offset)).collect()
+    }
+
+    /// The attribute descriptions, as they were written by hand before the derive.
+    fn hand_written(formats: &[(vulkanalia::vk::Format, usize)]) -> Vec<(u32, u32, vulkanalia::vk::Format, u32)> {
+        let mut offset = 0;
+        formats.iter().enumerate().map(|(location, (format, size))| {
+            let attribute = vulkanalia::vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(location as u32)
+                .format(*format)
+                .offset(offset as u32)
+                .build();
+            offset += size;
+            (attribute.location, attribute.binding, attribute.format, attribute.offset)
+        }).collect()
+    }
+
+    #[test]
+    fn static_vertex_layout() {
+        let expected = hand_written(&[
+            (vulkanalia::vk::Format::R32G32B32_SFLOAT, std::mem::size_of::<glam::Vec3>()),
+            (vulkanalia::vk::Format::R32G32B32_SFLOAT, std::mem::size_of::<glam::Vec3>()),
+            (vulkanalia::vk::Format::R32G32_SFLOAT, std::mem::size_of::<glam::Vec2>()),
+            (vulkanalia::vk::Format::R32G32B32A32_SFLOAT, std::mem::size_of::<glam::Vec4>()),
+        ]);
+        assert_eq!(layout::<StaticVertex>(), expected);
+        assert_eq!(StaticVertex::binding_description().stride as usize, std::mem::size_of::<StaticVertex>());
+        let vertex = StaticVertex::new(1., 2., 3., 0., 1., 0., 0., 0.);
+        assert_eq!(vertex.vertex_position(), Some(glam::vec3(1., 2., 3.)));
+    }
+
+    #[test]
+    fn skeletal_vertex_layout() {
+        let expected = hand_written(&[
+            (vulkanalia::vk::Format::R32G32B32_SFLOAT, std::mem::size_of::<glam::Vec3>()),
+            (vulkanalia::vk::Format::R32G32B32_SFLOAT, std::mem::size_of::<glam::Vec3>()),
+            (vulkanalia::vk::Format::R32G32_SFLOAT, std::mem::size_of::<glam::Vec2>()),
+            (vulkanalia::vk::Format::R32G32B32A32_UINT, std::mem::size_of::<[u32; 4]>()),
+            (vulkanalia::vk::Format::R32G32B32A32_SFLOAT, std::mem::size_of::<[f32; 4]>()),
+        ]);
+        assert_eq!(layout::<SkeletalVertex>(), expected);
+        assert_eq!(SkeletalVertex::binding_description().stride as usize, std::mem::size_of::<SkeletalVertex>());
+    }
+
+    type Position = glam::Vec3;
+
+    #[repr(C)]
+    #[derive(VulkanVertex)]
+    struct AliasedVertex {
+        color: glam::f32::Vec4,
+        position: Position,
+        #[vertex(format = "A2B10G10R10_SNORM_PACK32")]
+        normal: u32,
+    }
+
+    #[test]
+    fn aliases_and_qualified_paths_are_resolved() {
+        let expected = hand_written(&[
+            (vulkanalia::vk::Format::R32G32B32A32_SFLOAT, std::mem::size_of::<glam::Vec4>()),
+            (vulkanalia::vk::Format::R32G32B32_SFLOAT, std::mem::size_of::<glam::Vec3>()),
+            (vulkanalia::vk::Format::A2B10G10R10_SNORM_PACK32, std::mem::size_of::<u32>()),
+        ]);
+        assert_eq!(layout::<AliasedVertex>(), expected);
+        let vertex = AliasedVertex { color: glam::Vec4::ONE, position: glam::Vec3::X, normal: 0 };
+        assert_eq!(vertex.vertex_position(), Some(glam::Vec3::X));
+        assert_eq!(vertex.color, glam::Vec4::ONE);
+        assert_eq!(vertex.normal, 0);
+    }
+}
@@ -0,0 +1,27 @@
+use propellant_derive::VulkanVertex;
+
+/// Vertex of colored triangle meshes: a position and a color, without normals or uvs.
+/// Meshes of these vertices are drawn unlit, with the vertex color pipeline.
+#[repr(C)]
+#[derive(Debug, Clone, VulkanVertex)]
+pub struct ColorVertex {
+    position: glam::Vec3,
+    color: glam::Vec4,
+}
+
+impl ColorVertex {
+    pub fn new(position: glam::Vec3, color: glam::Vec4) -> ColorVertex {
+        ColorVertex {
+            position,
+            color,
+        }
+    }
+
+    pub fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    pub fn color(&self) -> glam::Vec4 {
+        self.color
+    }
+}
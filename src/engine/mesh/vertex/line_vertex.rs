@@ -0,0 +1,26 @@
+use propellant_derive::VulkanVertex;
+
+/// Vertex of line meshes: a position and a color, without normals.
+#[repr(C)]
+#[derive(Debug, Clone, VulkanVertex)]
+pub struct LineVertex {
+    position: glam::Vec3,
+    color: glam::Vec4,
+}
+
+impl LineVertex {
+    pub fn new(position: glam::Vec3, color: glam::Vec4) -> LineVertex {
+        LineVertex {
+            position,
+            color,
+        }
+    }
+
+    pub fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    pub fn color(&self) -> glam::Vec4 {
+        self.color
+    }
+}
@@ -0,0 +1,32 @@
+use propellant_derive::VulkanVertex;
+
+/// Vertex of point meshes: a position, a color and the size of the point on screen, in pixels.
+#[repr(C)]
+#[derive(Debug, Clone, VulkanVertex)]
+pub struct PointVertex {
+    position: glam::Vec3,
+    color: glam::Vec4,
+    size: f32,
+}
+
+impl PointVertex {
+    pub fn new(position: glam::Vec3, color: glam::Vec4, size: f32) -> PointVertex {
+        PointVertex {
+            position,
+            color,
+            size,
+        }
+    }
+
+    pub fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    pub fn color(&self) -> glam::Vec4 {
+        self.color
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+}
@@ -1,10 +1,53 @@
+use propellant_derive::VulkanVertex;
+
+use super::GeometryVertex;
 
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, VulkanVertex)]
 pub struct SkeletalVertex {
     position: glam::Vec3,
     normal: glam::Vec3,
     uv: glam::Vec2,
     joint_ids: [u32; 4],
     joint_weights: [f32; 4],
-}
\ No newline at end of file
+}
+
+impl SkeletalVertex {
+    /// Creates a new skeletal vertex, influenced by up to four joints of the skeleton.
+    /// The joint weights should add up to one. Unused influences can have a weight of zero.
+    pub fn new(position: glam::Vec3, normal: glam::Vec3, uv: glam::Vec2, joint_ids: [u32; 4], joint_weights: [f32; 4]) -> SkeletalVertex {
+        SkeletalVertex {
+            position,
+            normal,
+            uv,
+            joint_ids,
+            joint_weights,
+        }
+    }
+
+    pub fn joint_ids(&self) -> [u32; 4] {
+        self.joint_ids
+    }
+
+    pub fn joint_weights(&self) -> [f32; 4] {
+        self.joint_weights
+    }
+}
+
+impl GeometryVertex for SkeletalVertex {
+    fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    fn normal(&self) -> glam::Vec3 {
+        self.normal
+    }
+
+    fn uv(&self) -> glam::Vec2 {
+        self.uv
+    }
+
+    fn set_normal(&mut self, normal: glam::Vec3) {
+        self.normal = normal;
+    }
+}
@@ -1,13 +1,16 @@
-use vulkanalia::vk::HasBuilder;
+use propellant_derive::VulkanVertex;
 
-use super::VulkanVertex;
+use super::GeometryVertex;
 
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, VulkanVertex)]
 pub struct StaticVertex {
     position: glam::Vec3,
     normal: glam::Vec3,
     uv: glam::Vec2,
+    /// tangent of the vertex, with the handedness of the tangent frame in w.
+    /// A zero tangent means it has not been computed yet.
+    tangent: glam::Vec4,
 }
 
 impl StaticVertex {
@@ -16,42 +19,38 @@ impl StaticVertex {
             position: glam::Vec3::new(p0, p1, p2),
             normal: glam::Vec3::new(n0, n1, n2),
             uv: glam::Vec2::new(u, v),
+            tangent: glam::Vec4::ZERO,
         }
     }
+
+    pub fn tangent(&self) -> glam::Vec4 {
+        self.tangent
+    }
+
+    pub fn set_tangent(&mut self, tangent: glam::Vec4) {
+        self.tangent = tangent;
+    }
+
+    /// Whether the tangent of this vertex have been set. Valid tangents have a handedness of 1 or -1.
+    pub fn has_tangent(&self) -> bool {
+        self.tangent.w != 0.0
+    }
 }
 
+impl GeometryVertex for StaticVertex {
+    fn position(&self) -> glam::Vec3 {
+        self.position
+    }
 
-impl VulkanVertex for StaticVertex {
-    /// Tells to vulkan how to pass this data to the vertex shader.
-    fn binding_description() -> vulkanalia::vk::VertexInputBindingDescription {
-        vulkanalia::vk::VertexInputBindingDescription::builder()
-            .binding(0)
-            .stride(std::mem::size_of::<StaticVertex>() as u32)
-            .input_rate(vulkanalia::vk::VertexInputRate::VERTEX)
-            .build()
-    }
-
-    /// Tells the attribute descriptions to vulkan.
-    /// There are three of them, as for now: position, normal and uv.
-    fn attribute_description() -> Vec<vulkanalia::vk::VertexInputAttributeDescription> {
-        let pos = vulkanalia::vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(0)
-            .format(vulkanalia::vk::Format::R32G32B32_SFLOAT)
-            .offset(0)
-            .build();
-        let norm = vulkanalia::vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(1)
-            .format(vulkanalia::vk::Format::R32G32B32_SFLOAT)
-            .offset(std::mem::size_of::<glam::Vec3>() as u32)
-            .build();
-        let uvs = vulkanalia::vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(2)
-            .format(vulkanalia::vk::Format::R32G32_SFLOAT)
-            .offset(2 * std::mem::size_of::<glam::Vec3>() as u32)
-            .build();
-        vec![pos, norm, uvs]
-    }
-}
\ No newline at end of file
+    fn normal(&self) -> glam::Vec3 {
+        self.normal
+    }
+
+    fn uv(&self) -> glam::Vec2 {
+        self.uv
+    }
+
+    fn set_normal(&mut self, normal: glam::Vec3) {
+        self.normal = normal;
+    }
+}
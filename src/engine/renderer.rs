@@ -2,11 +2,13 @@ use std::collections::VecDeque;
 
 use foundry::ComponentTable;
 
+use crate::ClusteredLights;
 use crate::PropellantResources;
 use crate::VulkanInterface;
 use self::rendering_pipeline::RenderingPipeline;
 use self::rendering_pipeline::rendering_pipeline_builder::RenderingPipelineBuilder;
 use self::rendering_pipeline::rendering_pipeline_builder::states::RPBSReady;
+use self::screenshot::{ScreenshotCapture, ScreenshotRequest};
 use super::consts::PROPELLANT_DEBUG_FEATURES;
 use super::errors::PResult;
 use super::errors::PropellantError;
@@ -17,12 +19,16 @@ use vulkanalia::vk::HasBuilder;
 use vulkanalia::vk::KhrSwapchainExtension;
 use vulkanalia::vk::DeviceV1_0;
 
+pub(crate) mod offscreen_renderer;
+pub(crate) mod golden_images;
 pub(crate) mod rendering_pipeline;
 pub(crate) mod graphic_pipeline;
+pub(crate) mod compute_pipeline;
 #[allow(unused)]
 pub(crate) mod shaders;
 pub(crate) mod renderer_builder;
 pub(crate) mod rendering_map;
+pub(crate) mod screenshot;
 
 pub trait VulkanRenderer {
     /// Render the scene using the vulkan interface and the components.
@@ -43,6 +49,8 @@ pub trait VulkanRenderer {
     fn request_command_buffer_rebuild(&mut self);
     /// The engine is sending a flag to reload the textures.
     fn request_textures_reload(&mut self);
+    /// Capture the next presented frame, and resolve the request with it.
+    fn request_screenshot(&mut self, request: ScreenshotRequest);
     /// Destroy the current rendering pipeline.
     fn recreation_cleanup(&mut self, vk_device: &vulkanalia::Device);
     /// Clean up of all the vulkan resources.
@@ -124,6 +132,10 @@ impl SyncingState {
 pub struct DefaultVulkanRenderer {
     rendering_pipeline: RenderingPipeline,
     syncing_state: SyncingState,
+    /// Screenshots to take on the next frame.
+    screenshot_requests: Vec<ScreenshotRequest>,
+    /// Screenshots copied with a frame that may still be in flight.
+    pending_screenshots: Vec<ScreenshotCapture>,
 }
 
 impl DefaultVulkanRenderer {
@@ -139,6 +151,8 @@ impl DefaultVulkanRenderer {
         Ok(DefaultVulkanRenderer {
             rendering_pipeline: pipeline_lib,
             syncing_state: SyncingState::new(),
+            screenshot_requests: Vec::new(),
+            pending_screenshots: Vec::new(),
         })
     }
 
@@ -199,7 +213,9 @@ impl DefaultVulkanRenderer {
         vk_device: &vulkanalia::Device,
         image_index: usize,
         components: &mut ComponentTable,
-    ) -> PResult<()> {
+    ) -> PResult<bool> {
+        // the local lights are sorted once for all the lit pipelines.
+        ClusteredLights::update_singleton(components);
         self.rendering_pipeline.update_uniform_buffers(
             vk_device,
             image_index,
@@ -242,6 +258,8 @@ impl VulkanRenderer for DefaultVulkanRenderer {
             // if we have less than MAX_FRAMES_IN_FLIGHT frames in flight, this will do nothing.
             // otherwise, this will wait for the oldest frame to finish.
             self.rendering_pipeline.rendering_sync_mut().wait_for_frame_flight_fence(&vk_interface.device)?;
+            // the fence we just waited for is reset before the submit: the captures of its frame are finished before that.
+            self.pending_screenshots.retain_mut(|capture| !capture.try_finish(&vk_interface.device));
             // get the image index
             let image_index = vk_interface.device
                 .acquire_next_image_khr(
@@ -255,24 +273,88 @@ impl VulkanRenderer for DefaultVulkanRenderer {
                 // wait for any in flight image
             self.rendering_pipeline.rendering_sync_mut().wait_for_in_flight_image(image_index, &vk_interface.device)?;
 
+            // the image is not in use anymore, write the dynamic meshes in its buffers and free the released resources
+            let (dynamic_meshes_changed, resources_released, environment_changed) = match components.get_singleton_mut::<PropellantResources>() {
+                Some(resources) => (
+                    resources.meshes_mut().sync_dynamic_meshes(
+                        image_index,
+                        &vk_interface.instance,
+                        &vk_interface.device,
+                        vk_interface.physical_device,
+                    )?,
+                    resources.sync_released_resources(
+                        image_index,
+                        self.rendering_pipeline.swapchain_image_count(),
+                        &vk_interface.device,
+                    ),
+                    resources.textures_mut().take_environment_changed(),
+                ),
+                None => (false, false, false),
+            };
+
+            // released resources might still be referenced by the rendering maps, the draw commands and the environment descriptors
+            if resources_released {
+                self.request_scene_rebuild();
+                self.request_textures_reload();
+            }
+            // the environment descriptors still point to the previous environment
+            if environment_changed {
+                self.request_textures_reload();
+            }
+
             // look for flags
             self.check_flag_handling(vk_interface, components, image_index)?;
 
+            // dynamic meshes buffers or index counts changed, the draw commands of this image are outdated
+            if dynamic_meshes_changed {
+                if let Some(resources) = components.get_singleton::<PropellantResources>() {
+                    self.rendering_pipeline.register_draw_commands(&vk_interface.device, resources, image_index)?;
+                }
+            }
+
             // look for memory transfer flags
             vk_interface.check_and_process_memory_transfers()?;
 
             // update uniform buffer
-            self.update_uniform_buffer(&vk_interface.device, image_index, components)?;
+            let draw_commands_outdated = self.update_uniform_buffer(&vk_interface.device, image_index, components)?;
+
+            // sorted instances changed their draw order, the draw commands of this image are outdated
+            if draw_commands_outdated {
+                if let Some(resources) = components.get_singleton::<PropellantResources>() {
+                    self.rendering_pipeline.register_draw_commands(&vk_interface.device, resources, image_index)?;
+                }
+            }
+
+            // objects may have changed their level of detail
+            if self.rendering_pipeline.requires_scene_rebuild() {
+                self.request_scene_rebuild();
+            }
+
+            // copy the rendered image after the draw commands, if a screenshot was requested
+            let screenshot = match self.screenshot_requests.is_empty() {
+                true => None,
+                false => ScreenshotCapture::record(
+                    vk_interface,
+                    self.rendering_pipeline.swapchain(),
+                    image_index,
+                    self.rendering_pipeline.rendering_sync().frame_in_flight_fence(),
+                    self.rendering_pipeline.paper_white(components),
+                    std::mem::take(&mut self.screenshot_requests),
+                ),
+            };
 
             // create the draw command
             let wait_semaphores = &[self.rendering_pipeline.rendering_sync().image_available_semaphore(),];
             let wait_stages = &[vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-            let command_buffers = &[self.rendering_pipeline.command_manager().command_buffer(image_index)];
+            let mut command_buffers = vec![self.rendering_pipeline.command_manager().command_buffer(image_index)];
+            if let Some(capture) = &screenshot {
+                command_buffers.push(capture.command_buffer());
+            }
             let signal_semaphores = &[self.rendering_pipeline.rendering_sync().render_finished_semaphore()];
             let submit_info = vulkanalia::vk::SubmitInfo::builder()
                 .wait_semaphores(wait_semaphores)
                 .wait_dst_stage_mask(wait_stages)
-                .command_buffers(command_buffers)
+                .command_buffers(&command_buffers)
                 .signal_semaphores(signal_semaphores);
             
             // reset the fence for this frame
@@ -293,7 +375,13 @@ impl VulkanRenderer for DefaultVulkanRenderer {
                 .swapchains(swapchains)
                 .image_indices(image_indices);
             
-            let result = vk_interface.device.queue_present_khr(vk_interface.queue, &present_info)?;
+            let result = vk_interface.device.queue_present_khr(vk_interface.queue, &present_info);
+
+            // the copy is done along with the frame: the pixels are read on a later frame, once its fence is signaled.
+            if let Some(capture) = screenshot {
+                self.pending_screenshots.push(capture);
+            }
+            let result = result?;
             
             // adavance the frame
             self.rendering_pipeline.rendering_sync_mut().advance_frame();
@@ -335,11 +423,21 @@ impl VulkanRenderer for DefaultVulkanRenderer {
         self.syncing_state.add_flag(SyncingFlag::ReloadTextures, self.rendering_pipeline.swapchain_image_count())
     }
 
+    fn request_screenshot(&mut self, request: ScreenshotRequest) {
+        self.screenshot_requests.push(request);
+    }
+
     fn recreation_cleanup(&mut self, vk_device: &vulkanalia::Device) {
         self.rendering_pipeline.recreation_cleanup(vk_device);
     }
 
     fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        // the device is idle: the captures are either done, or their frame was never submitted.
+        for mut capture in self.pending_screenshots.drain(..) {
+            if !capture.try_finish(vk_device) {
+                capture.abort(vk_device);
+            }
+        }
         self.rendering_pipeline.destroy(vk_device);
     }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+use crate::engine::errors::PResult;
+
+use self::compute_pipeline_builder::ComputeShader;
+use super::shaders::FULLSCREEN_VERT;
+
+use vulkanalia::vk::DeviceV1_0;
+use vulkanalia::vk::Handle;
+use vulkanalia::vk::HasBuilder;
+
+pub(crate) mod compute_pipeline_builder;
+pub(crate) mod post_process_effects;
+
+/// Width and height of the workgroups of the compute shaders.
+pub(crate) const WORKGROUP_SIZE: u32 = 8;
+
+/// Post process pipeline, either a fullscreen draw or a compute dispatch.
+/// The descriptor sets are owned by the compute render pass, as they point to its render targets.
+/// The parameters are given when recording, so they can change at runtime.
+pub struct ComputePipeline {
+    shader: ComputeShader,
+    pipeline: vulkanalia::vk::Pipeline,
+    pipeline_layout: vulkanalia::vk::PipelineLayout,
+    descriptor_set_layout: vulkanalia::vk::DescriptorSetLayout,
+}
+
+impl ComputePipeline {
+    pub fn create(
+        vk_device: &vulkanalia::Device,
+        shader: ComputeShader,
+        secondary_input: bool,
+        extent: vulkanalia::vk::Extent2D,
+        render_pass: vulkanalia::vk::RenderPass,
+    ) -> PResult<ComputePipeline> {
+        let stage = match shader {
+            ComputeShader::Fullscreen(_) => vulkanalia::vk::ShaderStageFlags::FRAGMENT,
+            ComputeShader::Compute(_) => vulkanalia::vk::ShaderStageFlags::COMPUTE,
+        };
+
+        // binding 0 is the input image, compute shaders write the output image at binding 1.
+        // some effects read a second image, at binding 2.
+        let input_binding = vulkanalia::vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1)
+            .stage_flags(stage)
+            .build();
+        let output_binding = vulkanalia::vk::DescriptorSetLayoutBinding::builder()
+            .binding(1)
+            .descriptor_type(vulkanalia::vk::DescriptorType::STORAGE_IMAGE)
+            .descriptor_count(1)
+            .stage_flags(stage)
+            .build();
+        let secondary_input_binding = vulkanalia::vk::DescriptorSetLayoutBinding::builder()
+            .binding(2)
+            .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1)
+            .stage_flags(stage)
+            .build();
+        let mut bindings = match shader {
+            ComputeShader::Fullscreen(_) => vec![input_binding],
+            ComputeShader::Compute(_) => vec![input_binding, output_binding],
+        };
+        if secondary_input {
+            bindings.push(secondary_input_binding);
+        }
+        let info = vulkanalia::vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&bindings);
+        let descriptor_set_layout = unsafe { vk_device.create_descriptor_set_layout(&info, None)? };
+
+        let push_constant_range = vulkanalia::vk::PushConstantRange::builder()
+            .stage_flags(stage)
+            .offset(0)
+            .size(std::mem::size_of::<[f32; 12]>() as u32);
+        let set_layouts = &[descriptor_set_layout];
+        let push_constant_ranges = &[push_constant_range];
+        let layout_info = vulkanalia::vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(set_layouts)
+            .push_constant_ranges(push_constant_ranges);
+        let pipeline_layout = unsafe { vk_device.create_pipeline_layout(&layout_info, None)? };
+
+        let pipeline = match shader {
+            ComputeShader::Fullscreen(code) => Self::create_fullscreen_pipeline(vk_device, code, extent, pipeline_layout, render_pass)?,
+            ComputeShader::Compute(code) => Self::create_compute_pipeline(vk_device, code, pipeline_layout)?,
+        };
+
+        Ok(ComputePipeline {
+            shader,
+            pipeline,
+            pipeline_layout,
+            descriptor_set_layout,
+        })
+    }
+
+    fn create_fullscreen_pipeline(
+        vk_device: &vulkanalia::Device,
+        fragment_code: &[u32],
+        extent: vulkanalia::vk::Extent2D,
+        pipeline_layout: vulkanalia::vk::PipelineLayout,
+        render_pass: vulkanalia::vk::RenderPass,
+    ) -> PResult<vulkanalia::vk::Pipeline> {
+        let vertex_module = create_shader_module(FULLSCREEN_VERT, vk_device)?;
+        let fragment_module = create_shader_module(fragment_code, vk_device)?;
+        let stages = &[
+            vulkanalia::vk::PipelineShaderStageCreateInfo::builder()
+                .stage(vulkanalia::vk::ShaderStageFlags::VERTEX)
+                .module(vertex_module)
+                .name(b"main\0")
+                .build(),
+            vulkanalia::vk::PipelineShaderStageCreateInfo::builder()
+                .stage(vulkanalia::vk::ShaderStageFlags::FRAGMENT)
+                .module(fragment_module)
+                .name(b"main\0")
+                .build(),
+        ];
+
+        // the fullscreen triangle is generated from the vertex index, there are no vertex buffers.
+        let vertex_input_state = vulkanalia::vk::PipelineVertexInputStateCreateInfo::builder();
+        let input_assembly_state = vulkanalia::vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .topology(vulkanalia::vk::PrimitiveTopology::TRIANGLE_LIST)
+            .primitive_restart_enable(false);
+
+        let viewport = vulkanalia::vk::Viewport::builder()
+            .x(0.0)
+            .y(0.0)
+            .width(extent.width as f32)
+            .height(extent.height as f32)
+            .min_depth(0.0)
+            .max_depth(1.0);
+        let scissor = vulkanalia::vk::Rect2D::builder()
+            .offset(vulkanalia::vk::Offset2D { x: 0, y: 0 })
+            .extent(extent);
+        let viewports = &[viewport];
+        let scissors = &[scissor];
+        let viewport_state = vulkanalia::vk::PipelineViewportStateCreateInfo::builder()
+            .viewports(viewports)
+            .scissors(scissors);
+
+        let rasterization_state = vulkanalia::vk::PipelineRasterizationStateCreateInfo::builder()
+            .depth_clamp_enable(false)
+            .rasterizer_discard_enable(false)
+            .polygon_mode(vulkanalia::vk::PolygonMode::FILL)
+            .line_width(1.0)
+            .cull_mode(vulkanalia::vk::CullModeFlags::NONE)
+            .front_face(vulkanalia::vk::FrontFace::COUNTER_CLOCKWISE)
+            .depth_bias_enable(false);
+
+        let multisample_state = vulkanalia::vk::PipelineMultisampleStateCreateInfo::builder()
+            .sample_shading_enable(false)
+            .rasterization_samples(vulkanalia::vk::SampleCountFlags::_1);
+
+        // every pixel is overwritten, no blending.
+        let color_blend_attachment = vulkanalia::vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vulkanalia::vk::ColorComponentFlags::all())
+            .blend_enable(false);
+        let color_blend_attachments = &[color_blend_attachment];
+        let color_blend_state = vulkanalia::vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op_enable(false)
+            .logic_op(vulkanalia::vk::LogicOp::COPY)
+            .attachments(color_blend_attachments)
+            .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+        let info = vulkanalia::vk::GraphicsPipelineCreateInfo::builder()
+            .stages(stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .color_blend_state(&color_blend_state)
+            .layout(pipeline_layout)
+            .render_pass(render_pass)
+            .subpass(0)
+            .base_pipeline_handle(vulkanalia::vk::Pipeline::null())
+            .base_pipeline_index(-1);
+
+        let pipeline = unsafe { vk_device.create_graphics_pipelines(vulkanalia::vk::PipelineCache::null(), &[info], None) };
+        // the modules are not needed once the pipeline is created.
+        unsafe {
+            vk_device.destroy_shader_module(vertex_module, None);
+            vk_device.destroy_shader_module(fragment_module, None);
+        }
+
+        Ok(pipeline?.0)
+    }
+
+    fn create_compute_pipeline(
+        vk_device: &vulkanalia::Device,
+        compute_code: &[u32],
+        pipeline_layout: vulkanalia::vk::PipelineLayout,
+    ) -> PResult<vulkanalia::vk::Pipeline> {
+        let module = create_shader_module(compute_code, vk_device)?;
+        let stage = vulkanalia::vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vulkanalia::vk::ShaderStageFlags::COMPUTE)
+            .module(module)
+            .name(b"main\0");
+        let info = vulkanalia::vk::ComputePipelineCreateInfo::builder()
+            .stage(stage)
+            .layout(pipeline_layout)
+            .base_pipeline_handle(vulkanalia::vk::Pipeline::null())
+            .base_pipeline_index(-1);
+
+        let pipeline = unsafe { vk_device.create_compute_pipelines(vulkanalia::vk::PipelineCache::null(), &[info], None) };
+        unsafe { vk_device.destroy_shader_module(module, None) };
+
+        Ok(pipeline?.0)
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        matches!(self.shader, ComputeShader::Fullscreen(_))
+    }
+
+    pub fn descriptor_set_layout(&self) -> vulkanalia::vk::DescriptorSetLayout {
+        self.descriptor_set_layout
+    }
+
+    /// Record the draw or the dispatch over an output image of the given extent.
+    /// Fullscreen pipelines must be recorded inside their render pass, compute pipelines outside of any render pass.
+    pub fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        descriptor_set: vulkanalia::vk::DescriptorSet,
+        extent: vulkanalia::vk::Extent2D,
+        parameters: [glam::Vec4; 2],
+    ) {
+        let (bind_point, stage) = match self.shader {
+            ComputeShader::Fullscreen(_) => (vulkanalia::vk::PipelineBindPoint::GRAPHICS, vulkanalia::vk::ShaderStageFlags::FRAGMENT),
+            ComputeShader::Compute(_) => (vulkanalia::vk::PipelineBindPoint::COMPUTE, vulkanalia::vk::ShaderStageFlags::COMPUTE),
+        };
+
+        // texel size, padding to align the parameters on a vec4, and the parameters.
+        let push_constants = [
+            1.0 / extent.width.max(1) as f32, 1.0 / extent.height.max(1) as f32, 0.0, 0.0,
+        ].into_iter()
+            .chain(parameters.iter().flat_map(|parameter| parameter.to_array()))
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<u8>>();
+
+        unsafe {
+            vk_device.cmd_bind_pipeline(command_buffer, bind_point, self.pipeline);
+            vk_device.cmd_bind_descriptor_sets(command_buffer, bind_point, self.pipeline_layout, 0, &[descriptor_set], &[]);
+            vk_device.cmd_push_constants(command_buffer, self.pipeline_layout, stage, 0, &push_constants);
+            match self.shader {
+                ComputeShader::Fullscreen(_) => vk_device.cmd_draw(command_buffer, 3, 1, 0, 0),
+                ComputeShader::Compute(_) => vk_device.cmd_dispatch(
+                    command_buffer,
+                    extent.width.div_ceil(WORKGROUP_SIZE),
+                    extent.height.div_ceil(WORKGROUP_SIZE),
+                    1,
+                ),
+            }
+        }
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        unsafe {
+            vk_device.destroy_pipeline(self.pipeline, None);
+            vk_device.destroy_pipeline_layout(self.pipeline_layout, None);
+            vk_device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+fn create_shader_module(source_code: &[u32], vk_device: &vulkanalia::Device) -> PResult<vulkanalia::vk::ShaderModule> {
+    let info = vulkanalia::vk::ShaderModuleCreateInfo::builder()
+        .code_size(source_code.len() * 4)
+        .code(source_code); // x4 because we are using u32, and length is in byte
+
+    Ok(unsafe { vk_device.create_shader_module(&info, None)? })
+}
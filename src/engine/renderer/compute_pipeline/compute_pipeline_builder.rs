@@ -0,0 +1,212 @@
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::renderer::shaders::{COLOR_GRADING_COMP, FXAA_FRAG, TONEMAP_FRAG, VIGNETTE_FRAG};
+
+use super::ComputePipeline;
+use super::post_process_effects::{BloomSettings, PostProcessEffect, SsaoSettings, TonemapSettings};
+
+/// Shader of a post process pipeline.
+#[derive(Debug, Clone, Copy)]
+pub enum ComputeShader {
+    /// Fragment shader drawn over a fullscreen triangle, writing the output image as a color attachment.
+    /// It can write any format, including the swapchain.
+    Fullscreen(&'static [u32]),
+    /// Compute shader dispatched in 8x8 workgroups, writing the output image as a storage image at binding 1.
+    /// The output format must support storage, so it can not write the swapchain.
+    Compute(&'static [u32]),
+}
+
+#[derive(Debug, Clone)]
+enum ComputePipelineKind {
+    Shader(ComputeShader),
+    Effect(PostProcessEffect),
+    /// The fullscreen tonemapping shader, with parameters read from the settings.
+    Tonemap(TonemapSettings),
+}
+
+/// Post process pipeline, reading the image written by the previous pass and writing the next one.
+/// The shader samples the input image at `layout(set = 0, binding = 0)`, and receives the texel size of the output image
+/// and two vec4 of parameters as push constants:
+/// `layout(push_constant) uniform PostProcess { vec2 texelSize; vec2 padding; vec4 parameters[2]; }`.
+/// It can also be one of the built-in effects made of several dispatches, like the bloom.
+#[derive(Debug, Clone)]
+pub struct ComputePipelineBuilder {
+    kind: ComputePipelineKind,
+    parameters: [glam::Vec4; 2],
+}
+
+impl ComputePipelineBuilder {
+    pub fn fullscreen(fragment_shader: &'static [u32]) -> ComputePipelineBuilder {
+        ComputePipelineBuilder {
+            kind: ComputePipelineKind::Shader(ComputeShader::Fullscreen(fragment_shader)),
+            parameters: [glam::Vec4::ZERO; 2],
+        }
+    }
+
+    pub fn compute(compute_shader: &'static [u32]) -> ComputePipelineBuilder {
+        ComputePipelineBuilder {
+            kind: ComputePipelineKind::Shader(ComputeShader::Compute(compute_shader)),
+            parameters: [glam::Vec4::ZERO; 2],
+        }
+    }
+
+    pub fn effect(effect: PostProcessEffect) -> ComputePipelineBuilder {
+        ComputePipelineBuilder {
+            kind: ComputePipelineKind::Effect(effect),
+            parameters: [glam::Vec4::ZERO; 2],
+        }
+    }
+
+    /// The tonemapping pass, see `TonemapSettings`.
+    pub fn tonemap(settings: TonemapSettings) -> ComputePipelineBuilder {
+        ComputePipelineBuilder {
+            kind: ComputePipelineKind::Tonemap(settings),
+            parameters: settings.shader_parameters(vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR),
+        }
+    }
+
+    /// Parameters of the shader, sent in the push constants. Effects and the tonemapping ignore them.
+    pub fn with_parameters(self, parameters: [glam::Vec4; 2]) -> ComputePipelineBuilder {
+        ComputePipelineBuilder { parameters, ..self }
+    }
+
+    /// The shader of the pipeline, none for the built-in effects.
+    pub fn shader(&self) -> Option<ComputeShader> {
+        match self.kind {
+            ComputePipelineKind::Shader(shader) => Some(shader),
+            ComputePipelineKind::Effect(_) => None,
+            ComputePipelineKind::Tonemap(_) => Some(ComputeShader::Fullscreen(TONEMAP_FRAG)),
+        }
+    }
+
+    pub fn effect_settings(&self) -> Option<PostProcessEffect> {
+        match self.kind {
+            ComputePipelineKind::Effect(effect) => Some(effect),
+            _ => None,
+        }
+    }
+
+    /// The settings of the tonemapping pass, none for the other pipelines.
+    pub fn tonemap_settings(&self) -> Option<TonemapSettings> {
+        match self.kind {
+            ComputePipelineKind::Tonemap(settings) => Some(settings),
+            _ => None,
+        }
+    }
+
+    pub fn parameters(&self) -> [glam::Vec4; 2] {
+        self.parameters
+    }
+
+    /// Whether the pipeline draws a fullscreen triangle. The built-in effects are compute shaders.
+    pub fn is_fullscreen(&self) -> bool {
+        matches!(self.kind, ComputePipelineKind::Shader(ComputeShader::Fullscreen(_)) | ComputePipelineKind::Tonemap(_))
+    }
+
+    /// Build the pipeline for an output image of the given extent.
+    /// Fullscreen pipelines draw in the given render pass, compute pipelines ignore it.
+    /// The built-in effects are built by their own pass.
+    pub fn build(
+        &self,
+        vk_device: &vulkanalia::Device,
+        extent: vulkanalia::vk::Extent2D,
+        render_pass: vulkanalia::vk::RenderPass,
+    ) -> PResult<ComputePipeline> {
+        match self.kind {
+            ComputePipelineKind::Shader(shader) => ComputePipeline::create(vk_device, shader, false, extent, render_pass),
+            ComputePipelineKind::Tonemap(_) => ComputePipeline::create(vk_device, ComputeShader::Fullscreen(TONEMAP_FRAG), false, extent, render_pass),
+            ComputePipelineKind::Effect(effect) => Err(PropellantError::Custom(format!("The {effect:?} effect has no single pipeline to build."))),
+        }
+    }
+}
+
+/// Operator mapping the hdr colors of the scene to the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapping {
+    /// Reinhard operator on the luminance, soft and keeping the hues.
+    Reinhard,
+    /// Filmic curve fitted on the ACES reference, with more contrast.
+    Aces,
+    /// AgX curve, desaturating the brightest colors towards white instead of shifting their hues.
+    Agx,
+}
+
+impl Tonemapping {
+    pub(crate) fn shader_index(&self) -> f32 {
+        match self {
+            Tonemapping::Reinhard => 0.0,
+            Tonemapping::Aces => 1.0,
+            Tonemapping::Agx => 2.0,
+        }
+    }
+}
+
+/// Settings of the color grading pass, applied on the hdr colors before tonemapping.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorGrading {
+    /// Exposure, in stops.
+    pub exposure: f32,
+    /// Contrast around middle grey, 1 keeps the image unchanged.
+    pub contrast: f32,
+    /// 0 is greyscale, 1 keeps the image unchanged.
+    pub saturation: f32,
+    /// Multiplier of each channel, to tint the image.
+    pub gain: glam::Vec3,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading {
+            exposure: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gain: glam::Vec3::ONE,
+        }
+    }
+}
+
+/// Map the hdr image to the displayable range. It should come after the passes working on hdr colors.
+pub fn tonemap_pipeline(tonemapping: Tonemapping) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::tonemap(TonemapSettings::default().with_operator(tonemapping))
+}
+
+/// Map the hdr image to the displayable range with the given exposure and operator, see `TonemapSettings`.
+/// When it is the last pass, it writes the swapchain in a hdr color space if the display supports one.
+pub fn tonemap_pipeline_with_settings(settings: TonemapSettings) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::tonemap(settings)
+}
+
+/// Fast approximate anti aliasing, blurring the pixels along the edges it detects.
+/// It works on tonemapped colors, so it should come after the tonemapping.
+pub fn fxaa_pipeline() -> ComputePipelineBuilder {
+    // edge threshold, minimum edge threshold and subpixel blending of the reference implementation "default" quality.
+    ComputePipelineBuilder::fullscreen(FXAA_FRAG)
+        .with_parameters([glam::vec4(0.125, 0.0312, 0.75, 0.0), glam::Vec4::ZERO])
+}
+
+/// Darken the corners of the image.
+/// The intensity is the darkening of the corners, from 0 to 1, and the smoothness the length of the fade towards the center.
+pub fn vignette_pipeline(intensity: f32, smoothness: f32) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::fullscreen(VIGNETTE_FRAG)
+        .with_parameters([glam::vec4(intensity, smoothness, 0.0, 0.0), glam::Vec4::ZERO])
+}
+
+/// Exposure, contrast, saturation and tint of the hdr image, as a compute shader.
+/// It writes a hdr image, so its output render target must be `IntermediateRenderTargetBuilder::hdr_color`.
+pub fn color_grading_pipeline(grading: ColorGrading) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::compute(COLOR_GRADING_COMP)
+        .with_parameters([
+            grading.gain.extend(grading.contrast),
+            glam::vec4(grading.saturation, grading.exposure, 0.0, 0.0),
+        ])
+}
+
+/// Bloom over the bright parts of the hdr image, see `BloomSettings`.
+pub fn bloom_pipeline(settings: BloomSettings) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::effect(PostProcessEffect::Bloom(settings))
+}
+
+/// Screen space ambient occlusion from the depth of the scene, see `SsaoSettings`.
+/// It reads the depth texture the graphic pipelines drew the scene with.
+pub fn ssao_pipeline(settings: SsaoSettings) -> ComputePipelineBuilder {
+    ComputePipelineBuilder::effect(PostProcessEffect::AmbientOcclusion(settings))
+}
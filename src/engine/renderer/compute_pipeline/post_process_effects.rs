@@ -0,0 +1,314 @@
+use crate::engine::renderer::compute_pipeline::compute_pipeline_builder::Tonemapping;
+
+/// Effects made of several compute dispatches, with their own render targets.
+/// They work on hdr colors, so they come before the tonemapping, and their output render target must be `IntermediateRenderTargetBuilder::hdr_color`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostProcessEffect {
+    Bloom(BloomSettings),
+    AmbientOcclusion(SsaoSettings),
+}
+
+/// Settings of the bloom, the glow around the bright parts of the image.
+/// The pixels brighter than the threshold are blurred by downsampling them in a chain of smaller images,
+/// then upsampling them back and adding them to the image.
+///
+/// The pass reads the `BloomSettings` singleton every frame if there is one, so it can be tuned or disabled at runtime.
+/// The number of levels is only read when the pass is built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    enabled: bool,
+    /// Luminance from which the pixels glow.
+    threshold: f32,
+    /// Width of the soft transition around the threshold, from 0 (hard cut) to 1.
+    soft_knee: f32,
+    /// How much of the blurred image is added to the image.
+    intensity: f32,
+    /// Spread of the upsampling filter, in texels of each level.
+    radius: f32,
+    /// Number of downsampled images, each one half the size of the previous.
+    levels: u32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        BloomSettings {
+            enabled: true,
+            threshold: 1.0,
+            soft_knee: 0.5,
+            intensity: 0.3,
+            radius: 1.0,
+            levels: 5,
+        }
+    }
+}
+
+impl BloomSettings {
+    pub fn with_enabled(self, enabled: bool) -> BloomSettings {
+        BloomSettings { enabled, ..self }
+    }
+
+    pub fn with_threshold(self, threshold: f32) -> BloomSettings {
+        BloomSettings { threshold: threshold.max(0.0), ..self }
+    }
+
+    pub fn with_soft_knee(self, soft_knee: f32) -> BloomSettings {
+        BloomSettings { soft_knee: soft_knee.clamp(0.0, 1.0), ..self }
+    }
+
+    pub fn with_intensity(self, intensity: f32) -> BloomSettings {
+        BloomSettings { intensity: intensity.max(0.0), ..self }
+    }
+
+    pub fn with_radius(self, radius: f32) -> BloomSettings {
+        BloomSettings { radius: radius.max(0.0), ..self }
+    }
+
+    pub fn with_levels(self, levels: u32) -> BloomSettings {
+        BloomSettings { levels: levels.clamp(1, 8), ..self }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.max(0.0);
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.0);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    pub fn soft_knee(&self) -> f32 {
+        self.soft_knee
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+}
+
+/// Settings of the screen space ambient occlusion, darkening the creases and corners of the scene.
+/// The occlusion is computed from the depth of the scene, blurred, and multiplied with the image.
+///
+/// The pass reads the `SsaoSettings` singleton every frame if there is one, so it can be tuned or disabled at runtime.
+/// The projection of the main camera is read every frame as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    enabled: bool,
+    /// Radius of the hemisphere around each pixel where the geometry occludes it, in world units.
+    radius: f32,
+    /// Strength of the darkening, 0 leaves the image unchanged.
+    intensity: f32,
+    /// Depth offset avoiding the self occlusion of flat surfaces, in world units.
+    bias: f32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        SsaoSettings {
+            enabled: true,
+            radius: 0.5,
+            intensity: 1.0,
+            bias: 0.025,
+        }
+    }
+}
+
+impl SsaoSettings {
+    pub fn with_enabled(self, enabled: bool) -> SsaoSettings {
+        SsaoSettings { enabled, ..self }
+    }
+
+    pub fn with_radius(self, radius: f32) -> SsaoSettings {
+        SsaoSettings { radius: radius.max(0.0), ..self }
+    }
+
+    pub fn with_intensity(self, intensity: f32) -> SsaoSettings {
+        SsaoSettings { intensity: intensity.max(0.0), ..self }
+    }
+
+    pub fn with_bias(self, bias: f32) -> SsaoSettings {
+        SsaoSettings { bias: bias.max(0.0), ..self }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn set_radius(&mut self, radius: f32) {
+        self.radius = radius.max(0.0);
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.0);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn bias(&self) -> f32 {
+        self.bias
+    }
+}
+
+/// Settings of the tonemapping, mapping the hdr colors of the scene to the range of the display.
+/// When the hdr output is enabled and the tonemapping pass writes the swapchain of a display reporting a hdr color space,
+/// the swapchain is created in it, and the colors are mapped up to the peak luminance instead of the white of the sdr displays.
+/// It is disabled by default, the swapchain is then a srgb one. It is a srgb one as well when there are overlay pipelines, such as the ui.
+///
+/// The pass reads the `TonemapSettings` singleton every frame if there is one, so the exposure can be tuned at runtime.
+/// Whether the hdr color spaces are used is only read when the pass is built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonemapSettings {
+    operator: Tonemapping,
+    /// Exposure applied before the operator, in stops.
+    exposure: f32,
+    /// Whether the swapchain can be created in a hdr color space.
+    hdr_output: bool,
+    /// Luminance of the white of the sdr content on hdr displays, in nits.
+    paper_white: f32,
+    /// Highest luminance the colors are mapped to on hdr displays, in nits.
+    peak_luminance: f32,
+}
+
+impl Default for TonemapSettings {
+    fn default() -> Self {
+        TonemapSettings {
+            operator: Tonemapping::Aces,
+            exposure: 0.0,
+            hdr_output: false,
+            paper_white: 200.0,
+            peak_luminance: 1000.0,
+        }
+    }
+}
+
+impl TonemapSettings {
+    pub fn with_operator(self, operator: Tonemapping) -> TonemapSettings {
+        TonemapSettings { operator, ..self }
+    }
+
+    pub fn with_exposure(self, exposure: f32) -> TonemapSettings {
+        TonemapSettings { exposure, ..self }
+    }
+
+    pub fn with_hdr_output(self, hdr_output: bool) -> TonemapSettings {
+        TonemapSettings { hdr_output, ..self }
+    }
+
+    pub fn with_paper_white(self, paper_white: f32) -> TonemapSettings {
+        TonemapSettings { paper_white: paper_white.max(1.0), ..self }
+    }
+
+    pub fn with_peak_luminance(self, peak_luminance: f32) -> TonemapSettings {
+        TonemapSettings { peak_luminance: peak_luminance.max(1.0), ..self }
+    }
+
+    pub fn set_operator(&mut self, operator: Tonemapping) {
+        self.operator = operator;
+    }
+
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+
+    pub fn operator(&self) -> Tonemapping {
+        self.operator
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    pub fn hdr_output(&self) -> bool {
+        self.hdr_output
+    }
+
+    pub fn paper_white(&self) -> f32 {
+        self.paper_white
+    }
+
+    pub fn peak_luminance(&self) -> f32 {
+        self.peak_luminance
+    }
+
+    /// The push constants of the tonemapping shader, writing an image in the given color space.
+    pub(crate) fn shader_parameters(&self, color_space: vulkanalia::vk::ColorSpaceKHR) -> [glam::Vec4; 2] {
+        let encoding = match color_space {
+            vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => 1.0,
+            vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT => 2.0,
+            _ => 0.0,
+        };
+        [
+            glam::vec4(self.operator.shader_index(), self.exposure, encoding, 0.0),
+            // the sdr white can not be brighter than the peak.
+            glam::vec4(self.paper_white, self.peak_luminance.max(self.paper_white), 0.0, 0.0),
+        ]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{TonemapSettings, Tonemapping};
+
+    #[test]
+    fn encoding_follows_the_output_color_space() {
+        let settings = TonemapSettings::default();
+        for (color_space, encoding) in [
+            (vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR, 0.0),
+            (vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT, 1.0),
+            (vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT, 2.0),
+            // color spaces the shader can't write are encoded in srgb.
+            (vulkanalia::vk::ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT, 0.0),
+        ] {
+            assert_eq!(settings.shader_parameters(color_space)[0].z, encoding, "encoding of {color_space:?}");
+        }
+    }
+
+    #[test]
+    fn parameters_hold_the_operator_and_the_luminances() {
+        let settings = TonemapSettings::default()
+            .with_operator(Tonemapping::Agx)
+            .with_exposure(-1.5)
+            .with_paper_white(250.0)
+            .with_peak_luminance(600.0);
+        let [tonemap, luminance] = settings.shader_parameters(vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+        assert_eq!(tonemap, glam::vec4(Tonemapping::Agx.shader_index(), -1.5, 2.0, 0.0));
+        assert_eq!(luminance, glam::vec4(250.0, 600.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn peak_luminance_is_clamped_to_the_paper_white() {
+        let settings = TonemapSettings::default().with_paper_white(400.0).with_peak_luminance(300.0);
+        let [_, luminance] = settings.shader_parameters(vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+        assert_eq!(luminance.x, 400.0);
+        assert_eq!(luminance.y, 400.0);
+    }
+}
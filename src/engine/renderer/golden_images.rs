@@ -0,0 +1,419 @@
+use foundry::World;
+
+use crate::engine::consts::PROPELLANT_DEBUG_FEATURES;
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::errors::loading_errors::LoadingError;
+use crate::engine::errors::rendering_error::RenderingError;
+use crate::engine::window::vulkan::physical_device_prefs::{PhysicalDevicePreferences, SoftwarePhysicalDevicePreferences};
+#[cfg(feature = "ui")]
+use crate::engine::ui::ui_resolution::UiResolution;
+use crate::{ClusteredLights, PropellantResources, RenderingPipelineBuilder, VulkanInterface};
+
+use super::rendering_pipeline::rendering_pipeline_builder::states::RPBSReady;
+
+/// Simulated time between two rendered frames, so the scene systems update the same way on every run.
+const FRAME_DELTA: f32 = 1.0 / 60.0;
+
+/// Environment variable that makes the harness overwrite the reference images with the rendered ones.
+pub const BLESS_GOLDEN_IMAGES_VAR: &str = "PROPELLANT_BLESS_GOLDEN_IMAGES";
+
+/// How far a rendered image can be from its reference.
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenImageTolerance {
+    /// Largest perceptual difference (CIE76 delta E) for two pixels to be considered the same.
+    /// Around 2.3 is the smallest difference the eye notices.
+    max_delta_e: f32,
+    /// Proportion of the pixels that can differ, to absorb rasterization differences on the edges.
+    max_differing_ratio: f32,
+}
+
+impl Default for GoldenImageTolerance {
+    fn default() -> Self {
+        GoldenImageTolerance {
+            max_delta_e: 3.0,
+            max_differing_ratio: 0.002,
+        }
+    }
+}
+
+impl GoldenImageTolerance {
+    pub fn with_max_delta_e(self, max_delta_e: f32) -> GoldenImageTolerance {
+        GoldenImageTolerance { max_delta_e, ..self }
+    }
+
+    pub fn with_max_differing_ratio(self, max_differing_ratio: f32) -> GoldenImageTolerance {
+        GoldenImageTolerance { max_differing_ratio, ..self }
+    }
+}
+
+/// Result of the comparison of a rendered image with its reference.
+#[derive(Debug, Clone)]
+pub struct GoldenImageComparison {
+    differing_pixels: usize,
+    pixel_count: usize,
+    max_delta_e: f32,
+    /// The reference dimmed in grey, with the differing pixels in red.
+    diff: image::RgbaImage,
+}
+
+impl GoldenImageComparison {
+    /// Compare two images of the same size, pixel by pixel.
+    pub fn compare(
+        rendered: &image::RgbaImage,
+        reference: &image::RgbaImage,
+        tolerance: GoldenImageTolerance,
+    ) -> GoldenImageComparison {
+        let mut diff = image::RgbaImage::new(reference.width(), reference.height());
+        let mut differing_pixels = 0;
+        let mut max_delta_e = 0.0f32;
+        for ((rendered, reference), diff) in rendered.pixels().zip(reference.pixels()).zip(diff.pixels_mut()) {
+            let delta_e = srgb_to_lab(rendered.0).distance(srgb_to_lab(reference.0));
+            max_delta_e = max_delta_e.max(delta_e);
+            *diff = match delta_e > tolerance.max_delta_e {
+                true => {
+                    differing_pixels += 1;
+                    // the further from the reference, the brighter the red.
+                    let intensity = (delta_e / (4.0 * tolerance.max_delta_e)).clamp(0.5, 1.0);
+                    image::Rgba([(intensity * 255.0) as u8, 0, 0, 255])
+                },
+                false => {
+                    let [r, g, b, _] = reference.0;
+                    let grey = ((r as u32 + g as u32 + b as u32) / 9) as u8;
+                    image::Rgba([grey, grey, grey, 255])
+                },
+            };
+        }
+        GoldenImageComparison {
+            differing_pixels,
+            pixel_count: (reference.width() * reference.height()) as usize,
+            max_delta_e,
+            diff,
+        }
+    }
+
+    pub fn differing_ratio(&self) -> f32 {
+        match self.pixel_count {
+            0 => 0.0,
+            count => self.differing_pixels as f32 / count as f32,
+        }
+    }
+
+    pub fn max_delta_e(&self) -> f32 {
+        self.max_delta_e
+    }
+
+    pub fn passes(&self, tolerance: GoldenImageTolerance) -> bool {
+        self.differing_ratio() <= tolerance.max_differing_ratio
+    }
+
+    pub fn diff(&self) -> &image::RgbaImage {
+        &self.diff
+    }
+}
+
+/// Convert a srgb encoded pixel to the CIE Lab space (D65), where distances follow the perceived differences.
+fn srgb_to_lab(pixel: [u8; 4]) -> glam::Vec3 {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        match c <= 0.04045 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4),
+        }
+    };
+    let rgb = glam::vec3(linear(pixel[0]), linear(pixel[1]), linear(pixel[2]));
+    let xyz = glam::vec3(
+        rgb.dot(glam::vec3(0.4124, 0.3576, 0.1805)) / 0.95047,
+        rgb.dot(glam::vec3(0.2126, 0.7152, 0.0722)),
+        rgb.dot(glam::vec3(0.0193, 0.1192, 0.9505)) / 1.08883,
+    );
+    let f = |t: f32| match t > 0.008856 {
+        true => t.cbrt(),
+        false => 7.787 * t + 16.0 / 116.0,
+    };
+    let (fx, fy, fz) = (f(xyz.x), f(xyz.y), f(xyz.z));
+    glam::vec3(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Outcome of a golden image check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoldenImageOutcome {
+    /// The rendered image matches its reference.
+    Passed,
+    /// Blessing was asked: the rendered image became the reference.
+    Blessed,
+    /// There is no reference to compare with. The rendered image is written in the output directory.
+    MissingReference,
+    /// The rendered image is too far from its reference.
+    Failed {
+        differing_ratio: f32,
+        max_delta_e: f32,
+    },
+    /// The rendered image and its reference do not have the same size.
+    SizeMismatch,
+}
+
+/// Renders scenes offscreen and compares them against reference pngs, to catch shader and pipeline regressions.
+/// It runs on a software vulkan implementation, so the references do not depend on the gpu of the machine.
+/// On failure, the rendered image and a diff image are written in the output directory.
+pub struct GoldenImageHarness {
+    vk_interface: VulkanInterface,
+    reference_directory: std::path::PathBuf,
+    output_directory: std::path::PathBuf,
+    tolerance: GoldenImageTolerance,
+    /// Frames rendered before the comparison, to let the scene settle.
+    frame_count: usize,
+    width: u32,
+    height: u32,
+    bless: bool,
+    outcomes: Vec<(String, GoldenImageOutcome)>,
+}
+
+impl GoldenImageHarness {
+    pub fn create(
+        reference_directory: std::path::PathBuf,
+        output_directory: std::path::PathBuf,
+    ) -> PResult<GoldenImageHarness> {
+        let device_prefs: Box<dyn PhysicalDevicePreferences> = Box::new(SoftwarePhysicalDevicePreferences);
+        let vk_interface = VulkanInterface::create_headless(&device_prefs, "Propellant golden images".to_string())?;
+        Ok(GoldenImageHarness {
+            vk_interface,
+            reference_directory,
+            output_directory,
+            tolerance: GoldenImageTolerance::default(),
+            frame_count: 1,
+            width: 320,
+            height: 180,
+            bless: std::env::var_os(BLESS_GOLDEN_IMAGES_VAR).is_some(),
+            outcomes: Vec::new(),
+        })
+    }
+
+    /// Create the harness, or None when the machine has no vulkan implementation to render with.
+    /// This lets tests skip the golden images where there is no vulkan driver, rather than failing.
+    pub fn create_if_available(
+        reference_directory: std::path::PathBuf,
+        output_directory: std::path::PathBuf,
+    ) -> PResult<Option<GoldenImageHarness>> {
+        match Self::create(reference_directory, output_directory) {
+            Ok(harness) => Ok(Some(harness)),
+            Err(PropellantError::Loading(LoadingError::VulkanLibrary(_))) |
+            Err(PropellantError::Rendering(RenderingError::NoFittingVulkanDevice)) |
+            Err(PropellantError::Rendering(RenderingError::Vulkan(vulkanalia::vk::ErrorCode::INCOMPATIBLE_DRIVER))) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn with_tolerance(self, tolerance: GoldenImageTolerance) -> GoldenImageHarness {
+        GoldenImageHarness { tolerance, ..self }
+    }
+
+    pub fn with_frame_count(self, frame_count: usize) -> GoldenImageHarness {
+        GoldenImageHarness { frame_count: frame_count.max(1), ..self }
+    }
+
+    pub fn with_size(self, width: u32, height: u32) -> GoldenImageHarness {
+        GoldenImageHarness { width, height, ..self }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Build a scene in a new world, render it with the given pipelines and compare the last frame with the reference `<name>.png`.
+    /// The scene is given an empty world with the ui resolution, and must add the resources.
+    /// A missing reference is a failure, unless blessing was asked.
+    pub fn check<F: FnOnce(&mut World) -> PResult<()>>(
+        &mut self,
+        name: &str,
+        pipelines: RenderingPipelineBuilder<RPBSReady>,
+        scene: F,
+    ) -> PResult<GoldenImageOutcome> {
+        let frame = self.render_scene(pipelines, scene)?;
+
+        let reference_path = self.reference_directory.join(format!("{name}.png"));
+        let outcome = match (self.bless, reference_path.exists()) {
+            (true, _) => {
+                std::fs::create_dir_all(&self.reference_directory)
+                    .map_err(|e| PropellantError::Custom(format!("Unable to create the reference directory: {e}")))?;
+                frame.save_with_format(&reference_path, image::ImageFormat::Png)?;
+                GoldenImageOutcome::Blessed
+            },
+            (false, true) => {
+                let reference = image::open(&reference_path)?.to_rgba8();
+                self.compare(name, &frame, &reference)?
+            },
+            (false, false) => {
+                self.save_rendered(name, &frame)?;
+                GoldenImageOutcome::MissingReference
+            },
+        };
+
+        if PROPELLANT_DEBUG_FEATURES {
+            println!("[PROPELLANT DEBUG] [GOLDEN IMAGES] {name}: {outcome:?}");
+        }
+        self.outcomes.push((name.to_string(), outcome));
+        Ok(outcome)
+    }
+
+    fn render_scene<F: FnOnce(&mut World) -> PResult<()>>(
+        &mut self,
+        pipelines: RenderingPipelineBuilder<RPBSReady>,
+        scene: F,
+    ) -> PResult<image::RgbaImage> {
+        let mut world = World::default();
+        world.add_singleton(ClusteredLights::default());
+        #[cfg(feature = "ui")]
+        world.add_singleton(UiResolution::new(1.0, glam::vec2(self.width as f32, self.height as f32)));
+        scene(&mut world)?;
+
+        let mut renderer = pipelines.build_offscreen(&self.vk_interface, self.width, self.height)?;
+        let mut frame = Ok(image::RgbaImage::new(self.width, self.height));
+        for _ in 0..self.frame_count {
+            world.update(FRAME_DELTA);
+            frame = renderer.render(&mut self.vk_interface, &mut world);
+            if frame.is_err() {
+                break;
+            }
+        }
+
+        // the vulkan objects are destroyed whether the render succeeded or not.
+        self.vk_interface.wait_idle()?;
+        if let Some(mut resources) = world.remove_singleton::<PropellantResources>() {
+            resources.destroy(&self.vk_interface.device);
+        }
+        renderer.destroy(&self.vk_interface.device);
+
+        frame
+    }
+
+    fn compare(
+        &self,
+        name: &str,
+        frame: &image::RgbaImage,
+        reference: &image::RgbaImage,
+    ) -> PResult<GoldenImageOutcome> {
+        let comparison = match frame.dimensions() == reference.dimensions() {
+            true => Some(GoldenImageComparison::compare(frame, reference, self.tolerance)),
+            false => None,
+        };
+        if let Some(comparison) = &comparison {
+            if comparison.passes(self.tolerance) {
+                return Ok(GoldenImageOutcome::Passed);
+            }
+        }
+
+        // keep the rendered image next to the diff, so it can be inspected or blessed by hand.
+        self.save_rendered(name, frame)?;
+
+        let outcome = match comparison {
+            Some(comparison) => {
+                comparison.diff().save_with_format(self.output_directory.join(format!("{name}.diff.png")), image::ImageFormat::Png)?;
+                GoldenImageOutcome::Failed {
+                    differing_ratio: comparison.differing_ratio(),
+                    max_delta_e: comparison.max_delta_e(),
+                }
+            },
+            None => GoldenImageOutcome::SizeMismatch,
+        };
+
+        Ok(outcome)
+    }
+
+    fn save_rendered(&self, name: &str, frame: &image::RgbaImage) -> PResult<()> {
+        std::fs::create_dir_all(&self.output_directory)
+            .map_err(|e| PropellantError::Custom(format!("Unable to create the output directory: {e}")))?;
+        frame.save_with_format(self.output_directory.join(format!("{name}.png")), image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    /// Name and outcome of every check done so far.
+    pub fn outcomes(&self) -> &[(String, GoldenImageOutcome)] {
+        &self.outcomes
+    }
+
+    /// Whether no check failed so far. Blessed images count as passed, missing references do not.
+    pub fn all_passed(&self) -> bool {
+        self.outcomes.iter().all(|(_, outcome)| match outcome {
+            GoldenImageOutcome::Passed | GoldenImageOutcome::Blessed => true,
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pixels: &[[u8; 4]]) -> image::RgbaImage {
+        image::RgbaImage::from_raw(pixels.len() as u32, 1, pixels.concat()).unwrap()
+    }
+
+    fn assert_lab(pixel: [u8; 4], expected: glam::Vec3) {
+        let lab = srgb_to_lab(pixel);
+        assert!(lab.distance(expected) < 0.1, "{pixel:?}: {lab} instead of {expected}");
+    }
+
+    #[test]
+    fn srgb_to_lab_matches_the_reference_values() {
+        assert_lab([0, 0, 0, 255], glam::vec3(0.0, 0.0, 0.0));
+        assert_lab([255, 255, 255, 255], glam::vec3(100.0, 0.0, 0.0));
+        assert_lab([119, 119, 119, 255], glam::vec3(50.03, 0.0, 0.0));
+        assert_lab([255, 0, 0, 255], glam::vec3(53.24, 80.09, 67.20));
+        assert_lab([0, 255, 0, 255], glam::vec3(87.73, -86.18, 83.18));
+        assert_lab([0, 0, 255, 255], glam::vec3(32.30, 79.19, -107.86));
+        // the alpha is not compared.
+        assert_eq!(srgb_to_lab([10, 20, 30, 0]), srgb_to_lab([10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn identical_images_pass() {
+        let pixels = [[0, 0, 0, 255], [255, 0, 0, 255], [90, 180, 45, 255], [255, 255, 255, 255]];
+        let comparison = GoldenImageComparison::compare(&row(&pixels), &row(&pixels), GoldenImageTolerance::default());
+        assert_eq!(comparison.differing_ratio(), 0.0);
+        assert_eq!(comparison.max_delta_e(), 0.0);
+        assert!(comparison.passes(GoldenImageTolerance::default()));
+        // the diff is the reference in dimmed grey.
+        assert_eq!(comparison.diff().get_pixel(3, 0).0, [85, 85, 85, 255]);
+    }
+
+    #[test]
+    fn differences_below_the_tolerance_pass() {
+        let reference = row(&[[100, 100, 100, 255], [200, 50, 50, 255]]);
+        let rendered = row(&[[101, 100, 100, 255], [200, 51, 50, 255]]);
+        let comparison = GoldenImageComparison::compare(&rendered, &reference, GoldenImageTolerance::default());
+        assert_eq!(comparison.differing_ratio(), 0.0);
+        assert!(comparison.max_delta_e() > 0.0);
+        assert!(comparison.passes(GoldenImageTolerance::default()));
+    }
+
+    #[test]
+    fn differing_pixels_fail_and_are_shown_in_the_diff() {
+        let reference = row(&[[100, 100, 100, 255], [100, 100, 100, 255], [100, 100, 100, 255], [100, 100, 100, 255]]);
+        let rendered = row(&[[100, 100, 100, 255], [255, 0, 0, 255], [100, 100, 100, 255], [100, 100, 100, 255]]);
+        let tolerance = GoldenImageTolerance::default();
+        let comparison = GoldenImageComparison::compare(&rendered, &reference, tolerance);
+        assert_eq!(comparison.differing_ratio(), 0.25);
+        assert!(comparison.max_delta_e() > 50.0);
+        assert!(!comparison.passes(tolerance));
+        assert!(comparison.passes(tolerance.with_max_differing_ratio(0.25)));
+        // far from the reference, the pixel is bright red.
+        assert_eq!(comparison.diff().get_pixel(1, 0).0, [255, 0, 0, 255]);
+        assert_eq!(comparison.diff().get_pixel(0, 0).0, [33, 33, 33, 255]);
+
+        // with a looser delta e, the same pixel is not different anymore.
+        let comparison = GoldenImageComparison::compare(&rendered, &reference, tolerance.with_max_delta_e(200.0));
+        assert_eq!(comparison.differing_ratio(), 0.0);
+    }
+
+    #[test]
+    fn empty_images_pass() {
+        let empty = image::RgbaImage::new(0, 0);
+        let comparison = GoldenImageComparison::compare(&empty, &empty, GoldenImageTolerance::default());
+        assert_eq!(comparison.differing_ratio(), 0.0);
+        assert!(comparison.passes(GoldenImageTolerance::default()));
+    }
+}
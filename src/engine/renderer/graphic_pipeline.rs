@@ -7,12 +7,12 @@ use crate::{
 };
 
 use self::graphic_pipeline_state::GraphicPipelineCreationState;
+use super::rendering_pipeline::shadow_render_pass::shadow_map::ShadowMap;
 
 
 pub(crate) mod graphic_pipeline_builder;
 pub(crate) mod graphic_pipeline_gen;
 pub(crate) mod graphic_pipeline_state;
-#[allow(unused)] // todo remove
 pub(crate) mod graphic_pipeline_settings;
 pub(crate) mod renderable_component;
 pub(crate) mod uniform;
@@ -38,16 +38,22 @@ pub trait GraphicPipelineInterface {
         command_buffer: vulkanalia::vk::CommandBuffer,
         resources: &PropellantResources,
     );
+    /// Write the uniforms of the given image.
+    /// Returns true if the draw commands of the image must be recorded again, like when sorted instances changed order.
     fn update_uniform_buffers(
         &mut self,
         vk_device: &vulkanalia::Device,
         components: &foundry::ComponentTable,
         image_index: usize,
-    ) -> PResult<()>;
+    ) -> PResult<bool>;
     fn rebuild_rendering_map(
         &mut self,
         components: &foundry::ComponentTable,
     );
+    /// Whether the scene changed in a way the rendering map needs to be rebuilt, like meshes changing their level of detail.
+    fn requires_rendering_map_rebuild(&self) -> bool;
+    /// Transparent pipelines are recorded after the opaque ones.
+    fn is_transparent(&self) -> bool;
     fn assert_uniform_buffer_sizes(
         &mut self,
         image_index: usize,
@@ -61,6 +67,19 @@ pub trait GraphicPipelineInterface {
         image_index: usize,
         textures: &TextureLibrary,
     ) -> PResult<()>;
+    /// Point the shadow map uniforms of the pipeline to the shadow map and its cascades.
+    fn bind_shadow_map(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        shadow_map: &ShadowMap,
+    );
+    /// Record push constants for the next draws of the pipeline. Does nothing if the pipeline has no push constants.
+    fn push_constants(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        data: &[u8],
+    );
     fn destroy(
         &mut self,
         vk_device: &vulkanalia::Device,
@@ -6,20 +6,26 @@ use super::GraphicPipelineInterface;
 
 use super::uniform::uniform_buffer::UniformBufferBuilder;
 use super::uniform::uniform_buffer::UniformBuffer;
+use crate::engine::renderer::rendering_pipeline::shadow_render_pass::shadow_settings::ShadowSettings;
 
 pub trait GraphicPipelineBuilderInterface {
+    /// Build the pipeline for the given render pass, rasterizing with its sample count.
+    /// The line width of the pipeline is clamped to the range supported by the device.
     fn build(
         self: Box<Self>,
         vk_device: &vulkanalia::Device,
         swapchain_extent: vulkanalia::vk::Extent2D,
         frame_count: usize,
         render_pass: vulkanalia::vk::RenderPass,
+        samples: vulkanalia::vk::SampleCountFlags,
+        line_widths: [f32; 2],
     ) -> PResult<Box<dyn GraphicPipelineInterface>>; 
 }
 
 
 pub fn default_phong_pipeline() -> impl GraphicPipelineBuilderInterface {
     use crate::CameraUniformObject;
+    use crate::LocalLights;
     use crate::MainDirectionnalLight;
     use crate::ModelMatrixUniformObject;
     use crate::PhongMaterial;
@@ -39,6 +45,374 @@ pub fn default_phong_pipeline() -> impl GraphicPipelineBuilderInterface {
         (RenderableComponent, PhongMaterial, ShaderStage::Fragment), // phong material (one per object)
         (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
         (TexturesUniform, ShaderStage::Fragment), // textures
+        (FrameUniform, LocalLights, ShaderStage::Fragment), // point and spot lights (one per frame)
+        (ShadowMapUniform, ShaderStage::Fragment), // shadow map and cascades
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
+    )
+}
+
+/// Phong pipeline drawing skinned meshes.
+/// Skinned entities need a `InstancedMeshRenderer<Material, SkinnedMesh>` and a `SkinnedMeshRenderer`.
+/// The joints palettes are sent per instance in a storage buffer, and contain the model transform.
+pub fn default_skinned_phong_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::LocalLights;
+    use crate::MainDirectionnalLight;
+    use crate::PhongMaterial;
+    use crate::SkinnedMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::DEFAULT_FRAG;
+    use crate::engine::renderer::shaders::SKINNED_VERT;
+    use crate::engine::renderer::graphic_pipeline::uniform::object_uniform::joint_palette_uniform::JointPaletteUniformObject;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SKINNED_VERT), // skinning vert shader
+        (ShaderStage::Fragment, DEFAULT_FRAG); // frag shader
+        (Mesh, SkinnedMesh), // draw skinned meshes
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (FrameUniform, MainDirectionnalLight, ShaderStage::Fragment), // light uniforms (one per frame)
+        (RenderableComponent, PhongMaterial, ShaderStage::Fragment), // phong material (one per object)
+        (ObjectUniform, JointPaletteUniformObject, ShaderStage::Vertex), // joints palette (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+        (FrameUniform, LocalLights, ShaderStage::Fragment), // point and spot lights (one per frame)
+        (ShadowMapUniform, ShaderStage::Fragment), // shadow map and cascades
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
+    )
+}
+
+/// Physically based pipeline, drawing static meshes with a `PbrMaterial`.
+/// Shading uses the GGX distribution, the Smith geometry term and the Schlick fresnel approximation, in linear space.
+pub fn default_pbr_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::LocalLights;
+    use crate::MainDirectionnalLight;
+    use crate::ModelMatrixUniformObject;
+    use crate::PbrMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::PBR_FRAG;
+    use crate::engine::renderer::shaders::DEFAULT_VERT;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, DEFAULT_VERT), // vert shader
+        (ShaderStage::Fragment, PBR_FRAG); // pbr frag shader
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (FrameUniform, MainDirectionnalLight, ShaderStage::Fragment), // light uniforms (one per frame)
+        (RenderableComponent, PbrMaterial, ShaderStage::Fragment), // pbr material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+        (FrameUniform, LocalLights, ShaderStage::Fragment), // point and spot lights (one per frame)
+        (ShadowMapUniform, ShaderStage::Fragment), // shadow map and cascades
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
+    )
+}
+
+/// Physically based pipeline drawing skinned meshes.
+/// Skinned entities need a `InstancedMeshRenderer<PbrMaterial, SkinnedMesh>` and a `SkinnedMeshRenderer`.
+pub fn default_skinned_pbr_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::LocalLights;
+    use crate::MainDirectionnalLight;
+    use crate::PbrMaterial;
+    use crate::SkinnedMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::PBR_FRAG;
+    use crate::engine::renderer::shaders::SKINNED_VERT;
+    use crate::engine::renderer::graphic_pipeline::uniform::object_uniform::joint_palette_uniform::JointPaletteUniformObject;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SKINNED_VERT), // skinning vert shader
+        (ShaderStage::Fragment, PBR_FRAG); // pbr frag shader
+        (Mesh, SkinnedMesh), // draw skinned meshes
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (FrameUniform, MainDirectionnalLight, ShaderStage::Fragment), // light uniforms (one per frame)
+        (RenderableComponent, PbrMaterial, ShaderStage::Fragment), // pbr material (one per object)
+        (ObjectUniform, JointPaletteUniformObject, ShaderStage::Vertex), // joints palette (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+        (FrameUniform, LocalLights, ShaderStage::Fragment), // point and spot lights (one per frame)
+        (ShadowMapUniform, ShaderStage::Fragment), // shadow map and cascades
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
+    )
+}
+
+/// Physically based pipeline drawing transparent static meshes, such as glass or water.
+/// Transparent entities need a `InstancedMeshRenderer<TransparentPbrMaterial, StaticMesh>`.
+/// The pipeline is drawn after the opaque ones, alpha blends and sorts its instances back to front from the main camera.
+pub fn default_transparent_pbr_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::LocalLights;
+    use crate::MainDirectionnalLight;
+    use crate::ModelMatrixUniformObject;
+    use crate::TransparentPbrMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::PBR_FRAG;
+    use crate::engine::renderer::shaders::DEFAULT_VERT;
+    use super::graphic_pipeline_settings::GraphicPipelineSettings;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, DEFAULT_VERT), // vert shader
+        (ShaderStage::Fragment, PBR_FRAG); // pbr frag shader
+        (Settings, GraphicPipelineSettings::default().transparent()), // blended and sorted
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (FrameUniform, MainDirectionnalLight, ShaderStage::Fragment), // light uniforms (one per frame)
+        (RenderableComponent, TransparentPbrMaterial, ShaderStage::Fragment), // transparent pbr material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+        (FrameUniform, LocalLights, ShaderStage::Fragment), // point and spot lights (one per frame)
+        (ShadowMapUniform, ShaderStage::Fragment), // shadow map and cascades
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
+    )
+}
+
+/// Pipeline drawing the static meshes of the phong pipeline in the shadow map.
+/// It is registered by `RenderingPipelineBuilder::with_shadows`.
+pub fn default_phong_shadow_caster_pipeline(settings: &ShadowSettings) -> impl GraphicPipelineBuilderInterface {
+    use crate::ModelMatrixUniformObject;
+    use crate::PhongMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::SHADOW_VERT;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SHADOW_VERT); // depth only vert shader
+        (Settings, settings.caster_pipeline_settings()), // depth only and biased
+        (ShadowCascadesUniform, ShaderStage::Vertex), // cascades view projections
+        (RenderableComponent, PhongMaterial, ShaderStage::Vertex), // phong material (one per object), unused but drives the draws
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+    )
+}
+
+/// Pipeline drawing the skinned meshes of the skinned phong pipeline in the shadow map.
+pub fn default_skinned_phong_shadow_caster_pipeline(settings: &ShadowSettings) -> impl GraphicPipelineBuilderInterface {
+    use crate::PhongMaterial;
+    use crate::SkinnedMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::SKINNED_SHADOW_VERT;
+    use crate::engine::renderer::graphic_pipeline::uniform::object_uniform::joint_palette_uniform::JointPaletteUniformObject;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SKINNED_SHADOW_VERT); // skinning depth only vert shader
+        (Mesh, SkinnedMesh), // draw skinned meshes
+        (Settings, settings.caster_pipeline_settings()), // depth only and biased
+        (ShadowCascadesUniform, ShaderStage::Vertex), // cascades view projections
+        (RenderableComponent, PhongMaterial, ShaderStage::Vertex), // phong material (one per object), unused but drives the draws
+        (ObjectUniform, JointPaletteUniformObject, ShaderStage::Vertex), // joints palette (one per object)
+    )
+}
+
+/// Pipeline drawing the static meshes of the pbr pipeline in the shadow map.
+pub fn default_pbr_shadow_caster_pipeline(settings: &ShadowSettings) -> impl GraphicPipelineBuilderInterface {
+    use crate::ModelMatrixUniformObject;
+    use crate::PbrMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::SHADOW_VERT;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SHADOW_VERT); // depth only vert shader
+        (Settings, settings.caster_pipeline_settings()), // depth only and biased
+        (ShadowCascadesUniform, ShaderStage::Vertex), // cascades view projections
+        (RenderableComponent, PbrMaterial, ShaderStage::Vertex), // pbr material (one per object), unused but drives the draws
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+    )
+}
+
+/// Pipeline drawing the skinned meshes of the skinned pbr pipeline in the shadow map.
+pub fn default_skinned_pbr_shadow_caster_pipeline(settings: &ShadowSettings) -> impl GraphicPipelineBuilderInterface {
+    use crate::PbrMaterial;
+    use crate::SkinnedMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::SKINNED_SHADOW_VERT;
+    use crate::engine::renderer::graphic_pipeline::uniform::object_uniform::joint_palette_uniform::JointPaletteUniformObject;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SKINNED_SHADOW_VERT); // skinning depth only vert shader
+        (Mesh, SkinnedMesh), // draw skinned meshes
+        (Settings, settings.caster_pipeline_settings()), // depth only and biased
+        (ShadowCascadesUniform, ShaderStage::Vertex), // cascades view projections
+        (RenderableComponent, PbrMaterial, ShaderStage::Vertex), // pbr material (one per object), unused but drives the draws
+        (ObjectUniform, JointPaletteUniformObject, ShaderStage::Vertex), // joints palette (one per object)
+    )
+}
+
+/// Pipeline drawing static meshes with an `UnlitMaterial`, ignoring the lights.
+/// Unlit entities need a `InstancedMeshRenderer<UnlitMaterial, StaticMesh>`.
+pub fn default_unlit_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::UnlitMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::UNLIT_FRAG;
+    use crate::engine::renderer::shaders::UNLIT_VERT;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, UNLIT_VERT), // unlit vert shader
+        (ShaderStage::Fragment, UNLIT_FRAG); // unlit frag shader
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, UnlitMaterial, ShaderStage::Fragment), // unlit material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+    )
+}
+
+/// Pipeline drawing transparent static meshes with an unlit material, such as glowing signs that fade out.
+/// Transparent unlit entities need a `InstancedMeshRenderer<TransparentUnlitMaterial, StaticMesh>`.
+pub fn default_transparent_unlit_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::TransparentUnlitMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::UNLIT_FRAG;
+    use crate::engine::renderer::shaders::UNLIT_VERT;
+    use super::graphic_pipeline_settings::GraphicPipelineSettings;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, UNLIT_VERT), // unlit vert shader
+        (ShaderStage::Fragment, UNLIT_FRAG); // unlit frag shader
+        (Settings, GraphicPipelineSettings::default().transparent()), // blended and sorted
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, TransparentUnlitMaterial, ShaderStage::Fragment), // transparent unlit material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+        (TexturesUniform, ShaderStage::Fragment), // textures
+    )
+}
+
+/// Pipeline drawing color meshes, with the vertex colors and without lighting.
+/// Vertex color entities need a `InstancedMeshRenderer<VertexColorMaterial, ColorMesh>`.
+pub fn default_vertex_color_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::VertexColorMaterial;
+    use crate::ColorMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::LINES_FRAG;
+    use crate::engine::renderer::shaders::LINES_VERT;
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, LINES_VERT), // position and color vert shader, shared with lines
+        (ShaderStage::Fragment, LINES_FRAG); // vertex color frag shader
+        (Mesh, ColorMesh), // draw color meshes
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, VertexColorMaterial, ShaderStage::Vertex), // vertex color material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+    )
+}
+
+/// Pipeline drawing line meshes, such as grids, trajectories or debug shapes.
+/// Line entities need a `InstancedMeshRenderer<TintMaterial, LineMesh>`.
+pub fn default_line_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::TintMaterial;
+    use crate::LineMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::LINES_FRAG;
+    use crate::engine::renderer::shaders::LINES_VERT;
+    use super::graphic_pipeline_settings::{GraphicPipelineSettings, PrimitiveTopology};
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, LINES_VERT), // lines vert shader
+        (ShaderStage::Fragment, LINES_FRAG); // vertex color frag shader
+        (Mesh, LineMesh), // draw line meshes
+        (Settings, GraphicPipelineSettings::default().topology(PrimitiveTopology::LineList)), // as a line list
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, TintMaterial, ShaderStage::Vertex), // tint material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+    )
+}
+
+/// Pipeline drawing point meshes, such as point clouds.
+/// Point entities need a `InstancedMeshRenderer<TintMaterial, PointMesh>`.
+pub fn default_point_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::TintMaterial;
+    use crate::PointMesh;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::LINES_FRAG;
+    use crate::engine::renderer::shaders::POINTS_VERT;
+    use super::graphic_pipeline_settings::{GraphicPipelineSettings, PrimitiveTopology};
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, POINTS_VERT), // points vert shader
+        (ShaderStage::Fragment, LINES_FRAG); // vertex color frag shader
+        (Mesh, PointMesh), // draw point meshes
+        (Settings, GraphicPipelineSettings::default().topology(PrimitiveTopology::PointList)), // as a point list
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, TintMaterial, ShaderStage::Vertex), // tint material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object)
+    )
+}
+
+/// Pipeline drawing the environment cubemap of the texture library behind the scene.
+/// The skybox entity needs a `InstancedMeshRenderer<SkyboxMaterial, StaticMesh>` with a cube mesh.
+/// It is drawn at the far plane with a depth test only, so it can be drawn in any order with the opaque pipelines.
+pub fn default_skybox_pipeline() -> impl GraphicPipelineBuilderInterface {
+    use crate::CameraUniformObject;
+    use crate::ModelMatrixUniformObject;
+    use crate::SkyboxMaterial;
+    use crate::create_graphic_pipeline;
+    use crate::engine::renderer::shaders::SKYBOX_FRAG;
+    use crate::engine::renderer::shaders::SKYBOX_VERT;
+    use super::graphic_pipeline_settings::{GraphicPipelineSettings, DepthSetting, CompareOp, CullMode};
+    use super::renderable_component::RenderableComponent;
+    use super::uniform::frame_uniform::FrameUniform;
+    use super::uniform::object_uniform::ObjectUniform;
+    use crate::ShaderStage;
+    use crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState;
+    create_graphic_pipeline!(
+        (ShaderStage::Vertex, SKYBOX_VERT), // skybox vert shader
+        (ShaderStage::Fragment, SKYBOX_FRAG); // skybox frag shader
+        (Settings, GraphicPipelineSettings::default().depth(DepthSetting::Read).depth_compare_op(CompareOp::LessOrEqual).cull_mode(CullMode::None)), // at the far plane, seen from inside
+        (FrameUniform, CameraUniformObject, ShaderStage::Vertex), // camera uniforms (one per frame)
+        (RenderableComponent, SkyboxMaterial, ShaderStage::Fragment), // skybox material (one per object)
+        (ObjectUniform, ModelMatrixUniformObject, ShaderStage::Vertex), // model matrix (one per object), unused as the cube follows the camera
+        (EnvironmentUniform, ShaderStage::Fragment), // environment cubemaps
     )
 }
 
@@ -31,15 +31,48 @@ impl Into<vulkanalia::vk::ShaderStageFlags> for ShaderStage {
     }
 }
 
+#[macro_export]
+#[doc(hidden)]
+/// Query the components of the entities drawn by a pipeline, with the query matching the number of component types.
+/// Prefixing the table with `mut` gives a mutable query.
+macro_rules! graphic_pipeline_query {
+    (mut $components:expr; $a:ty $(,)?) => { $components.query1d_mut::<$a>() };
+    (mut $components:expr; $a:ty, $b:ty $(,)?) => { $components.query2d_mut::<$a, $b>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty $(,)?) => { $components.query3d_mut::<$a, $b, $c>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty, $d:ty $(,)?) => { $components.query4d_mut::<$a, $b, $c, $d>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty $(,)?) => { $components.query5d_mut::<$a, $b, $c, $d, $e>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty $(,)?) => { $components.query6d_mut::<$a, $b, $c, $d, $e, $f>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty, $g:ty $(,)?) => { $components.query7d_mut::<$a, $b, $c, $d, $e, $f, $g>() };
+    (mut $components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty, $g:ty, $h:ty $(,)?) => { $components.query8d_mut::<$a, $b, $c, $d, $e, $f, $g, $h>() };
+    ($components:expr; $a:ty $(,)?) => { $components.query1d::<$a>() };
+    ($components:expr; $a:ty, $b:ty $(,)?) => { $components.query2d::<$a, $b>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty $(,)?) => { $components.query3d::<$a, $b, $c>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty, $d:ty $(,)?) => { $components.query4d::<$a, $b, $c, $d>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty $(,)?) => { $components.query5d::<$a, $b, $c, $d, $e>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty $(,)?) => { $components.query6d::<$a, $b, $c, $d, $e, $f>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty, $g:ty $(,)?) => { $components.query7d::<$a, $b, $c, $d, $e, $f, $g>() };
+    ($components:expr; $a:ty, $b:ty, $c:ty, $d:ty, $e:ty, $f:ty, $g:ty, $h:ty $(,)?) => { $components.query8d::<$a, $b, $c, $d, $e, $f, $g, $h>() };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Keep the first token of a list. This is used to get the renderable component driving the draws of a pipeline.
+macro_rules! graphic_pipeline_first {
+    () => { compile_error!("graphic pipelines require at least one RenderableComponent") };
+    ($first:tt $($rest:tt)*) => { $first };
+}
+
 #[macro_export]
 macro_rules! create_graphic_pipeline_impl {
     // ========== Recursive call to build the fields properly ==========
     (
         $(($shader_stage:path, $shader_code:ident)),*;
-        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*);
-        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*);
-        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*);
-        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*);
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
         ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
         ($($ordered_field:tt)*);
         (FrameUniform, $uniform:ident, $stage:path),
@@ -48,10 +81,12 @@ macro_rules! create_graphic_pipeline_impl {
         // register a new frame uniform
         crate::create_graphic_pipeline_impl!(
             $(($shader_stage, $shader_code)),*;
-            ($($frm_uniforms_decl)* [<frame_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*);
-            ($($frm_buffers_decl)* [<frame_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*);
-            ($($frm_uniforms_field)* [<frame_uniform_ $uniform:snake>]); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*);
-            ($($frm_uniforms_build)* [<frame_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), vulkanalia::vk::DescriptorType::UNIFORM_BUFFER),); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*);
+            $mesh_type;
+            $settings;
+            ($($frm_uniforms_decl)* [<frame_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*); ($($shadow_uniforms_decl)*);
+            ($($frm_buffers_decl)* [<frame_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*); ($($shadow_buffers_decl)*);
+            ($($frm_uniforms_field)* [<frame_uniform_ $uniform:snake>]); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*); ($($shadow_uniforms_field)*);
+            ($($frm_uniforms_build)* [<frame_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), <$uniform as crate::engine::renderer::graphic_pipeline::uniform::frame_uniform::FrameUniform>::DESCRIPTOR_TYPE),); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*); ($($shadow_uniforms_build)*);
             ($($frm_uniforms_type)* $uniform); ($($obj_uniforms_type)*); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
             ($($ordered_field)* [<frame_uniform_ $uniform:snake>]);
             $($rest)*
@@ -59,10 +94,12 @@ macro_rules! create_graphic_pipeline_impl {
     };
     (
         $(($shader_stage:path, $shader_code:ident)),*;
-        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*);
-        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*);
-        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*);
-        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*);
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
         ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
         ($($ordered_field:tt)*);
         (ObjectUniform, $uniform:ident, $stage:path),
@@ -71,10 +108,12 @@ macro_rules! create_graphic_pipeline_impl {
         // register a new renderable object uniform
         crate::create_graphic_pipeline_impl!(
             $(($shader_stage, $shader_code)),*;
-            ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)* [<object_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*);
-            ($($frm_buffers_decl)*); ($($obj_buffers_decl)* [<object_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*);
-            ($($frm_uniforms_field)*); ($($obj_uniforms_field)* [<object_uniform_ $uniform:snake>]); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*);
-            ($($frm_uniforms_build)*); ($($obj_uniforms_build)* [<object_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), vulkanalia::vk::DescriptorType::STORAGE_BUFFER),); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*);
+            $mesh_type;
+            $settings;
+            ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)* [<object_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*); ($($shadow_uniforms_decl)*);
+            ($($frm_buffers_decl)*); ($($obj_buffers_decl)* [<object_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*); ($($shadow_buffers_decl)*);
+            ($($frm_uniforms_field)*); ($($obj_uniforms_field)* [<object_uniform_ $uniform:snake>]); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*); ($($shadow_uniforms_field)*);
+            ($($frm_uniforms_build)*); ($($obj_uniforms_build)* [<object_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), vulkanalia::vk::DescriptorType::STORAGE_BUFFER),); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*); ($($shadow_uniforms_build)*);
             ($($frm_uniforms_type)*); ($($obj_uniforms_type)* $uniform); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
             ($($ordered_field)* [<object_uniform_ $uniform:snake>]);
             $($rest)*
@@ -82,10 +121,12 @@ macro_rules! create_graphic_pipeline_impl {
     };
     (
         $(($shader_stage:path, $shader_code:ident)),*;
-        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*);
-        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*);
-        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*);
-        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*);
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
         ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
         ($($ordered_field:tt)*);
         (RenderableComponent, $uniform:ty, $stage:path),
@@ -95,10 +136,12 @@ macro_rules! create_graphic_pipeline_impl {
         {
             crate::create_graphic_pipeline_impl!(
                 $(($shader_stage, $shader_code)),*;
-                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($texture_uniforms_decl)*);
-                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($texture_buffers_decl)*);
-                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)* [<renderable_comp_uniform_ $uniform:snake>]); ($($texture_uniforms_field)*);
-                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), vulkanalia::vk::DescriptorType::STORAGE_BUFFER),); ($($texture_uniforms_build)*);
+                $mesh_type;
+                $settings;
+                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBufferBuilder<$uniform>,); ($($texture_uniforms_decl)*); ($($shadow_uniforms_decl)*);
+                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBuffer<$uniform>,); ($($texture_buffers_decl)*); ($($shadow_buffers_decl)*);
+                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)* [<renderable_comp_uniform_ $uniform:snake>]); ($($texture_uniforms_field)*); ($($shadow_uniforms_field)*);
+                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)* [<renderable_comp_uniform_ $uniform:snake>]: UniformBufferBuilder::new($stage.into(), vulkanalia::vk::DescriptorType::STORAGE_BUFFER),); ($($texture_uniforms_build)*); ($($shadow_uniforms_build)*);
                 ($($frm_uniforms_type)*); ($($obj_uniforms_type)*); ($($rc_uniforms_type)* $uniform); ($($texture_uniforms_type)*);
                 ($($ordered_field)* [<renderable_comp_uniform_ $uniform:snake>]);
                 $($rest)*
@@ -107,10 +150,12 @@ macro_rules! create_graphic_pipeline_impl {
     };
     (
         $(($shader_stage:path, $shader_code:ident)),*;
-        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*);
-        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*);
-        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*);
-        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*);
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
         ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
         ($($ordered_field:tt)*);
         (TexturesUniform, $stage:path),
@@ -124,23 +169,126 @@ macro_rules! create_graphic_pipeline_impl {
             };
             crate::create_graphic_pipeline_impl!(
                 $(($shader_stage, $shader_code)),*;
-                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)* textures_uniform: TextureUniformBuilder,);
-                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)* textures_uniform: TextureUniform,);
-                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)* textures_uniform);
-                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)* textures_uniform: TextureUniformBuilder::new($stage.into()),);
+                $mesh_type;
+                $settings;
+                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)* textures_uniform: TextureUniformBuilder,); ($($shadow_uniforms_decl)*);
+                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)* textures_uniform: TextureUniform,); ($($shadow_buffers_decl)*);
+                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)* textures_uniform); ($($shadow_uniforms_field)*);
+                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)* textures_uniform: TextureUniformBuilder::new($stage.into()),); ($($shadow_uniforms_build)*);
                 ($($frm_uniforms_type)*); ($($obj_uniforms_type)*); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
                 ($($ordered_field)* textures_uniform);
                 $($rest)*
             )
         }
     };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
+        ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
+        ($($ordered_field:tt)*);
+        (EnvironmentUniform, $stage:path),
+        $($rest:tt)*
+    ) => {
+        // register the environment cubemaps, reloaded with the textures
+        {
+            use crate::engine::renderer::graphic_pipeline::uniform::environment_uniform::{
+                EnvironmentUniformBuilder,
+                EnvironmentUniform,
+            };
+            crate::create_graphic_pipeline_impl!(
+                $(($shader_stage, $shader_code)),*;
+                $mesh_type;
+                $settings;
+                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)* environment_uniform: EnvironmentUniformBuilder,); ($($shadow_uniforms_decl)*);
+                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)* environment_uniform: EnvironmentUniform,); ($($shadow_buffers_decl)*);
+                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)* environment_uniform); ($($shadow_uniforms_field)*);
+                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)* environment_uniform: EnvironmentUniformBuilder::new($stage.into()),); ($($shadow_uniforms_build)*);
+                ($($frm_uniforms_type)*); ($($obj_uniforms_type)*); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
+                ($($ordered_field)* environment_uniform);
+                $($rest)*
+            )
+        }
+    };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
+        ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
+        ($($ordered_field:tt)*);
+        (ShadowMapUniform, $stage:path),
+        $($rest:tt)*
+    ) => {
+        // register the shadow map and its cascades
+        {
+            use crate::engine::renderer::graphic_pipeline::uniform::shadow_map_uniform::{
+                ShadowMapUniformBuilder,
+                ShadowMapUniform,
+            };
+            crate::create_graphic_pipeline_impl!(
+                $(($shader_stage, $shader_code)),*;
+                $mesh_type;
+                $settings;
+                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*); ($($shadow_uniforms_decl)* shadow_map_uniform: ShadowMapUniformBuilder,);
+                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*); ($($shadow_buffers_decl)* shadow_map_uniform: ShadowMapUniform,);
+                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*); ($($shadow_uniforms_field)* shadow_map_uniform);
+                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*); ($($shadow_uniforms_build)* shadow_map_uniform: ShadowMapUniformBuilder::new($stage.into(), true),);
+                ($($frm_uniforms_type)*); ($($obj_uniforms_type)*); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
+                ($($ordered_field)* shadow_map_uniform);
+                $($rest)*
+            )
+        }
+    };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
+        ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
+        ($($ordered_field:tt)*);
+        (ShadowCascadesUniform, $stage:path),
+        $($rest:tt)*
+    ) => {
+        // register the shadow cascades, without the shadow map, for the pipelines drawing into it
+        {
+            use crate::engine::renderer::graphic_pipeline::uniform::shadow_map_uniform::{
+                ShadowMapUniformBuilder,
+                ShadowMapUniform,
+            };
+            crate::create_graphic_pipeline_impl!(
+                $(($shader_stage, $shader_code)),*;
+                $mesh_type;
+                $settings;
+                ($($frm_uniforms_decl)*); ($($obj_uniforms_decl)*); ($($rc_uniforms_decl)*); ($($texture_uniforms_decl)*); ($($shadow_uniforms_decl)* shadow_cascades_uniform: ShadowMapUniformBuilder,);
+                ($($frm_buffers_decl)*); ($($obj_buffers_decl)*); ($($rc_buffers_decl)*); ($($texture_buffers_decl)*); ($($shadow_buffers_decl)* shadow_cascades_uniform: ShadowMapUniform,);
+                ($($frm_uniforms_field)*); ($($obj_uniforms_field)*); ($($rc_uniforms_field)*); ($($texture_uniforms_field)*); ($($shadow_uniforms_field)* shadow_cascades_uniform);
+                ($($frm_uniforms_build)*); ($($obj_uniforms_build)*); ($($rc_uniforms_build)*); ($($texture_uniforms_build)*); ($($shadow_uniforms_build)* shadow_cascades_uniform: ShadowMapUniformBuilder::new($stage.into(), false),);
+                ($($frm_uniforms_type)*); ($($obj_uniforms_type)*); ($($rc_uniforms_type)*); ($($texture_uniforms_type)*);
+                ($($ordered_field)* shadow_cascades_uniform);
+                $($rest)*
+            )
+        }
+    };
     // ========== Macro expansion with properly built fields ==========
     (
         $(($shader_stage:path, $shader_code:ident)),*;
-        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*);
-        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*);
-        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*);
-        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*);
+        $mesh_type:ty;
+        $settings:expr;
+        ($($frm_uniforms_decl:tt)*); ($($obj_uniforms_decl:tt)*); ($($rc_uniforms_decl:tt)*); ($($texture_uniforms_decl:tt)*); ($($shadow_uniforms_decl:tt)*);
+        ($($frm_buffers_decl:tt)*); ($($obj_buffers_decl:tt)*); ($($rc_buffers_decl:tt)*); ($($texture_buffers_decl:tt)*); ($($shadow_buffers_decl:tt)*);
+        ($($frm_uniforms_field:tt)*); ($($obj_uniforms_field:tt)*); ($($rc_uniforms_field:tt)*); ($($texture_uniforms_field:tt)*); ($($shadow_uniforms_field:tt)*);
+        ($($frm_uniforms_build:tt)*); ($($obj_uniforms_build:tt)*); ($($rc_uniforms_build:tt)*); ($($texture_uniforms_build:tt)*); ($($shadow_uniforms_build:tt)*);
         ($($frm_uniforms_type:tt)*); ($($obj_uniforms_type:tt)*); ($($rc_uniforms_type:tt)*); ($($texture_uniforms_type:tt)*);
         ($($ordered_field:tt)*);
     ) => {
@@ -152,10 +300,7 @@ macro_rules! create_graphic_pipeline_impl {
             use vulkanalia::vk::HasBuilder;
             use vulkanalia::vk::DeviceV1_0;
             use crate::engine::errors::PResult;
-            use crate::engine::mesh::vertex::StaticVertex;
             use super::GraphicPipelineInterface;
-            use crate::engine::mesh::vertex::VulkanVertex;
-            use crate::StaticMesh;
 
 
             // ========== Some helper funcs ==========
@@ -170,16 +315,17 @@ macro_rules! create_graphic_pipeline_impl {
             
             fn create_descriptor_pool(
                 vk_device: &vulkanalia::Device,
-                descriptor_types: Vec<vulkanalia::vk::DescriptorType>,
+                descriptor_types: Vec<Vec<vulkanalia::vk::DescriptorType>>,
                 frame_count: usize,
             ) -> PResult<vulkanalia::vk::DescriptorPool> {
             
+                // descriptor types are given for each binding of each set.
                 let descriptor_set_count = descriptor_types.len() * frame_count;
             
                 // for each layout type, we count how many descriptor sets we need.
                 let mut ds_count_map = HashMap::with_capacity(3);
                 
-                for ds_type in descriptor_types.into_iter() {
+                for ds_type in descriptor_types.into_iter().flatten() {
                     match ds_count_map.get_mut(&ds_type) {
                         Some(count) => *count += frame_count,
                         None => { ds_count_map.insert(ds_type, frame_count); },
@@ -211,10 +357,13 @@ macro_rules! create_graphic_pipeline_impl {
                 vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
                 creation_state: crate::engine::renderer::graphic_pipeline::GraphicPipelineCreationState,
                 rendering_map: crate::engine::renderer::rendering_map::RenderingMap,
+                /// uniform buffer index of each object, keyed by entity id, when the pipeline owns them.
+                instance_indices: std::collections::HashMap<usize, usize>,
                 $($frm_buffers_decl)*
                 $($obj_buffers_decl)*
                 $($rc_buffers_decl)*
                 $($texture_buffers_decl)*
+                $($shadow_buffers_decl)*
             }
 
             impl GraphicPipeline {
@@ -226,11 +375,14 @@ macro_rules! create_graphic_pipeline_impl {
                     swapchain_extent: vulkanalia::vk::Extent2D,
                     pipeline_layout: vulkanalia::vk::PipelineLayout,
                     render_pass: vulkanalia::vk::RenderPass,
+                    samples: vulkanalia::vk::SampleCountFlags,
                     vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
+                    settings: crate::engine::renderer::graphic_pipeline::graphic_pipeline_settings::GraphicPipelineSettings,
                     $($frm_buffers_decl)*
                     $($obj_buffers_decl)*
                     $($rc_buffers_decl)*
                     $($texture_buffers_decl)*
+                    $($shadow_buffers_decl)*
                 ) -> PResult<GraphicPipeline> {
             
                     let vertex_input_state = vulkanalia::vk::PipelineVertexInputStateCreateInfo::builder()
@@ -238,9 +390,9 @@ macro_rules! create_graphic_pipeline_impl {
                         .vertex_attribute_descriptions(&vertex_attribute_description)
                         .build();
                     
-                    // here, default values to draw triangles. Maybe to rework at some point ? 
+                    // the topology of the primitives is given by the pipeline settings
                     let input_assembly_state = vulkanalia::vk::PipelineInputAssemblyStateCreateInfo::builder()
-                        .topology(vulkanalia::vk::PrimitiveTopology::TRIANGLE_LIST)
+                        .topology(settings.vk_topology())
                         .primitive_restart_enable(false)
                         .build();
                     
@@ -264,31 +416,17 @@ macro_rules! create_graphic_pipeline_impl {
                         .viewports(viewports)
                         .scissors(scissors);
                     
-                    // create the rasterizer
-                    let rasterization_state = vulkanalia::vk::PipelineRasterizationStateCreateInfo::builder()
-                        .depth_clamp_enable(false)
-                        .rasterizer_discard_enable(false)
-                        .polygon_mode(vulkanalia::vk::PolygonMode::FILL) // can be set to fill here !
-                        .line_width(1.0)
-                        .cull_mode(vulkanalia::vk::CullModeFlags::BACK)
-                        .front_face(vulkanalia::vk::FrontFace::CLOCKWISE)
-                        .depth_bias_enable(false)
-                        .build();
+                    // create the rasterizer: polygon mode, culling and depth bias come from the settings
+                    let rasterization_state = settings.vk_rasterization_state();
             
-                    // multisampling state: antialiasing here
+                    // multisampling state: the sample count of the render pass attachments, for antialiasing.
                     let multisample_state = vulkanalia::vk::PipelineMultisampleStateCreateInfo::builder()
                         .sample_shading_enable(false)
-                        .rasterization_samples(vulkanalia::vk::SampleCountFlags::_1)
-                        .build();
-            
-                    // color blending. transparency and alpha color blending can be done here !
-            
-                    let color_attachment = vulkanalia::vk::PipelineColorBlendAttachmentState::builder()
-                        .color_write_mask(vulkanalia::vk::ColorComponentFlags::all())
-                        .blend_enable(false)
+                        .rasterization_samples(samples)
                         .build();
             
-                    let color_blend_attachments = vec![color_attachment];
+                    // color blending, one state per color attachment. transparency and alpha color blending are set in the settings.
+                    let color_blend_attachments = settings.vk_color_blend_attachments();
             
                     let color_blend_state = vulkanalia::vk::PipelineColorBlendStateCreateInfo::builder()
                         .logic_op_enable(false)
@@ -297,13 +435,7 @@ macro_rules! create_graphic_pipeline_impl {
                         .blend_constants([0.0, 0.0, 0.0, 0.0])
                         .build();
             
-                    let depth_stencil_state = vulkanalia::vk::PipelineDepthStencilStateCreateInfo::builder()
-                        .depth_test_enable(true)
-                        .depth_write_enable(true)
-                        .depth_compare_op(vulkanalia::vk::CompareOp::LESS)
-                        .depth_bounds_test_enable(false)
-                        .stencil_test_enable(false)
-                        .build();
+                    let depth_stencil_state = settings.vk_depth_stencil_state();
             
                     // create the pipeline ! 
                     let stages = shader_stages.iter().map(|(stage, shader_module)|
@@ -313,6 +445,12 @@ macro_rules! create_graphic_pipeline_impl {
                             .name(b"main\0")
                             .build()
                     ).collect::<Vec<_>>();
+
+                    // transparent pipelines draw their instances sorted back to front.
+                    let rendering_map = match settings.is_transparent() {
+                        true => crate::engine::renderer::rendering_map::RenderingMap::sorted(),
+                        false => crate::engine::renderer::rendering_map::RenderingMap::new(),
+                    };
             
                     let creation_state = GraphicPipelineCreationState {
                         stages,
@@ -325,6 +463,7 @@ macro_rules! create_graphic_pipeline_impl {
                         color_blend_state,
                         depth_stencil_state,
                         color_blend_attachments,
+                        settings,
                     };
             
                     let info = vulkanalia::vk::GraphicsPipelineCreateInfo::builder()
@@ -335,7 +474,7 @@ macro_rules! create_graphic_pipeline_impl {
                         .rasterization_state(&creation_state.rasterization_state)
                         .multisample_state(&creation_state.multisample_state)
                         .color_blend_state(&creation_state.color_blend_state)
-                        .depth_stencil_state(&depth_stencil_state)
+                        .depth_stencil_state(&creation_state.depth_stencil_state)
                         .layout(pipeline_layout)
                         .render_pass(render_pass)
                         .subpass(0)
@@ -351,11 +490,13 @@ macro_rules! create_graphic_pipeline_impl {
                         $($obj_uniforms_field,)*
                         $($rc_uniforms_field,)*
                         $($texture_uniforms_field,)*
+                        $($shadow_uniforms_field,)*
                         pipeline,
                         pipeline_layout,
                         vk_descriptor_pool,
                         creation_state,
-                        rendering_map: crate::engine::renderer::rendering_map::RenderingMap::new(),
+                        rendering_map,
+                        instance_indices: std::collections::HashMap::new(),
                     })
                 }
             }
@@ -424,7 +565,9 @@ macro_rules! create_graphic_pipeline_impl {
                     vk_device: &vulkanalia::Device,
                     components: &foundry::ComponentTable,
                     image_index: usize,
-                ) -> PResult<()> {
+                ) -> PResult<bool> {
+                    // the draw commands of sorted pipelines are outdated when the order of the meshes changed.
+                    let mut draw_commands_outdated = false;
                     // map all the buffers
                     $(self.$frm_uniforms_field.map(vk_device, image_index)?;)*
                     $(self.$obj_uniforms_field.map(vk_device, image_index)?;)*
@@ -433,19 +576,62 @@ macro_rules! create_graphic_pipeline_impl {
                     $(
                         $frm_uniforms_type::set_uniform(components, &mut |comp| self.$frm_uniforms_field.update_buffer(0, image_index, comp));
                     )*
-                    // object uniforms
-                    // TODO : hard coded query 2D here, but this depends on the number of object + resources uniforms.
+                    // levels of detail selection, to check the rendering map is still valid.
+                    let lod_selector = crate::engine::mesh::lod::LodSelector::new(components);
+                    let mut lod_instance_counts = std::collections::BTreeMap::new();
+                    // sorted pipelines order their instances by distance to the main camera.
+                    let camera_position = match self.rendering_map.is_sorted() {
+                        true => crate::engine::common_components::camera::Camera::main_camera_position(components),
+                        false => None,
+                    };
+                    // collect the objects, with their squared distance to the camera, uniform buffer offset, mesh and instance count.
+                    // the first renderable component drives the draws: its mesh and its instances offset are used for all the object uniforms.
+                    let mut objects = Vec::new();
+                    let owns_instance_indices = self.creation_state.settings.owns_instance_indices();
                     for (
-                        _,
+                        entity,
                         $($rc_uniforms_field,)*
                         $($obj_uniforms_field,)*
-                    ) in components.query2d::<
-                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<StaticMesh>,)*
+                    ) in crate::graphic_pipeline_query!(components;
+                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<$mesh_type>,)*
                         $(<$obj_uniforms_type as ObjectUniform>::FromComponent,)*
-                    >() {
-                        $(let uniform_buffer_offset = <$rc_uniforms_type as RenderableComponent>::uniform_buffer_index::<StaticMesh>($rc_uniforms_field);)*
-                        $(let instance_count = <$rc_uniforms_type as RenderableComponent>::instance_count::<StaticMesh>($rc_uniforms_field);)*
-                        $(<$rc_uniforms_type as RenderableComponent>::set_uniform::<StaticMesh>(
+                    ) {
+                        let world_transform: Option<glam::Mat4> = None $(.or(<$obj_uniforms_type as ObjectUniform>::world_transform($obj_uniforms_field)))*;
+                        let uniform_buffer_offset = match owns_instance_indices {
+                            true => match self.instance_indices.get(&entity) {
+                                Some(index) => *index,
+                                // objects added since the last rebuild of the rendering map are not drawn yet.
+                                None => continue,
+                            },
+                            false => <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::uniform_buffer_index::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*)),
+                        };
+                        let instance_count = <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::instance_count::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*));
+                        let mesh_key = lod_selector.select(
+                            <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::mesh_id::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*)),
+                            world_transform
+                        );
+                        let distance = match (camera_position, world_transform) {
+                            (Some(camera_position), Some(world_transform)) => camera_position.distance_squared(world_transform.w_axis.truncate()),
+                            _ => 0.0,
+                        };
+                        objects.push((distance, uniform_buffer_offset, mesh_key, instance_count, ($($rc_uniforms_field,)* $($obj_uniforms_field,)*)));
+                    }
+                    // sorted pipelines draw back to front: the instances are placed in the buffers in that order.
+                    if self.rendering_map.is_sorted() {
+                        objects.sort_by(|(d1, ..), (d2, ..)| d2.total_cmp(d1));
+                        let mut offset = 0;
+                        for (_, uniform_buffer_offset, _, instance_count, _) in objects.iter_mut() {
+                            *uniform_buffer_offset = offset;
+                            offset += *instance_count;
+                        }
+                        draw_commands_outdated = self.rendering_map.set_sorted_draws(
+                            image_index,
+                            objects.iter().map(|(_, _, mesh_key, instance_count, _)| (*mesh_key, *instance_count))
+                        );
+                    }
+                    // object uniforms
+                    for (_, uniform_buffer_offset, mesh_key, instance_count, ($($rc_uniforms_field,)* $($obj_uniforms_field,)*)) in objects.into_iter() {
+                        $(<$rc_uniforms_type as RenderableComponent>::set_uniform::<$mesh_type>(
                             $rc_uniforms_field,
                             &mut |comp, instance_offset| self.$rc_uniforms_field.update_buffer(uniform_buffer_offset + instance_offset, image_index, comp),
                             instance_count
@@ -455,13 +641,19 @@ macro_rules! create_graphic_pipeline_impl {
                             &mut |comp, instance_offset| self.$obj_uniforms_field.update_buffer(uniform_buffer_offset + instance_offset, image_index, comp),
                             instance_count
                         );)*
+                        if lod_selector.has_lods() {
+                            *lod_instance_counts.entry(mesh_key).or_insert(0) += instance_count;
+                        }
+                    }
+                    if lod_selector.has_lods() {
+                        self.rendering_map.check_instance_counts(&lod_instance_counts);
                     }
                     // unmap all the buffers
                     $(self.$frm_uniforms_field.unmap(vk_device, image_index);)*
                     $(self.$obj_uniforms_field.unmap(vk_device, image_index);)*
                     $(self.$rc_uniforms_field.unmap(vk_device, image_index);)*
                 
-                    Ok(())
+                    Ok(draw_commands_outdated)
                 }
             
                 fn register_draw_commands(
@@ -497,20 +689,27 @@ macro_rules! create_graphic_pipeline_impl {
                     }
 
                     // for each concerned mesh; bind it and draw instanced !
-                    let mut first_instance = 0;
-                    for (mesh, instance_count) in self.rendering_map.iter(resources) {
-                        mesh.bind_mesh(vk_device, command_buffer);
+                    // meshes sharing the pool buffers are only bound once.
+                    let mut bound_mesh: Option<crate::engine::resources::mesh_library::MeshDraw> = None;
+                    for (mesh, first_instance, instance_count) in self.rendering_map.iter(resources, image_index) {
+                        let already_bound = match bound_mesh {
+                            Some(bound) => bound.shares_bindings(&mesh),
+                            None => false,
+                        };
+                        if !already_bound {
+                            mesh.bind_mesh(vk_device, command_buffer);
+                            bound_mesh = Some(mesh);
+                        }
                         unsafe {
                             vk_device.cmd_draw_indexed(
                                 command_buffer,
                                 mesh.index_count() as u32,
                                 instance_count as u32,
-                                0,
-                                0,
+                                mesh.first_index(),
+                                mesh.vertex_offset(),
                                 first_instance as u32
                             );
                         }
-                        first_instance += instance_count;
                     }
                 }
             
@@ -520,6 +719,10 @@ macro_rules! create_graphic_pipeline_impl {
                 ) {
                     // assert buffer sizes
                     // self.frame_1_uniform_buffer.assert_buffer_size(object_count, image_index, vk_instance, vk_device, vk_physical_device)
+                    // the mesh used for each object depends on its level of detail
+                    let lod_selector = crate::engine::mesh::lod::LodSelector::new(components);
+                    let owns_instance_indices = self.creation_state.settings.owns_instance_indices();
+                    self.instance_indices.clear();
                     let map = self.rendering_map.map_mut();
                     // clear the map
                     map.clear();
@@ -528,18 +731,21 @@ macro_rules! create_graphic_pipeline_impl {
                     for (
                         _,
                         $($rc_uniforms_field,)*
-                        _,
-                    ) in components.query2d::<
-                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<StaticMesh>,)*
+                        $($obj_uniforms_field,)*
+                    ) in crate::graphic_pipeline_query!(components;
+                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<$mesh_type>,)*
                         $(<$obj_uniforms_type as ObjectUniform>::FromComponent,)*
-                    >() {
-                        $(
-                            let added_instance_count = <$rc_uniforms_type as RenderableComponent>::instance_count::<StaticMesh>($rc_uniforms_field);
-                            match map.get_mut(&<$rc_uniforms_type as RenderableComponent>::mesh_id::<StaticMesh>($rc_uniforms_field)) {
-                                Some((instance_count, _, _)) => *instance_count += added_instance_count,
-                                None => {map.insert(<$rc_uniforms_type as RenderableComponent>::mesh_id::<StaticMesh>($rc_uniforms_field), (added_instance_count, 0, 0));},
-                            }
-                        )*
+                    ) {
+                        let world_transform: Option<glam::Mat4> = None $(.or(<$obj_uniforms_type as ObjectUniform>::world_transform($obj_uniforms_field)))*;
+                        let added_instance_count = <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::instance_count::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*));
+                        let mesh_key = lod_selector.select(
+                            <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::mesh_id::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*)),
+                            world_transform
+                        );
+                        match map.get_mut(&mesh_key) {
+                            Some((instance_count, _, _)) => *instance_count += added_instance_count,
+                            None => {map.insert(mesh_key, (added_instance_count, 0, 0));},
+                        }
                     }
                     // add offsets to the map
                     let mut offset = 0;
@@ -550,21 +756,38 @@ macro_rules! create_graphic_pipeline_impl {
                     }
                     // final loop to set the buffers offsets
                     for (
-                        _,
+                        entity,
                         $($rc_uniforms_field,)*
-                        _,
-                    ) in components.query2d_mut::<
-                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<StaticMesh>,)*
+                        $($obj_uniforms_field,)*
+                    ) in crate::graphic_pipeline_query!(mut components;
+                        $(<$rc_uniforms_type as RenderableComponent>::FromComponent<$mesh_type>,)*
                         $(<$obj_uniforms_type as ObjectUniform>::FromComponent,)*
-                    >() {
-                        $(
-                            let (_, mesh_offset, counter) = map.get_mut(&<$rc_uniforms_type as RenderableComponent>::mesh_id::<StaticMesh>($rc_uniforms_field)).unwrap();
-                            <$rc_uniforms_type as RenderableComponent>::set_uniform_buffer_index::<StaticMesh>($rc_uniforms_field, *mesh_offset + *counter);
-                            *counter += <$rc_uniforms_type as RenderableComponent>::instance_count::<StaticMesh>($rc_uniforms_field);
-                        )*
+                    ) {
+                        let world_transform: Option<glam::Mat4> = None $(.or(<$obj_uniforms_type as ObjectUniform>::world_transform($obj_uniforms_field)))*;
+                        let mesh_key = lod_selector.select(
+                            <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::mesh_id::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*)),
+                            world_transform
+                        );
+                        let instance_count = <crate::graphic_pipeline_first!($($rc_uniforms_type)*) as RenderableComponent>::instance_count::<$mesh_type>(crate::graphic_pipeline_first!($($rc_uniforms_field)*));
+                        let (_, mesh_offset, counter) = map.get_mut(&mesh_key).unwrap();
+                        if owns_instance_indices {
+                            self.instance_indices.insert(entity, *mesh_offset + *counter);
+                        } else {
+                            // all the renderable components of the entity share the instances of the first one.
+                            $(<$rc_uniforms_type as RenderableComponent>::set_uniform_buffer_index::<$mesh_type>($rc_uniforms_field, *mesh_offset + *counter);)*
+                        }
+                        *counter += instance_count;
                     }
                 }
             
+                fn requires_rendering_map_rebuild(&self) -> bool {
+                    self.rendering_map.is_outdated()
+                }
+
+                fn is_transparent(&self) -> bool {
+                    self.creation_state.settings.is_transparent()
+                }
+
                 fn assert_uniform_buffer_sizes(
                     &mut self,
                     image_index: usize,
@@ -598,6 +821,38 @@ macro_rules! create_graphic_pipeline_impl {
                     )?;)*
                     Ok(())
                 }
+
+                fn bind_shadow_map(
+                    &mut self,
+                    #[allow(unused_variables)]
+                    vk_device: &vulkanalia::Device,
+                    #[allow(unused_variables)]
+                    shadow_map: &crate::engine::renderer::rendering_pipeline::shadow_render_pass::shadow_map::ShadowMap,
+                ) {
+                    $(self.$shadow_uniforms_field.populate_descriptor_sets(
+                        vk_device,
+                        shadow_map,
+                    );)*
+                }
+
+                fn push_constants(
+                    &self,
+                    vk_device: &vulkanalia::Device,
+                    command_buffer: vulkanalia::vk::CommandBuffer,
+                    data: &[u8],
+                ) {
+                    if let Some(range) = self.creation_state.settings.vk_push_constant_ranges().first() {
+                        unsafe {
+                            vk_device.cmd_push_constants(
+                                command_buffer,
+                                self.pipeline_layout,
+                                range.stage_flags,
+                                0,
+                                data,
+                            );
+                        }
+                    }
+                }
             
                 fn destroy(
                     &mut self,
@@ -617,10 +872,12 @@ macro_rules! create_graphic_pipeline_impl {
 
             pub struct GraphicPipelineBuilder {
                 shaders: std::collections::HashMap<crate::ShaderStage, Vec<u32>>,
+                settings: crate::engine::renderer::graphic_pipeline::graphic_pipeline_settings::GraphicPipelineSettings,
                 $($frm_uniforms_decl)*
                 $($obj_uniforms_decl)*
                 $($rc_uniforms_decl)*
                 $($texture_uniforms_decl)*
+                $($shadow_uniforms_decl)*
             }
 
             impl GraphicPipelineBuilder {
@@ -629,7 +886,9 @@ macro_rules! create_graphic_pipeline_impl {
                     vk_device: &vulkanalia::Device,
                     swapchain_extent: vulkanalia::vk::Extent2D,
                     swapchain_image_count: usize,
-                    render_pass: vulkanalia::vk::RenderPass
+                    render_pass: vulkanalia::vk::RenderPass,
+                    samples: vulkanalia::vk::SampleCountFlags,
+                    line_widths: [f32; 2],
                 ) -> PResult<GraphicPipeline> {
                     // create shader modules (compile byte code)
                     let shader_stages = self.shaders.iter().map(|(stage, code)| {
@@ -637,7 +896,7 @@ macro_rules! create_graphic_pipeline_impl {
                     }).collect::<Result<std::collections::HashMap<_, _>, _>>()?;
 
                     let descriptor_types = vec![
-                        $((self.$ordered_field).descriptor_type(),)*
+                        $((self.$ordered_field).descriptor_types(),)*
                     ];
             
                     // create the descriptor pool, to allocate descriptor sets.
@@ -655,15 +914,17 @@ macro_rules! create_graphic_pipeline_impl {
                     ];
                     
                     // pipeline layout is where we set all our uniforms declaration
+                    let push_constant_ranges = self.settings.vk_push_constant_ranges();
                     let layout_info = vulkanalia::vk::PipelineLayoutCreateInfo::builder()
-                        .set_layouts(&layouts);
+                        .set_layouts(&layouts)
+                        .push_constant_ranges(&push_constant_ranges);
             
                     // create the pipeline layout and the pipeline.
                     let pipeline_layout = unsafe { vk_device.create_pipeline_layout(&layout_info, None)? };
                     
                     // set the vertex input state
-                    let vertex_binding_description = vec![StaticVertex::binding_description()];
-                    let vertex_attribute_description = StaticVertex::attribute_description();
+                    let vertex_binding_description = vec![<$mesh_type>::vertex_binding_description()];
+                    let vertex_attribute_description = <$mesh_type>::vertex_attribute_description();
                     
                     GraphicPipeline::create(
                         vk_device, 
@@ -673,11 +934,14 @@ macro_rules! create_graphic_pipeline_impl {
                         swapchain_extent,
                         pipeline_layout,
                         render_pass,
+                        samples,
                         vk_descriptor_pool,
+                        self.settings.clamp_line_width(line_widths),
                         $($frm_uniforms_field,)*
                         $($obj_uniforms_field,)*
                         $($rc_uniforms_field,)*
                         $($texture_uniforms_field,)*
+                        $($shadow_uniforms_field,)*
                     )
                 }
             }
@@ -689,8 +953,10 @@ macro_rules! create_graphic_pipeline_impl {
                     swapchain_extent: vulkanalia::vk::Extent2D,
                     frame_count: usize,
                     render_pass: vulkanalia::vk::RenderPass,
+                    samples: vulkanalia::vk::SampleCountFlags,
+                    line_widths: [f32; 2],
                 ) -> PResult<Box<dyn GraphicPipelineInterface>> {
-                    Ok(Box::new(self.build_inner(vk_device, swapchain_extent, frame_count, render_pass)?))
+                    Ok(Box::new(self.build_inner(vk_device, swapchain_extent, frame_count, render_pass, samples, line_widths)?))
                 }
             }
 
@@ -700,10 +966,12 @@ macro_rules! create_graphic_pipeline_impl {
                         ($shader_stage, $shader_code.to_vec()),
                     )*
                 ].into_iter().collect(),
+                settings: $settings,
                 $($frm_uniforms_build)*
                 $($obj_uniforms_build)*
                 $($rc_uniforms_build)*
                 $($texture_uniforms_build)*
+                $($shadow_uniforms_build)*
             }
         } }
     }
@@ -735,8 +1003,29 @@ macro_rules! create_graphic_pipeline_impl {
 /// - FrameUniform : a uniform set once per frame
 /// - ObjectUniform : a uniform set once per object
 /// - RenderableComponent : a uniform required to have exactly once, and that is used to render the object.
-/// 
+///
+/// Some uniforms are given without a name, as `([type], [stage])` :
+/// - TexturesUniform : all the loaded textures.
+/// - EnvironmentUniform : the prefiltered specular and irradiance cubemaps of the environment, at bindings 0 and 1 of the set.
+/// - ShadowMapUniform : the cascades and the shadow map of the main directionnal light, at bindings 0 and 1 of the set.
+/// - ShadowCascadesUniform : the cascades only, for the pipelines drawing the shadow casters.
+///
 /// The name must be a valid struct name, that implements the corresponding uniform trait.
+///
+/// A pipeline can have several object uniforms and renderable components, up to 8 of them combined:
+/// it draws the entities that have all of them. The first renderable component drives the draws,
+/// its mesh and instances are used for every per object uniform.
+/// 
+/// Before the uniforms, the type of mesh the pipeline draws can be given with `(Mesh, [type]),`.
+/// It is the mesh type parameter of the renderable components, and sets the vertex layout of the pipeline.
+/// If it is not specified, the pipeline draws static meshes.
+/// For meshes with a custom vertex layout, `(Vertex, [type]),` can be given instead: the pipeline then draws `Mesh<[type], u32>`,
+/// the mesh type of `MeshType::custom_mesh`.
+/// 
+/// After the mesh type, the fixed function settings of the pipeline can be given with `(Settings, [expr]),`,
+/// where the expression evaluates to a `GraphicPipelineSettings`. This sets the primitive topology (triangles, lines, points),
+/// the polygon mode, the line width, the culling, the depth test and bias, and the blending of each color attachment.
+/// If they are not specified, the pipeline draws opaque filled triangles, back face culled and depth tested.
 /// 
 /// The stage is the shader stage where the uniform will be used.
 /// 
@@ -745,17 +1034,66 @@ macro_rules! create_graphic_pipeline_impl {
 macro_rules! create_graphic_pipeline {
     (
         $(($shader_stage:path, $shader_code:ident)),*;
+        (Vertex, $vertex_type:ty),
+        $($uniform_data:tt)*
+    ) => {
+        crate::create_graphic_pipeline!(
+            $(($shader_stage, $shader_code)),*;
+            (Mesh, crate::engine::mesh::Mesh<$vertex_type, u32>),
+            $($uniform_data)*
+        )
+    };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        (Mesh, $mesh_type:ty),
+        (Settings, $settings:expr),
         $($uniform_data:tt)*
     ) => {
         crate::create_graphic_pipeline_impl!(
             $(($shader_stage, $shader_code)),*;
-            (); (); (); (); // uniforms declaration
-            (); (); (); (); // built buffers declaration
-            (); (); (); (); // uniforms field
-            (); (); (); (); // uniforms build
+            $mesh_type; // mesh type the pipeline draws
+            $settings; // fixed function settings of the pipeline
+            (); (); (); (); (); // uniforms declaration
+            (); (); (); (); (); // built buffers declaration
+            (); (); (); (); (); // uniforms field
+            (); (); (); (); (); // uniforms build
             (); (); (); (); // uniforms types
             (); // ordered fields
             $($uniform_data)*
         )
     };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        (Mesh, $mesh_type:ty),
+        $($uniform_data:tt)*
+    ) => {
+        crate::create_graphic_pipeline!(
+            $(($shader_stage, $shader_code)),*;
+            (Mesh, $mesh_type),
+            (Settings, crate::engine::renderer::graphic_pipeline::graphic_pipeline_settings::GraphicPipelineSettings::default()),
+            $($uniform_data)*
+        )
+    };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        (Settings, $settings:expr),
+        $($uniform_data:tt)*
+    ) => {
+        crate::create_graphic_pipeline!(
+            $(($shader_stage, $shader_code)),*;
+            (Mesh, crate::StaticMesh), // default to static meshes
+            (Settings, $settings),
+            $($uniform_data)*
+        )
+    };
+    (
+        $(($shader_stage:path, $shader_code:ident)),*;
+        $($uniform_data:tt)*
+    ) => {
+        crate::create_graphic_pipeline!(
+            $(($shader_stage, $shader_code)),*;
+            (Mesh, crate::StaticMesh), // default to static meshes
+            $($uniform_data)*
+        )
+    };
 }
@@ -1,20 +1,136 @@
+use vulkanalia::vk::InstanceV1_0;
 
+use crate::engine::consts::PROPELLANT_DEBUG_FEATURES;
 
+use super::graphic_pipeline_gen::ShaderStage;
+
+/// How the pipeline uses the depth buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DepthSetting {
+    /// No depth test, no depth write: everything is drawn on top of what is already there.
     None,
+    /// Depth test against the depth buffer, but does not write to it. Used for transparent objects.
     Read,
+    /// Always write to the depth buffer, without testing against it.
     Write,
+    /// Depth test and write, the usual setting for opaque objects.
     ReadWrite,
 }
 
+/// Comparison used by the depth test, between the fragment depth and the stored depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+/// Which faces of the triangles are discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+/// Winding order of the front faces of triangles, as seen on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// How the output color of the fragment is blended with the color already in the attachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The fragment color replaces the attachment color.
+    Opaque,
+    /// Classic transparency: `src * src_alpha + dst * (1 - src_alpha)`.
+    AlphaBlend,
+    /// Transparency with colors already multiplied by their alpha: `src + dst * (1 - src_alpha)`.
+    PremultipliedAlpha,
+    /// The fragment color is added to the attachment color, for glows and particles.
+    Additive,
+    /// The fragment color is multiplied with the attachment color, for decals and stains.
+    Multiply,
+}
+
+/// How the vertices of the meshes are assembled into primitives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    /// Every three indices form a triangle.
+    TriangleList,
+    /// Every index forms a triangle with the two previous ones.
+    TriangleStrip,
+    /// Every two indices form a line.
+    LineList,
+    /// Every index forms a line with the previous one.
+    LineStrip,
+    /// Every index is a point.
+    PointList,
+}
+
+/// How the triangles are rasterized.
+/// Anything else than `Fill` requires the `fillModeNonSolid` device feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+    Fill,
+    /// Only draw the edges of the triangles, for wireframes.
+    Line,
+    /// Only draw the vertices of the triangles.
+    Point,
+}
+
+/// Offset added to the depth of the fragments, to avoid z-fighting of coplanar geometry like decals or shadows.
+#[derive(Debug, Clone, Copy)]
+struct DepthBias {
+    constant_factor: f32,
+    slope_factor: f32,
+}
+
+/// Fixed function settings of a graphic pipeline: how primitives are assembled, rasterized,
+/// depth tested and blended into the attachments.
+#[derive(Debug, Clone)]
 pub struct GraphicPipelineSettings {
     depth: DepthSetting,
+    depth_compare_op: CompareOp,
+    depth_bias: Option<DepthBias>,
+    cull_mode: CullMode,
+    front_face: FrontFace,
+    topology: PrimitiveTopology,
+    polygon_mode: PolygonMode,
+    line_width: f32,
+    /// blend mode of each color attachment, in the order of the attachments of the subpass.
+    attachment_blends: Vec<BlendMode>,
+    /// transparent pipelines are drawn after the opaque ones, with their instances sorted back to front.
+    transparent: bool,
+    /// stage and size in bytes of the push constants of the pipeline.
+    push_constants: Option<(ShaderStage, u32)>,
+    /// whether the uniform buffer indices of the objects are kept by the pipeline, rather than by their renderable components.
+    own_instance_indices: bool,
 }
 
 impl Default for GraphicPipelineSettings {
     fn default() -> Self {
         GraphicPipelineSettings {
             depth: DepthSetting::ReadWrite,
+            depth_compare_op: CompareOp::Less,
+            depth_bias: None,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            topology: PrimitiveTopology::TriangleList,
+            polygon_mode: PolygonMode::Fill,
+            line_width: 1.0,
+            attachment_blends: vec![BlendMode::Opaque],
+            transparent: false,
+            push_constants: None,
+            own_instance_indices: false,
         }
     }
 }
@@ -22,7 +138,311 @@ impl Default for GraphicPipelineSettings {
 impl GraphicPipelineSettings {
     pub fn depth(self, depth: DepthSetting) -> GraphicPipelineSettings {
         Self {
-            depth,           
+            depth,
+            ..self
+        }
+    }
+
+    /// Comparison of the depth test. Skyboxes drawn at the far plane use `LessOrEqual`.
+    pub fn depth_compare_op(self, depth_compare_op: CompareOp) -> GraphicPipelineSettings {
+        Self {
+            depth_compare_op,
+            ..self
+        }
+    }
+
+    /// Offset the depth of the fragments by `constant_factor` plus `slope_factor` times the depth slope of the polygon.
+    pub fn depth_bias(self, constant_factor: f32, slope_factor: f32) -> GraphicPipelineSettings {
+        Self {
+            depth_bias: Some(DepthBias { constant_factor, slope_factor }),
+            ..self
+        }
+    }
+
+    pub fn cull_mode(self, cull_mode: CullMode) -> GraphicPipelineSettings {
+        Self {
+            cull_mode,
+            ..self
+        }
+    }
+
+    pub fn front_face(self, front_face: FrontFace) -> GraphicPipelineSettings {
+        Self {
+            front_face,
+            ..self
+        }
+    }
+
+    pub fn topology(self, topology: PrimitiveTopology) -> GraphicPipelineSettings {
+        Self {
+            topology,
+            ..self
+        }
+    }
+
+    pub fn polygon_mode(self, polygon_mode: PolygonMode) -> GraphicPipelineSettings {
+        Self {
+            polygon_mode,
+            ..self
+        }
+    }
+
+    /// Width of the rasterized lines, in pixels.
+    /// Widths other than 1.0 require the `wideLines` device feature:
+    /// when the pipeline is built, the width is clamped to the range supported by the device.
+    pub fn line_width(self, line_width: f32) -> GraphicPipelineSettings {
+        Self {
+            line_width,
+            ..self
+        }
+    }
+
+    /// Clamp the line width to the range the device can rasterize, given by `supported_line_widths`.
+    pub fn clamp_line_width(self, supported: [f32; 2]) -> GraphicPipelineSettings {
+        let line_width = self.line_width.clamp(supported[0], supported[1]);
+        if PROPELLANT_DEBUG_FEATURES && line_width != self.line_width {
+            println!("[PROPELLANT DEBUG] Line width of {} is not supported by the device, clamped to {}", self.line_width, line_width);
+        }
+        Self {
+            line_width,
+            ..self
+        }
+    }
+
+    /// Set the blend mode of all the color attachments.
+    pub fn blend(mut self, blend: BlendMode) -> GraphicPipelineSettings {
+        self.attachment_blends.iter_mut().for_each(|b| *b = blend);
+        self
+    }
+
+    /// Set the blend mode of a single color attachment.
+    /// Attachments without a blend mode are opaque.
+    pub fn attachment_blend(mut self, attachment: usize, blend: BlendMode) -> GraphicPipelineSettings {
+        if self.attachment_blends.len() <= attachment {
+            self.attachment_blends.resize(attachment + 1, BlendMode::Opaque);
+        }
+        self.attachment_blends[attachment] = blend;
+        self
+    }
+
+    /// Make the pipeline transparent: it alpha blends, tests against the depth buffer without writing to it,
+    /// is recorded after the opaque pipelines and sorts its instances back to front every frame.
+    pub fn transparent(self) -> GraphicPipelineSettings {
+        Self {
+            transparent: true,
+            ..self
+        }.blend(BlendMode::AlphaBlend).depth(DepthSetting::Read)
+    }
+
+    /// Make the pipeline draw in a render pass without color attachments, like shadow maps.
+    /// Such pipelines usually have no fragment shader.
+    pub fn depth_only(self) -> GraphicPipelineSettings {
+        Self {
+            attachment_blends: Vec::with_capacity(0),
+            ..self
+        }
+    }
+
+    /// Declare push constants of `size` bytes, starting at offset 0, used in the given stage.
+    /// They are recorded in the command buffer with `GraphicPipelineInterface::push_constants`.
+    pub fn push_constants(self, stage: ShaderStage, size: u32) -> GraphicPipelineSettings {
+        Self {
+            push_constants: Some((stage, size)),
+            ..self
+        }
+    }
+
+    /// Keep the uniform buffer indices of the drawn objects in the pipeline, instead of writing them in their renderable components.
+    /// Pipelines drawing the same components as another pipeline, like the shadow casters, need it
+    /// so they don't overwrite the indices of the other one.
+    pub fn own_instance_indices(self) -> GraphicPipelineSettings {
+        Self {
+            own_instance_indices: true,
+            ..self
+        }
+    }
+
+    pub fn owns_instance_indices(&self) -> bool {
+        self.own_instance_indices
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    pub fn depth_setting(&self) -> DepthSetting {
+        self.depth
+    }
+
+    pub fn attachment_blends(&self) -> &[BlendMode] {
+        &self.attachment_blends
+    }
+
+    pub fn vk_topology(&self) -> vulkanalia::vk::PrimitiveTopology {
+        match self.topology {
+            PrimitiveTopology::TriangleList => vulkanalia::vk::PrimitiveTopology::TRIANGLE_LIST,
+            PrimitiveTopology::TriangleStrip => vulkanalia::vk::PrimitiveTopology::TRIANGLE_STRIP,
+            PrimitiveTopology::LineList => vulkanalia::vk::PrimitiveTopology::LINE_LIST,
+            PrimitiveTopology::LineStrip => vulkanalia::vk::PrimitiveTopology::LINE_STRIP,
+            PrimitiveTopology::PointList => vulkanalia::vk::PrimitiveTopology::POINT_LIST,
         }
     }
-}
\ No newline at end of file
+
+    pub fn vk_polygon_mode(&self) -> vulkanalia::vk::PolygonMode {
+        match self.polygon_mode {
+            PolygonMode::Fill => vulkanalia::vk::PolygonMode::FILL,
+            PolygonMode::Line => vulkanalia::vk::PolygonMode::LINE,
+            PolygonMode::Point => vulkanalia::vk::PolygonMode::POINT,
+        }
+    }
+
+    pub fn vk_line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Whether the primitives are triangles. Culling only applies to triangles.
+    pub fn draws_triangles(&self) -> bool {
+        matches!(self.topology, PrimitiveTopology::TriangleList | PrimitiveTopology::TriangleStrip)
+    }
+
+    pub fn vk_cull_mode(&self) -> vulkanalia::vk::CullModeFlags {
+        if !self.draws_triangles() {
+            return vulkanalia::vk::CullModeFlags::NONE;
+        }
+        match self.cull_mode {
+            CullMode::None => vulkanalia::vk::CullModeFlags::NONE,
+            CullMode::Front => vulkanalia::vk::CullModeFlags::FRONT,
+            CullMode::Back => vulkanalia::vk::CullModeFlags::BACK,
+            CullMode::FrontAndBack => vulkanalia::vk::CullModeFlags::FRONT_AND_BACK,
+        }
+    }
+
+    pub fn vk_front_face(&self) -> vulkanalia::vk::FrontFace {
+        match self.front_face {
+            FrontFace::Clockwise => vulkanalia::vk::FrontFace::CLOCKWISE,
+            FrontFace::CounterClockwise => vulkanalia::vk::FrontFace::COUNTER_CLOCKWISE,
+        }
+    }
+
+    pub fn vk_rasterization_state(&self) -> vulkanalia::vk::PipelineRasterizationStateCreateInfo {
+        use vulkanalia::vk::HasBuilder;
+        let (depth_bias_enable, constant_factor, slope_factor) = match self.depth_bias {
+            Some(bias) => (true, bias.constant_factor, bias.slope_factor),
+            None => (false, 0.0, 0.0),
+        };
+        vulkanalia::vk::PipelineRasterizationStateCreateInfo::builder()
+            .depth_clamp_enable(false)
+            .rasterizer_discard_enable(false)
+            .polygon_mode(self.vk_polygon_mode())
+            .line_width(self.vk_line_width())
+            .cull_mode(self.vk_cull_mode())
+            .front_face(self.vk_front_face())
+            .depth_bias_enable(depth_bias_enable)
+            .depth_bias_constant_factor(constant_factor)
+            .depth_bias_slope_factor(slope_factor)
+            .depth_bias_clamp(0.0)
+            .build()
+    }
+
+    pub fn vk_depth_stencil_state(&self) -> vulkanalia::vk::PipelineDepthStencilStateCreateInfo {
+        use vulkanalia::vk::HasBuilder;
+        let (test, write, compare_op) = match self.depth {
+            DepthSetting::None => (false, false, self.depth_compare_op),
+            DepthSetting::Read => (true, false, self.depth_compare_op),
+            // the depth test have to be enabled for depth writes to happen, so make it always pass.
+            DepthSetting::Write => (true, true, CompareOp::Always),
+            DepthSetting::ReadWrite => (true, true, self.depth_compare_op),
+        };
+        vulkanalia::vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(test)
+            .depth_write_enable(write)
+            .depth_compare_op(vk_compare_op(compare_op))
+            .depth_bounds_test_enable(false)
+            .stencil_test_enable(false)
+            .build()
+    }
+
+    pub fn vk_push_constant_ranges(&self) -> Vec<vulkanalia::vk::PushConstantRange> {
+        use vulkanalia::vk::HasBuilder;
+        match self.push_constants {
+            Some((stage, size)) => vec![
+                vulkanalia::vk::PushConstantRange::builder()
+                    .stage_flags(stage.into())
+                    .offset(0)
+                    .size(size)
+                    .build()
+            ],
+            None => Vec::with_capacity(0),
+        }
+    }
+
+    pub fn vk_color_blend_attachments(&self) -> Vec<vulkanalia::vk::PipelineColorBlendAttachmentState> {
+        self.attachment_blends.iter().map(|blend| vk_blend_attachment(*blend)).collect()
+    }
+}
+
+fn vk_compare_op(compare_op: CompareOp) -> vulkanalia::vk::CompareOp {
+    match compare_op {
+        CompareOp::Never => vulkanalia::vk::CompareOp::NEVER,
+        CompareOp::Less => vulkanalia::vk::CompareOp::LESS,
+        CompareOp::Equal => vulkanalia::vk::CompareOp::EQUAL,
+        CompareOp::LessOrEqual => vulkanalia::vk::CompareOp::LESS_OR_EQUAL,
+        CompareOp::Greater => vulkanalia::vk::CompareOp::GREATER,
+        CompareOp::NotEqual => vulkanalia::vk::CompareOp::NOT_EQUAL,
+        CompareOp::GreaterOrEqual => vulkanalia::vk::CompareOp::GREATER_OR_EQUAL,
+        CompareOp::Always => vulkanalia::vk::CompareOp::ALWAYS,
+    }
+}
+
+fn vk_blend_attachment(blend: BlendMode) -> vulkanalia::vk::PipelineColorBlendAttachmentState {
+    use vulkanalia::vk::HasBuilder;
+    use vulkanalia::vk::BlendFactor;
+    let builder = vulkanalia::vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vulkanalia::vk::ColorComponentFlags::all())
+        .color_blend_op(vulkanalia::vk::BlendOp::ADD)
+        .alpha_blend_op(vulkanalia::vk::BlendOp::ADD);
+    // (src color, dst color, src alpha, dst alpha)
+    let factors = match blend {
+        BlendMode::Opaque => return builder.blend_enable(false).build(),
+        BlendMode::AlphaBlend => (BlendFactor::SRC_ALPHA, BlendFactor::ONE_MINUS_SRC_ALPHA, BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA),
+        BlendMode::PremultipliedAlpha => (BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA, BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Additive => (BlendFactor::SRC_ALPHA, BlendFactor::ONE, BlendFactor::ZERO, BlendFactor::ONE),
+        BlendMode::Multiply => (BlendFactor::DST_COLOR, BlendFactor::ZERO, BlendFactor::ZERO, BlendFactor::ONE),
+    };
+    builder
+        .blend_enable(true)
+        .src_color_blend_factor(factors.0)
+        .dst_color_blend_factor(factors.1)
+        .src_alpha_blend_factor(factors.2)
+        .dst_alpha_blend_factor(factors.3)
+        .build()
+}
+
+/// Range of line widths the device can rasterize. Without the `wideLines` feature, only 1.0 is allowed.
+pub fn supported_line_widths(vk_instance: &vulkanalia::Instance, vk_physical_device: vulkanalia::vk::PhysicalDevice) -> [f32; 2] {
+    let features = unsafe { vk_instance.get_physical_device_features(vk_physical_device) };
+    if features.wide_lines != vulkanalia::vk::TRUE {
+        return [1.0, 1.0];
+    }
+    let properties = unsafe { vk_instance.get_physical_device_properties(vk_physical_device) };
+    properties.limits.line_width_range
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphicPipelineSettings;
+
+    #[test]
+    fn line_width_is_clamped_without_wide_lines() {
+        let settings = GraphicPipelineSettings::default().line_width(3.0).clamp_line_width([1.0, 1.0]);
+        assert_eq!(settings.vk_line_width(), 1.0);
+    }
+
+    #[test]
+    fn line_width_is_clamped_to_the_device_range() {
+        let settings = GraphicPipelineSettings::default().line_width(3.0).clamp_line_width([1.0, 8.0]);
+        assert_eq!(settings.vk_line_width(), 3.0);
+        let settings = GraphicPipelineSettings::default().line_width(16.0).clamp_line_width([1.0, 8.0]);
+        assert_eq!(settings.vk_line_width(), 8.0);
+    }
+}
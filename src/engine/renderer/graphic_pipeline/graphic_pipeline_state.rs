@@ -1,5 +1,7 @@
 use vulkanalia::vk::DeviceV1_0;
 
+use super::graphic_pipeline_settings::GraphicPipelineSettings;
+
 pub struct GraphicPipelineCreationState {
     pub stages: Vec<vulkanalia::vk::PipelineShaderStageCreateInfo>,
     pub vertex_input_state: vulkanalia::vk::PipelineVertexInputStateCreateInfo,
@@ -11,6 +13,8 @@ pub struct GraphicPipelineCreationState {
     pub color_blend_state: vulkanalia::vk::PipelineColorBlendStateCreateInfo,
     pub depth_stencil_state: vulkanalia::vk::PipelineDepthStencilStateCreateInfo,
     pub color_blend_attachments: Vec<vulkanalia::vk::PipelineColorBlendAttachmentState>,
+    /// settings the states were created from, kept so that recreated pipelines are identical.
+    pub settings: GraphicPipelineSettings,
 }
 
 impl GraphicPipelineCreationState {
@@ -1,6 +1,6 @@
-
 pub(crate) mod uniform_buffer;
 pub(crate) mod frame_uniform;
 pub(crate) mod object_uniform;
 pub(crate) mod textures_uniform;
-
+pub(crate) mod shadow_map_uniform;
+pub(crate) mod environment_uniform;
@@ -0,0 +1,152 @@
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::resources::texture_library::TextureLibrary;
+
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+#[derive(Debug)]
+pub struct EnvironmentUniformBuilder {
+    stage: vulkanalia::vk::ShaderStageFlags,
+}
+
+impl EnvironmentUniformBuilder {
+    pub fn new(stage: vulkanalia::vk::ShaderStageFlags) -> Self {
+        Self {
+            stage,
+        }
+    }
+
+    /// Descriptor types of the bindings of the set: the specular map at binding 0, the irradiance map at binding 1.
+    pub fn descriptor_types(&self) -> Vec<vulkanalia::vk::DescriptorType> {
+        vec![
+            vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        ]
+    }
+
+    pub fn build(
+        self,
+        vk_device: &vulkanalia::Device,
+        vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
+        image_count: usize,
+    ) -> PResult<EnvironmentUniform> {
+        EnvironmentUniform::new(
+            &self,
+            vk_device,
+            vk_descriptor_pool,
+            image_count,
+        )
+    }
+}
+
+/// Uniform binding the prefiltered cubemaps of the environment.
+/// The cubemaps are owned by the texture library, we only hold the descriptor sets pointing to them.
+#[derive(Debug)]
+pub struct EnvironmentUniform {
+    /// DS layout
+    layout: vulkanalia::vk::DescriptorSetLayout,
+    /// descriptor sets for each frame.
+    descriptor_sets: Vec<vulkanalia::vk::DescriptorSet>,
+}
+
+impl EnvironmentUniform {
+    pub fn new(
+        builder: &EnvironmentUniformBuilder,
+        vk_device: &vulkanalia::Device,
+        vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
+        image_count: usize,
+    ) -> PResult<Self> {
+        // the layout is a blueprint on how the descriptor set matches the shader.
+        let layout_bindings = builder.descriptor_types().into_iter().enumerate().map(|(binding, descriptor_type)| {
+            vulkanalia::vk::DescriptorSetLayoutBinding::builder()
+                .binding(binding as u32)
+                .descriptor_type(descriptor_type)
+                .descriptor_count(1)
+                .stage_flags(builder.stage)
+                .build()
+        }).collect::<Vec<_>>();
+
+        let info = vulkanalia::vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&layout_bindings);
+
+        let layout = unsafe { vk_device.create_descriptor_set_layout(&info, None)? };
+
+        // create one descriptor set per swapchain image.
+        let layouts = vec![layout; image_count];
+        let info = vulkanalia::vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(vk_descriptor_pool)
+            .set_layouts(&layouts);
+
+        let descriptor_sets = unsafe { vk_device.allocate_descriptor_sets(&info)? };
+
+        Ok(EnvironmentUniform {
+            layout,
+            descriptor_sets,
+        })
+    }
+
+    pub fn layout(&self) -> vulkanalia::vk::DescriptorSetLayout {
+        self.layout
+    }
+
+    pub fn set(&self, image_index: usize) -> vulkanalia::vk::DescriptorSet {
+        self.descriptor_sets[image_index]
+    }
+
+    /// Point the descriptor set of the image to the current environment of the texture library,
+    /// which is the black environment until the environment cubemap is loaded.
+    /// This has the signature of the textures uniform, so both are reloaded together.
+    pub fn populate_descriptor_sets(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        image_index: usize,
+        _descriptor_pool: vulkanalia::vk::DescriptorPool,
+        textures: &TextureLibrary,
+    ) -> PResult<()> {
+        let environment = match textures.environment() {
+            Some(environment) => environment,
+            // the black environment is loaded with the first textures, the descriptors can't be left unwritten.
+            None => return Err(PropellantError::Custom(
+                "The environment descriptors can not be written before the black environment is loaded".to_string()
+            )),
+        };
+
+        let specular_info = &[vulkanalia::vk::DescriptorImageInfo::builder()
+            .image_layout(vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(environment.specular_view())
+            .sampler(environment.sampler())
+        ];
+        let irradiance_info = &[vulkanalia::vk::DescriptorImageInfo::builder()
+            .image_layout(vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(environment.irradiance_view())
+            .sampler(environment.sampler())
+        ];
+
+        let specular_write = vulkanalia::vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_sets[image_index])
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(specular_info);
+
+        let irradiance_write = vulkanalia::vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_sets[image_index])
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(irradiance_info);
+
+        unsafe {
+            vk_device.update_descriptor_sets(&[specular_write, irradiance_write], &[] as &[vulkanalia::vk::CopyDescriptorSet]);
+        }
+
+        Ok(())
+    }
+
+    // the cubemaps are owned by the texture library, only the layout is ours.
+    pub fn destroy_buffer(&mut self, vk_device: &vulkanalia::Device) {
+        unsafe {
+            vk_device.destroy_descriptor_set_layout(self.layout, None);
+        }
+    }
+}
@@ -4,10 +4,14 @@ use foundry::ComponentTable;
 
 pub(crate) mod camera_uniform;
 pub(crate) mod main_directionnal_light;
+pub(crate) mod local_lights;
 
 /// handle around a per frame uniform
 /// It acts as the layer between our raw uniform buffer and a more abstract uniform object.
 pub trait FrameUniform: Debug + Sized {
+    /// Frame uniforms are uniform buffers, that are fast but limited in size.
+    /// Big uniforms should use storage buffers instead.
+    const DESCRIPTOR_TYPE: vulkanalia::vk::DescriptorType = vulkanalia::vk::DescriptorType::UNIFORM_BUFFER;
     /// Set the uniform to the gpu buffer.
     /// The write_to_buf function is a closure sending data to the buffer, and should be called with the according data.
     fn set_uniform(components: &ComponentTable, write_to_buf: &mut dyn FnMut(&[Self]));
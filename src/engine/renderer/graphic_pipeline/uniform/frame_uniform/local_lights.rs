@@ -0,0 +1,438 @@
+use foundry::ComponentTable;
+
+use crate::{
+    Transform,
+    Camera,
+    PointLight,
+    SpotLight,
+};
+
+use super::FrameUniform;
+
+/// Maximum number of point and spot lights sent to the shaders.
+/// When there are more lights in the scene, the ones closest to the main camera are kept.
+pub const MAX_LOCAL_LIGHTS: usize = 256;
+/// Number of clusters the view frustum is split into, along the screen width, the screen height and the depth.
+/// These must match the constants of the lit shaders.
+pub const CLUSTER_GRID_SIZE: (usize, usize, usize) = (16, 9, 16);
+const CLUSTER_COUNT: usize = CLUSTER_GRID_SIZE.0 * CLUSTER_GRID_SIZE.1 * CLUSTER_GRID_SIZE.2;
+/// Maximum number of light references, summed over all the clusters.
+/// Once reached, the remaining lights are ignored in the remaining clusters.
+pub const MAX_CLUSTER_LIGHT_INDICES: usize = 16384;
+
+/// A point or spot light, as sent to the shaders.
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, Clone, Copy)]
+struct LocalLight {
+    position: glam::Vec3,
+    range: f32,
+    color: glam::Vec3,
+    intensity: f32,
+    direction: glam::Vec3,
+    /// point lights have cones cosines of -2 and -1, so they are never faded.
+    cos_outer_cone: f32,
+    cos_inner_cone: f32,
+    _padd_0: f32,
+    _padd_1: f32,
+    _padd_2: f32,
+}
+
+impl LocalLight {
+    const NONE: LocalLight = LocalLight {
+        position: glam::Vec3::ZERO,
+        range: 0.0,
+        color: glam::Vec3::ZERO,
+        intensity: 0.0,
+        direction: glam::Vec3::NEG_Z,
+        cos_outer_cone: -2.0,
+        cos_inner_cone: -1.0,
+        _padd_0: 0.0,
+        _padd_1: 0.0,
+        _padd_2: 0.0,
+    };
+
+    fn from_point_light(transform: &Transform, light: &PointLight) -> LocalLight {
+        LocalLight {
+            position: transform.world_pos().w_axis.truncate(),
+            range: light.range,
+            color: light.color,
+            intensity: light.intensity,
+            direction: glam::Vec3::NEG_Z,
+            cos_outer_cone: -2.0,
+            cos_inner_cone: -1.0,
+            _padd_0: 0.0,
+            _padd_1: 0.0,
+            _padd_2: 0.0,
+        }
+    }
+
+    fn from_spot_light(transform: &Transform, light: &SpotLight) -> LocalLight {
+        let world_pos = transform.world_pos();
+        let cos_outer_cone = light.outer_cone_angle.cos();
+        LocalLight {
+            position: world_pos.w_axis.truncate(),
+            range: light.range,
+            color: light.color,
+            intensity: light.intensity,
+            direction: world_pos.transform_vector3(glam::Vec3::NEG_Z).normalize_or_zero(),
+            cos_outer_cone,
+            // the fade is undefined if the inner cone is not strictly inside the outer one.
+            cos_inner_cone: light.inner_cone_angle.cos().max(cos_outer_cone + 0.0001),
+            _padd_0: 0.0,
+            _padd_1: 0.0,
+            _padd_2: 0.0,
+        }
+    }
+}
+
+/// All the point and spot lights of the scene, sorted into clusters of the view frustum of the main camera.
+/// Each fragment only loops over the lights of its cluster, so scenes can have a lot of local lights.
+/// This is a storage buffer, as it is too big for uniform buffers.
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, Clone)]
+pub struct LocalLights {
+    view: glam::Mat4,
+    view_projection: glam::Mat4,
+    z_near: f32,
+    z_far: f32,
+    light_count: u32,
+    _padd: u32,
+    lights: [LocalLight; MAX_LOCAL_LIGHTS],
+    /// for each cluster, the offset of its first light index and its light count.
+    clusters: [[u32; 2]; CLUSTER_COUNT],
+    light_indices: [u32; MAX_CLUSTER_LIGHT_INDICES],
+}
+
+impl LocalLights {
+    fn empty() -> Box<LocalLights> {
+        Box::new(LocalLights {
+            view: glam::Mat4::IDENTITY,
+            view_projection: glam::Mat4::IDENTITY,
+            z_near: 0.1,
+            z_far: 1.0,
+            light_count: 0,
+            _padd: 0,
+            lights: [LocalLight::NONE; MAX_LOCAL_LIGHTS],
+            clusters: [[0; 2]; CLUSTER_COUNT],
+            light_indices: [0; MAX_CLUSTER_LIGHT_INDICES],
+        })
+    }
+
+    /// Compute the view space bounding boxes of the clusters.
+    /// The screen is split in tiles in normalized device coordinates, and the depth is split exponentially,
+    /// so clusters are about as deep as they are wide on screen.
+    fn cluster_bounds(&self, projection: glam::Mat4) -> Vec<(glam::Vec3, glam::Vec3)> {
+        let (x_count, y_count, z_count) = CLUSTER_GRID_SIZE;
+        let inverse_projection = projection.inverse();
+        let unproject = |x: f32, y: f32, depth: f32| {
+            let clip = projection * glam::Vec4::new(0.0, 0.0, -depth, 1.0);
+            let view = inverse_projection * glam::Vec4::new(x, y, clip.z / clip.w, 1.0);
+            view.truncate() / view.w
+        };
+        let mut bounds = Vec::with_capacity(CLUSTER_COUNT);
+        for z in 0..z_count {
+            let near = self.slice_depth(z);
+            let far = self.slice_depth(z + 1);
+            for y in 0..y_count {
+                let y0 = -1.0 + 2.0 * y as f32 / y_count as f32;
+                let y1 = -1.0 + 2.0 * (y + 1) as f32 / y_count as f32;
+                for x in 0..x_count {
+                    let x0 = -1.0 + 2.0 * x as f32 / x_count as f32;
+                    let x1 = -1.0 + 2.0 * (x + 1) as f32 / x_count as f32;
+                    let corners = [
+                        unproject(x0, y0, near), unproject(x1, y0, near), unproject(x0, y1, near), unproject(x1, y1, near),
+                        unproject(x0, y0, far), unproject(x1, y0, far), unproject(x0, y1, far), unproject(x1, y1, far),
+                    ];
+                    let min = corners.iter().fold(glam::Vec3::splat(f32::INFINITY), |min, c| min.min(*c));
+                    let max = corners.iter().fold(glam::Vec3::splat(f32::NEG_INFINITY), |max, c| max.max(*c));
+                    bounds.push((min, max));
+                }
+            }
+        }
+        bounds
+    }
+
+    /// View depth of the near side of a depth slice.
+    fn slice_depth(&self, slice: usize) -> f32 {
+        self.z_near * (self.z_far / self.z_near).powf(slice as f32 / CLUSTER_GRID_SIZE.2 as f32)
+    }
+
+    /// Depth slice containing the view depth, clamped to the frustum.
+    fn depth_slice(&self, depth: f32) -> usize {
+        let slice = (depth.max(self.z_near) / self.z_near).ln() / (self.z_far / self.z_near).ln() * CLUSTER_GRID_SIZE.2 as f32;
+        (slice.max(0.0) as usize).min(CLUSTER_GRID_SIZE.2 - 1)
+    }
+
+    /// Fill the clusters with the lights whose range intersect them.
+    /// The bounds are the ones computed by `cluster_bounds` for the current projection.
+    fn assign_clusters(&mut self, bounds: &[(glam::Vec3, glam::Vec3)]) {
+        let (x_count, y_count, _) = CLUSTER_GRID_SIZE;
+        let mut cluster_lights: Vec<Vec<u32>> = vec![Vec::new(); CLUSTER_COUNT];
+        for (light_index, light) in self.lights[..self.light_count as usize].iter().enumerate() {
+            let center = self.view.transform_point3(light.position);
+            let depth = -center.z;
+            if depth + light.range < self.z_near || depth - light.range > self.z_far {
+                continue;
+            }
+            // only look at the depth slices the light range overlaps.
+            for z in self.depth_slice(depth - light.range)..=self.depth_slice(depth + light.range) {
+                for cluster in z * x_count * y_count..(z + 1) * x_count * y_count {
+                    let (min, max) = bounds[cluster];
+                    if center.clamp(min, max).distance_squared(center) <= light.range * light.range {
+                        cluster_lights[cluster].push(light_index as u32);
+                    }
+                }
+            }
+        }
+        let mut offset = 0;
+        for (cluster, lights) in cluster_lights.into_iter().enumerate() {
+            let count = lights.len().min(MAX_CLUSTER_LIGHT_INDICES - offset);
+            self.light_indices[offset..offset + count].copy_from_slice(&lights[..count]);
+            self.clusters[cluster] = [offset as u32, count as u32];
+            offset += count;
+        }
+    }
+}
+
+/// The main camera and the local lights of the scene, as read from the components on a frame.
+struct LocalLightsScene {
+    view: glam::Mat4,
+    projection: glam::Mat4,
+    z_near: f32,
+    z_far: f32,
+    lights: Vec<LocalLight>,
+}
+
+impl LocalLightsScene {
+    /// Look for the main camera and the lights it should see. None if there is no main camera.
+    fn gather(components: &ComponentTable) -> Option<LocalLightsScene> {
+        let mut camera = None;
+        for (_, tf, cam) in components.query2d::<Transform, Camera>() {
+            if cam.is_main() {
+                // orthographic cameras may have their near plane at zero, but the depth slices need a positive one.
+                let z_near = cam.z_near().max(0.001);
+                // the camera transform is used as the view matrix
+                camera = Some((tf.world_pos(), cam.projection_matrix(), z_near, cam.z_far().max(z_near + 0.001)));
+                break;
+            }
+        }
+        let (view, projection, z_near, z_far) = camera?;
+
+        let mut lights = Vec::new();
+        for (_, tf, light) in components.query2d::<Transform, PointLight>() {
+            lights.push(LocalLight::from_point_light(tf, light));
+        }
+        for (_, tf, light) in components.query2d::<Transform, SpotLight>() {
+            lights.push(LocalLight::from_spot_light(tf, light));
+        }
+        // keep the lights that affect the closest surfaces to the camera.
+        if lights.len() > MAX_LOCAL_LIGHTS {
+            let camera_position = view.inverse().w_axis.truncate();
+            lights.sort_by(|l1, l2| {
+                let d1 = l1.position.distance(camera_position) - l1.range;
+                let d2 = l2.position.distance(camera_position) - l2.range;
+                d1.total_cmp(&d2)
+            });
+            lights.truncate(MAX_LOCAL_LIGHTS);
+        }
+
+        Some(LocalLightsScene { view, projection, z_near, z_far, lights })
+    }
+}
+
+/// Singleton holding the clustered local lights of the current frame.
+/// The lights are sorted into the clusters once per frame by the renderer, and every lit pipeline uploads the same result.
+/// The cluster bounds are only computed again when the projection of the main camera changes.
+///
+/// The engine adds it to the world. Worlds rendered with an offscreen renderer should add it as well,
+/// otherwise each lit pipeline sorts the lights again.
+pub struct ClusteredLights {
+    local_lights: Box<LocalLights>,
+    bounds: Vec<(glam::Vec3, glam::Vec3)>,
+    /// projection, near and far planes the bounds were computed for.
+    bounds_projection: Option<(glam::Mat4, f32, f32)>,
+}
+
+impl Default for ClusteredLights {
+    fn default() -> Self {
+        ClusteredLights {
+            local_lights: LocalLights::empty(),
+            bounds: Vec::with_capacity(0),
+            bounds_projection: None,
+        }
+    }
+}
+
+impl ClusteredLights {
+    /// Sort the lights of the scene into the clusters of the singleton, if there is one.
+    /// This is called by the renderers once per frame, before the uniform buffers are updated.
+    pub(crate) fn update_singleton(components: &mut ComponentTable) {
+        if components.get_singleton::<ClusteredLights>().is_none() {
+            return;
+        }
+        let scene = LocalLightsScene::gather(components);
+        if let Some(clustered_lights) = components.get_singleton_mut::<ClusteredLights>() {
+            clustered_lights.update(scene);
+        }
+    }
+
+    fn update(&mut self, scene: Option<LocalLightsScene>) {
+        let scene = match scene {
+            Some(scene) => scene,
+            None => {
+                // without a main camera, no light is sent to the shaders.
+                self.local_lights.light_count = 0;
+                self.local_lights.clusters = [[0; 2]; CLUSTER_COUNT];
+                return;
+            }
+        };
+
+        let local_lights = &mut self.local_lights;
+        local_lights.view = scene.view;
+        local_lights.view_projection = scene.projection * scene.view;
+        local_lights.z_near = scene.z_near;
+        local_lights.z_far = scene.z_far;
+        local_lights.light_count = scene.lights.len() as u32;
+        local_lights.lights[..scene.lights.len()].copy_from_slice(&scene.lights);
+
+        let bounds_projection = Some((scene.projection, scene.z_near, scene.z_far));
+        if self.bounds_projection != bounds_projection {
+            self.bounds = local_lights.cluster_bounds(scene.projection);
+            self.bounds_projection = bounds_projection;
+        }
+        local_lights.assign_clusters(&self.bounds);
+    }
+}
+
+impl FrameUniform for LocalLights {
+    const DESCRIPTOR_TYPE: vulkanalia::vk::DescriptorType = vulkanalia::vk::DescriptorType::STORAGE_BUFFER;
+
+    fn set_uniform(components: &ComponentTable, write_to_buf: &mut dyn FnMut(&[Self])) {
+        match components.get_singleton::<ClusteredLights>() {
+            Some(clustered_lights) => write_to_buf(std::slice::from_ref(&*clustered_lights.local_lights)),
+            None => {
+                // no shared clusters, sort the lights for this pipeline only.
+                let mut clustered_lights = ClusteredLights::default();
+                clustered_lights.update(LocalLightsScene::gather(components));
+                write_to_buf(std::slice::from_ref(&*clustered_lights.local_lights));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(projection: glam::Mat4, lights: Vec<LocalLight>) -> LocalLightsScene {
+        LocalLightsScene {
+            view: glam::Mat4::IDENTITY,
+            projection,
+            z_near: 0.1,
+            z_far: 100.0,
+            lights,
+        }
+    }
+
+    fn point_light(position: glam::Vec3, range: f32) -> LocalLight {
+        LocalLight { position, range, ..LocalLight::NONE }
+    }
+
+    fn cluster_index(x: usize, y: usize, z: usize) -> usize {
+        (z * CLUSTER_GRID_SIZE.1 + y) * CLUSTER_GRID_SIZE.0 + x
+    }
+
+    fn cluster_lights(local_lights: &LocalLights, cluster: usize) -> &[u32] {
+        let [offset, count] = local_lights.clusters[cluster];
+        &local_lights.light_indices[offset as usize..(offset + count) as usize]
+    }
+
+    #[test]
+    fn depth_slices_round_trip() {
+        let mut local_lights = LocalLights::empty();
+        local_lights.z_near = 0.1;
+        local_lights.z_far = 100.0;
+        assert_eq!(local_lights.slice_depth(0), 0.1);
+        assert!((local_lights.slice_depth(CLUSTER_GRID_SIZE.2) - 100.0).abs() < 0.001);
+        for slice in 0..CLUSTER_GRID_SIZE.2 {
+            let near = local_lights.slice_depth(slice);
+            let far = local_lights.slice_depth(slice + 1);
+            assert!(near < far);
+            assert_eq!(local_lights.depth_slice(near * 1.001), slice);
+            assert_eq!(local_lights.depth_slice((near + far) / 2.0), slice);
+            assert_eq!(local_lights.depth_slice(far * 0.999), slice);
+        }
+        // depths outside of the frustum are clamped to the first and last slices.
+        assert_eq!(local_lights.depth_slice(0.0), 0);
+        assert_eq!(local_lights.depth_slice(-5.0), 0);
+        assert_eq!(local_lights.depth_slice(1000.0), CLUSTER_GRID_SIZE.2 - 1);
+    }
+
+    #[test]
+    fn lights_are_assigned_to_the_clusters_they_reach() {
+        let projection = glam::Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, 100.0);
+        let lights = vec![
+            // in front of the camera, at the center of the screen.
+            point_light(glam::vec3(0.0, 0.0, -10.0), 0.5),
+            // behind the camera.
+            point_light(glam::vec3(0.0, 0.0, 10.0), 0.5),
+        ];
+        let mut clustered_lights = ClusteredLights::default();
+        clustered_lights.update(Some(scene(projection, lights)));
+        let local_lights = &clustered_lights.local_lights;
+        assert_eq!(local_lights.light_count, 2);
+
+        // the center of the screen is on the edge of the two middle columns, in the middle row.
+        let center_z = local_lights.depth_slice(10.0);
+        let center_y = CLUSTER_GRID_SIZE.1 / 2;
+        let (center_x0, center_x1) = (CLUSTER_GRID_SIZE.0 / 2 - 1, CLUSTER_GRID_SIZE.0 / 2);
+        assert_eq!(cluster_lights(local_lights, cluster_index(center_x0, center_y, center_z)), &[0]);
+        assert_eq!(cluster_lights(local_lights, cluster_index(center_x1, center_y, center_z)), &[0]);
+
+        // the light only reaches the clusters around the center, and the other light reaches none.
+        let min_z = local_lights.depth_slice(9.5);
+        let max_z = local_lights.depth_slice(10.5);
+        for z in 0..CLUSTER_GRID_SIZE.2 {
+            for y in 0..CLUSTER_GRID_SIZE.1 {
+                for x in 0..CLUSTER_GRID_SIZE.0 {
+                    let lights = cluster_lights(local_lights, cluster_index(x, y, z));
+                    assert!(!lights.contains(&1));
+                    if lights.contains(&0) {
+                        assert!((min_z..=max_z).contains(&z));
+                        assert!((center_x0 - 1..=center_x1 + 1).contains(&x) && y.abs_diff(center_y) <= 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cluster_bounds_are_computed_again_when_the_projection_changes() {
+        let projection = glam::Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, 100.0);
+        let mut clustered_lights = ClusteredLights::default();
+        clustered_lights.update(Some(scene(projection, Vec::new())));
+        assert_eq!(clustered_lights.bounds.len(), CLUSTER_COUNT);
+        assert_eq!(clustered_lights.bounds_projection, Some((projection, 0.1, 100.0)));
+        let bounds = clustered_lights.bounds.clone();
+
+        clustered_lights.update(Some(scene(projection, vec![point_light(glam::vec3(0.0, 0.0, -10.0), 0.5)])));
+        assert_eq!(clustered_lights.bounds, bounds);
+
+        let wider = glam::Mat4::perspective_rh(1.5, 16.0 / 9.0, 0.1, 100.0);
+        clustered_lights.update(Some(scene(wider, Vec::new())));
+        assert_eq!(clustered_lights.bounds_projection, Some((wider, 0.1, 100.0)));
+        assert_ne!(clustered_lights.bounds, bounds);
+    }
+
+    #[test]
+    fn no_light_is_sent_without_a_main_camera() {
+        let projection = glam::Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, 100.0);
+        let mut clustered_lights = ClusteredLights::default();
+        clustered_lights.update(Some(scene(projection, vec![point_light(glam::vec3(0.0, 0.0, -10.0), 0.5)])));
+        clustered_lights.update(None);
+        assert_eq!(clustered_lights.local_lights.light_count, 0);
+        assert!(clustered_lights.local_lights.clusters.iter().all(|cluster| cluster[1] == 0));
+    }
+}
@@ -10,7 +10,7 @@ pub struct MainDirectionnalLight {
     direction: glam::Vec3,
     _padd_0: f32,
     ambiant_color: glam::Vec3,
-    _padd_1: f32,
+    environment_intensity: f32,
     direct_color: glam::Vec3,
     _padd_2: f32,
 }
@@ -22,7 +22,7 @@ impl FrameUniform for MainDirectionnalLight {
                 direction: main_light.direction,
                 _padd_0: 0.0,
                 ambiant_color: main_light.ambiant_color,
-                _padd_1: 0.0,
+                environment_intensity: main_light.environment_intensity,
                 direct_color: main_light.direct_color,
                 _padd_2: 0.0
             }]),
@@ -30,7 +30,7 @@ impl FrameUniform for MainDirectionnalLight {
                 direction: glam::Vec3::NEG_Y,
                 _padd_0: 0.0,
                 ambiant_color: glam::Vec3::ZERO,
-                _padd_1: 0.0,
+                environment_intensity: 0.0,
                 direct_color: glam::Vec3::ZERO,
                 _padd_2: 0.0
             }]),
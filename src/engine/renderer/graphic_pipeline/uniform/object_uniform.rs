@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 
+pub(crate) mod joint_palette_uniform;
 pub(crate) mod model_uniform;
 #[cfg(feature = "ui")]
 pub(crate) mod ui_model_uniform;
@@ -16,4 +17,9 @@ pub trait ObjectUniform: Debug + Sized {
     /// The instance count is how many instance this object wants to render,
     /// and so how many we should write to the buffer.
     fn set_uniform(component: &Self::FromComponent, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize);
+    /// World transform of the object, if this uniform knows about it.
+    /// This is used to select the level of detail of the meshes.
+    fn world_transform(_component: &Self::FromComponent) -> Option<glam::Mat4> {
+        None
+    }
 }
@@ -0,0 +1,28 @@
+use crate::engine::{
+    consts::PROPELLANT_MAX_SKELETON_JOINTS,
+    mesh::mesh_renderer::skinned_mesh_renderer::SkinnedMeshRenderer,
+};
+use super::ObjectUniform;
+
+#[repr(C)] // important for any data we send to the gpu
+#[allow(unused)] // we don't use the fields directly, but they are used by the gpu
+#[derive(Debug, Clone, Copy)]
+pub struct JointPaletteUniformObject {
+    /// Skinning matrices, already containing the model world transform.
+    joints: [glam::Mat4; PROPELLANT_MAX_SKELETON_JOINTS],
+}
+
+impl ObjectUniform for JointPaletteUniformObject {
+    type FromComponent = SkinnedMeshRenderer;
+    fn set_uniform(skinned_mesh: &Self::FromComponent, write_to_buf: &mut dyn FnMut(&[Self], usize), instance_count: usize) {
+        let mut uniform = JointPaletteUniformObject {
+            joints: [glam::Mat4::IDENTITY; PROPELLANT_MAX_SKELETON_JOINTS],
+        };
+        for (joint, matrix) in uniform.joints.iter_mut().zip(skinned_mesh.palette().iter()) {
+            *joint = *matrix;
+        }
+        for i in 0..instance_count {
+            write_to_buf(&[uniform], i);
+        }
+    }
+}
@@ -17,4 +17,8 @@ impl ObjectUniform for ModelMatrixUniformObject {
             }], i);
         }
     }
+
+    fn world_transform(transform: &Self::FromComponent) -> Option<glam::Mat4> {
+        Some(transform.world_pos())
+    }
 }
\ No newline at end of file
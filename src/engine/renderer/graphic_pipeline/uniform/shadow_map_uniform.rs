@@ -0,0 +1,149 @@
+use crate::engine::errors::PResult;
+use crate::engine::renderer::rendering_pipeline::shadow_render_pass::shadow_map::ShadowMap;
+
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+#[derive(Debug)]
+pub struct ShadowMapUniformBuilder {
+    stage: vulkanalia::vk::ShaderStageFlags,
+    /// Whether the shadow map itself is bound, or only the cascades for the pipelines drawing in it.
+    sampled: bool,
+}
+
+impl ShadowMapUniformBuilder {
+    pub fn new(stage: vulkanalia::vk::ShaderStageFlags, sampled: bool) -> Self {
+        Self {
+            stage,
+            sampled,
+        }
+    }
+
+    /// Descriptor types of the bindings of the set: the cascades at binding 0, the shadow map at binding 1.
+    pub fn descriptor_types(&self) -> Vec<vulkanalia::vk::DescriptorType> {
+        match self.sampled {
+            true => vec![
+                vulkanalia::vk::DescriptorType::UNIFORM_BUFFER,
+                vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            ],
+            false => vec![vulkanalia::vk::DescriptorType::UNIFORM_BUFFER],
+        }
+    }
+
+    pub fn build(
+        self,
+        vk_device: &vulkanalia::Device,
+        vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
+        image_count: usize,
+    ) -> PResult<ShadowMapUniform> {
+        ShadowMapUniform::new(
+            &self,
+            vk_device,
+            vk_descriptor_pool,
+            image_count,
+        )
+    }
+}
+
+/// Uniform binding the shadow cascades and the shadow map.
+/// The buffers and the image are owned by the shadow map, we only hold the descriptor sets pointing to them.
+#[derive(Debug)]
+pub struct ShadowMapUniform {
+    /// DS layout
+    layout: vulkanalia::vk::DescriptorSetLayout,
+    /// descriptor sets for each frame.
+    descriptor_sets: Vec<vulkanalia::vk::DescriptorSet>,
+    sampled: bool,
+}
+
+impl ShadowMapUniform {
+    pub fn new(
+        builder: &ShadowMapUniformBuilder,
+        vk_device: &vulkanalia::Device,
+        vk_descriptor_pool: vulkanalia::vk::DescriptorPool,
+        image_count: usize,
+    ) -> PResult<Self> {
+        // the layout is a blueprint on how the descriptor set matches the shader.
+        let layout_bindings = builder.descriptor_types().into_iter().enumerate().map(|(binding, descriptor_type)| {
+            vulkanalia::vk::DescriptorSetLayoutBinding::builder()
+                .binding(binding as u32)
+                .descriptor_type(descriptor_type)
+                .descriptor_count(1)
+                .stage_flags(builder.stage)
+                .build()
+        }).collect::<Vec<_>>();
+
+        let info = vulkanalia::vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&layout_bindings);
+
+        let layout = unsafe { vk_device.create_descriptor_set_layout(&info, None)? };
+
+        // create one descriptor set per swapchain image.
+        let layouts = vec![layout; image_count];
+        let info = vulkanalia::vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(vk_descriptor_pool)
+            .set_layouts(&layouts);
+
+        let descriptor_sets = unsafe { vk_device.allocate_descriptor_sets(&info)? };
+
+        Ok(ShadowMapUniform {
+            layout,
+            descriptor_sets,
+            sampled: builder.sampled,
+        })
+    }
+
+    pub fn layout(&self) -> vulkanalia::vk::DescriptorSetLayout {
+        self.layout
+    }
+
+    pub fn set(&self, image_index: usize) -> vulkanalia::vk::DescriptorSet {
+        self.descriptor_sets[image_index]
+    }
+
+    /// Point the descriptor sets of every image to the cascades buffers and the shadow map.
+    pub fn populate_descriptor_sets(
+        &self,
+        vk_device: &vulkanalia::Device,
+        shadow_map: &ShadowMap,
+    ) {
+        for (image_index, set) in self.descriptor_sets.iter().enumerate() {
+            let buffer_info = &[shadow_map.cascade_buffer(image_index).buffer_info()];
+            let image_info = &[vulkanalia::vk::DescriptorImageInfo::builder()
+                .image_layout(vulkanalia::vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL)
+                .image_view(shadow_map.array_view())
+                .sampler(shadow_map.sampler())
+            ];
+
+            let ubo_write = vulkanalia::vk::WriteDescriptorSet::builder()
+                .dst_set(*set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vulkanalia::vk::DescriptorType::UNIFORM_BUFFER)
+                .buffer_info(buffer_info);
+
+            let sampler_write = vulkanalia::vk::WriteDescriptorSet::builder()
+                .dst_set(*set)
+                .dst_binding(1)
+                .dst_array_element(0)
+                .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(image_info);
+
+            let writes = match self.sampled {
+                true => vec![ubo_write, sampler_write],
+                false => vec![ubo_write],
+            };
+
+            unsafe {
+                vk_device.update_descriptor_sets(&writes, &[] as &[vulkanalia::vk::CopyDescriptorSet]);
+            }
+        }
+    }
+
+    // the buffers and the image are owned by the shadow map, only the layout is ours.
+    pub fn destroy_buffer(&mut self, vk_device: &vulkanalia::Device) {
+        unsafe {
+            vk_device.destroy_descriptor_set_layout(self.layout, None);
+        }
+    }
+}
@@ -1,7 +1,7 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::engine::consts::PROPELLANT_MAX_LOADED_TEXTURE_COUNT;
-use crate::engine::errors::PResult;
+use crate::engine::errors::{PResult, PropellantError};
 use crate::engine::resources::texture_library::TextureLibrary;
 use crate::engine::window::vulkan::sync_state::VulkanSyncState;
 
@@ -26,8 +26,8 @@ impl TextureUniformBuilder {
         self.stage
     }
 
-    pub fn descriptor_type(&self) -> vulkanalia::vk::DescriptorType {
-        vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+    pub fn descriptor_types(&self) -> Vec<vulkanalia::vk::DescriptorType> {
+        vec![vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER]
     }
 
     pub fn build(
@@ -54,8 +54,8 @@ pub struct TextureUniform {
     descriptor_set: VulkanSyncState<vulkanalia::vk::DescriptorSet>,
     /// The current number of descriptor we can have without reallocating the descriptor set. 
     descriptor_size: u32,
-    /// Keep track of loaded texture index.
-    loaded_textures: BTreeSet<u32>,
+    /// Keep track of loaded texture index, with the generation of the texture written at that index.
+    loaded_textures: BTreeMap<u32, u64>,
 }
 
 impl TextureUniform {
@@ -102,7 +102,7 @@ impl TextureUniform {
             layout,
             descriptor_set: VulkanSyncState::new(descriptor_set),
             descriptor_size: START_DESCRIPTOR_SIZE,
-            loaded_textures: BTreeSet::new(),
+            loaded_textures: BTreeMap::new(),
         })
     }
 
@@ -167,10 +167,19 @@ impl TextureUniform {
             }
         }
 
-        let infos = textures.textures()
-            .filter(|(index, _texture)| {
-                self.loaded_textures.insert(*index) // will return true if the value was inserted, so the texture wasn't loaded.
-            })
+        let (loaded, unloaded) = Self::plan_texture_writes(&mut self.loaded_textures, textures.textures());
+
+        // unloaded textures are written with the fallback texture, so the slots stop pointing at destroyed image views.
+        let fallback = match (unloaded.is_empty(), textures.fallback_texture()) {
+            (true, _) => None,
+            (false, Some(fallback)) => Some(fallback),
+            (false, None) => return Err(PropellantError::Custom(
+                "Textures have been unloaded, but the fallback texture is not loaded".to_string()
+            )),
+        };
+
+        let infos = loaded.into_iter()
+            .chain(unloaded.into_iter().filter_map(|index| fallback.map(|texture| (index, texture))))
             .map(|(index, texture)| {
                 ([
                     vulkanalia::vk::DescriptorImageInfo::builder()
@@ -200,6 +209,32 @@ impl TextureUniform {
         Ok(())
     }
 
+    /// Find the texture slots to write, and update the generations written at each index.
+    /// Returns the loaded textures that are not written yet or whose index was recycled,
+    /// and the indices that were written but no longer have a loaded texture.
+    fn plan_texture_writes<T>(
+        loaded_textures: &mut BTreeMap<u32, u64>,
+        textures: impl Iterator<Item = (u32, u64, T)>,
+    ) -> (Vec<(u32, T)>, Vec<u32>) {
+        let mut still_loaded = BTreeSet::new();
+        let loaded = textures
+            .filter(|(index, generation, _texture)| {
+                still_loaded.insert(*index);
+                // write the texture if it's index was not loaded, or if the index was recycled for another texture.
+                loaded_textures.insert(*index, *generation) != Some(*generation)
+            })
+            .map(|(index, _generation, texture)| (index, texture))
+            .collect::<Vec<_>>();
+        let unloaded = loaded_textures.keys()
+            .filter(|index| !still_loaded.contains(index))
+            .cloned()
+            .collect::<Vec<_>>();
+        for index in unloaded.iter() {
+            loaded_textures.remove(index);
+        }
+        (loaded, unloaded)
+    }
+
     pub fn set(&self, image_index: usize) -> vulkanalia::vk::DescriptorSet {
         match &self.descriptor_set {
             VulkanSyncState::Sane(set) => *set,
@@ -217,3 +252,39 @@ impl TextureUniform {
     }
 
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::TextureUniform;
+
+    #[test]
+    fn new_and_recycled_indices_are_written() {
+        let mut loaded_textures = BTreeMap::new();
+        let (loaded, unloaded) = TextureUniform::plan_texture_writes(&mut loaded_textures, [(0, 0, ()), (1, 1, ())].into_iter());
+        assert_eq!(loaded, vec![(0, ()), (1, ())]);
+        assert!(unloaded.is_empty());
+
+        // index 1 got recycled for a texture of a newer generation
+        let (loaded, unloaded) = TextureUniform::plan_texture_writes(&mut loaded_textures, [(0, 0, ()), (1, 2, ())].into_iter());
+        assert_eq!(loaded, vec![(1, ())]);
+        assert!(unloaded.is_empty());
+        assert_eq!(loaded_textures.get(&1), Some(&2));
+    }
+
+    #[test]
+    fn unloaded_indices_are_written_once() {
+        let mut loaded_textures = BTreeMap::new();
+        TextureUniform::plan_texture_writes(&mut loaded_textures, [(0, 0, ()), (1, 1, ()), (2, 2, ())].into_iter());
+
+        let (loaded, unloaded) = TextureUniform::plan_texture_writes(&mut loaded_textures, [(0, 0, ()), (2, 2, ())].into_iter());
+        assert!(loaded.is_empty());
+        assert_eq!(unloaded, vec![1]);
+
+        let (loaded, unloaded) = TextureUniform::plan_texture_writes(&mut loaded_textures, [(0, 0, ()), (2, 2, ())].into_iter());
+        assert!(loaded.is_empty());
+        assert!(unloaded.is_empty());
+    }
+}
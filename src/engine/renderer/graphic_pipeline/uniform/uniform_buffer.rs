@@ -48,6 +48,11 @@ impl<T: Debug + 'static> UniformBufferBuilder<T> {
     pub fn descriptor_type(&self) -> vulkanalia::vk::DescriptorType {
         self.descriptor_type
     }
+
+    /// Descriptor types of the bindings of the set, to size the descriptor pool.
+    pub fn descriptor_types(&self) -> Vec<vulkanalia::vk::DescriptorType> {
+        vec![self.descriptor_type]
+    }
 }
 
 
@@ -0,0 +1,402 @@
+use foundry::ComponentTable;
+
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::window::vulkan::rendering_command_manager::RenderingCommandManager;
+use crate::engine::window::vulkan::vulkan_buffer::VulkanBuffer;
+#[cfg(feature = "ui")]
+use crate::engine::ui::ui_resolution::UiResolution;
+use crate::resource_loading::RequireResourcesLoadingFlag;
+use crate::{ClusteredLights, PropellantResources, VulkanInterface};
+
+use super::rendering_pipeline::RenderingPipeline;
+use super::rendering_pipeline::attachments::multisample_attachment::clamp_sample_count;
+use super::rendering_pipeline::compute_render_pass::ComputeRenderPass;
+use super::rendering_pipeline::graphic_render_pass::GraphicRenderpass;
+use super::rendering_pipeline::intermediate_render_targets::IntermediateRenderTargetBuilder;
+use super::rendering_pipeline::rendering_pipeline_builder::RenderingPipelineBuilder;
+use super::rendering_pipeline::rendering_pipeline_builder::states::RPBSReady;
+use super::rendering_pipeline::shadow_render_pass::ShadowRenderPass;
+
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+/// Format of the offscreen color image. It is srgb like the swapchain, so the frames look the same as on screen.
+const OFFSCREEN_FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::R8G8B8A8_SRGB;
+
+/// Renders the scene into an image instead of a window, and copies it back to host memory.
+/// It works with a headless vulkan interface, for thumbnails, visual tests or server side renders.
+/// Each render is synchronous, and rebuilds the scene from the components: it is not meant for real time rendering.
+/// The post process pipelines of the builder run after the graphic pipelines, and the last one writes the read back image.
+/// The overlay pipelines, such as the ui, are drawn over it before it is read back.
+pub struct OffscreenRenderer {
+    shadow_render_pass: ShadowRenderPass,
+    graphic_render_pass: GraphicRenderpass,
+    /// Post process passes, run in order after the graphic render pass. The last one writes the read back image.
+    compute_render_passes: Vec<ComputeRenderPass>,
+    /// Pass drawing the overlay pipelines over the image written by the post process passes, when there are both.
+    overlay_render_pass: Option<GraphicRenderpass>,
+    command_manager: RenderingCommandManager,
+    /// Signaled once the frame is rendered and copied.
+    fence: vulkanalia::vk::Fence,
+    /// Host visible buffer the color image is copied into.
+    readback_buffer: VulkanBuffer,
+    extent: vulkanalia::vk::Extent2D,
+}
+
+impl OffscreenRenderer {
+    /// Create a renderer drawing images of the given size with the pipelines of the builder.
+    /// Fails if the width or the height is zero, as vulkan images can't be empty.
+    pub fn create(
+        builder: RenderingPipelineBuilder<RPBSReady>,
+        vk_interface: &VulkanInterface,
+        width: u32,
+        height: u32,
+    ) -> PResult<OffscreenRenderer> {
+        if width == 0 || height == 0 {
+            return Err(PropellantError::Custom(format!("Offscreen renderer can't render images of size {width}x{height}.")));
+        }
+        let extent = vulkanalia::vk::Extent2D { width, height };
+
+        let mut builder = builder;
+        let clear_color = builder.clear_color();
+        let samples = clamp_sample_count(&vk_interface.instance, vk_interface.physical_device, builder.msaa_samples());
+        let shadow_casters = builder.take_shadow_casters();
+        let overlay_pipelines = builder.take_overlay_pipelines();
+        let shadow_render_pass = ShadowRenderPass::create(
+            builder.shadows().cloned(),
+            shadow_casters,
+            &vk_interface.instance,
+            &vk_interface.device,
+            vk_interface.physical_device,
+            1,
+        )?;
+        let builder_state: RPBSReady = builder.into();
+
+        let (graphic_render_pass, compute_render_passes, overlay_render_pass) = if builder_state.compute_pipelines.is_empty() {
+            // the overlays are drawn last in the graphic render pass.
+            let mut graphic_pipelines = builder_state.graphic_pipelines;
+            graphic_pipelines.extend(overlay_pipelines);
+            (
+                GraphicRenderpass::create_offscreen_pass(
+                    graphic_pipelines,
+                    &vk_interface.instance,
+                    &vk_interface.device,
+                    vk_interface.physical_device,
+                    OFFSCREEN_FORMAT,
+                    extent,
+                    shadow_render_pass.shadow_map(),
+                    clear_color,
+                    samples,
+                )?,
+                Vec::with_capacity(0),
+                None,
+            )
+        } else {
+            // same chain as the rendering pipeline, but the last pass writes an image we read back instead of the swapchain.
+            let compute_pipelines = builder_state.compute_pipelines;
+            let graphic_render_pass = GraphicRenderpass::create_intermediate_pass(
+                builder_state.graphic_pipelines,
+                compute_pipelines[0].1.clone(),
+                &vk_interface.instance,
+                &vk_interface.device,
+                vk_interface.physical_device,
+                extent,
+                1,
+                vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                shadow_render_pass.shadow_map(),
+                clear_color,
+                samples,
+            )?;
+            let mut readback_rt = IntermediateRenderTargetBuilder::new();
+            readback_rt.add_render_texture(OFFSCREEN_FORMAT, vulkanalia::vk::ImageAspectFlags::COLOR);
+            let output_rts = compute_pipelines.iter().skip(1).map(|(_, rt, _)| rt.clone()).chain(std::iter::once(readback_rt)).collect::<Vec<_>>();
+            let depth_views = RenderingPipeline::scene_depth_views(&graphic_render_pass, 1)?;
+            let mut compute_render_passes: Vec<ComputeRenderPass> = Vec::with_capacity(compute_pipelines.len());
+            for ((id, _, pipeline), output_rt) in compute_pipelines.into_iter().zip(output_rts) {
+                let input_views = RenderingPipeline::compute_pass_input_views(&graphic_render_pass, compute_render_passes.last(), 1)?;
+                compute_render_passes.push(ComputeRenderPass::create(
+                    id,
+                    pipeline,
+                    Some(output_rt),
+                    &input_views,
+                    &depth_views,
+                    &vk_interface.instance,
+                    &vk_interface.device,
+                    vk_interface.physical_device,
+                    extent,
+                    1,
+                    None,
+                )?);
+            }
+            // the overlays are drawn over the read back image, and leave it ready to be copied.
+            let overlay_render_pass = match (overlay_pipelines.is_empty(), compute_render_passes.last().and_then(|pass| pass.output_views())) {
+                (true, _) => None,
+                (false, Some(target_views)) => Some(GraphicRenderpass::create_overlay_pass(
+                    overlay_pipelines,
+                    builder_state.final_rt,
+                    &vk_interface.instance,
+                    &vk_interface.device,
+                    vk_interface.physical_device,
+                    &target_views,
+                    OFFSCREEN_FORMAT,
+                    extent,
+                    vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    shadow_render_pass.shadow_map(),
+                )?),
+                (false, None) => return Err(PropellantError::Custom("Offscreen post process pass has no target image.".to_string())),
+            };
+            (graphic_render_pass, compute_render_passes, overlay_render_pass)
+        };
+
+        let command_manager = RenderingCommandManager::create(&vk_interface.device, 1, vk_interface.indices)?;
+        let fence = unsafe { vk_interface.device.create_fence(&vulkanalia::vk::FenceCreateInfo::builder(), None)? };
+
+        let readback_buffer = VulkanBuffer::create(
+            &vk_interface.instance,
+            &vk_interface.device,
+            vk_interface.physical_device,
+            width as u64 * height as u64 * 4,
+            vulkanalia::vk::BufferUsageFlags::TRANSFER_DST,
+            vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE | vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        Ok(OffscreenRenderer {
+            shadow_render_pass,
+            graphic_render_pass,
+            compute_render_passes,
+            overlay_render_pass,
+            command_manager,
+            fence,
+            readback_buffer,
+            extent,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.extent.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.extent.height
+    }
+
+    /// Render the scene and wait for the frame, returning its srgb encoded pixels.
+    /// Queued resources are loaded first. The main camera should have the aspect ratio of the renderer.
+    /// If there is a ui resolution, the ui is laid out on the size of the renderer.
+    /// If there is a `ClusteredLights` singleton, the local lights are sorted once for all the lit pipelines.
+    pub fn render(
+        &mut self,
+        vk_interface: &mut VulkanInterface,
+        components: &mut ComponentTable,
+    ) -> PResult<image::RgbaImage> {
+        // no frame is in flight between two renders: released resources can be destroyed right away.
+        match components.get_singleton_mut::<PropellantResources>() {
+            Some(resources) => {
+                resources.load_resources(
+                    RequireResourcesLoadingFlag::ALL,
+                    &vk_interface.instance,
+                    &vk_interface.device,
+                    vk_interface.physical_device,
+                    &mut vk_interface.transfer_manager,
+                )?;
+                resources.meshes_mut().sync_dynamic_meshes(
+                    0,
+                    &vk_interface.instance,
+                    &vk_interface.device,
+                    vk_interface.physical_device,
+                )?;
+                resources.sync_released_resources(0, 1, &vk_interface.device);
+            },
+            None => return Err(PropellantError::NoResources),
+        }
+        vk_interface.check_and_process_memory_transfers()?;
+
+        // the ui is laid out on the size of the image, as it would be on the window.
+        #[cfg(feature = "ui")]
+        if let Some(ui_res) = components.get_singleton_mut::<UiResolution>() {
+            ui_res.set_window_size(glam::vec2(self.extent.width as f32, self.extent.height as f32));
+            UiResolution::propagate(components);
+        }
+
+        // there are no engine flags telling what changed since the last render, so everything is bound again.
+        self.shadow_render_pass.scene_recreation(components)?;
+        self.graphic_render_pass.scene_recreation(components)?;
+        self.shadow_render_pass.assert_uniform_buffer_sizes(0, &vk_interface.instance, &vk_interface.device, vk_interface.physical_device)?;
+        self.graphic_render_pass.assert_uniform_buffer_sizes(0, &vk_interface.instance, &vk_interface.device, vk_interface.physical_device)?;
+        ClusteredLights::update_singleton(components);
+        self.shadow_render_pass.update_uniform_buffers(&vk_interface.device, 0, components)?;
+        self.graphic_render_pass.update_uniform_buffers(&vk_interface.device, 0, components)?;
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.scene_recreation(components)?;
+            overlay_render_pass.assert_uniform_buffer_sizes(0, &vk_interface.instance, &vk_interface.device, vk_interface.physical_device)?;
+            overlay_render_pass.update_uniform_buffers(&vk_interface.device, 0, components)?;
+        }
+        // the commands are recorded on every render, there is no need to know whether the effect settings changed.
+        for compute_render_pass in self.compute_render_passes.iter_mut() {
+            compute_render_pass.update_uniform_buffers(0, components);
+        }
+
+        let resources = match components.get_singleton::<PropellantResources>() {
+            Some(resources) => resources,
+            None => return Err(PropellantError::NoResources),
+        };
+        self.graphic_render_pass.reload_textures(&vk_interface.device, 0, resources.textures())?;
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.reload_textures(&vk_interface.device, 0, resources.textures())?;
+        }
+
+        self.register_commands(&vk_interface.device, resources)?;
+
+        // submit and wait for the frame to be rendered and copied
+        let command_buffers = &[self.command_manager.command_buffer(0)];
+        let submit_info = vulkanalia::vk::SubmitInfo::builder()
+            .command_buffers(command_buffers);
+        unsafe {
+            vk_interface.device.queue_submit(vk_interface.queue, &[submit_info], self.fence)?;
+            vk_interface.device.wait_for_fences(&[self.fence], true, u64::MAX)?;
+            vk_interface.device.reset_fences(&[self.fence])?;
+        }
+
+        // the buffer is tightly packed, with the same layout as the image.
+        let mut frame = image::RgbaImage::new(self.extent.width, self.extent.height);
+        let memory = self.readback_buffer.map(&vk_interface.device)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(memory.cast::<u8>(), frame.as_mut_ptr(), frame.len());
+        }
+        self.readback_buffer.unmap(&vk_interface.device);
+
+        Ok(frame)
+    }
+
+    fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        resources: &PropellantResources,
+    ) -> PResult<()> {
+        let command_buffer = self.command_manager.command_buffer(0);
+        self.command_manager.start_recording_command_buffer(vk_device, 0)?;
+
+        self.shadow_render_pass.register_draw_commands(vk_device, command_buffer, resources, 0)?;
+        self.graphic_render_pass.register_draw_commands(vk_device, command_buffer, self.extent, resources, 0)?;
+        for compute_render_pass in self.compute_render_passes.iter() {
+            compute_render_pass.register_draw_commands(vk_device, command_buffer, 0)?;
+        }
+        if let Some(overlay_render_pass) = &self.overlay_render_pass {
+            overlay_render_pass.register_draw_commands(vk_device, command_buffer, self.extent, resources, 0)?;
+        }
+
+        // the graphic and overlay render passes leave the color image in transfer source layout, copy it in the readback buffer.
+        // the post process passes leave it in shader read only layout, it is moved to transfer source first.
+        let image = match self.compute_render_passes.last() {
+            Some(compute_render_pass) => match compute_render_pass.output_image(0) {
+                Some(image) => {
+                    if self.overlay_render_pass.is_none() {
+                        self.register_transfer_source_barrier(vk_device, command_buffer, image.image());
+                    }
+                    image.image()
+                },
+                None => return Err(PropellantError::Custom("Offscreen post process pass has no target image.".to_string())),
+            },
+            None => match self.graphic_render_pass.target_image() {
+                Some(image) => image.image(),
+                None => return Err(PropellantError::Custom("Offscreen render pass has no target image.".to_string())),
+            },
+        };
+        let subresource = vulkanalia::vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1);
+        let region = vulkanalia::vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(subresource)
+            .image_offset(vulkanalia::vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vulkanalia::vk::Extent3D { width: self.extent.width, height: self.extent.height, depth: 1 });
+
+        // make the copied pixels visible to the host once the fence is signaled.
+        let barrier = vulkanalia::vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.readback_buffer.buffer())
+            .offset(0)
+            .size(self.readback_buffer.size());
+
+        unsafe {
+            vk_device.cmd_copy_image_to_buffer(
+                command_buffer,
+                image,
+                vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.readback_buffer.buffer(),
+                &[region],
+            );
+            vk_device.cmd_pipeline_barrier(
+                command_buffer,
+                vulkanalia::vk::PipelineStageFlags::TRANSFER,
+                vulkanalia::vk::PipelineStageFlags::HOST,
+                vulkanalia::vk::DependencyFlags::empty(),
+                &[] as &[vulkanalia::vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vulkanalia::vk::ImageMemoryBarrier],
+            );
+        }
+
+        self.command_manager.end_recording_command_buffer(vk_device, 0)?;
+        Ok(())
+    }
+
+    /// Wait for the last post process pass to write the image, and move it to transfer source layout.
+    fn register_transfer_source_barrier(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        image: vulkanalia::vk::Image,
+    ) {
+        let subresource_range = vulkanalia::vk::ImageSubresourceRange::builder()
+            .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1);
+        let barrier = vulkanalia::vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_READ)
+            .old_layout(vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .new_layout(vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range);
+
+        unsafe {
+            vk_device.cmd_pipeline_barrier(
+                command_buffer,
+                vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER,
+                vulkanalia::vk::PipelineStageFlags::TRANSFER,
+                vulkanalia::vk::DependencyFlags::empty(),
+                &[] as &[vulkanalia::vk::MemoryBarrier],
+                &[] as &[vulkanalia::vk::BufferMemoryBarrier],
+                &[barrier],
+            );
+        }
+    }
+
+    /// Destroy the vulkan objects of the renderer. The device must be idle.
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.readback_buffer.destroy(vk_device);
+        unsafe {
+            vk_device.destroy_fence(self.fence, None);
+        }
+        self.command_manager.destroy(vk_device);
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.destroy(vk_device);
+        }
+        self.compute_render_passes.iter_mut().for_each(|compute_render_pass| compute_render_pass.destroy(vk_device));
+        self.graphic_render_pass.destroy(vk_device);
+        self.shadow_render_pass.destroy(vk_device);
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::BTreeMap;
 use crate::{
     PropellantResources,
     engine::{
-        resources::mesh_library::LoadedMesh,
+        resources::mesh_library::MeshDraw,
         consts::PROPELLANT_DEBUG_FEATURES
     }
 };
@@ -12,37 +12,114 @@ pub(crate) mod hasher;
 
 /// A rendering map builds the rendering commands from the scene.
 pub struct RenderingMap {
-    /// key : mesh id
+    /// key : mesh id and level of detail
     /// value : number of instances, total offset, temp counter
-    pub map: BTreeMap<u64, (usize, usize, usize)>,
+    pub map: BTreeMap<(u64, usize), (usize, usize, usize)>,
+    /// Set when the objects no longer match the map, for example when a mesh changed its level of detail.
+    outdated: bool,
+    /// For sorted maps, the draws of each swapchain image in back to front order.
+    /// Each draw is the mesh id and level of detail, the first instance and the number of instances.
+    sorted_draws: Option<Vec<Vec<((u64, usize), usize, usize)>>>,
 }
 
 impl RenderingMap {
     pub fn new() -> RenderingMap {
         RenderingMap {
             map: BTreeMap::new(),
+            outdated: false,
+            sorted_draws: None,
         }
     }
 
-    /// Creates a iterator over the meshes and the instances to draw.
+    /// Creates a map that draws the instances in the order they were last sorted in, instead of grouped by mesh.
+    pub fn sorted() -> RenderingMap {
+        RenderingMap {
+            map: BTreeMap::new(),
+            outdated: false,
+            sorted_draws: Some(Vec::new()),
+        }
+    }
+
+    /// Whether this map draws its instances in sorted order.
+    pub fn is_sorted(&self) -> bool {
+        self.sorted_draws.is_some()
+    }
+
+    /// Set the sorted order of the instances for the given swapchain image, as a list of mesh id and level of detail, and instance count.
+    /// Consecutive instances of the same mesh are merged in a single draw.
+    /// Returns true if the draws changed, meaning the draw commands of this image must be recorded again.
+    pub fn set_sorted_draws(&mut self, image_index: usize, instances: impl Iterator<Item = ((u64, usize), usize)>) -> bool {
+        let sorted_draws = match &mut self.sorted_draws {
+            Some(sorted_draws) => sorted_draws,
+            None => return false,
+        };
+        let mut draws: Vec<((u64, usize), usize, usize)> = Vec::new();
+        let mut first_instance = 0;
+        for (mesh_id, instance_count) in instances {
+            match draws.last_mut() {
+                Some((last_id, _, last_count)) if *last_id == mesh_id => *last_count += instance_count,
+                _ => draws.push((mesh_id, first_instance, instance_count)),
+            }
+            first_instance += instance_count;
+        }
+        if sorted_draws.len() <= image_index {
+            sorted_draws.resize(image_index + 1, Vec::new());
+        }
+        if sorted_draws[image_index] == draws {
+            false
+        } else {
+            sorted_draws[image_index] = draws;
+            true
+        }
+    }
+
+    /// Creates a iterator over the meshes, the first instance and the number of instances to draw.
     /// With the provided resources, zip the mesh id to the mesh and filters the non existing meshes.
-    pub fn iter<'a>(&'a self, resources: &'a PropellantResources) -> impl Iterator<Item = (&'a LoadedMesh, usize)> + '_ {
-        self.map.iter().map(|(k, v)| {
-            let mesh = resources.meshes().loaded_mesh(k);
+    /// Dynamic meshes are drawn from their buffers for the given swapchain image.
+    /// Sorted maps iterate over the draws in the last sorted order of the image.
+    pub fn iter<'a>(&'a self, resources: &'a PropellantResources, image_index: usize) -> impl Iterator<Item = (MeshDraw, usize, usize)> + '_ {
+        let draws: Box<dyn Iterator<Item = (&(u64, usize), &usize, &usize)>> = match &self.sorted_draws {
+            Some(sorted_draws) => Box::new(
+                sorted_draws.get(image_index).into_iter().flatten()
+                    .map(|(k, first_instance, instance_count)| (k, instance_count, first_instance))
+            ),
+            None => Box::new(
+                self.map.iter().map(|(k, (instance_count, first_instance, _))| (k, instance_count, first_instance))
+            ),
+        };
+        draws.filter_map(move |(k, instance_count, first_instance)| {
+            let (mesh_id, lod_level) = k;
+            let mesh = resources.meshes().mesh_draw(mesh_id, *lod_level, image_index);
             if PROPELLANT_DEBUG_FEATURES {
                 if mesh.is_none() {
-                    println!("[PROPELLANT DEBUG] Mesh not in mesh library (id {})", k);
+                    println!("[PROPELLANT DEBUG] Mesh not in mesh library (id {}, level of detail {})", mesh_id, lod_level);
                 }
             }
-            (mesh, v.0)
-        }).filter(|(m, _)| m.is_some())
-        .map(|(m, c)| (m.unwrap(), c))
+            mesh.map(|mesh| (mesh, *first_instance, *instance_count))
+        })
     }
 
-    pub fn map_mut(&mut self) -> &mut BTreeMap<u64, (usize, usize, usize)> {
+    /// Get the map to rebuild it. This also marks the map as up to date.
+    pub fn map_mut(&mut self) -> &mut BTreeMap<(u64, usize), (usize, usize, usize)> {
+        self.outdated = false;
         &mut self.map
     }
 
+    /// Compare the instance count per mesh with the one of the map, and flag the map as outdated if they differ.
+    pub fn check_instance_counts(&mut self, instance_counts: &BTreeMap<(u64, usize), usize>) {
+        let matches = self.map.len() == instance_counts.len()
+            && self.map.iter().zip(instance_counts.iter())
+                .all(|((id, (count, _, _)), (other_id, other_count))| id == other_id && count == other_count);
+        if !matches {
+            self.outdated = true;
+        }
+    }
+
+    /// Whether the map needs to be rebuilt.
+    pub fn is_outdated(&self) -> bool {
+        self.outdated
+    }
+
     /// Get the number of objects in the scene rendered by this pipeline.
     pub fn object_count(&self) -> usize {
         self.map.iter().map(|(_, (instance_count, _, _))| *instance_count).sum()
@@ -50,3 +127,44 @@ impl RenderingMap {
 
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::RenderingMap;
+
+    #[test]
+    fn consecutive_instances_of_a_mesh_are_merged() {
+        let mut map = RenderingMap::sorted();
+        let instances = [((1, 0), 1), ((1, 0), 2), ((2, 0), 1), ((1, 1), 1), ((1, 0), 1)];
+        assert!(map.set_sorted_draws(0, instances.into_iter()));
+        assert_eq!(map.sorted_draws, Some(vec![vec![
+            ((1, 0), 0, 3),
+            ((2, 0), 3, 1),
+            // another level of detail of the same mesh is another draw
+            ((1, 1), 4, 1),
+            ((1, 0), 5, 1),
+        ]]));
+    }
+
+    #[test]
+    fn unchanged_draws_do_not_require_recording() {
+        let mut map = RenderingMap::sorted();
+        let instances = [((1, 0), 1), ((2, 0), 1)];
+        assert!(map.set_sorted_draws(1, instances.into_iter()));
+        assert!(!map.set_sorted_draws(1, instances.into_iter()));
+        assert!(map.set_sorted_draws(1, [((2, 0), 1), ((1, 0), 1)].into_iter()));
+        // the draws are kept per swapchain image
+        assert!(map.set_sorted_draws(0, [((1, 0), 2)].into_iter()));
+        // instances merged in the same draws are the same draws
+        assert!(!map.set_sorted_draws(0, [((1, 0), 1), ((1, 0), 1)].into_iter()));
+    }
+
+    #[test]
+    fn unsorted_maps_ignore_sorted_draws() {
+        let mut map = RenderingMap::new();
+        assert!(!map.set_sorted_draws(0, [((1, 0), 1)].into_iter()));
+        assert!(!map.is_sorted());
+        assert!(map.sorted_draws.is_none());
+    }
+}
@@ -8,30 +8,42 @@ use crate::{
             rendering_command_manager::RenderingCommandManager,
             rendering_sync::RenderingSync
         },
-        errors::PResult, resources::texture_library::TextureLibrary
+        errors::{PResult, PropellantError}, resources::texture_library::TextureLibrary
     },
     RenderingPipelineBuilder,
     PropellantResources,
 };
 use self::{
+    attachments::multisample_attachment::clamp_sample_count,
     rendering_pipeline_builder::states::RPBSReady,
-    graphic_render_pass::GraphicRenderpass
+    graphic_render_pass::GraphicRenderpass,
+    shadow_render_pass::ShadowRenderPass,
+    compute_render_pass::ComputeRenderPass,
 };
 
 
 pub(crate) mod attachments;
+pub(crate) mod compute_render_pass;
 pub(crate) mod final_render_target;
 pub(crate) mod intermediate_render_targets;
 pub(crate) mod rendering_pipeline_builder;
 pub(crate) mod graphic_render_pass;
+pub(crate) mod shadow_render_pass;
 
 pub(crate) const MAX_FRAMES_IN_FLIGHT: usize = 4;
 
 pub struct RenderingPipeline {
+    /// Depth only pass drawing the shadow map, before the graphic render pass that samples it.
+    shadow_render_pass: ShadowRenderPass,
     graphic_render_pass: GraphicRenderpass,
-    #[allow(unused)]
-    compute_render_passes: Vec<()>,
+    /// Post process passes, run in order after the graphic render pass. The last one writes the swapchain.
+    compute_render_passes: Vec<ComputeRenderPass>,
+    /// Pass drawing the overlay pipelines, such as the ui, over the swapchain image once the post process passes ran.
+    /// Without post process passes, the overlays are drawn by the graphic render pass and there is none.
+    overlay_render_pass: Option<GraphicRenderpass>,
     swapchain: SwapchainInterface,
+    /// Whether the swapchain is created in a hdr color space when the surface supports one.
+    hdr_output: bool,
     command_manager: RenderingCommandManager,
     rendering_sync: RenderingSync<MAX_FRAMES_IN_FLIGHT>,
 }
@@ -47,34 +59,105 @@ impl RenderingPipeline {
         queue_indices: QueueFamilyIndices,
     ) -> PResult<RenderingPipeline> {
 
+        let hdr_output = builder.hdr_output();
         let swapchain = SwapchainInterface::create(
             vk_instance,
             window,
             surface,
             vk_physical_device,
             vk_device,
-            queue_indices
+            queue_indices,
+            hdr_output,
         )?;
 
+        let mut builder = builder;
         let clear_color = builder.clear_color();
+        let samples = clamp_sample_count(vk_instance, vk_physical_device, builder.msaa_samples());
+        let shadow_casters = builder.take_shadow_casters();
+        let overlay_pipelines = builder.take_overlay_pipelines();
+        let shadow_render_pass = ShadowRenderPass::create(
+            builder.shadows().cloned(),
+            shadow_casters,
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            swapchain.images().len(),
+        )?;
         let builder_state: RPBSReady = builder.into();
 
-        let (graphic_render_pass, compute_render_passes) = if builder_state.compute_pipelines.is_empty() {
-            // only graphic render_pass, no compute render_passes.
+        let (graphic_render_pass, compute_render_passes, overlay_render_pass) = if builder_state.compute_pipelines.is_empty() {
+            // only graphic render_pass, no compute render_passes: the overlays are drawn last in the same pass.
+            let mut graphic_pipelines = builder_state.graphic_pipelines;
+            graphic_pipelines.extend(overlay_pipelines);
             (
                 GraphicRenderpass::create_final_pass(
-                    builder_state.graphic_pipelines,
+                    graphic_pipelines,
                     builder_state.final_rt,
                     vk_instance,
                     vk_device,
                     vk_physical_device,
                     &swapchain,
+                    shadow_render_pass.shadow_map(),
                     clear_color,
+                    samples,
                 )?,
                 Vec::with_capacity(0),
+                None,
             )
         } else {
-            unimplemented!()
+            // the graphic render_pass draws in the first intermediate render target, read by the first compute render_pass.
+            let compute_pipelines = builder_state.compute_pipelines;
+            let graphic_render_pass = GraphicRenderpass::create_intermediate_pass(
+                builder_state.graphic_pipelines,
+                compute_pipelines[0].1.clone(),
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                swapchain.extent(),
+                swapchain.images().len(),
+                vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                shadow_render_pass.shadow_map(),
+                clear_color,
+                samples,
+            )?;
+            // each compute render_pass writes the render target the next one reads, and the last one writes the swapchain.
+            let output_rts = compute_pipelines.iter().skip(1).map(|(_, rt, _)| Some(rt.clone())).chain(std::iter::once(None)).collect::<Vec<_>>();
+            let depth_views = Self::scene_depth_views(&graphic_render_pass, swapchain.images().len())?;
+            let mut compute_render_passes: Vec<ComputeRenderPass> = Vec::with_capacity(compute_pipelines.len());
+            for ((id, _, pipeline), output_rt) in compute_pipelines.into_iter().zip(output_rts) {
+                let input_views = Self::compute_pass_input_views(&graphic_render_pass, compute_render_passes.last(), swapchain.images().len())?;
+                compute_render_passes.push(ComputeRenderPass::create(
+                    id,
+                    pipeline,
+                    output_rt,
+                    &input_views,
+                    &depth_views,
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    swapchain.extent(),
+                    swapchain.images().len(),
+                    Some(&swapchain),
+                )?);
+            }
+            // the overlays are drawn over the swapchain image written by the last compute render_pass, out of the post process chain.
+            let overlay_render_pass = match overlay_pipelines.is_empty() {
+                true => None,
+                false => Some(GraphicRenderpass::create_overlay_pass(
+                    overlay_pipelines,
+                    builder_state.final_rt,
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    swapchain.image_views(),
+                    swapchain.format(),
+                    swapchain.extent(),
+                    vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR,
+                    vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR,
+                    shadow_render_pass.shadow_map(),
+                )?),
+            };
+            (graphic_render_pass, compute_render_passes, overlay_render_pass)
         };
 
         // create sync system and transfer manager
@@ -82,18 +165,60 @@ impl RenderingPipeline {
         let rendering_sync = RenderingSync::create(vk_device, swapchain.images().len())?;
 
         Ok(RenderingPipeline {
+            shadow_render_pass,
             graphic_render_pass,
             compute_render_passes,
+            overlay_render_pass,
             swapchain,
+            hdr_output,
             command_manager,
             rendering_sync,
         })
     }
 
+    /// The views a compute render_pass reads: the output of the previous compute render_pass, or of the graphic render_pass for the first one.
+    pub(crate) fn compute_pass_input_views(
+        graphic_render_pass: &GraphicRenderpass,
+        previous_pass: Option<&ComputeRenderPass>,
+        image_count: usize,
+    ) -> PResult<Vec<vulkanalia::vk::ImageView>> {
+        let views = match previous_pass {
+            Some(previous_pass) => previous_pass.output_views(),
+            None => (0..image_count).map(|image_index| graphic_render_pass.target_views(image_index).map(|(color, _)| color)).collect(),
+        };
+        match views {
+            Some(views) => Ok(views),
+            None => Err(PropellantError::Custom("A compute render pass reads a pass that writes the swapchain.".to_string())),
+        }
+    }
+
+    /// The depth views the graphic render_pass drew the scene with, when it draws in intermediate render targets.
+    /// A multisampled depth can not be sampled, there are none when the scene is multisampled.
+    pub(crate) fn scene_depth_views(
+        graphic_render_pass: &GraphicRenderpass,
+        image_count: usize,
+    ) -> PResult<Vec<vulkanalia::vk::ImageView>> {
+        if graphic_render_pass.samples() != vulkanalia::vk::SampleCountFlags::_1 {
+            return Ok(Vec::with_capacity(0));
+        }
+        match (0..image_count).map(|image_index| graphic_render_pass.target_views(image_index).map(|(_, depth)| depth)).collect() {
+            Some(views) => Ok(views),
+            None => Err(PropellantError::Custom("The compute render passes read a graphic render pass that writes the swapchain.".to_string())),
+        }
+    }
+
     pub fn swapchain(&self) -> &SwapchainInterface {
         &self.swapchain
     }
 
+    /// Luminance of the sdr white on hdr swapchains, from the tonemapping pass writing the swapchain.
+    pub fn paper_white(&self, components: &ComponentTable) -> f32 {
+        self.compute_render_passes.last()
+            .and_then(|compute_render_pass| compute_render_pass.tonemap_settings(components))
+            .unwrap_or_default()
+            .paper_white()
+    }
+
     pub fn swapchain_image_count(&self) -> usize {
         self.swapchain.images().len()
     }
@@ -107,6 +232,14 @@ impl RenderingPipeline {
 
         // start recording
         self.command_manager.start_recording_command_buffer(vk_device, image_index)?;
+
+        // commands for the shadow map, sampled by the graphic render_pass
+        self.shadow_render_pass.register_draw_commands(
+            vk_device,
+            self.command_manager.command_buffer(image_index),
+            resources,
+            image_index,
+        )?;
         
         // commands for graphic render_pass
         self.graphic_render_pass.register_draw_commands(
@@ -116,8 +249,24 @@ impl RenderingPipeline {
             resources,
             image_index,
         )?;
-        // commands for compute render_passes
-        // todo 
+        // commands for compute render_passes, each one reading the image written by the previous one.
+        for compute_render_pass in self.compute_render_passes.iter() {
+            compute_render_pass.register_draw_commands(
+                vk_device,
+                self.command_manager.command_buffer(image_index),
+                image_index,
+            )?;
+        }
+        // commands for the overlays, drawn over the post processed image.
+        if let Some(overlay_render_pass) = &self.overlay_render_pass {
+            overlay_render_pass.register_draw_commands(
+                vk_device,
+                self.command_manager.command_buffer(image_index),
+                self.swapchain.extent(),
+                resources,
+                image_index,
+            )?;
+        }
 
         // end recording
         self.command_manager.end_recording_command_buffer(vk_device, image_index)?;
@@ -130,6 +279,10 @@ impl RenderingPipeline {
         &mut self,
         vk_device: &vulkanalia::Device,
     ) {
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.recreation_cleanup(vk_device);
+        }
+        self.compute_render_passes.iter_mut().for_each(|compute_render_pass| compute_render_pass.destroy(vk_device));
         self.graphic_render_pass.recreation_cleanup(vk_device);
         self.swapchain.destroy(vk_device);
     }
@@ -150,7 +303,8 @@ impl RenderingPipeline {
             surface,
             vk_physical_device,
             vk_device,
-            queue_indices
+            queue_indices,
+            self.hdr_output,
         )?;
         
         self.graphic_render_pass.recreate(
@@ -160,6 +314,33 @@ impl RenderingPipeline {
             &self.swapchain
         )?;
 
+        // the compute render_passes are rebuilt in order, as each one reads the images of the previous one.
+        let depth_views = match self.compute_render_passes.is_empty() {
+            true => Vec::with_capacity(0),
+            false => Self::scene_depth_views(&self.graphic_render_pass, self.swapchain.images().len())?,
+        };
+        for index in 0..self.compute_render_passes.len() {
+            let (previous_passes, next_passes) = self.compute_render_passes.split_at_mut(index);
+            let input_views = Self::compute_pass_input_views(&self.graphic_render_pass, previous_passes.last(), self.swapchain.images().len())?;
+            next_passes[0].recreate(
+                &input_views,
+                &depth_views,
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                &self.swapchain,
+            )?;
+        }
+
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.recreate(
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                &self.swapchain,
+            )?;
+        }
+
         Ok(())
     }
 
@@ -169,8 +350,19 @@ impl RenderingPipeline {
         vk_device: &vulkanalia::Device,
         image_index: usize,
         components: &ComponentTable,
-    ) -> PResult<()> {
-        self.graphic_render_pass.update_uniform_buffers(vk_device, image_index, components)
+    ) -> PResult<bool> {
+        let shadows_outdated = self.shadow_render_pass.update_uniform_buffers(vk_device, image_index, components)?;
+        let graphics_outdated = self.graphic_render_pass.update_uniform_buffers(vk_device, image_index, components)?;
+        let overlays_outdated = match &mut self.overlay_render_pass {
+            Some(overlay_render_pass) => overlay_render_pass.update_uniform_buffers(vk_device, image_index, components)?,
+            None => false,
+        };
+        // the effects record their settings in the command buffers, they are outdated when the settings change.
+        let mut effects_outdated = false;
+        for compute_render_pass in self.compute_render_passes.iter_mut() {
+            effects_outdated |= compute_render_pass.update_uniform_buffers(image_index, components);
+        }
+        Ok(shadows_outdated || graphics_outdated || overlays_outdated || effects_outdated)
     }
 
     #[inline]
@@ -178,9 +370,20 @@ impl RenderingPipeline {
         &mut self,
         components: &ComponentTable,
     ) -> PResult<()> {
+        self.shadow_render_pass.scene_recreation(components)?;
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.scene_recreation(components)?;
+        }
         self.graphic_render_pass.scene_recreation(components)
     }
 
+    #[inline]
+    pub fn requires_scene_rebuild(&self) -> bool {
+        self.shadow_render_pass.requires_scene_rebuild()
+            || self.graphic_render_pass.requires_scene_rebuild()
+            || self.overlay_render_pass.as_ref().is_some_and(|overlay_render_pass| overlay_render_pass.requires_scene_rebuild())
+    }
+
     #[inline]
     pub fn assert_uniform_buffer_sizes(
         &mut self,
@@ -189,6 +392,10 @@ impl RenderingPipeline {
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
     ) -> PResult<()> {
+        self.shadow_render_pass.assert_uniform_buffer_sizes(image_index, vk_instance, vk_device, vk_physical_device)?;
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.assert_uniform_buffer_sizes(image_index, vk_instance, vk_device, vk_physical_device)?;
+        }
         self.graphic_render_pass.assert_uniform_buffer_sizes(image_index, vk_instance, vk_device, vk_physical_device)
     }
 
@@ -199,6 +406,9 @@ impl RenderingPipeline {
         image_index: usize,
         textures: &TextureLibrary,
     ) -> PResult<()> {
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.reload_textures(vk_device, image_index, textures)?;
+        }
         self.graphic_render_pass.reload_textures(vk_device, image_index, textures)
     }
 
@@ -221,7 +431,12 @@ impl RenderingPipeline {
         self.command_manager.destroy(vk_device);
         self.rendering_sync.destroy(vk_device);
         self.swapchain.destroy(vk_device);
+        if let Some(overlay_render_pass) = &mut self.overlay_render_pass {
+            overlay_render_pass.destroy(vk_device);
+        }
+        self.compute_render_passes.iter_mut().for_each(|compute_render_pass| compute_render_pass.destroy(vk_device));
         self.graphic_render_pass.destroy(vk_device);
+        self.shadow_render_pass.destroy(vk_device);
     }
 }
 
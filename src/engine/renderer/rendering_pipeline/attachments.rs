@@ -1,2 +1,3 @@
 pub(crate) mod depth_attachment;
+pub(crate) mod multisample_attachment;
 
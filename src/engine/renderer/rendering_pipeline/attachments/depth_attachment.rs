@@ -15,21 +15,24 @@ use crate::engine::{
 use vulkanalia::vk::InstanceV1_0;
 
 
+/// Create the depth image of a render pass, with the sample count of its color attachment.
 pub fn create_depth_objects(
     vk_instance: &vulkanalia::Instance,
     vk_device: &vulkanalia::Device,
     vk_physical_device: vulkanalia::vk::PhysicalDevice,
     swapchain_extent: vulkanalia::vk::Extent2D,
+    samples: vulkanalia::vk::SampleCountFlags,
 ) -> PResult<(VulkanImage, vulkanalia::vk::ImageView)> {
 
     let format = get_depth_format(vk_instance, vk_physical_device)?;
 
-    let image = VulkanImage::create(
+    let image = VulkanImage::create_multisampled(
         vk_instance,
         vk_device,
         vk_physical_device,
         swapchain_extent.width,
         swapchain_extent.height,
+        samples,
         vulkanalia::vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
         format
     )?;
@@ -90,11 +93,37 @@ pub fn get_depth_format(
         vulkanalia::vk::Format::D24_UNORM_S8_UINT,
     ];
 
+    // prefer the formats that can be sampled, so the post process passes can read the depth of the scene.
     get_supported_format(
+        vk_instance,
+        vk_physical_device,
+        candidates,
+        vulkanalia::vk::ImageTiling::OPTIMAL,
+        vulkanalia::vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT | vulkanalia::vk::FormatFeatureFlags::SAMPLED_IMAGE,
+    ).or_else(|_| get_supported_format(
         vk_instance,
         vk_physical_device,
         candidates,
         vulkanalia::vk::ImageTiling::OPTIMAL,
         vulkanalia::vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+    ))
+}
+
+/// Depth format of the shadow maps, that are rendered to and then sampled.
+pub fn get_shadow_map_format(
+    vk_instance: &vulkanalia::Instance,
+    vk_physical_device: vulkanalia::vk::PhysicalDevice
+) -> PResult<vulkanalia::vk::Format> {
+    let candidates = &[
+        vulkanalia::vk::Format::D32_SFLOAT,
+        vulkanalia::vk::Format::D16_UNORM,
+    ];
+
+    get_supported_format(
+        vk_instance,
+        vk_physical_device,
+        candidates,
+        vulkanalia::vk::ImageTiling::OPTIMAL,
+        vulkanalia::vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT | vulkanalia::vk::FormatFeatureFlags::SAMPLED_IMAGE,
     )
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use crate::engine::{
+    errors::PResult,
+    window::vulkan::vulkan_image::{
+        VulkanImage,
+        vulkan_image_view::create_image_view
+    },
+};
+
+use vulkanalia::vk::InstanceV1_0;
+
+/// The sample counts a multisampled render pass can use, from the highest to the lowest.
+const SAMPLE_COUNTS: [(u32, vulkanalia::vk::SampleCountFlags); 7] = [
+    (64, vulkanalia::vk::SampleCountFlags::_64),
+    (32, vulkanalia::vk::SampleCountFlags::_32),
+    (16, vulkanalia::vk::SampleCountFlags::_16),
+    (8, vulkanalia::vk::SampleCountFlags::_8),
+    (4, vulkanalia::vk::SampleCountFlags::_4),
+    (2, vulkanalia::vk::SampleCountFlags::_2),
+    (1, vulkanalia::vk::SampleCountFlags::_1),
+];
+
+/// The highest sample count supported by both the color and depth attachments of the device, that is not above the requested one.
+pub fn clamp_sample_count(
+    vk_instance: &vulkanalia::Instance,
+    vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    requested: u32,
+) -> vulkanalia::vk::SampleCountFlags {
+    let properties = unsafe { vk_instance.get_physical_device_properties(vk_physical_device) };
+    let supported = properties.limits.framebuffer_color_sample_counts & properties.limits.framebuffer_depth_sample_counts;
+    SAMPLE_COUNTS.iter()
+        .find(|(count, flag)| *count <= requested && supported.contains(*flag))
+        .map_or(vulkanalia::vk::SampleCountFlags::_1, |(_, flag)| *flag)
+}
+
+/// Create the multisampled color image a multisampled render pass draws into, before resolving it.
+/// It is only used during the render pass, so it can live in transient memory.
+pub fn create_multisampled_color_objects(
+    vk_instance: &vulkanalia::Instance,
+    vk_device: &vulkanalia::Device,
+    vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    extent: vulkanalia::vk::Extent2D,
+    format: vulkanalia::vk::Format,
+    samples: vulkanalia::vk::SampleCountFlags,
+) -> PResult<(VulkanImage, vulkanalia::vk::ImageView)> {
+    let image = VulkanImage::create_multisampled(
+        vk_instance,
+        vk_device,
+        vk_physical_device,
+        extent.width,
+        extent.height,
+        samples,
+        vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT | vulkanalia::vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        format,
+    )?;
+
+    let image_view = create_image_view(
+        vk_device,
+        &image,
+        format,
+        vulkanalia::vk::ImageAspectFlags::COLOR,
+    )?;
+
+    Ok((image, image_view))
+}
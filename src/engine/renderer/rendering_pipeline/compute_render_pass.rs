@@ -1,5 +1,509 @@
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::renderer::compute_pipeline::ComputePipeline;
+use crate::engine::renderer::compute_pipeline::compute_pipeline_builder::ComputePipelineBuilder;
+use crate::engine::renderer::compute_pipeline::post_process_effects::{PostProcessEffect, TonemapSettings};
+use crate::engine::window::vulkan::swapchain_interface::SwapchainInterface;
+use crate::engine::window::vulkan::vulkan_image::VulkanImage;
 
+use foundry::ComponentTable;
+use vulkanalia::vk::DeviceV1_0;
+use vulkanalia::vk::Handle;
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::InstanceV1_0;
 
+use self::bloom_pass::BloomPass;
+use self::compute_step::register_compute_dispatch;
+use self::ssao_pass::SsaoPass;
+
+use super::intermediate_render_targets::{IntermediateRenderTarget, IntermediateRenderTargetBuilder};
+
+pub(crate) mod bloom_pass;
+pub(crate) mod compute_step;
+pub(crate) mod ssao_pass;
+
+enum ComputePassTarget {
+    /// The last pass of the chain writes the swapchain images, we only own the framebuffers.
+    Swapchain(Vec<vulkanalia::vk::Framebuffer>),
+    /// Intermediate render targets, one per swapchain image, read by the next pass.
+    Intermediate(Vec<IntermediateRenderTarget>),
+}
+
+enum ComputePassKind {
+    /// A single pipeline, with one descriptor set per swapchain image pointing to its input and output images.
+    Single {
+        pipeline: ComputePipeline,
+        descriptor_pool: vulkanalia::vk::DescriptorPool,
+        descriptor_sets: Vec<vulkanalia::vk::DescriptorSet>,
+        /// The parameters the command buffer of each swapchain image was recorded with.
+        recorded_parameters: Vec<[glam::Vec4; 2]>,
+    },
+    Bloom(BloomPass),
+    AmbientOcclusion(SsaoPass),
+}
+
+/// Post process pass, running a single pipeline or one of the built-in effects over the image written by the previous pass.
+/// Fullscreen pipelines draw in a render pass, compute pipelines and effects write their output as a storage image.
+/// Every pass has its own images per swapchain image, as several frames are in flight.
 pub struct ComputeRenderPass {
-    
-}
\ No newline at end of file
+    id: u64,
+    builder: ComputePipelineBuilder,
+    /// The target the pass writes, the swapchain when there is none.
+    output_rt: Option<IntermediateRenderTargetBuilder>,
+    kind: ComputePassKind,
+    /// Render pass of the fullscreen pipelines, null for compute pipelines.
+    render_pass: vulkanalia::vk::RenderPass,
+    target: ComputePassTarget,
+    /// Color space of the images the pass writes, the one of the swapchain when it writes it.
+    output_color_space: vulkanalia::vk::ColorSpaceKHR,
+    /// Sampler of the input images.
+    sampler: vulkanalia::vk::Sampler,
+    extent: vulkanalia::vk::Extent2D,
+}
+
+impl ComputeRenderPass {
+    /// Create the pass reading the given input views, one per swapchain image, in shader read only layout.
+    /// The depth views are the ones the scene was drawn with, read by the ambient occlusion.
+    /// The pass writes the swapchain when it has no output render target, offscreen chains give one to their last pass.
+    pub fn create(
+        id: u64,
+        builder: ComputePipelineBuilder,
+        output_rt: Option<IntermediateRenderTargetBuilder>,
+        input_views: &[vulkanalia::vk::ImageView],
+        depth_views: &[vulkanalia::vk::ImageView],
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        extent: vulkanalia::vk::Extent2D,
+        image_count: usize,
+        swapchain: Option<&SwapchainInterface>,
+    ) -> PResult<ComputeRenderPass> {
+        let (output_format, output_color_space) = match (&output_rt, swapchain) {
+            (Some(output_rt), _) => match output_rt.color_format() {
+                Some(format) => (format, vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR),
+                None => return Err(PropellantError::Custom(format!("The output render target of the post process pass {id} has no color texture."))),
+            },
+            (None, Some(swapchain)) => (swapchain.format(), swapchain.color_space()),
+            (None, None) => return Err(PropellantError::Custom(format!("The post process pass {id} has neither an output render target nor a swapchain to write."))),
+        };
+
+        let (render_pass, target) = match (builder.is_fullscreen(), &output_rt, swapchain) {
+            (true, Some(output_rt), _) => {
+                let render_pass = Self::create_fullscreen_render_pass(vk_device, output_format, vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+                let targets = (0..image_count).map(|_| output_rt.build(
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    render_pass,
+                    extent.width,
+                    extent.height,
+                )).collect::<PResult<Vec<_>>>()?;
+                (render_pass, ComputePassTarget::Intermediate(targets))
+            },
+            (true, None, Some(swapchain)) => {
+                let render_pass = Self::create_fullscreen_render_pass(vk_device, output_format, vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR)?;
+                let framebuffers = swapchain.image_views().iter().map(|view| {
+                    let attachments = &[*view];
+                    let create_info = vulkanalia::vk::FramebufferCreateInfo::builder()
+                        .render_pass(render_pass)
+                        .attachments(attachments)
+                        .width(extent.width)
+                        .height(extent.height)
+                        .layers(1);
+                    unsafe { vk_device.create_framebuffer(&create_info, None) }
+                }).collect::<Result<Vec<_>, _>>()?;
+                (render_pass, ComputePassTarget::Swapchain(framebuffers))
+            },
+            (false, Some(output_rt), _) => {
+                // compute shaders and effects write the image directly, it must support storage.
+                let properties = unsafe { vk_instance.get_physical_device_format_properties(vk_physical_device, output_format) };
+                if !properties.optimal_tiling_features.contains(vulkanalia::vk::FormatFeatureFlags::STORAGE_IMAGE) {
+                    return Err(PropellantError::Custom(format!("The output format {output_format:?} of the compute pass {id} can not be used as a storage image.")));
+                }
+                let targets = (0..image_count).map(|_| output_rt.build(
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    vulkanalia::vk::RenderPass::null(),
+                    extent.width,
+                    extent.height,
+                )).collect::<PResult<Vec<_>>>()?;
+                (vulkanalia::vk::RenderPass::null(), ComputePassTarget::Intermediate(targets))
+            },
+            (false, None, _) => return Err(PropellantError::Custom(format!(
+                "The compute pass {id} can not write the swapchain: the last post process pass must be a fullscreen one."
+            ))),
+            (true, None, None) => return Err(PropellantError::Custom(format!("The post process pass {id} has no swapchain to write."))),
+        };
+
+        let info = vulkanalia::vk::SamplerCreateInfo::builder()
+            .mag_filter(vulkanalia::vk::Filter::LINEAR)
+            .min_filter(vulkanalia::vk::Filter::LINEAR)
+            .address_mode_u(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(vulkanalia::vk::BorderColor::FLOAT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .mipmap_mode(vulkanalia::vk::SamplerMipmapMode::NEAREST)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(0.0);
+        let sampler = unsafe { vk_device.create_sampler(&info, None)? };
+
+        let kind = match (builder.effect_settings(), &target) {
+            (None, _) => {
+                let pipeline = builder.build(vk_device, extent, render_pass)?;
+                let (descriptor_pool, descriptor_sets) = Self::create_descriptor_sets(vk_device, &pipeline, &target, sampler, input_views)?;
+                // the tonemapping encodes the colors for the color space it writes.
+                let parameters = match builder.tonemap_settings() {
+                    Some(settings) => settings.shader_parameters(output_color_space),
+                    None => builder.parameters(),
+                };
+                ComputePassKind::Single { pipeline, descriptor_pool, descriptor_sets, recorded_parameters: vec![parameters; image_count] }
+            },
+            (Some(PostProcessEffect::Bloom(settings)), ComputePassTarget::Intermediate(targets)) => {
+                let output_views = targets.iter().map(|target| target.view(0)).collect::<Vec<_>>();
+                ComputePassKind::Bloom(BloomPass::create(
+                    settings,
+                    sampler,
+                    input_views,
+                    &output_views,
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    extent,
+                )?)
+            },
+            (Some(PostProcessEffect::AmbientOcclusion(settings)), ComputePassTarget::Intermediate(targets)) => {
+                if depth_views.is_empty() {
+                    return Err(PropellantError::Custom(format!(
+                        "The ambient occlusion pass {id} can not read the depth of the scene: it is multisampled."
+                    )));
+                }
+                let output_views = targets.iter().map(|target| target.view(0)).collect::<Vec<_>>();
+                ComputePassKind::AmbientOcclusion(SsaoPass::create(
+                    settings,
+                    sampler,
+                    input_views,
+                    depth_views,
+                    &output_views,
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    extent,
+                )?)
+            },
+            (Some(_), ComputePassTarget::Swapchain(_)) => return Err(PropellantError::Custom(format!(
+                "The effect of the post process pass {id} can not write the swapchain: the last post process pass must be a fullscreen one."
+            ))),
+        };
+
+        Ok(ComputeRenderPass {
+            id,
+            builder,
+            output_rt,
+            kind,
+            render_pass,
+            target,
+            output_color_space,
+            sampler,
+            extent,
+        })
+    }
+
+    fn create_descriptor_sets(
+        vk_device: &vulkanalia::Device,
+        pipeline: &ComputePipeline,
+        target: &ComputePassTarget,
+        sampler: vulkanalia::vk::Sampler,
+        input_views: &[vulkanalia::vk::ImageView],
+    ) -> PResult<(vulkanalia::vk::DescriptorPool, Vec<vulkanalia::vk::DescriptorSet>)> {
+        let image_count = input_views.len();
+        let sampler_size = vulkanalia::vk::DescriptorPoolSize::builder()
+            .type_(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(image_count as u32);
+        let storage_size = vulkanalia::vk::DescriptorPoolSize::builder()
+            .type_(vulkanalia::vk::DescriptorType::STORAGE_IMAGE)
+            .descriptor_count(image_count as u32);
+        let pool_sizes = match pipeline.is_fullscreen() {
+            true => vec![sampler_size],
+            false => vec![sampler_size, storage_size],
+        };
+        let info = vulkanalia::vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(&pool_sizes)
+            .max_sets(image_count as u32);
+        let descriptor_pool = unsafe { vk_device.create_descriptor_pool(&info, None)? };
+
+        let layouts = vec![pipeline.descriptor_set_layout(); image_count];
+        let info = vulkanalia::vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&layouts);
+        let descriptor_sets = unsafe { vk_device.allocate_descriptor_sets(&info)? };
+
+        for (image_index, (descriptor_set, input_view)) in descriptor_sets.iter().zip(input_views.iter()).enumerate() {
+            let input_info = vulkanalia::vk::DescriptorImageInfo::builder()
+                .sampler(sampler)
+                .image_view(*input_view)
+                .image_layout(vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            let input_infos = &[input_info];
+            let input_write = vulkanalia::vk::WriteDescriptorSet::builder()
+                .dst_set(*descriptor_set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(input_infos);
+
+            match (pipeline.is_fullscreen(), target) {
+                (false, ComputePassTarget::Intermediate(targets)) => {
+                    let output_info = vulkanalia::vk::DescriptorImageInfo::builder()
+                        .image_view(targets[image_index].view(0))
+                        .image_layout(vulkanalia::vk::ImageLayout::GENERAL);
+                    let output_infos = &[output_info];
+                    let output_write = vulkanalia::vk::WriteDescriptorSet::builder()
+                        .dst_set(*descriptor_set)
+                        .dst_binding(1)
+                        .dst_array_element(0)
+                        .descriptor_type(vulkanalia::vk::DescriptorType::STORAGE_IMAGE)
+                        .image_info(output_infos);
+                    unsafe { vk_device.update_descriptor_sets(&[input_write, output_write], &[] as &[vulkanalia::vk::CopyDescriptorSet]) };
+                },
+                _ => unsafe { vk_device.update_descriptor_sets(&[input_write], &[] as &[vulkanalia::vk::CopyDescriptorSet]) },
+            }
+        }
+
+        Ok((descriptor_pool, descriptor_sets))
+    }
+
+    fn create_fullscreen_render_pass(
+        vk_device: &vulkanalia::Device,
+        color_format: vulkanalia::vk::Format,
+        final_layout: vulkanalia::vk::ImageLayout,
+    ) -> PResult<vulkanalia::vk::RenderPass> {
+        // every pixel is drawn, the previous content is not loaded.
+        let color_attachment = vulkanalia::vk::AttachmentDescription::builder()
+            .format(color_format)
+            .samples(vulkanalia::vk::SampleCountFlags::_1)
+            .load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vulkanalia::vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+            .final_layout(final_layout);
+
+        let color_attachment_ref = vulkanalia::vk::AttachmentReference::builder()
+            .attachment(0)
+            .layout(vulkanalia::vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+        let color_attachments = &[color_attachment_ref];
+        let subpass = vulkanalia::vk::SubpassDescription::builder()
+            .pipeline_bind_point(vulkanalia::vk::PipelineBindPoint::GRAPHICS)
+            .color_attachments(color_attachments);
+
+        // wait for the swapchain image to be acquired before writing it.
+        let dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vulkanalia::vk::AccessFlags::empty())
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
+        // the next post process pass waits for the color writes before sampling the image.
+        let sampled_dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::FRAGMENT_SHADER | vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::SHADER_READ);
+
+        let attachments = &[color_attachment];
+        let subpasses = &[subpass];
+        let dependencies = match final_layout {
+            vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => vec![dependency, sampled_dependency],
+            _ => vec![dependency],
+        };
+        let info = vulkanalia::vk::RenderPassCreateInfo::builder()
+            .attachments(attachments)
+            .subpasses(subpasses)
+            .dependencies(&dependencies);
+
+        Ok(unsafe { vk_device.create_render_pass(&info, None)? })
+    }
+
+    /// The color views the pass writes for each swapchain image, when it does not write the swapchain.
+    pub fn output_views(&self) -> Option<Vec<vulkanalia::vk::ImageView>> {
+        match &self.target {
+            ComputePassTarget::Swapchain(_) => None,
+            ComputePassTarget::Intermediate(targets) => Some(targets.iter().map(|target| target.view(0)).collect()),
+        }
+    }
+
+    /// The color image the pass writes, in shader read only layout once the pass is done, when it does not write the swapchain.
+    pub fn output_image(&self, image_index: usize) -> Option<&VulkanImage> {
+        match &self.target {
+            ComputePassTarget::Swapchain(_) => None,
+            ComputePassTarget::Intermediate(targets) => targets.get(image_index).map(|target| target.image(0)),
+        }
+    }
+
+    /// The tonemapping settings of the pass if it is a tonemapping one, the ones of the scene overriding the ones of the builder.
+    pub fn tonemap_settings(&self, components: &ComponentTable) -> Option<TonemapSettings> {
+        let settings = self.builder.tonemap_settings()?;
+        match components.get_singleton::<TonemapSettings>() {
+            // the color space is the one the swapchain was created with.
+            Some(runtime_settings) => Some(runtime_settings.with_hdr_output(settings.hdr_output())),
+            None => Some(settings),
+        }
+    }
+
+    /// Read the settings of the effect in the scene, and check whether the commands of the image were recorded with other ones.
+    pub fn update_uniform_buffers(&mut self, image_index: usize, components: &ComponentTable) -> bool {
+        let tonemap_settings = self.tonemap_settings(components);
+        match &mut self.kind {
+            ComputePassKind::Single { recorded_parameters, .. } => match tonemap_settings {
+                Some(settings) => {
+                    let parameters = settings.shader_parameters(self.output_color_space);
+                    match recorded_parameters[image_index] == parameters {
+                        true => false,
+                        false => {
+                            recorded_parameters[image_index] = parameters;
+                            true
+                        }
+                    }
+                },
+                None => false,
+            },
+            ComputePassKind::Bloom(bloom) => bloom.update_uniform_buffers(image_index, components),
+            ComputePassKind::AmbientOcclusion(ssao) => ssao.update_uniform_buffers(image_index, components),
+        }
+    }
+
+    pub fn register_draw_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        image_index: usize,
+    ) -> PResult<()> {
+        match (&self.kind, &self.target) {
+            (ComputePassKind::Single { pipeline, descriptor_sets, recorded_parameters, .. }, ComputePassTarget::Swapchain(framebuffers)) => {
+                self.register_fullscreen_commands(
+                    vk_device,
+                    command_buffer,
+                    pipeline,
+                    framebuffers[image_index],
+                    descriptor_sets[image_index],
+                    recorded_parameters[image_index],
+                );
+            },
+            (ComputePassKind::Single { pipeline, descriptor_sets, recorded_parameters, .. }, ComputePassTarget::Intermediate(targets)) => match pipeline.is_fullscreen() {
+                true => self.register_fullscreen_commands(
+                    vk_device,
+                    command_buffer,
+                    pipeline,
+                    targets[image_index].framebuffer(),
+                    descriptor_sets[image_index],
+                    recorded_parameters[image_index],
+                ),
+                false => register_compute_dispatch(
+                    vk_device,
+                    command_buffer,
+                    pipeline,
+                    descriptor_sets[image_index],
+                    targets[image_index].image(0).image(),
+                    self.extent,
+                    recorded_parameters[image_index],
+                ),
+            },
+            (ComputePassKind::Bloom(bloom), ComputePassTarget::Intermediate(targets)) => {
+                bloom.register_commands(vk_device, command_buffer, image_index, targets[image_index].image(0).image(), self.extent);
+            },
+            (ComputePassKind::AmbientOcclusion(ssao), ComputePassTarget::Intermediate(targets)) => {
+                ssao.register_commands(vk_device, command_buffer, image_index, targets[image_index].image(0).image(), self.extent);
+            },
+            (_, ComputePassTarget::Swapchain(_)) => return Err(PropellantError::Custom(format!(
+                "The effect of the post process pass {} can not write the swapchain.", self.id
+            ))),
+        }
+        Ok(())
+    }
+
+    fn register_fullscreen_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        pipeline: &ComputePipeline,
+        framebuffer: vulkanalia::vk::Framebuffer,
+        descriptor_set: vulkanalia::vk::DescriptorSet,
+        parameters: [glam::Vec4; 2],
+    ) {
+        let render_area = vulkanalia::vk::Rect2D::builder()
+            .offset(vulkanalia::vk::Offset2D::default())
+            .extent(self.extent);
+        let info = vulkanalia::vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(framebuffer)
+            .render_area(render_area);
+
+        unsafe { vk_device.cmd_begin_render_pass(command_buffer, &info, vulkanalia::vk::SubpassContents::INLINE) };
+        pipeline.register_commands(vk_device, command_buffer, descriptor_set, self.extent, parameters);
+        unsafe { vk_device.cmd_end_render_pass(command_buffer) };
+    }
+
+    /// Rebuild the pass over the new swapchain, reading the new input views.
+    /// The pass must have been destroyed by `destroy` before.
+    pub fn recreate(
+        &mut self,
+        input_views: &[vulkanalia::vk::ImageView],
+        depth_views: &[vulkanalia::vk::ImageView],
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        swapchain: &SwapchainInterface,
+    ) -> PResult<()> {
+        *self = ComputeRenderPass::create(
+            self.id,
+            self.builder.clone(),
+            self.output_rt.clone(),
+            input_views,
+            depth_views,
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            swapchain.extent(),
+            swapchain.images().len(),
+            Some(swapchain),
+        )?;
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        match &mut self.kind {
+            ComputePassKind::Single { pipeline, descriptor_pool, descriptor_sets, .. } => {
+                pipeline.destroy(vk_device);
+                // the descriptor sets are freed with their pool.
+                unsafe { vk_device.destroy_descriptor_pool(*descriptor_pool, None) };
+                descriptor_sets.clear();
+            },
+            ComputePassKind::Bloom(bloom) => bloom.destroy(vk_device),
+            ComputePassKind::AmbientOcclusion(ssao) => ssao.destroy(vk_device),
+        }
+        unsafe {
+            vk_device.destroy_sampler(self.sampler, None);
+            vk_device.destroy_render_pass(self.render_pass, None);
+        }
+        match &mut self.target {
+            ComputePassTarget::Swapchain(framebuffers) => {
+                for framebuffer in framebuffers.drain(..) {
+                    unsafe { vk_device.destroy_framebuffer(framebuffer, None) };
+                }
+            },
+            ComputePassTarget::Intermediate(targets) => {
+                for mut target in targets.drain(..) {
+                    target.destroy(vk_device);
+                }
+            },
+        }
+    }
+}
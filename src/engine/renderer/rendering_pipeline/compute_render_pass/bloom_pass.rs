@@ -0,0 +1,207 @@
+use crate::engine::errors::PResult;
+use crate::engine::renderer::compute_pipeline::post_process_effects::BloomSettings;
+use crate::engine::renderer::rendering_pipeline::intermediate_render_targets::{IntermediateRenderTarget, IntermediateRenderTargetBuilder};
+use crate::engine::renderer::shaders::{BLOOM_COMPOSITE_COMP, BLOOM_DOWNSAMPLE_COMP, BLOOM_PREFILTER_COMP, BLOOM_UPSAMPLE_COMP};
+
+use foundry::ComponentTable;
+use vulkanalia::vk::Handle;
+
+use super::compute_step::{ComputeStep, ComputeStepBindings, SampledInput};
+
+/// The images of the bloom of one swapchain image.
+struct BloomImages {
+    /// The bright parts of the scene, downsampled: the first level is half the size of the scene, each next one half the previous.
+    downsampled: Vec<IntermediateRenderTarget>,
+    /// The upsampled blur of each level but the smallest, the first one is the final bloom.
+    upsampled: Vec<IntermediateRenderTarget>,
+}
+
+impl BloomImages {
+    /// The blurred image added to the scene.
+    fn bloom_view(&self) -> vulkanalia::vk::ImageView {
+        match self.upsampled.first() {
+            Some(upsampled) => upsampled.view(0),
+            None => self.downsampled[0].view(0),
+        }
+    }
+}
+
+/// Bloom effect: the bright parts of the image are extracted in a half size image, downsampled in a chain of smaller images,
+/// upsampled back while summing the levels, and the result is added to the image.
+/// Every step is a compute dispatch, with its own images for each swapchain image.
+pub(crate) struct BloomPass {
+    settings: BloomSettings,
+    /// The settings the command buffer of each swapchain image was recorded with.
+    recorded_settings: Vec<BloomSettings>,
+    images: Vec<BloomImages>,
+    /// Extent of each downsampled level.
+    level_extents: Vec<vulkanalia::vk::Extent2D>,
+    prefilter: ComputeStep,
+    /// One descriptor set per level after the first, for each swapchain image. None with a single level.
+    downsample: Option<ComputeStep>,
+    /// One descriptor set per level before the last, for each swapchain image. None with a single level.
+    upsample: Option<ComputeStep>,
+    /// Two descriptor sets per swapchain image: adding the bloom, and copying the scene when the bloom is disabled.
+    composite: ComputeStep,
+}
+
+impl BloomPass {
+    pub fn create(
+        settings: BloomSettings,
+        sampler: vulkanalia::vk::Sampler,
+        input_views: &[vulkanalia::vk::ImageView],
+        output_views: &[vulkanalia::vk::ImageView],
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        extent: vulkanalia::vk::Extent2D,
+    ) -> PResult<BloomPass> {
+        let levels = settings.levels() as usize;
+        let level_extents = (0..levels).map(|level| vulkanalia::vk::Extent2D {
+            width: (extent.width >> (level + 1)).max(1),
+            height: (extent.height >> (level + 1)).max(1),
+        }).collect::<Vec<_>>();
+
+        let level_builder = IntermediateRenderTargetBuilder::hdr_color();
+        let images = input_views.iter().map(|_| {
+            let build_level = |level_extent: &vulkanalia::vk::Extent2D| level_builder.build(
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                vulkanalia::vk::RenderPass::null(),
+                level_extent.width,
+                level_extent.height,
+            );
+            Ok(BloomImages {
+                downsampled: level_extents.iter().map(build_level).collect::<PResult<Vec<_>>>()?,
+                upsampled: level_extents.iter().take(levels - 1).map(build_level).collect::<PResult<Vec<_>>>()?,
+            })
+        }).collect::<PResult<Vec<_>>>()?;
+
+        let prefilter_bindings = input_views.iter().zip(images.iter()).map(|(input_view, images)| ComputeStepBindings {
+            input: SampledInput::color(sampler, *input_view),
+            output: images.downsampled[0].view(0),
+            secondary_input: None,
+        }).collect::<Vec<_>>();
+        let prefilter = ComputeStep::create(vk_device, BLOOM_PREFILTER_COMP, &prefilter_bindings)?;
+
+        let (downsample, upsample) = match levels {
+            1 => (None, None),
+            _ => {
+                let downsample_bindings = images.iter().flat_map(|images| (1..levels).map(|level| ComputeStepBindings {
+                    input: SampledInput::color(sampler, images.downsampled[level - 1].view(0)),
+                    output: images.downsampled[level].view(0),
+                    secondary_input: None,
+                })).collect::<Vec<_>>();
+                // the upsampled level l is the level l + 1 upsampled, added to the downsampled level l.
+                let upsample_bindings = images.iter().flat_map(|images| (0..levels - 1).map(|level| ComputeStepBindings {
+                    input: match level + 2 == levels {
+                        true => SampledInput::color(sampler, images.downsampled[level + 1].view(0)),
+                        false => SampledInput::color(sampler, images.upsampled[level + 1].view(0)),
+                    },
+                    output: images.upsampled[level].view(0),
+                    secondary_input: Some(SampledInput::color(sampler, images.downsampled[level].view(0))),
+                })).collect::<Vec<_>>();
+                (
+                    Some(ComputeStep::create(vk_device, BLOOM_DOWNSAMPLE_COMP, &downsample_bindings)?),
+                    Some(ComputeStep::create(vk_device, BLOOM_UPSAMPLE_COMP, &upsample_bindings)?),
+                )
+            }
+        };
+
+        let composite_bindings = input_views.iter().zip(output_views.iter()).zip(images.iter()).flat_map(|((input_view, output_view), images)| [
+            ComputeStepBindings {
+                input: SampledInput::color(sampler, *input_view),
+                output: *output_view,
+                secondary_input: Some(SampledInput::color(sampler, images.bloom_view())),
+            },
+            ComputeStepBindings {
+                input: SampledInput::color(sampler, *input_view),
+                output: *output_view,
+                secondary_input: Some(SampledInput::color(sampler, *input_view)),
+            },
+        ]).collect::<Vec<_>>();
+        let composite = ComputeStep::create(vk_device, BLOOM_COMPOSITE_COMP, &composite_bindings)?;
+
+        Ok(BloomPass {
+            settings,
+            recorded_settings: vec![settings; input_views.len()],
+            images,
+            level_extents,
+            prefilter,
+            downsample,
+            upsample,
+            composite,
+        })
+    }
+
+    /// Read the bloom settings of the scene, and check whether the commands of the image were recorded with other ones.
+    pub fn update_uniform_buffers(&mut self, image_index: usize, components: &ComponentTable) -> bool {
+        let settings = match components.get_singleton::<BloomSettings>() {
+            // the levels are the ones the images were built with.
+            Some(settings) => settings.with_levels(self.settings.levels()),
+            None => self.settings,
+        };
+        match self.recorded_settings[image_index] == settings {
+            true => false,
+            false => {
+                self.recorded_settings[image_index] = settings;
+                true
+            }
+        }
+    }
+
+    pub fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        image_index: usize,
+        output_image: vulkanalia::vk::Image,
+        extent: vulkanalia::vk::Extent2D,
+    ) {
+        let settings = self.recorded_settings[image_index];
+        if !settings.enabled() {
+            // copy the scene to the output, without the bloom.
+            self.composite.register_commands(vk_device, command_buffer, image_index * 2 + 1, output_image, extent, [glam::Vec4::ZERO; 2]);
+            return;
+        }
+
+        let levels = self.level_extents.len();
+        let images = &self.images[image_index];
+        let prefilter_parameters = [glam::vec4(settings.threshold(), settings.soft_knee(), 0.0, 0.0), glam::Vec4::ZERO];
+        self.prefilter.register_commands(vk_device, command_buffer, image_index, images.downsampled[0].image(0).image(), self.level_extents[0], prefilter_parameters);
+
+        if let Some(downsample) = &self.downsample {
+            for level in 1..levels {
+                let set_index = image_index * (levels - 1) + level - 1;
+                downsample.register_commands(vk_device, command_buffer, set_index, images.downsampled[level].image(0).image(), self.level_extents[level], [glam::Vec4::ZERO; 2]);
+            }
+        }
+
+        if let Some(upsample) = &self.upsample {
+            let upsample_parameters = [glam::vec4(settings.radius(), 0.0, 0.0, 0.0), glam::Vec4::ZERO];
+            // from the smallest level to the largest, each one reading the previous one.
+            for level in (0..levels - 1).rev() {
+                let set_index = image_index * (levels - 1) + level;
+                upsample.register_commands(vk_device, command_buffer, set_index, images.upsampled[level].image(0).image(), self.level_extents[level], upsample_parameters);
+            }
+        }
+
+        let composite_parameters = [glam::vec4(settings.intensity(), 0.0, 0.0, 0.0), glam::Vec4::ZERO];
+        self.composite.register_commands(vk_device, command_buffer, image_index * 2, output_image, extent, composite_parameters);
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.prefilter.destroy(vk_device);
+        if let Some(downsample) = &mut self.downsample {
+            downsample.destroy(vk_device);
+        }
+        if let Some(upsample) = &mut self.upsample {
+            upsample.destroy(vk_device);
+        }
+        self.composite.destroy(vk_device);
+        for mut images in self.images.drain(..) {
+            images.downsampled.iter_mut().chain(images.upsampled.iter_mut()).for_each(|target| target.destroy(vk_device));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::engine::errors::PResult;
+use crate::engine::renderer::compute_pipeline::ComputePipeline;
+use crate::engine::renderer::compute_pipeline::compute_pipeline_builder::ComputeShader;
+
+use vulkanalia::vk::DeviceV1_0;
+use vulkanalia::vk::Handle;
+use vulkanalia::vk::HasBuilder;
+
+/// A sampled image of a compute step, with the sampler and the layout it is read in.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SampledInput {
+    pub sampler: vulkanalia::vk::Sampler,
+    pub view: vulkanalia::vk::ImageView,
+    pub layout: vulkanalia::vk::ImageLayout,
+}
+
+impl SampledInput {
+    /// A color image written by a previous pass or step.
+    pub fn color(sampler: vulkanalia::vk::Sampler, view: vulkanalia::vk::ImageView) -> SampledInput {
+        SampledInput {
+            sampler,
+            view,
+            layout: vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+/// The images a descriptor set of a compute step points to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ComputeStepBindings {
+    /// Sampled at binding 0.
+    pub input: SampledInput,
+    /// Written as a storage image at binding 1.
+    pub output: vulkanalia::vk::ImageView,
+    /// Sampled at binding 2, for the steps reading two images.
+    pub secondary_input: Option<SampledInput>,
+}
+
+/// A single compute dispatch of an effect, with all the descriptor sets it can be recorded with.
+/// The effects made of several dispatches chain steps over their own images.
+pub(crate) struct ComputeStep {
+    pipeline: ComputePipeline,
+    descriptor_pool: vulkanalia::vk::DescriptorPool,
+    descriptor_sets: Vec<vulkanalia::vk::DescriptorSet>,
+}
+
+impl ComputeStep {
+    /// Create the step, with one descriptor set for each of the given bindings, in the same order.
+    pub fn create(
+        vk_device: &vulkanalia::Device,
+        shader: &'static [u32],
+        bindings: &[ComputeStepBindings],
+    ) -> PResult<ComputeStep> {
+        let secondary_input = bindings.first().is_some_and(|binding| binding.secondary_input.is_some());
+        let pipeline = ComputePipeline::create(
+            vk_device,
+            ComputeShader::Compute(shader),
+            secondary_input,
+            vulkanalia::vk::Extent2D::default(),
+            vulkanalia::vk::RenderPass::null(),
+        )?;
+
+        let set_count = bindings.len() as u32;
+        let sampler_count = match secondary_input {
+            true => set_count * 2,
+            false => set_count,
+        };
+        let pool_sizes = &[
+            vulkanalia::vk::DescriptorPoolSize::builder()
+                .type_(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_count(sampler_count),
+            vulkanalia::vk::DescriptorPoolSize::builder()
+                .type_(vulkanalia::vk::DescriptorType::STORAGE_IMAGE)
+                .descriptor_count(set_count),
+        ];
+        let info = vulkanalia::vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(set_count);
+        let descriptor_pool = unsafe { vk_device.create_descriptor_pool(&info, None)? };
+
+        let layouts = vec![pipeline.descriptor_set_layout(); bindings.len()];
+        let info = vulkanalia::vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&layouts);
+        let descriptor_sets = unsafe { vk_device.allocate_descriptor_sets(&info)? };
+
+        for (descriptor_set, binding) in descriptor_sets.iter().zip(bindings.iter()) {
+            let input_infos = &[Self::sampled_info(binding.input)];
+            let output_infos = &[vulkanalia::vk::DescriptorImageInfo::builder()
+                .image_view(binding.output)
+                .image_layout(vulkanalia::vk::ImageLayout::GENERAL)];
+            let mut writes = vec![
+                vulkanalia::vk::WriteDescriptorSet::builder()
+                    .dst_set(*descriptor_set)
+                    .dst_binding(0)
+                    .dst_array_element(0)
+                    .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .image_info(input_infos),
+                vulkanalia::vk::WriteDescriptorSet::builder()
+                    .dst_set(*descriptor_set)
+                    .dst_binding(1)
+                    .dst_array_element(0)
+                    .descriptor_type(vulkanalia::vk::DescriptorType::STORAGE_IMAGE)
+                    .image_info(output_infos),
+            ];
+            let secondary_infos = binding.secondary_input.map(|input| [Self::sampled_info(input)]);
+            if let Some(secondary_infos) = &secondary_infos {
+                writes.push(vulkanalia::vk::WriteDescriptorSet::builder()
+                    .dst_set(*descriptor_set)
+                    .dst_binding(2)
+                    .dst_array_element(0)
+                    .descriptor_type(vulkanalia::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .image_info(secondary_infos));
+            }
+            unsafe { vk_device.update_descriptor_sets(&writes, &[] as &[vulkanalia::vk::CopyDescriptorSet]) };
+        }
+
+        Ok(ComputeStep {
+            pipeline,
+            descriptor_pool,
+            descriptor_sets,
+        })
+    }
+
+    fn sampled_info(input: SampledInput) -> vulkanalia::vk::DescriptorImageInfo {
+        vulkanalia::vk::DescriptorImageInfo::builder()
+            .sampler(input.sampler)
+            .image_view(input.view)
+            .image_layout(input.layout)
+            .build()
+    }
+
+    /// Record the dispatch with the descriptor set at the given index, writing the output image of the given extent.
+    pub fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        set_index: usize,
+        output_image: vulkanalia::vk::Image,
+        extent: vulkanalia::vk::Extent2D,
+        parameters: [glam::Vec4; 2],
+    ) {
+        register_compute_dispatch(
+            vk_device,
+            command_buffer,
+            &self.pipeline,
+            self.descriptor_sets[set_index],
+            output_image,
+            extent,
+            parameters,
+        );
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.pipeline.destroy(vk_device);
+        // the descriptor sets are freed with their pool.
+        unsafe { vk_device.destroy_descriptor_pool(self.descriptor_pool, None) };
+        self.descriptor_sets.clear();
+    }
+}
+
+/// Record a compute pipeline writing the whole output image, with the barriers around it.
+/// The previous content of the image is discarded, and it is left ready to be sampled by the next dispatches and passes.
+pub(crate) fn register_compute_dispatch(
+    vk_device: &vulkanalia::Device,
+    command_buffer: vulkanalia::vk::CommandBuffer,
+    pipeline: &ComputePipeline,
+    descriptor_set: vulkanalia::vk::DescriptorSet,
+    output_image: vulkanalia::vk::Image,
+    extent: vulkanalia::vk::Extent2D,
+    parameters: [glam::Vec4; 2],
+) {
+    let subresource = vulkanalia::vk::ImageSubresourceRange::builder()
+        .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+    // the previous content is discarded, the shader writes every pixel.
+    let to_general = vulkanalia::vk::ImageMemoryBarrier::builder()
+        .old_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+        .new_layout(vulkanalia::vk::ImageLayout::GENERAL)
+        .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+        .image(output_image)
+        .subresource_range(subresource)
+        .src_access_mask(vulkanalia::vk::AccessFlags::empty())
+        .dst_access_mask(vulkanalia::vk::AccessFlags::SHADER_WRITE);
+    // the next dispatch or pass samples the image once it is written.
+    let to_sampled = vulkanalia::vk::ImageMemoryBarrier::builder()
+        .old_layout(vulkanalia::vk::ImageLayout::GENERAL)
+        .new_layout(vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+        .image(output_image)
+        .subresource_range(subresource)
+        .src_access_mask(vulkanalia::vk::AccessFlags::SHADER_WRITE)
+        .dst_access_mask(vulkanalia::vk::AccessFlags::SHADER_READ);
+
+    unsafe {
+        vk_device.cmd_pipeline_barrier(
+            command_buffer,
+            vulkanalia::vk::PipelineStageFlags::TOP_OF_PIPE,
+            vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER,
+            vulkanalia::vk::DependencyFlags::empty(),
+            &[] as &[vulkanalia::vk::MemoryBarrier],
+            &[] as &[vulkanalia::vk::BufferMemoryBarrier],
+            &[to_general],
+        );
+    }
+    pipeline.register_commands(vk_device, command_buffer, descriptor_set, extent, parameters);
+    unsafe {
+        vk_device.cmd_pipeline_barrier(
+            command_buffer,
+            vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER,
+            vulkanalia::vk::PipelineStageFlags::FRAGMENT_SHADER | vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER,
+            vulkanalia::vk::DependencyFlags::empty(),
+            &[] as &[vulkanalia::vk::MemoryBarrier],
+            &[] as &[vulkanalia::vk::BufferMemoryBarrier],
+            &[to_sampled],
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::engine::common_components::camera::Camera;
+use crate::engine::errors::PResult;
+use crate::engine::renderer::compute_pipeline::post_process_effects::SsaoSettings;
+use crate::engine::renderer::rendering_pipeline::intermediate_render_targets::{IntermediateRenderTarget, IntermediateRenderTargetBuilder};
+use crate::engine::renderer::shaders::{SSAO_BLUR_COMP, SSAO_COMPOSITE_COMP, SSAO_COMP};
+
+use foundry::ComponentTable;
+use vulkanalia::vk::DeviceV1_0;
+use vulkanalia::vk::Handle;
+use vulkanalia::vk::HasBuilder;
+
+use super::compute_step::{ComputeStep, ComputeStepBindings, SampledInput};
+
+/// What the commands of a swapchain image were recorded with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SsaoState {
+    settings: SsaoSettings,
+    /// Projection terms of the main camera, none when there is no main camera to rebuild the positions with.
+    projection: Option<glam::Vec4>,
+    orthographic: bool,
+}
+
+/// Screen space ambient occlusion: the occlusion of each pixel is computed from the depth the scene was drawn with,
+/// blurred to remove the noise of the sampling, and multiplied with the image.
+/// Every step is a compute dispatch, with its own images for each swapchain image.
+pub(crate) struct SsaoPass {
+    settings: SsaoSettings,
+    recorded_states: Vec<SsaoState>,
+    /// The raw and the blurred occlusion of each swapchain image.
+    occlusion_images: Vec<(IntermediateRenderTarget, IntermediateRenderTarget)>,
+    /// Sampler of the depth texture, that can not be filtered on every device.
+    depth_sampler: vulkanalia::vk::Sampler,
+    occlusion: ComputeStep,
+    blur: ComputeStep,
+    /// Two descriptor sets per swapchain image: applying the occlusion, and copying the scene when it is disabled.
+    composite: ComputeStep,
+}
+
+impl SsaoPass {
+    pub fn create(
+        settings: SsaoSettings,
+        sampler: vulkanalia::vk::Sampler,
+        input_views: &[vulkanalia::vk::ImageView],
+        depth_views: &[vulkanalia::vk::ImageView],
+        output_views: &[vulkanalia::vk::ImageView],
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        extent: vulkanalia::vk::Extent2D,
+    ) -> PResult<SsaoPass> {
+        let occlusion_builder = IntermediateRenderTargetBuilder::hdr_color();
+        let build_occlusion = || occlusion_builder.build(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            vulkanalia::vk::RenderPass::null(),
+            extent.width,
+            extent.height,
+        );
+        let occlusion_images = input_views.iter()
+            .map(|_| Ok((build_occlusion()?, build_occlusion()?)))
+            .collect::<PResult<Vec<_>>>()?;
+
+        let info = vulkanalia::vk::SamplerCreateInfo::builder()
+            .mag_filter(vulkanalia::vk::Filter::NEAREST)
+            .min_filter(vulkanalia::vk::Filter::NEAREST)
+            .address_mode_u(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(vulkanalia::vk::BorderColor::FLOAT_OPAQUE_WHITE)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .mipmap_mode(vulkanalia::vk::SamplerMipmapMode::NEAREST)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(0.0);
+        let depth_sampler = unsafe { vk_device.create_sampler(&info, None)? };
+
+        let occlusion_bindings = depth_views.iter().zip(occlusion_images.iter()).map(|(depth_view, (raw, _))| ComputeStepBindings {
+            // the graphic render pass leaves the depth in a read only layout when it is sampled.
+            input: SampledInput {
+                sampler: depth_sampler,
+                view: *depth_view,
+                layout: vulkanalia::vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            },
+            output: raw.view(0),
+            secondary_input: None,
+        }).collect::<Vec<_>>();
+        let occlusion = ComputeStep::create(vk_device, SSAO_COMP, &occlusion_bindings)?;
+
+        let blur_bindings = occlusion_images.iter().map(|(raw, blurred)| ComputeStepBindings {
+            input: SampledInput::color(sampler, raw.view(0)),
+            output: blurred.view(0),
+            secondary_input: None,
+        }).collect::<Vec<_>>();
+        let blur = ComputeStep::create(vk_device, SSAO_BLUR_COMP, &blur_bindings)?;
+
+        let composite_bindings = input_views.iter().zip(output_views.iter()).zip(occlusion_images.iter()).flat_map(|((input_view, output_view), (_, blurred))| [
+            ComputeStepBindings {
+                input: SampledInput::color(sampler, *input_view),
+                output: *output_view,
+                secondary_input: Some(SampledInput::color(sampler, blurred.view(0))),
+            },
+            ComputeStepBindings {
+                input: SampledInput::color(sampler, *input_view),
+                output: *output_view,
+                secondary_input: Some(SampledInput::color(sampler, *input_view)),
+            },
+        ]).collect::<Vec<_>>();
+        let composite = ComputeStep::create(vk_device, SSAO_COMPOSITE_COMP, &composite_bindings)?;
+
+        let initial_state = SsaoState {
+            settings,
+            projection: None,
+            orthographic: false,
+        };
+
+        Ok(SsaoPass {
+            settings,
+            recorded_states: vec![initial_state; input_views.len()],
+            occlusion_images,
+            depth_sampler,
+            occlusion,
+            blur,
+            composite,
+        })
+    }
+
+    /// Read the ssao settings and the main camera of the scene, and check whether the commands of the image were recorded with other ones.
+    pub fn update_uniform_buffers(&mut self, image_index: usize, components: &ComponentTable) -> bool {
+        let settings = match components.get_singleton::<SsaoSettings>() {
+            Some(settings) => *settings,
+            None => self.settings,
+        };
+        let camera = components.query1d::<Camera>().map(|(_, camera)| camera).find(|camera| camera.is_main());
+        let state = SsaoState {
+            settings,
+            // the terms of the projection needed to rebuild the view space positions from the depth.
+            projection: camera.map(|camera| {
+                let projection = camera.projection_matrix();
+                glam::vec4(projection.x_axis.x, projection.y_axis.y, projection.z_axis.z, projection.w_axis.z)
+            }),
+            orthographic: camera.is_some_and(|camera| camera.is_orthographic()),
+        };
+        match self.recorded_states[image_index] == state {
+            true => false,
+            false => {
+                self.recorded_states[image_index] = state;
+                true
+            }
+        }
+    }
+
+    pub fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        image_index: usize,
+        output_image: vulkanalia::vk::Image,
+        extent: vulkanalia::vk::Extent2D,
+    ) {
+        let state = self.recorded_states[image_index];
+        let projection = match (state.settings.enabled(), state.projection) {
+            (true, Some(projection)) => projection,
+            _ => {
+                // copy the scene to the output, without occlusion.
+                self.composite.register_commands(vk_device, command_buffer, image_index * 2 + 1, output_image, extent, [glam::Vec4::ZERO; 2]);
+                return;
+            }
+        };
+
+        let (raw, blurred) = &self.occlusion_images[image_index];
+        let orthographic = match state.orthographic {
+            true => 1.0,
+            false => 0.0,
+        };
+        let occlusion_parameters = [projection, glam::vec4(state.settings.radius(), state.settings.bias(), orthographic, 0.0)];
+        self.occlusion.register_commands(vk_device, command_buffer, image_index, raw.image(0).image(), extent, occlusion_parameters);
+        self.blur.register_commands(vk_device, command_buffer, image_index, blurred.image(0).image(), extent, [glam::Vec4::ZERO; 2]);
+
+        let composite_parameters = [glam::vec4(state.settings.intensity(), 0.0, 0.0, 0.0), glam::Vec4::ZERO];
+        self.composite.register_commands(vk_device, command_buffer, image_index * 2, output_image, extent, composite_parameters);
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.occlusion.destroy(vk_device);
+        self.blur.destroy(vk_device);
+        self.composite.destroy(vk_device);
+        unsafe { vk_device.destroy_sampler(self.depth_sampler, None) };
+        for (mut raw, mut blurred) in self.occlusion_images.drain(..) {
+            raw.destroy(vk_device);
+            blurred.destroy(vk_device);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use vulkanalia::vk::DeviceV1_0;
 use vulkanalia::vk::HasBuilder;
 
 use super::attachments::depth_attachment::create_depth_objects;
+use super::attachments::multisample_attachment::create_multisampled_color_objects;
 
 
 pub struct FinalRenderTargetBuilder {
@@ -30,6 +31,39 @@ pub struct FinalRenderTarget {
     additional_image_views: Vec<vulkanalia::vk::ImageView>,
 }
 
+/// The images drawn before being resolved in the swapchain images, when the render pass is multisampled.
+/// The depth image is first, followed by the multisampled color image when there is one.
+fn create_additional_images(
+    vk_instance: &vulkanalia::Instance,
+    vk_device: &vulkanalia::Device,
+    vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    extent: vulkanalia::vk::Extent2D,
+    color_format: vulkanalia::vk::Format,
+    samples: vulkanalia::vk::SampleCountFlags,
+) -> PResult<(Vec<VulkanImage>, Vec<vulkanalia::vk::ImageView>)> {
+    let (depth_image, depth_image_view) = create_depth_objects(
+        vk_instance,
+        vk_device,
+        vk_physical_device,
+        extent,
+        samples,
+    )?;
+    match samples {
+        vulkanalia::vk::SampleCountFlags::_1 => Ok((vec![depth_image], vec![depth_image_view])),
+        _ => {
+            let (color_image, color_image_view) = create_multisampled_color_objects(
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                extent,
+                color_format,
+                samples,
+            )?;
+            Ok((vec![depth_image, color_image], vec![depth_image_view, color_image_view]))
+        }
+    }
+}
+
 impl FinalRenderTarget {
     pub fn create(
         _builder: FinalRenderTargetBuilder,
@@ -39,43 +73,49 @@ impl FinalRenderTarget {
         image_views: &Vec<vulkanalia::vk::ImageView>,
         render_pass: vulkanalia::vk::RenderPass,
         extent: vulkanalia::vk::Extent2D,
+        color_format: vulkanalia::vk::Format,
+        samples: vulkanalia::vk::SampleCountFlags,
     ) -> PResult<FinalRenderTarget> {
-
-        // hard coded depth objects for now
-        let (depth_image, depth_image_view) = create_depth_objects(
+        let (additional_images, additional_image_views) = create_additional_images(
             vk_instance,
             vk_device,
             vk_physical_device,
             extent,
+            color_format,
+            samples,
         )?;
 
         Ok(FinalRenderTarget {
             framebuffers: Self::create_framebuffers(
                 vk_device,
                 image_views,
-                depth_image_view,
+                &additional_image_views,
                 render_pass,
                 extent
             )?,
-            additional_images: vec![depth_image],
-            additional_image_views: vec![depth_image_view],
+            additional_images,
+            additional_image_views,
         })
     }
 
     fn create_framebuffers(
         vk_device: &vulkanalia::Device,
         image_views: &Vec<vulkanalia::vk::ImageView>,
-        depth_image_view: vulkanalia::vk::ImageView,
+        additional_image_views: &[vulkanalia::vk::ImageView],
         render_pass: vulkanalia::vk::RenderPass,
         extent: vulkanalia::vk::Extent2D
     ) -> PResult<Vec<vulkanalia::vk::Framebuffer>> {
         Ok(image_views
             .iter()
             .map(|i| {
-                let attachments = &[*i, depth_image_view];
+                // multisampled render passes draw in the multisampled color image, and resolve it in the swapchain image.
+                let attachments = match additional_image_views {
+                    [depth_image_view, color_image_view] => vec![*color_image_view, *depth_image_view, *i],
+                    _ => vec![*i, additional_image_views[0]],
+                };
                 let create_info = vulkanalia::vk::FramebufferCreateInfo::builder()
                     .render_pass(render_pass)
-                    .attachments(attachments)
+                    .attachments(&attachments)
                     .width(extent.width)
                     .height(extent.height)
                     .layers(1);
@@ -114,25 +154,28 @@ impl FinalRenderTarget {
         image_views: &Vec<vulkanalia::vk::ImageView>,
         render_pass: vulkanalia::vk::RenderPass,
         extent: vulkanalia::vk::Extent2D,
+        color_format: vulkanalia::vk::Format,
+        samples: vulkanalia::vk::SampleCountFlags,
     ) -> PResult<()> {
-        // hard coded depth objects for now
-        let (depth_image, depth_image_view) = create_depth_objects(
+        let (additional_images, additional_image_views) = create_additional_images(
             vk_instance,
             vk_device,
             vk_physical_device,
             extent,
+            color_format,
+            samples,
         )?;
 
         self.framebuffers = Self::create_framebuffers(
             vk_device,
             image_views,
-            depth_image_view, // todo : hard coded for now
+            &additional_image_views,
             render_pass,
             extent
         )?;
 
-        self.additional_images.push(depth_image);
-        self.additional_image_views.push(depth_image_view);
+        self.additional_images = additional_images;
+        self.additional_image_views = additional_image_views;
 
 
         Ok(())
@@ -1,8 +1,10 @@
 use crate::engine::renderer::graphic_pipeline::GraphicPipelineInterface;
 use crate::engine::renderer::graphic_pipeline::graphic_pipeline_builder::GraphicPipelineBuilderInterface;
+use crate::engine::renderer::graphic_pipeline::graphic_pipeline_settings::supported_line_widths;
 use crate::engine::resources::texture_library::TextureLibrary;
 use crate::{PropellantResources, FinalRenderTargetBuilder};
-use crate::engine::{errors::PResult, window::vulkan::swapchain_interface::SwapchainInterface};
+use crate::engine::{errors::{PResult, PropellantError}, window::vulkan::swapchain_interface::SwapchainInterface};
+use crate::engine::window::vulkan::vulkan_image::VulkanImage;
 
 use foundry::ComponentTable;
 use vulkanalia::vk::HasBuilder;
@@ -10,22 +12,27 @@ use vulkanalia::vk::DeviceV1_0;
 
 use super::attachments::depth_attachment::get_depth_format;
 use super::final_render_target::FinalRenderTarget;
-use super::intermediate_render_targets::IntermediateRenderTarget;
+use super::intermediate_render_targets::{IntermediateRenderTarget, IntermediateRenderTargetBuilder};
+use super::shadow_render_pass::shadow_map::ShadowMap;
 
 enum RenderingPipelinePassTarget {
     /// We are targetting the swapchain, and only own the framebuffers.
-    /// The image and views are owned by the swapchain.
+    /// The image and views are owned by the swapchain, or by the post process pass the overlays are drawn over offscreen.
     Swapchain(FinalRenderTarget),
-    /// We are targetting an intermediate render target, and own the image and views.
-    #[allow(unused)]
-    Intermediate(IntermediateRenderTarget),
+    /// We are targetting intermediate render targets, one per swapchain image, and own the images and views.
+    /// The builder is kept to recreate them when the swapchain is resized.
+    Intermediate {
+        builder: IntermediateRenderTargetBuilder,
+        targets: Vec<IntermediateRenderTarget>,
+        final_layout: vulkanalia::vk::ImageLayout,
+    },
 }
 
 impl RenderingPipelinePassTarget {
     pub fn framebuffer(&self, image_index: usize) -> vulkanalia::vk::Framebuffer {
         match self {
             RenderingPipelinePassTarget::Swapchain(frt) => frt.framebuffer(image_index),
-            RenderingPipelinePassTarget::Intermediate(irt) => irt.framebuffer(),
+            RenderingPipelinePassTarget::Intermediate { targets, .. } => targets[image_index].framebuffer(),
         }
     }
 }
@@ -38,8 +45,13 @@ pub struct GraphicRenderpass {
     target: RenderingPipelinePassTarget,
     /// render_pass object.
     render_pass: vulkanalia::vk::RenderPass,
+    /// Samples per pixel of the color and depth attachments, resolved in the target images when there are several.
+    samples: vulkanalia::vk::SampleCountFlags,
     /// The color to clear the screen with.
     clear_color: (f32, f32, f32),
+    /// Layout the target color is loaded in by the overlay passes, that draw over the image of the previous passes.
+    /// None when the target is cleared.
+    load_layout: Option<vulkanalia::vk::ImageLayout>,
 }
 
 impl GraphicRenderpass {
@@ -50,7 +62,9 @@ impl GraphicRenderpass {
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
         swapchain: &SwapchainInterface,
+        shadow_map: &ShadowMap,
         clear_color: (f32, f32, f32),
+        samples: vulkanalia::vk::SampleCountFlags,
     ) -> PResult<GraphicRenderpass> {
         // build the render pass and the framebuffers, targetting the swapchain images
         let render_pass = Self::create_final_render_pass(
@@ -58,6 +72,9 @@ impl GraphicRenderpass {
             vk_device,
             vk_physical_device,
             swapchain.format(),
+            None,
+            vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR,
+            samples,
         )?;
         let final_render_target = FinalRenderTarget::create(
             final_rt,
@@ -67,25 +84,267 @@ impl GraphicRenderpass {
             swapchain.image_views(),
             render_pass,
             swapchain.extent(),
+            swapchain.format(),
+            samples,
+        )?;
+        let pipelines = Self::build_pipelines(
+            pipelines,
+            vk_device,
+            swapchain.extent(),
+            swapchain.images().len(),
+            render_pass,
+            samples,
+            supported_line_widths(vk_instance, vk_physical_device),
+            shadow_map,
         )?;
-        // build the pipelines
-        let pipelines = pipelines.into_iter().map(|(id, pipeline)| {
-            // create the pipeline hash map for this layer.
-            pipeline.build(
-                vk_device,
-                swapchain.extent(),
-                swapchain.images().len(),
-                render_pass
-            ).and_then(|result| Ok((id, result)))
-        }).collect::<PResult<Vec<(u64, Box<dyn GraphicPipelineInterface>)>>>()?;
 
         Ok(GraphicRenderpass {
             pipelines,
             target: RenderingPipelinePassTarget::Swapchain(final_render_target),
             render_pass,
+            samples,
             clear_color,
+            load_layout: None,
+        })
+
+    }
+
+    /// Create a pass drawing the overlay pipelines, such as the ui, over the images written by the post process passes.
+    /// The target images are loaded in the given layout instead of being cleared, and left in the final layout.
+    /// The loaded images can not be multisampled, so the overlays are drawn with a single sample.
+    pub fn create_overlay_pass(
+        pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+        final_rt: FinalRenderTargetBuilder,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        target_views: &Vec<vulkanalia::vk::ImageView>,
+        format: vulkanalia::vk::Format,
+        extent: vulkanalia::vk::Extent2D,
+        load_layout: vulkanalia::vk::ImageLayout,
+        final_layout: vulkanalia::vk::ImageLayout,
+        shadow_map: &ShadowMap,
+    ) -> PResult<GraphicRenderpass> {
+        let samples = vulkanalia::vk::SampleCountFlags::_1;
+        let render_pass = Self::create_final_render_pass(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            format,
+            Some(load_layout),
+            final_layout,
+            samples,
+        )?;
+        let final_render_target = FinalRenderTarget::create(
+            final_rt,
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            target_views,
+            render_pass,
+            extent,
+            format,
+            samples,
+        )?;
+        let pipelines = Self::build_pipelines(
+            pipelines,
+            vk_device,
+            extent,
+            target_views.len(),
+            render_pass,
+            samples,
+            supported_line_widths(vk_instance, vk_physical_device),
+            shadow_map,
+        )?;
+
+        Ok(GraphicRenderpass {
+            pipelines,
+            target: RenderingPipelinePassTarget::Swapchain(final_render_target),
+            render_pass,
+            samples,
+            clear_color: (0.0, 0.0, 0.0),
+            load_layout: Some(load_layout),
         })
+    }
 
+    /// Create a pass drawing into its own color and depth images instead of the swapchain.
+    /// There is a single target image, and the color image is left ready to be copied from once the pass is done.
+    pub fn create_offscreen_pass(
+        pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        format: vulkanalia::vk::Format,
+        extent: vulkanalia::vk::Extent2D,
+        shadow_map: &ShadowMap,
+        clear_color: (f32, f32, f32),
+        samples: vulkanalia::vk::SampleCountFlags,
+    ) -> PResult<GraphicRenderpass> {
+        let mut target_builder = IntermediateRenderTargetBuilder::new();
+        target_builder.add_render_texture(format, vulkanalia::vk::ImageAspectFlags::COLOR);
+        target_builder.add_depth_texture();
+        Self::create_intermediate_pass(
+            pipelines,
+            target_builder,
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            extent,
+            1,
+            vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            shadow_map,
+            clear_color,
+            samples,
+        )
+    }
+
+    /// Create a pass drawing into intermediate render targets, one per swapchain image, for the post process pipelines to read.
+    /// The target must have a color texture followed by a depth texture.
+    /// When multisampled, the color is resolved in the color texture of the target, and the depth can not be sampled.
+    pub fn create_intermediate_pass(
+        pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+        target_builder: IntermediateRenderTargetBuilder,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        extent: vulkanalia::vk::Extent2D,
+        image_count: usize,
+        final_layout: vulkanalia::vk::ImageLayout,
+        shadow_map: &ShadowMap,
+        clear_color: (f32, f32, f32),
+        samples: vulkanalia::vk::SampleCountFlags,
+    ) -> PResult<GraphicRenderpass> {
+        let color_format = match (target_builder.color_format(), target_builder.has_depth_texture()) {
+            (Some(format), true) => format,
+            _ => return Err(PropellantError::Custom("The graphic pass intermediate render target needs a color and a depth texture.".to_string())),
+        };
+        let render_pass = Self::create_final_render_pass(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            color_format,
+            None,
+            final_layout,
+            samples,
+        )?;
+        let targets = Self::build_intermediate_targets(
+            &target_builder,
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            render_pass,
+            extent,
+            image_count,
+            samples,
+        )?;
+        let pipelines = Self::build_pipelines(
+            pipelines,
+            vk_device,
+            extent,
+            image_count,
+            render_pass,
+            samples,
+            supported_line_widths(vk_instance, vk_physical_device),
+            shadow_map,
+        )?;
+
+        Ok(GraphicRenderpass {
+            pipelines,
+            target: RenderingPipelinePassTarget::Intermediate {
+                builder: target_builder,
+                targets,
+                final_layout,
+            },
+            render_pass,
+            samples,
+            clear_color,
+            load_layout: None,
+        })
+    }
+
+    fn build_intermediate_targets(
+        target_builder: &IntermediateRenderTargetBuilder,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        render_pass: vulkanalia::vk::RenderPass,
+        extent: vulkanalia::vk::Extent2D,
+        image_count: usize,
+        samples: vulkanalia::vk::SampleCountFlags,
+    ) -> PResult<Vec<IntermediateRenderTarget>> {
+        (0..image_count).map(|_| match samples {
+            vulkanalia::vk::SampleCountFlags::_1 => target_builder.build(
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                render_pass,
+                extent.width,
+                extent.height,
+            ),
+            _ => target_builder.build_multisampled(
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                render_pass,
+                extent.width,
+                extent.height,
+                samples,
+            ),
+        }).collect()
+    }
+
+    fn build_pipelines(
+        pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+        vk_device: &vulkanalia::Device,
+        extent: vulkanalia::vk::Extent2D,
+        image_count: usize,
+        render_pass: vulkanalia::vk::RenderPass,
+        samples: vulkanalia::vk::SampleCountFlags,
+        line_widths: [f32; 2],
+        shadow_map: &ShadowMap,
+    ) -> PResult<Vec<(u64, Box<dyn GraphicPipelineInterface>)>> {
+        let pipelines = pipelines.into_iter().map(|(id, pipeline)| {
+            // create the pipeline hash map for this layer.
+            pipeline.build(
+                vk_device,
+                extent,
+                image_count,
+                render_pass,
+                samples,
+                line_widths,
+            ).and_then(|mut result| {
+                // lit pipelines sample the shadow map, others ignore it.
+                result.bind_shadow_map(vk_device, shadow_map);
+                Ok((id, result))
+            })
+        }).collect::<PResult<Vec<(u64, Box<dyn GraphicPipelineInterface>)>>>()?;
+        // transparent pipelines blend over the opaque geometry, so they are drawn last.
+        // the sort is stable, keeping the registration order otherwise.
+        let mut pipelines = pipelines;
+        pipelines.sort_by_key(|(_, pipeline)| pipeline.is_transparent());
+        Ok(pipelines)
+    }
+
+    /// The color image the pass draws into for the first swapchain image, when it does not target the swapchain.
+    pub fn target_image(&self) -> Option<&VulkanImage> {
+        match &self.target {
+            RenderingPipelinePassTarget::Swapchain(_) => None,
+            RenderingPipelinePassTarget::Intermediate { targets, .. } => targets.first().map(|target| target.image(0)),
+        }
+    }
+
+    /// The color and depth views the pass draws into for the given swapchain image, when it does not target the swapchain.
+    /// When the pass is multisampled, the color view is the resolved one, but the depth view is multisampled.
+    pub fn target_views(&self, image_index: usize) -> Option<(vulkanalia::vk::ImageView, vulkanalia::vk::ImageView)> {
+        match &self.target {
+            RenderingPipelinePassTarget::Swapchain(_) => None,
+            RenderingPipelinePassTarget::Intermediate { targets, .. } => Some((targets[image_index].view(0), targets[image_index].view(1))),
+        }
+    }
+
+    /// Samples per pixel the pass draws with.
+    pub fn samples(&self) -> vulkanalia::vk::SampleCountFlags {
+        self.samples
     }
 
     pub fn update_uniform_buffers(
@@ -93,15 +352,16 @@ impl GraphicRenderpass {
         vk_device: &vulkanalia::Device,
         image_index: usize,
         components: &ComponentTable,
-    ) -> PResult<()> {
+    ) -> PResult<bool> {
+        let mut draw_commands_outdated = false;
         for pipeline in self.pipelines.iter_mut().map(|(_k, v)| v) {
-            pipeline.update_uniform_buffers(
+            draw_commands_outdated |= pipeline.update_uniform_buffers(
                 vk_device,
                 components,
                 image_index,
             )?;
         }
-        Ok(())
+        Ok(draw_commands_outdated)
     }
 
     pub fn scene_recreation(
@@ -114,6 +374,10 @@ impl GraphicRenderpass {
         Ok(())
     }
 
+    pub fn requires_scene_rebuild(&self) -> bool {
+        self.pipelines.iter().any(|(_, pipeline)| pipeline.requires_rendering_map_rebuild())
+    }
+
     pub fn reload_textures(
         &mut self,
         vk_device: &vulkanalia::Device,
@@ -204,8 +468,10 @@ impl GraphicRenderpass {
             RenderingPipelinePassTarget::Swapchain(ref mut final_render_target) => {
                 final_render_target.recreation_cleanup(vk_device);
             },
-            RenderingPipelinePassTarget::Intermediate(ref mut intermediate) => {
-                intermediate.destroy(vk_device);
+            RenderingPipelinePassTarget::Intermediate { ref mut targets, .. } => {
+                for mut target in targets.drain(..) {
+                    target.destroy(vk_device);
+                }
             }
         }
         unsafe { vk_device.destroy_render_pass(self.render_pass, None) };
@@ -225,6 +491,9 @@ impl GraphicRenderpass {
                     vk_device,
                     vk_physical_device,
                     swapchain.format(),
+                    self.load_layout,
+                    vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR,
+                    self.samples,
                 )?;
                 final_render_target.recreate(
                     vk_instance,
@@ -233,11 +502,35 @@ impl GraphicRenderpass {
                     swapchain.image_views(),
                     self.render_pass,
                     swapchain.extent(),
+                    swapchain.format(),
+                    self.samples,
                 )?;
 
             },
-            RenderingPipelinePassTarget::Intermediate(ref mut _intermediate) => {
-                unimplemented!()
+            RenderingPipelinePassTarget::Intermediate { ref builder, ref mut targets, final_layout } => {
+                let color_format = match builder.color_format() {
+                    Some(format) => format,
+                    None => return Err(PropellantError::Custom("The graphic pass intermediate render target has no color texture.".to_string())),
+                };
+                self.render_pass = Self::create_final_render_pass(
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    color_format,
+                    None,
+                    final_layout,
+                    self.samples,
+                )?;
+                *targets = Self::build_intermediate_targets(
+                    builder,
+                    vk_instance,
+                    vk_device,
+                    vk_physical_device,
+                    self.render_pass,
+                    swapchain.extent(),
+                    swapchain.images().len(),
+                    self.samples,
+                )?;
             }
         }
         for pipeline in self.pipelines.iter_mut().map(|(_k, v)| v) {
@@ -254,29 +547,67 @@ impl GraphicRenderpass {
         vk_instance: &vulkanalia::Instance,
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
-        swapchain_format: vulkanalia::vk::Format,
+        color_format: vulkanalia::vk::Format,
+        load_layout: Option<vulkanalia::vk::ImageLayout>,
+        final_layout: vulkanalia::vk::ImageLayout,
+        samples: vulkanalia::vk::SampleCountFlags,
     ) -> PResult<vulkanalia::vk::RenderPass> {
-        // create the color attachment
+        let multisampled = samples != vulkanalia::vk::SampleCountFlags::_1;
+
+        // overlay passes draw over the image written by the previous passes, it is loaded instead of cleared.
+        let (color_load_op, color_initial_layout) = match load_layout {
+            Some(layout) => (vulkanalia::vk::AttachmentLoadOp::LOAD, layout),
+            None => (vulkanalia::vk::AttachmentLoadOp::CLEAR, vulkanalia::vk::ImageLayout::UNDEFINED),
+        };
+
+        // create the color attachment. When multisampled, it is only used during the pass, and resolved in the target image.
+        let (color_store_op, color_final_layout) = match multisampled {
+            true => (vulkanalia::vk::AttachmentStoreOp::DONT_CARE, vulkanalia::vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
+            false => (vulkanalia::vk::AttachmentStoreOp::STORE, final_layout),
+        };
         let color_attachment = vulkanalia::vk::AttachmentDescription::builder()
-            .format(swapchain_format)
-            .samples(vulkanalia::vk::SampleCountFlags::_1)
-            .load_op(vulkanalia::vk::AttachmentLoadOp::CLEAR)
-            .store_op(vulkanalia::vk::AttachmentStoreOp::STORE)
+            .format(color_format)
+            .samples(samples)
+            .load_op(color_load_op)
+            .store_op(color_store_op)
             .stencil_load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
-            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
-            .final_layout(vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR);
+            .initial_layout(color_initial_layout)
+            .final_layout(color_final_layout);
 
-        // depth attachment
+        // depth attachment, kept for the post process pipelines when they sample the image, as they can read the depth as well.
+        // a multisampled depth can not be sampled, it is discarded.
+        let (depth_store_op, depth_final_layout) = match (final_layout, multisampled) {
+            (vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, false) => (
+                vulkanalia::vk::AttachmentStoreOp::STORE,
+                vulkanalia::vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            ),
+            _ => (
+                vulkanalia::vk::AttachmentStoreOp::DONT_CARE,
+                vulkanalia::vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ),
+        };
         let depth_stencil_attachment = vulkanalia::vk::AttachmentDescription::builder()
             .format(get_depth_format(vk_instance, vk_physical_device)?)
-            .samples(vulkanalia::vk::SampleCountFlags::_1)
+            .samples(samples)
             .load_op(vulkanalia::vk::AttachmentLoadOp::CLEAR)
-            .store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
+            .store_op(depth_store_op)
+            .stencil_load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+            .final_layout(depth_final_layout);
+
+        // resolve attachment, the target image the multisampled color is resolved in at the end of the subpass.
+        // every pixel is written by the resolve, the previous content is not loaded.
+        let resolve_attachment = vulkanalia::vk::AttachmentDescription::builder()
+            .format(color_format)
+            .samples(vulkanalia::vk::SampleCountFlags::_1)
+            .load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vulkanalia::vk::AttachmentStoreOp::STORE)
             .stencil_load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
             .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
-            .final_layout(vulkanalia::vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+            .final_layout(final_layout);
 
         // create the color attachment reference
         let color_attachment_ref = vulkanalia::vk::AttachmentReference::builder()
@@ -288,29 +619,75 @@ impl GraphicRenderpass {
             .attachment(1)
             .layout(vulkanalia::vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
 
+        // resolve attachment reference
+        let resolve_attachment_ref = vulkanalia::vk::AttachmentReference::builder()
+            .attachment(2)
+            .layout(vulkanalia::vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
         // create the subpass
         let color_attachments = &[color_attachment_ref];
+        let resolve_attachments = &[resolve_attachment_ref];
         let subpass = vulkanalia::vk::SubpassDescription::builder()
             .pipeline_bind_point(vulkanalia::vk::PipelineBindPoint::GRAPHICS)
             .color_attachments(color_attachments)
             .depth_stencil_attachment(&depth_stencil_attachment_ref);
+        let subpass = match multisampled {
+            true => subpass.resolve_attachments(resolve_attachments),
+            false => subpass,
+        };
 
         // create the subpass dependency
+        // a loaded image waits for the fullscreen or compute writes of the previous pass, to blend over them.
+        let (src_stage_mask, src_access_mask, dst_access_mask) = match load_layout {
+            Some(_) => (
+                vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER,
+                vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::SHADER_WRITE,
+                vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_READ | vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            None => (
+                vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                vulkanalia::vk::AccessFlags::empty(),
+                vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+        };
         let dependency = vulkanalia::vk::SubpassDependency::builder()
             .src_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
             .dst_subpass(0)
-            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
-            .src_access_mask(vulkanalia::vk::AccessFlags::empty())
+            .src_stage_mask(src_stage_mask)
+            .src_access_mask(src_access_mask)
             .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
-            .dst_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+            .dst_access_mask(dst_access_mask);
+        // when the image is sampled by the post process pipelines, they wait for the color and depth writes.
+        let sampled_dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vulkanalia::vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .src_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::FRAGMENT_SHADER | vulkanalia::vk::PipelineStageFlags::COMPUTE_SHADER)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::SHADER_READ);
+        // when the image is read back, the copy waits for the color writes.
+        let readback_dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::TRANSFER)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_READ);
         // create the render pass
-        let attachments = &[color_attachment, depth_stencil_attachment];
+        let attachments = match multisampled {
+            true => vec![color_attachment, depth_stencil_attachment, resolve_attachment],
+            false => vec![color_attachment, depth_stencil_attachment],
+        };
         let subpasses = &[subpass];
-        let dependencies = &[dependency];
+        let dependencies = match final_layout {
+            vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL => vec![dependency, readback_dependency],
+            vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => vec![dependency, sampled_dependency],
+            _ => vec![dependency],
+        };
         let info = vulkanalia::vk::RenderPassCreateInfo::builder()
-            .attachments(attachments)
+            .attachments(&attachments)
             .subpasses(subpasses)
-            .dependencies(dependencies);
+            .dependencies(&dependencies);
         
         Ok(unsafe {
             vk_device.create_render_pass(&info, None)?
@@ -331,8 +708,10 @@ impl GraphicRenderpass {
             RenderingPipelinePassTarget::Swapchain(final_render_target) => {
                 final_render_target.destroy(vk_device);
             },
-            RenderingPipelinePassTarget::Intermediate(intermediate_render_target) => {
-                intermediate_render_target.destroy(vk_device);
+            RenderingPipelinePassTarget::Intermediate { targets, .. } => {
+                for target in targets.iter_mut() {
+                    target.destroy(vk_device);
+                }
             }
         }
     }
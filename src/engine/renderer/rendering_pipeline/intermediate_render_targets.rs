@@ -1,14 +1,25 @@
 use crate::engine::{
-    window::vulkan::vulkan_image::VulkanImage,
+    window::vulkan::vulkan_image::{VulkanImage, vulkan_image_view::create_image_view},
     errors::PResult
 };
 
 use vulkanalia::vk::DeviceV1_0;
 use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::Handle;
+use vulkanalia::vk::InstanceV1_0;
 
+use super::attachments::depth_attachment::get_depth_format;
+use super::attachments::multisample_attachment::create_multisampled_color_objects;
 
+/// Format of the high dynamic range render textures, where lighting is not clamped before post processing.
+pub const HDR_FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::R16G16B16A16_SFLOAT;
+/// Format of the low dynamic range render textures, once the colors are tonemapped.
+pub const LDR_FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::R8G8B8A8_SRGB;
+
+#[derive(Debug, Clone)]
 pub struct IntermediateRenderTargetBuilder {
-    render_textures_formats: Vec<(vulkanalia::vk::Format, vulkanalia::vk::ImageAspectFlags)>,
+    /// Format and aspect of each texture. Depth textures without format use the depth format of the device.
+    render_textures_formats: Vec<(Option<vulkanalia::vk::Format>, vulkanalia::vk::ImageAspectFlags)>,
 }
 
 impl IntermediateRenderTargetBuilder {
@@ -18,12 +29,52 @@ impl IntermediateRenderTargetBuilder {
         }
     }
 
+    /// Target of the scene before post processing: a hdr color texture and a depth texture.
+    pub fn hdr_scene() -> Self {
+        let mut builder = Self::new();
+        builder.add_render_texture(HDR_FORMAT, vulkanalia::vk::ImageAspectFlags::COLOR);
+        builder.add_depth_texture();
+        builder
+    }
+
+    /// Single hdr color texture, written by a post process pipeline.
+    pub fn hdr_color() -> Self {
+        let mut builder = Self::new();
+        builder.add_render_texture(HDR_FORMAT, vulkanalia::vk::ImageAspectFlags::COLOR);
+        builder
+    }
+
+    /// Single srgb color texture, written by a post process pipeline after tonemapping.
+    pub fn ldr_color() -> Self {
+        let mut builder = Self::new();
+        builder.add_render_texture(LDR_FORMAT, vulkanalia::vk::ImageAspectFlags::COLOR);
+        builder
+    }
+
     pub fn add_render_texture(&mut self, format: vulkanalia::vk::Format, aspect: vulkanalia::vk::ImageAspectFlags) {
-        self.render_textures_formats.push((format, aspect));
+        self.render_textures_formats.push((Some(format), aspect));
     }
 
+    /// Add a depth texture, in the depth format supported by the device.
+    pub fn add_depth_texture(&mut self) {
+        self.render_textures_formats.push((None, vulkanalia::vk::ImageAspectFlags::DEPTH));
+    }
+
+    /// Format of the first color texture, the one the passes draw into and the next ones read.
+    pub fn color_format(&self) -> Option<vulkanalia::vk::Format> {
+        self.render_textures_formats.iter()
+            .find(|(_, aspect)| aspect.contains(vulkanalia::vk::ImageAspectFlags::COLOR))
+            .and_then(|(format, _)| *format)
+    }
+
+    pub fn has_depth_texture(&self) -> bool {
+        self.render_textures_formats.iter().any(|(_, aspect)| aspect.contains(vulkanalia::vk::ImageAspectFlags::DEPTH))
+    }
+
+    /// Create the render textures, and the framebuffer of the render pass over them.
+    /// Targets written by compute shaders have no render pass: a null render pass skips the framebuffer.
     pub fn build(
-        self,
+        &self,
         vk_instance: &vulkanalia::Instance,
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
@@ -31,19 +82,103 @@ impl IntermediateRenderTargetBuilder {
         width: u32,
         height: u32,
     ) -> PResult<IntermediateRenderTarget> {
-        let (images, views): (Vec<VulkanImage>, Vec<vulkanalia::vk::ImageView>) = self.render_textures_formats.into_iter().map(|(format, aspects)| {
-            Self::create_image_and_view(vk_instance, vk_device, vk_physical_device, width, height, format, aspects)
+        let (images, views): (Vec<VulkanImage>, Vec<vulkanalia::vk::ImageView>) = self.render_textures_formats.iter().map(|(format, aspects)| {
+            let format = match format {
+                Some(format) => *format,
+                None => get_depth_format(vk_instance, vk_physical_device)?,
+            };
+            Self::create_image_and_view(vk_instance, vk_device, vk_physical_device, width, height, format, *aspects)
         }).collect::<PResult<Vec<_>>>()?.into_iter().unzip();
 
+        let framebuffer = match render_pass.is_null() {
+            true => vulkanalia::vk::Framebuffer::null(),
+            false => {
+                let create_info = vulkanalia::vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(&views)
+                    .width(width)
+                    .height(height)
+                    .layers(1);
+                unsafe {
+                    vk_device.create_framebuffer(&create_info, None)?
+                }
+            },
+        };
+
+        Ok(IntermediateRenderTarget {
+            images,
+            views,
+            framebuffer,
+        })
+    }
+
+    /// Create the render textures of a multisampled render pass, and the framebuffer over them.
+    /// Every texture is drawn with the given sample count, and the color textures are resolved in single sampled textures read by the next passes.
+    /// The textures keep the order of the builder, with the resolved textures in place of the color ones, followed by the multisampled color textures.
+    /// The framebuffer takes the multisampled textures in the order of the builder, then the resolved ones.
+    pub fn build_multisampled(
+        &self,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        render_pass: vulkanalia::vk::RenderPass,
+        width: u32,
+        height: u32,
+        samples: vulkanalia::vk::SampleCountFlags,
+    ) -> PResult<IntermediateRenderTarget> {
+        let extent = vulkanalia::vk::Extent2D { width, height };
+        let mut images = Vec::with_capacity(self.render_textures_formats.len() * 2);
+        let mut views = Vec::with_capacity(self.render_textures_formats.len() * 2);
+        let mut multisampled_colors = Vec::new();
+        let mut drawn_views = Vec::with_capacity(self.render_textures_formats.len());
+        let mut resolved_views = Vec::new();
+        for (format, aspects) in self.render_textures_formats.iter() {
+            let format = match format {
+                Some(format) => *format,
+                None => get_depth_format(vk_instance, vk_physical_device)?,
+            };
+            match aspects.contains(vulkanalia::vk::ImageAspectFlags::DEPTH) {
+                true => {
+                    // the depth is only used during the render pass, it is not resolved.
+                    let image = VulkanImage::create_multisampled(
+                        vk_instance,
+                        vk_device,
+                        vk_physical_device,
+                        width,
+                        height,
+                        samples,
+                        vulkanalia::vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                        format,
+                    )?;
+                    let view = create_image_view(vk_device, &image, format, *aspects)?;
+                    drawn_views.push(view);
+                    images.push(image);
+                    views.push(view);
+                },
+                false => {
+                    let (resolved_image, resolved_view) = Self::create_image_and_view(vk_instance, vk_device, vk_physical_device, width, height, format, *aspects)?;
+                    let (multisampled_image, multisampled_view) = create_multisampled_color_objects(vk_instance, vk_device, vk_physical_device, extent, format, samples)?;
+                    drawn_views.push(multisampled_view);
+                    resolved_views.push(resolved_view);
+                    images.push(resolved_image);
+                    views.push(resolved_view);
+                    multisampled_colors.push((multisampled_image, multisampled_view));
+                },
+            }
+        }
+        for (image, view) in multisampled_colors {
+            images.push(image);
+            views.push(view);
+        }
+
+        let attachments = drawn_views.into_iter().chain(resolved_views).collect::<Vec<_>>();
         let create_info = vulkanalia::vk::FramebufferCreateInfo::builder()
             .render_pass(render_pass)
-            .attachments(&views)
+            .attachments(&attachments)
             .width(width)
             .height(height)
             .layers(1);
-        let framebuffer = unsafe {
-            vk_device.create_framebuffer(&create_info, None)?
-        };
+        let framebuffer = unsafe { vk_device.create_framebuffer(&create_info, None)? };
 
         Ok(IntermediateRenderTarget {
             images,
@@ -61,13 +196,28 @@ impl IntermediateRenderTargetBuilder {
         format: vulkanalia::vk::Format,
         aspects: vulkanalia::vk::ImageAspectFlags,
     ) -> PResult<(VulkanImage, vulkanalia::vk::ImageView)> {
+        // depth textures are depth attachments, color textures are sampled by the next passes and can be read back once rendered.
+        // compute shaders can write the color textures when the format allows it.
+        let usage = match aspects.contains(vulkanalia::vk::ImageAspectFlags::DEPTH) {
+            true => vulkanalia::vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vulkanalia::vk::ImageUsageFlags::SAMPLED,
+            false => {
+                let properties = unsafe { vk_instance.get_physical_device_format_properties(vk_physical_device, format) };
+                let usage = vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vulkanalia::vk::ImageUsageFlags::SAMPLED
+                    | vulkanalia::vk::ImageUsageFlags::TRANSFER_SRC;
+                match properties.optimal_tiling_features.contains(vulkanalia::vk::FormatFeatureFlags::STORAGE_IMAGE) {
+                    true => usage | vulkanalia::vk::ImageUsageFlags::STORAGE,
+                    false => usage,
+                }
+            },
+        };
         let image = VulkanImage::create(
             vk_instance,
             vk_device,
             vk_physical_device,
             width,
             height,
-            vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            usage,
             format,
         )?;
 
@@ -115,6 +265,15 @@ impl IntermediateRenderTarget {
         self.framebuffer
     }
 
+    /// The render texture at the given index, in the order they were added to the builder.
+    pub fn image(&self, index: usize) -> &VulkanImage {
+        &self.images[index]
+    }
+
+    pub fn view(&self, index: usize) -> vulkanalia::vk::ImageView {
+        self.views[index]
+    }
+
     pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
         self.images.iter_mut().for_each(|image| image.destroy(vk_device));
         self.views.iter_mut().for_each(|view| unsafe { vk_device.destroy_image_view(*view, None) });
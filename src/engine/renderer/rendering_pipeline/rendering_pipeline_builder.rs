@@ -1,9 +1,12 @@
 
 use crate::{engine::{
     errors::PResult,
+    renderer::compute_pipeline::compute_pipeline_builder::{ComputePipelineBuilder, tonemap_pipeline_with_settings},
+    renderer::compute_pipeline::post_process_effects::TonemapSettings,
+    renderer::offscreen_renderer::OffscreenRenderer,
     window::vulkan::queues::QueueFamilyIndices,
-    renderer::graphic_pipeline::graphic_pipeline_builder::{GraphicPipelineBuilderInterface, default_phong_pipeline, text_pipeline},
-}, id};
+    renderer::graphic_pipeline::graphic_pipeline_builder::{GraphicPipelineBuilderInterface, default_phong_pipeline, default_skinned_phong_pipeline, default_unlit_pipeline, default_transparent_unlit_pipeline, default_vertex_color_pipeline, default_pbr_pipeline, default_skinned_pbr_pipeline, default_transparent_pbr_pipeline, default_line_pipeline, default_point_pipeline, default_skybox_pipeline, text_pipeline, default_phong_shadow_caster_pipeline, default_skinned_phong_shadow_caster_pipeline, default_pbr_shadow_caster_pipeline, default_skinned_pbr_shadow_caster_pipeline},
+}, id, VulkanInterface};
 
 #[cfg(feature = "ui")]
 use crate::engine::renderer::graphic_pipeline::graphic_pipeline_builder::default_ui_pipeline;
@@ -13,7 +16,8 @@ use self::states::{RPBSRegisteringGraphic, RPBSWaitingComputePipeline, RPBSReady
 use super::{
     RenderingPipeline,
     intermediate_render_targets::IntermediateRenderTargetBuilder,
-    final_render_target::FinalRenderTargetBuilder
+    final_render_target::FinalRenderTargetBuilder,
+    shadow_render_pass::shadow_settings::ShadowSettings,
 };
 
 pub(crate) mod states;
@@ -24,6 +28,14 @@ pub struct RenderingPipelineBuilder<T> {
     state_data: T,
     // any state data
     clear_color: (f32, f32, f32),
+    /// Shadows of the main directionnal light, disabled when none.
+    shadows: Option<ShadowSettings>,
+    /// Pipelines drawing the shadow casters in the shadow map.
+    shadow_casters: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+    /// Requested samples per pixel of the graphic pipelines, 1 disables the multisampling.
+    msaa_samples: u32,
+    /// Pipelines drawn over the final image once the post process pipelines ran, such as the ui.
+    overlay_pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
 }
 
 impl<T> RenderingPipelineBuilder<T> {
@@ -43,6 +55,10 @@ impl RenderingPipelineBuilder<RPBSRegisteringGraphic> {
                 graphic_pipelines: Vec::new(),
             },
             clear_color: (0.0, 0.0, 0.0),
+            shadows: None,
+            shadow_casters: Vec::new(),
+            msaa_samples: 1,
+            overlay_pipelines: Vec::new(),
         }
     }
 
@@ -59,9 +75,21 @@ impl RenderingPipelineBuilder<RPBSRegisteringGraphic> {
                 intermediate_rt,
             },
             clear_color: self.clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 
+    /// Draw the scene in a hdr render target, mapped to the swapchain by a tonemapping pass with the given settings.
+    /// The lighting is not clamped before the tonemapping, and the exposure can be tuned at runtime with the `TonemapSettings` singleton.
+    pub fn with_hdr(self, settings: TonemapSettings) -> RenderingPipelineBuilder<RPBSReady> {
+        self.with_intermediate_rt(IntermediateRenderTargetBuilder::hdr_scene())
+            .with_compute_pipeline(tonemap_pipeline_with_settings(settings), id("tonemap"))
+            .with_final_rt(FinalRenderTargetBuilder::default())
+    }
+
     pub fn with_final_rt(self, final_rt: FinalRenderTargetBuilder) -> RenderingPipelineBuilder<RPBSReady> {
 
         let new_state = RPBSReady {
@@ -73,15 +101,21 @@ impl RenderingPipelineBuilder<RPBSRegisteringGraphic> {
         RenderingPipelineBuilder {
             state_data: new_state,
             clear_color: self.clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 }
 
 impl RenderingPipelineBuilder<RPBSWaitingComputePipeline> {
 
-    pub fn with_compute_pipeline(self, pipeline: (/* compute pipeline builder */), id: u64) -> RenderingPipelineBuilder<RPBSWaitingRenderTargets> {
+    /// Register a post process pipeline, reading the last registered intermediate render target.
+    /// It writes the next intermediate render target, or the swapchain if it is the last one.
+    pub fn with_compute_pipeline(self, pipeline: ComputePipelineBuilder, id: u64) -> RenderingPipelineBuilder<RPBSWaitingRenderTargets> {
         let mut previous_compute_pipelines = self.state_data.compute_pipelines;
-        let new_compute_pipeline = (id, self.state_data.intermediate_rt, Box::new(pipeline));
+        let new_compute_pipeline = (id, self.state_data.intermediate_rt, pipeline);
         previous_compute_pipelines.push(new_compute_pipeline);
         
         let new_state = RPBSWaitingRenderTargets {
@@ -92,6 +126,10 @@ impl RenderingPipelineBuilder<RPBSWaitingComputePipeline> {
         RenderingPipelineBuilder {
             state_data: new_state,
             clear_color: self.clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 }
@@ -108,6 +146,10 @@ impl RenderingPipelineBuilder<RPBSWaitingRenderTargets> {
         RenderingPipelineBuilder {
             state_data: new_state,
             clear_color: self.clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 
@@ -119,6 +161,10 @@ impl RenderingPipelineBuilder<RPBSWaitingRenderTargets> {
                 final_rt,
             },
             clear_color: self.clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 }
@@ -143,6 +189,26 @@ impl RenderingPipelineBuilder<RPBSReady> {
             queue_indices,
         )
     }
+
+    /// Build the pipelines to render offscreen, into an image of the given size that is read back after each frame.
+    /// The post process pipelines run as they would on screen, the last one writing a srgb image instead of the swapchain.
+    pub fn build_offscreen(
+        self,
+        vk_interface: &VulkanInterface,
+        width: u32,
+        height: u32,
+    ) -> PResult<OffscreenRenderer> {
+        OffscreenRenderer::create(self, vk_interface, width, height)
+    }
+
+    /// Whether the swapchain can be created in a hdr color space: the last pass must be a tonemapping one that allows it,
+    /// as it is the one encoding the colors for the display.
+    /// The overlay pipelines write srgb colors over the tonemapped image, so with overlays the swapchain stays a srgb one.
+    pub(crate) fn hdr_output(&self) -> bool {
+        self.overlay_pipelines.is_empty() && self.state_data.compute_pipelines.last()
+            .and_then(|(_, _, pipeline)| pipeline.tonemap_settings())
+            .is_some_and(|settings| settings.hdr_output())
+    }
 }
 
 impl<T> RenderingPipelineBuilder<T> {
@@ -150,12 +216,82 @@ impl<T> RenderingPipelineBuilder<T> {
         RenderingPipelineBuilder {
             state_data: self.state_data,
             clear_color,
+            shadows: self.shadows,
+            shadow_casters: self.shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
         }
     }
 
     pub fn clear_color(&self) -> (f32, f32, f32) {
         self.clear_color
     }
+
+    /// Enable the shadows of the main directionnal light, cast by the meshes of the default phong and pbr pipelines.
+    /// Custom pipelines can cast shadows as well, with `with_shadow_caster_pipeline`.
+    pub fn with_shadows(self, settings: ShadowSettings) -> RenderingPipelineBuilder<T> {
+        let casters: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)> = vec![
+            (id("default-shadow"), Box::new(default_phong_shadow_caster_pipeline(&settings))),
+            (id("skinned-shadow"), Box::new(default_skinned_phong_shadow_caster_pipeline(&settings))),
+            (id("pbr-shadow"), Box::new(default_pbr_shadow_caster_pipeline(&settings))),
+            (id("skinned-pbr-shadow"), Box::new(default_skinned_pbr_shadow_caster_pipeline(&settings))),
+        ];
+        let mut shadow_casters = self.shadow_casters;
+        shadow_casters.extend(casters);
+        RenderingPipelineBuilder {
+            state_data: self.state_data,
+            clear_color: self.clear_color,
+            shadows: Some(settings),
+            shadow_casters,
+            msaa_samples: self.msaa_samples,
+            overlay_pipelines: self.overlay_pipelines,
+        }
+    }
+
+    /// Register a pipeline drawing in the shadow map. It is only built when the shadows are enabled.
+    /// The pipeline must bind the cascades with `ShadowCascadesUniform`, and read the cascade index from its push constants.
+    pub fn with_shadow_caster_pipeline<P: GraphicPipelineBuilderInterface + 'static>(mut self, id: u64, pipeline: P) -> RenderingPipelineBuilder<T> {
+        self.shadow_casters.push((id, Box::new(pipeline)));
+        self
+    }
+
+    /// Multisample anti aliasing of the graphic pipelines, with the given samples per pixel.
+    /// The count is clamped to the highest one supported by the device, and 1 disables it.
+    /// The scene is drawn in multisampled images, resolved in the swapchain image or in the render target read by the post process pipelines.
+    /// The depth of a multisampled scene can not be read by the post process pipelines, so it can not be used with the ambient occlusion.
+    pub fn with_msaa_samples(self, samples: u32) -> RenderingPipelineBuilder<T> {
+        RenderingPipelineBuilder {
+            msaa_samples: samples.max(1),
+            ..self
+        }
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    pub fn shadows(&self) -> Option<&ShadowSettings> {
+        self.shadows.as_ref()
+    }
+
+    /// Take the shadow casters pipelines out of the builder, to build them in the shadow pass.
+    pub(crate) fn take_shadow_casters(&mut self) -> Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)> {
+        std::mem::take(&mut self.shadow_casters)
+    }
+
+    /// Register a pipeline drawing over the final image, after the post process pipelines, such as the ui.
+    /// It is not tonemapped nor graded, and is not multisampled when there are post process pipelines.
+    /// Without post process pipelines, it is drawn after the graphic pipelines in the same pass.
+    /// Overlays are written in srgb, so the swapchain is never created in a hdr color space when there are some.
+    pub fn with_overlay_pipeline<P: GraphicPipelineBuilderInterface + 'static>(mut self, id: u64, pipeline: P) -> RenderingPipelineBuilder<T> {
+        self.overlay_pipelines.push((id, Box::new(pipeline)));
+        self
+    }
+
+    /// Take the overlay pipelines out of the builder, to build them in the overlay pass.
+    pub(crate) fn take_overlay_pipelines(&mut self) -> Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)> {
+        std::mem::take(&mut self.overlay_pipelines)
+    }
 }
 
 impl From<RenderingPipelineBuilder<RPBSReady>> for RPBSReady {
@@ -164,15 +300,48 @@ impl From<RenderingPipelineBuilder<RPBSReady>> for RPBSReady {
     }
 }
 
+/// The default pipelines, drawing the scene in a hdr render target tonemapped in the swapchain with the default settings.
+/// The ui and the text are drawn over the tonemapped image.
 impl Default for RenderingPipelineBuilder<RPBSReady> {
     fn default() -> Self {
         let renderer = RenderingPipelineBuilder::new()
-            .with_graphic_pipeline(id("default"), default_phong_pipeline());
+            .with_graphic_pipeline(id("default"), default_phong_pipeline())
+            .with_graphic_pipeline(id("skinned"), default_skinned_phong_pipeline())
+            .with_graphic_pipeline(id("unlit"), default_unlit_pipeline())
+            .with_graphic_pipeline(id("transparent-unlit"), default_transparent_unlit_pipeline())
+            .with_graphic_pipeline(id("vertex-color"), default_vertex_color_pipeline())
+            .with_graphic_pipeline(id("pbr"), default_pbr_pipeline())
+            .with_graphic_pipeline(id("skinned-pbr"), default_skinned_pbr_pipeline())
+            .with_graphic_pipeline(id("transparent-pbr"), default_transparent_pbr_pipeline())
+            .with_graphic_pipeline(id("lines"), default_line_pipeline())
+            .with_graphic_pipeline(id("points"), default_point_pipeline())
+            .with_graphic_pipeline(id("skybox"), default_skybox_pipeline());
 
+        // the ui and the text are drawn over the final image, out of the post process chain.
         #[cfg(feature = "ui")]
-        let renderer = renderer.with_graphic_pipeline(id("ui-default"), default_ui_pipeline());
-        let renderer = renderer.with_graphic_pipeline(id("text"), text_pipeline());
+        let renderer = renderer.with_overlay_pipeline(id("ui-default"), default_ui_pipeline());
+        let renderer = renderer.with_overlay_pipeline(id("text"), text_pipeline());
             
-        renderer.with_final_rt(FinalRenderTargetBuilder::default())
+        renderer.with_hdr(TonemapSettings::default())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::id;
+
+    use super::{RenderingPipelineBuilder, TonemapSettings, text_pipeline};
+
+    #[test]
+    fn overlays_keep_the_swapchain_sdr() {
+        let hdr = TonemapSettings::default().with_hdr_output(true);
+        assert!(RenderingPipelineBuilder::new().with_hdr(hdr).hdr_output());
+        assert!(!RenderingPipelineBuilder::new().with_hdr(TonemapSettings::default()).hdr_output());
+        assert!(!RenderingPipelineBuilder::new()
+            .with_overlay_pipeline(id("text"), text_pipeline())
+            .with_hdr(hdr)
+            .hdr_output()
+        );
     }
 }
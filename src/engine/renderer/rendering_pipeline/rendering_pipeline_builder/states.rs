@@ -1,4 +1,4 @@
-use crate::{engine::renderer::graphic_pipeline::graphic_pipeline_builder::GraphicPipelineBuilderInterface, IntermediateRenderTargetBuilder, FinalRenderTargetBuilder};
+use crate::{engine::renderer::graphic_pipeline::graphic_pipeline_builder::GraphicPipelineBuilderInterface, IntermediateRenderTargetBuilder, FinalRenderTargetBuilder, ComputePipelineBuilder};
 
 
 /// The rendering pipeline is currently registering graphic pipelines.
@@ -9,20 +9,20 @@ pub struct RPBSRegisteringGraphic {
 /// The rendering pipeline is currently waiting for compute pipelines.
 pub struct RPBSWaitingComputePipeline {
     pub graphic_pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
-    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, Box<(/* compute pipeline builder */)>)>,
+    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, ComputePipelineBuilder)>,
     pub intermediate_rt: IntermediateRenderTargetBuilder,
 }
 
 /// The rendering pipeline is currently waiting for render targets.
 pub struct RPBSWaitingRenderTargets {
     pub graphic_pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
-    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, Box<(/* compute pipeline builder */)>)>,
+    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, ComputePipelineBuilder)>,
 }
 
 /// The pipeline is ready to be built.
 pub struct RPBSReady {
     pub graphic_pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
-    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, Box<(/* compute pipeline builder */)>)>,
+    pub compute_pipelines: Vec<(u64, IntermediateRenderTargetBuilder, ComputePipelineBuilder)>,
     pub final_rt: FinalRenderTargetBuilder,
 }
 
@@ -0,0 +1,252 @@
+use crate::engine::renderer::graphic_pipeline::GraphicPipelineInterface;
+use crate::engine::renderer::graphic_pipeline::graphic_pipeline_builder::GraphicPipelineBuilderInterface;
+use crate::engine::renderer::graphic_pipeline::graphic_pipeline_settings::supported_line_widths;
+use crate::engine::errors::PResult;
+use crate::PropellantResources;
+
+use foundry::ComponentTable;
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+use self::shadow_cascades::ShadowCascades;
+use self::shadow_map::ShadowMap;
+use self::shadow_settings::ShadowSettings;
+
+use super::attachments::depth_attachment::get_shadow_map_format;
+
+pub(crate) mod shadow_cascades;
+pub(crate) mod shadow_map;
+pub(crate) mod shadow_settings;
+
+/// Depth only pass, drawing the shadow casters in each cascade of the shadow map before the final pass.
+/// Without shadow settings, the pass only clears a single texel shadow map, so the lit pipelines always have one to sample.
+pub struct ShadowRenderPass {
+    /// The shadow casters pipelines, drawing in every cascade.
+    pipelines: Vec<(u64, Box<dyn GraphicPipelineInterface>)>,
+    /// render_pass object.
+    render_pass: vulkanalia::vk::RenderPass,
+    shadow_map: ShadowMap,
+    settings: Option<ShadowSettings>,
+}
+
+impl ShadowRenderPass {
+    pub fn create(
+        settings: Option<ShadowSettings>,
+        pipelines: Vec<(u64, Box<dyn GraphicPipelineBuilderInterface>)>,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        image_count: usize,
+    ) -> PResult<ShadowRenderPass> {
+        let render_pass = Self::create_shadow_render_pass(vk_instance, vk_device, vk_physical_device)?;
+        let (resolution, layer_count) = match &settings {
+            Some(settings) => (settings.resolution(), settings.cascade_count()),
+            None => (1, 1),
+        };
+        let shadow_map = ShadowMap::create(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            resolution,
+            layer_count,
+            render_pass,
+            image_count,
+        )?;
+        // the casters are only drawn when shadows are enabled.
+        let pipelines = match settings {
+            Some(_) => pipelines,
+            None => Vec::with_capacity(0),
+        };
+        let pipelines = pipelines.into_iter().map(|(id, pipeline)| {
+            pipeline.build(
+                vk_device,
+                shadow_map.extent(),
+                image_count,
+                render_pass,
+                vulkanalia::vk::SampleCountFlags::_1,
+                supported_line_widths(vk_instance, vk_physical_device),
+            ).and_then(|mut result| {
+                result.bind_shadow_map(vk_device, &shadow_map);
+                Ok((id, result))
+            })
+        }).collect::<PResult<Vec<(u64, Box<dyn GraphicPipelineInterface>)>>>()?;
+
+        Ok(ShadowRenderPass {
+            pipelines,
+            render_pass,
+            shadow_map,
+            settings,
+        })
+    }
+
+    pub fn shadow_map(&self) -> &ShadowMap {
+        &self.shadow_map
+    }
+
+    pub fn update_uniform_buffers(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        image_index: usize,
+        components: &ComponentTable,
+    ) -> PResult<bool> {
+        // fit the cascades to the main camera for this frame.
+        let cascades = match &self.settings {
+            Some(settings) => ShadowCascades::compute(settings, components),
+            None => ShadowCascades::disabled(),
+        };
+        self.shadow_map.write_cascades(vk_device, image_index, &cascades)?;
+
+        let mut draw_commands_outdated = false;
+        for pipeline in self.pipelines.iter_mut().map(|(_k, v)| v) {
+            draw_commands_outdated |= pipeline.update_uniform_buffers(
+                vk_device,
+                components,
+                image_index,
+            )?;
+        }
+        Ok(draw_commands_outdated)
+    }
+
+    pub fn scene_recreation(
+        &mut self,
+        components: &ComponentTable,
+    ) -> PResult<()> {
+        for pipeline in self.pipelines.iter_mut().map(|(_k, v)| v) {
+            pipeline.rebuild_rendering_map(components);
+        }
+        Ok(())
+    }
+
+    pub fn requires_scene_rebuild(&self) -> bool {
+        self.pipelines.iter().any(|(_, pipeline)| pipeline.requires_rendering_map_rebuild())
+    }
+
+    pub fn assert_uniform_buffer_sizes(
+        &mut self,
+        image_index: usize,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<()> {
+        for pipeline in self.pipelines.iter_mut().map(|(_k, v)| v) {
+            pipeline.assert_uniform_buffer_sizes(image_index, vk_instance, vk_device, vk_physical_device)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn register_draw_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        resources: &PropellantResources,
+        image_index: usize,
+    ) -> PResult<()> {
+        let render_area = vulkanalia::vk::Rect2D::builder()
+            .offset(vulkanalia::vk::Offset2D::default())
+            .extent(self.shadow_map.extent());
+
+        let depth_clear_value = vulkanalia::vk::ClearValue {
+            depth_stencil: vulkanalia::vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        };
+
+        let clear_values = &[depth_clear_value];
+
+        // one render pass per cascade, each one drawing in its layer of the shadow map.
+        for layer in 0..self.shadow_map.layer_count() {
+            let info = vulkanalia::vk::RenderPassBeginInfo::builder()
+                .render_pass(self.render_pass)
+                .framebuffer(self.shadow_map.framebuffer(layer))
+                .render_area(render_area)
+                .clear_values(clear_values);
+
+            unsafe { vk_device.cmd_begin_render_pass(command_buffer, &info, vulkanalia::vk::SubpassContents::INLINE) };
+
+            for (_, pipeline) in self.pipelines.iter() {
+                // the casters read the view projection of the cascade from its index.
+                pipeline.push_constants(vk_device, command_buffer, &(layer as u32).to_ne_bytes());
+                pipeline.register_draw_commands(
+                    vk_device,
+                    image_index,
+                    command_buffer,
+                    resources,
+                );
+            }
+
+            unsafe { vk_device.cmd_end_render_pass(command_buffer) };
+        }
+
+        Ok(())
+    }
+
+    fn create_shadow_render_pass(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<vulkanalia::vk::RenderPass> {
+        // depth attachment, kept to be sampled by the final pass
+        let depth_attachment = vulkanalia::vk::AttachmentDescription::builder()
+            .format(get_shadow_map_format(vk_instance, vk_physical_device)?)
+            .samples(vulkanalia::vk::SampleCountFlags::_1)
+            .load_op(vulkanalia::vk::AttachmentLoadOp::CLEAR)
+            .store_op(vulkanalia::vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vulkanalia::vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vulkanalia::vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+            .final_layout(vulkanalia::vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+
+        let depth_attachment_ref = vulkanalia::vk::AttachmentReference::builder()
+            .attachment(0)
+            .layout(vulkanalia::vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+        // no color attachments, only depth
+        let subpass = vulkanalia::vk::SubpassDescription::builder()
+            .pipeline_bind_point(vulkanalia::vk::PipelineBindPoint::GRAPHICS)
+            .depth_stencil_attachment(&depth_attachment_ref);
+
+        // wait for the previous frame to be done sampling the shadow map before drawing in it.
+        let begin_dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .src_access_mask(vulkanalia::vk::AccessFlags::SHADER_READ)
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vulkanalia::vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+        // the final pass samples the shadow map once it is written.
+        let end_dependency = vulkanalia::vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vulkanalia::vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vulkanalia::vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .src_access_mask(vulkanalia::vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_stage_mask(vulkanalia::vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::SHADER_READ);
+
+        let attachments = &[depth_attachment];
+        let subpasses = &[subpass];
+        let dependencies = &[begin_dependency, end_dependency];
+        let info = vulkanalia::vk::RenderPassCreateInfo::builder()
+            .attachments(attachments)
+            .subpasses(subpasses)
+            .dependencies(dependencies);
+
+        Ok(unsafe {
+            vk_device.create_render_pass(&info, None)?
+        })
+    }
+
+    pub fn destroy(
+        &mut self,
+        vk_device: &vulkanalia::Device
+    ) {
+        for (_id, mut pipeline) in self.pipelines.drain(..) {
+            pipeline.destroy(vk_device);
+        }
+        self.shadow_map.destroy(vk_device);
+        unsafe {
+            vk_device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use foundry::ComponentTable;
+
+use crate::{
+    Camera,
+    Transform,
+    DirectionnalLight,
+};
+
+use super::shadow_settings::{
+    ShadowSettings,
+    MAX_SHADOW_CASCADES,
+};
+
+/// View projections of the cascades of the shadow map, as sent to the shaders.
+/// The shadow casters are drawn with them, and the lit pipelines use them to look up the shadow map.
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, Clone)]
+pub struct ShadowCascades {
+    /// view matrix of the main camera, to select the cascade of the fragments.
+    view: glam::Mat4,
+    view_projections: [glam::Mat4; MAX_SHADOW_CASCADES],
+    /// view depth of the far side of each cascade.
+    split_depths: glam::Vec4,
+    /// world size of a shadow map texel in each cascade, to scale the normal bias.
+    texel_sizes: glam::Vec4,
+    /// zero when there are no shadows to draw.
+    cascade_count: u32,
+    normal_bias: f32,
+    pcf_radius: i32,
+    _padd: f32,
+}
+
+/// The main camera of the scene, as read from the components on a frame.
+struct ShadowCamera {
+    view: glam::Mat4,
+    projection: glam::Mat4,
+    z_near: f32,
+    z_far: f32,
+}
+
+impl ShadowCamera {
+    /// Look for the main camera. None if there is no main camera.
+    fn gather(components: &ComponentTable) -> Option<ShadowCamera> {
+        for (_, tf, cam) in components.query2d::<Transform, Camera>() {
+            if cam.is_main() {
+                return Some(ShadowCamera {
+                    // the camera transform is used as the view matrix
+                    view: tf.world_pos(),
+                    projection: cam.projection_matrix(),
+                    // orthographic cameras may have their near plane at zero, but the logarithmic splits need a positive one.
+                    z_near: cam.z_near().max(0.001),
+                    z_far: cam.z_far(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl ShadowCascades {
+    /// Cascades of a scene without shadows: the lit pipelines skip the shadow map lookup.
+    pub fn disabled() -> ShadowCascades {
+        ShadowCascades {
+            view: glam::Mat4::IDENTITY,
+            view_projections: [glam::Mat4::IDENTITY; MAX_SHADOW_CASCADES],
+            split_depths: glam::Vec4::ZERO,
+            texel_sizes: glam::Vec4::ZERO,
+            cascade_count: 0,
+            normal_bias: 0.0,
+            pcf_radius: 0,
+            _padd: 0.0,
+        }
+    }
+
+    /// Fit the cascades to the view frustum of the main camera, seen from the main directionnal light.
+    /// Without main camera or light, there are no shadows.
+    pub fn compute(settings: &ShadowSettings, components: &ComponentTable) -> ShadowCascades {
+        let light_direction = components.get_singleton::<DirectionnalLight>()
+            .map(|light| light.direction.normalize_or_zero());
+        Self::fit(settings, light_direction, ShadowCamera::gather(components))
+    }
+
+    /// Fit the cascades to the view frustum of the camera, seen from a light with the given normalized direction.
+    fn fit(settings: &ShadowSettings, light_direction: Option<glam::Vec3>, camera: Option<ShadowCamera>) -> ShadowCascades {
+        let light_direction = match light_direction {
+            Some(direction) if direction != glam::Vec3::ZERO => direction,
+            _ => return ShadowCascades::disabled(),
+        };
+        let ShadowCamera { view, projection, z_near, z_far } = match camera {
+            Some(camera) => camera,
+            None => return ShadowCascades::disabled(),
+        };
+        let z_far = z_far.min(settings.distance());
+        if z_far <= z_near {
+            return ShadowCascades::disabled();
+        }
+
+        let cascade_count = settings.cascade_count() as usize;
+        let inverse_view_projection = (projection * view).inverse();
+        // rotation of the world into the light space, looking along the light direction.
+        let up = match light_direction.y.abs() > 0.99 {
+            true => glam::Vec3::Z,
+            false => glam::Vec3::Y,
+        };
+        let light_view = glam::Mat4::look_at_rh(glam::Vec3::ZERO, light_direction, up);
+
+        let mut cascades = ShadowCascades::disabled();
+        cascades.view = view;
+        cascades.cascade_count = cascade_count as u32;
+        cascades.normal_bias = settings.normal_bias();
+        cascades.pcf_radius = settings.pcf_radius() as i32;
+        let mut near = z_near;
+        for cascade in 0..cascade_count {
+            let far = Self::split_depth(settings, z_near, z_far, cascade + 1);
+            // world space corners of the slice of the view frustum.
+            let corners = [near, far].into_iter().flat_map(|depth| {
+                let clip = projection * glam::Vec4::new(0.0, 0.0, -depth, 1.0);
+                let ndc_depth = clip.z / clip.w;
+                [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].into_iter().map(move |(x, y)| {
+                    let world = inverse_view_projection * glam::Vec4::new(x, y, ndc_depth, 1.0);
+                    world.truncate() / world.w
+                })
+            }).collect::<Vec<_>>();
+            // the cascade covers the bounding sphere of the slice, so its size does not change as the camera rotates.
+            let center = corners.iter().fold(glam::Vec3::ZERO, |sum, corner| sum + *corner) / corners.len() as f32;
+            let radius = corners.iter().fold(0.0f32, |radius, corner| radius.max(corner.distance(center)));
+            // round the radius, to avoid float imprecisions changing the texel size from frame to frame.
+            let radius = (radius * 16.0).ceil() / 16.0;
+            let texel_size = 2.0 * radius / settings.resolution() as f32;
+            // snap the center to the texels, so the shadows do not shimmer when the camera moves.
+            let light_center = light_view.transform_point3(center);
+            let light_center = glam::Vec3::new(
+                (light_center.x / texel_size).floor() * texel_size,
+                (light_center.y / texel_size).floor() * texel_size,
+                light_center.z,
+            );
+            // the depth range goes further towards the light, to keep the casters that are outside of the view.
+            let light_projection = glam::Mat4::orthographic_rh(
+                light_center.x - radius,
+                light_center.x + radius,
+                light_center.y - radius,
+                light_center.y + radius,
+                -(light_center.z + radius + settings.distance()),
+                -(light_center.z - radius),
+            );
+            cascades.view_projections[cascade] = light_projection * light_view;
+            cascades.split_depths[cascade] = far;
+            cascades.texel_sizes[cascade] = texel_size;
+            near = far;
+        }
+
+        cascades
+    }
+
+    /// View depth of the far side of a cascade, blending uniform and logarithmic splits.
+    fn split_depth(settings: &ShadowSettings, z_near: f32, z_far: f32, cascade: usize) -> f32 {
+        let ratio = cascade as f32 / settings.cascade_count() as f32;
+        let logarithmic = z_near * (z_far / z_near).powf(ratio);
+        let uniform = z_near + (z_far - z_near) * ratio;
+        settings.split_lambda() * logarithmic + (1.0 - settings.split_lambda()) * uniform
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{ShadowCamera, ShadowCascades, ShadowSettings};
+
+    fn camera(z_far: f32) -> ShadowCamera {
+        ShadowCamera {
+            view: glam::Mat4::look_at_rh(glam::vec3(0.0, 2.0, 5.0), glam::Vec3::ZERO, glam::Vec3::Y),
+            projection: glam::Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, z_far),
+            z_near: 0.1,
+            z_far,
+        }
+    }
+
+    fn light_direction() -> Option<glam::Vec3> {
+        Some(glam::vec3(-1.0, -2.0, -0.5).normalize())
+    }
+
+    #[test]
+    fn splits_grow_towards_the_far_plane() {
+        for split_lambda in [0.0, 0.75, 1.0] {
+            let settings = ShadowSettings::default().with_split_lambda(split_lambda);
+            let mut near = 0.1;
+            for cascade in 1..=settings.cascade_count() as usize {
+                let far = ShadowCascades::split_depth(&settings, 0.1, 100.0, cascade);
+                assert!(far > near, "split {cascade} at {far} is not after {near}");
+                near = far;
+            }
+        }
+
+        let cascades = ShadowCascades::fit(&ShadowSettings::default(), light_direction(), Some(camera(100.0)));
+        assert_eq!(cascades.cascade_count, 4);
+        let splits = cascades.split_depths.to_array();
+        assert!(splits[0] > 0.1);
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]), "splits {splits:?}");
+        // further cascades cover more of the view, with bigger texels.
+        let texel_sizes = cascades.texel_sizes.to_array();
+        assert!(texel_sizes.windows(2).all(|pair| pair[0] <= pair[1]), "texel sizes {texel_sizes:?}");
+    }
+
+    #[test]
+    fn last_split_is_the_closest_of_the_far_plane_and_the_shadow_distance() {
+        let settings = ShadowSettings::default().with_distance(50.0).with_cascade_count(3);
+        for (z_far, last_split) in [(20.0, 20.0), (50.0, 50.0), (1000.0, 50.0)] {
+            let cascades = ShadowCascades::fit(&settings, light_direction(), Some(camera(z_far)));
+            assert_eq!(cascades.cascade_count, 3);
+            assert!((cascades.split_depths[2] - last_split).abs() < 1e-3, "last split {} for a far plane at {z_far}", cascades.split_depths[2]);
+            // unused cascades are left empty.
+            assert_eq!(cascades.split_depths[3], 0.0);
+        }
+    }
+
+    #[test]
+    fn shadows_are_disabled_without_main_light_or_camera() {
+        let settings = ShadowSettings::default();
+        assert_eq!(ShadowCascades::fit(&settings, None, Some(camera(100.0))).cascade_count, 0);
+        assert_eq!(ShadowCascades::fit(&settings, Some(glam::Vec3::ZERO), Some(camera(100.0))).cascade_count, 0);
+        assert_eq!(ShadowCascades::fit(&settings, light_direction(), None).cascade_count, 0);
+        // a shadow distance before the near plane leaves nothing to shadow.
+        let settings = settings.with_distance(0.05);
+        assert_eq!(ShadowCascades::fit(&settings, light_direction(), Some(camera(100.0))).cascade_count, 0);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::engine::errors::PResult;
+use crate::engine::window::vulkan::vulkan_buffer::VulkanBuffer;
+use crate::engine::window::vulkan::vulkan_image::{
+    VulkanImage,
+    vulkan_image_view::create_layered_image_view,
+};
+
+use vulkanalia::vk::DeviceV1_0;
+use vulkanalia::vk::HasBuilder;
+
+use super::shadow_cascades::ShadowCascades;
+use crate::engine::renderer::rendering_pipeline::attachments::depth_attachment::get_shadow_map_format;
+
+/// Layered depth image the shadow casters are drawn into, one layer per cascade.
+/// It also holds the cascades view projections, one buffer per swapchain image,
+/// so the shadow pass and the lit pipelines read the same matrices.
+pub struct ShadowMap {
+    image: VulkanImage,
+    /// One view per layer, to render each cascade.
+    layer_views: Vec<vulkanalia::vk::ImageView>,
+    /// View on all the layers, to sample the cascades in the lit pipelines.
+    array_view: vulkanalia::vk::ImageView,
+    framebuffers: Vec<vulkanalia::vk::Framebuffer>,
+    /// Comparison sampler, for hardware filtered shadow lookups.
+    sampler: vulkanalia::vk::Sampler,
+    cascade_buffers: Vec<VulkanBuffer>,
+    extent: vulkanalia::vk::Extent2D,
+}
+
+impl ShadowMap {
+    pub fn create(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        resolution: u32,
+        layer_count: u32,
+        render_pass: vulkanalia::vk::RenderPass,
+        image_count: usize,
+    ) -> PResult<ShadowMap> {
+        let format = get_shadow_map_format(vk_instance, vk_physical_device)?;
+        let image = VulkanImage::create_layered(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            resolution,
+            resolution,
+            layer_count,
+            vulkanalia::vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vulkanalia::vk::ImageUsageFlags::SAMPLED,
+            format,
+        )?;
+
+        let layer_views = (0..layer_count).map(|layer| create_layered_image_view(
+            vk_device,
+            &image,
+            format,
+            vulkanalia::vk::ImageAspectFlags::DEPTH,
+            vulkanalia::vk::ImageViewType::_2D,
+            layer,
+            1,
+        )).collect::<PResult<Vec<_>>>()?;
+
+        let array_view = create_layered_image_view(
+            vk_device,
+            &image,
+            format,
+            vulkanalia::vk::ImageAspectFlags::DEPTH,
+            vulkanalia::vk::ImageViewType::_2D_ARRAY,
+            0,
+            layer_count,
+        )?;
+
+        let framebuffers = layer_views.iter().map(|view| {
+            let attachments = &[*view];
+            let create_info = vulkanalia::vk::FramebufferCreateInfo::builder()
+                .render_pass(render_pass)
+                .attachments(attachments)
+                .width(resolution)
+                .height(resolution)
+                .layers(1);
+
+            unsafe {
+                vk_device.create_framebuffer(&create_info, None)
+            }
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        // outside of the cascades, the border is at max depth: nothing is in shadow.
+        let info = vulkanalia::vk::SamplerCreateInfo::builder()
+            .mag_filter(vulkanalia::vk::Filter::LINEAR)
+            .min_filter(vulkanalia::vk::Filter::LINEAR)
+            .address_mode_u(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_v(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_w(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(vulkanalia::vk::BorderColor::FLOAT_OPAQUE_WHITE)
+            .unnormalized_coordinates(false)
+            .compare_enable(true)
+            .compare_op(vulkanalia::vk::CompareOp::LESS_OR_EQUAL)
+            .mipmap_mode(vulkanalia::vk::SamplerMipmapMode::NEAREST)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(0.0);
+
+        let sampler = unsafe { vk_device.create_sampler(&info, None)? };
+
+        let cascade_buffers = (0..image_count).map(|_| VulkanBuffer::create(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            std::mem::size_of::<ShadowCascades>() as u64,
+            vulkanalia::vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE | vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT,
+        )).collect::<PResult<Vec<_>>>()?;
+
+        Ok(ShadowMap {
+            image,
+            layer_views,
+            array_view,
+            framebuffers,
+            sampler,
+            cascade_buffers,
+            extent: vulkanalia::vk::Extent2D { width: resolution, height: resolution },
+        })
+    }
+
+    pub fn framebuffer(&self, layer: usize) -> vulkanalia::vk::Framebuffer {
+        self.framebuffers[layer]
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layer_views.len()
+    }
+
+    pub fn extent(&self) -> vulkanalia::vk::Extent2D {
+        self.extent
+    }
+
+    pub fn array_view(&self) -> vulkanalia::vk::ImageView {
+        self.array_view
+    }
+
+    pub fn sampler(&self) -> vulkanalia::vk::Sampler {
+        self.sampler
+    }
+
+    pub fn cascade_buffer(&self, image_index: usize) -> &VulkanBuffer {
+        &self.cascade_buffers[image_index]
+    }
+
+    /// Write the cascades of the frame in the buffer of the given swapchain image.
+    pub fn write_cascades(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        image_index: usize,
+        cascades: &ShadowCascades,
+    ) -> PResult<()> {
+        let buffer = &mut self.cascade_buffers[image_index];
+        let memory = buffer.map(vk_device)?;
+        buffer.write(memory, std::slice::from_ref(cascades));
+        buffer.unmap(vk_device);
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        for mut buffer in self.cascade_buffers.drain(..) {
+            buffer.destroy(vk_device);
+        }
+        unsafe {
+            vk_device.destroy_sampler(self.sampler, None);
+        }
+        for framebuffer in self.framebuffers.drain(..) {
+            unsafe {
+                vk_device.destroy_framebuffer(framebuffer, None);
+            }
+        }
+        unsafe {
+            vk_device.destroy_image_view(self.array_view, None);
+        }
+        for view in self.layer_views.drain(..) {
+            unsafe {
+                vk_device.destroy_image_view(view, None);
+            }
+        }
+        self.image.destroy(vk_device);
+    }
+}
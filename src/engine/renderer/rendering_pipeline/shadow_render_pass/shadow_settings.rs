@@ -0,0 +1,148 @@
+use crate::{
+    engine::renderer::graphic_pipeline::graphic_pipeline_settings::{
+        GraphicPipelineSettings,
+        CullMode,
+    },
+    ShaderStage,
+};
+
+/// Maximum number of cascades of the shadow map. This must match the constant of the shaders.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+/// Settings of the shadows cast by the main directionnal light.
+/// The view frustum of the main camera is split in cascades, each one rendered in its own layer of the shadow map,
+/// so close shadows are sharp while far shadows still cover the whole view.
+#[derive(Debug, Clone)]
+pub struct ShadowSettings {
+    resolution: u32,
+    cascade_count: u32,
+    depth_bias_constant: f32,
+    depth_bias_slope: f32,
+    normal_bias: f32,
+    distance: f32,
+    split_lambda: f32,
+    pcf_radius: u32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowSettings {
+            resolution: 2048,
+            cascade_count: 4,
+            depth_bias_constant: 1.25,
+            depth_bias_slope: 1.75,
+            normal_bias: 1.0,
+            distance: 100.0,
+            split_lambda: 0.75,
+            pcf_radius: 1,
+        }
+    }
+}
+
+impl ShadowSettings {
+    /// Width and height in texels of each cascade of the shadow map.
+    pub fn with_resolution(self, resolution: u32) -> ShadowSettings {
+        Self {
+            resolution: resolution.max(1),
+            ..self
+        }
+    }
+
+    /// Number of cascades, between 1 and `MAX_SHADOW_CASCADES`.
+    pub fn with_cascade_count(self, cascade_count: u32) -> ShadowSettings {
+        Self {
+            cascade_count: cascade_count.clamp(1, MAX_SHADOW_CASCADES as u32),
+            ..self
+        }
+    }
+
+    /// Depth bias of the shadow casters, as a constant factor plus a factor of the depth slope of the polygons.
+    pub fn with_depth_bias(self, constant_factor: f32, slope_factor: f32) -> ShadowSettings {
+        Self {
+            depth_bias_constant: constant_factor,
+            depth_bias_slope: slope_factor,
+            ..self
+        }
+    }
+
+    /// Offset of the shadow receivers along their normal, in texels of their cascade.
+    pub fn with_normal_bias(self, normal_bias: f32) -> ShadowSettings {
+        Self {
+            normal_bias,
+            ..self
+        }
+    }
+
+    /// Distance from the camera after which there are no shadows.
+    pub fn with_distance(self, distance: f32) -> ShadowSettings {
+        Self {
+            distance,
+            ..self
+        }
+    }
+
+    /// Blend between uniform (0.0) and logarithmic (1.0) cascade splits.
+    /// Logarithmic splits give more resolution close to the camera.
+    pub fn with_split_lambda(self, split_lambda: f32) -> ShadowSettings {
+        Self {
+            split_lambda: split_lambda.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Radius in texels of the percentage closer filtering: the shadow is averaged over `(2 * radius + 1)²` samples.
+    pub fn with_pcf_radius(self, pcf_radius: u32) -> ShadowSettings {
+        Self {
+            pcf_radius,
+            ..self
+        }
+    }
+
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    pub fn cascade_count(&self) -> u32 {
+        self.cascade_count
+    }
+
+    pub fn normal_bias(&self) -> f32 {
+        self.normal_bias
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn split_lambda(&self) -> f32 {
+        self.split_lambda
+    }
+
+    pub fn pcf_radius(&self) -> u32 {
+        self.pcf_radius
+    }
+
+    /// Settings of the pipelines drawing shadow casters: depth only, biased, without culling so open meshes cast shadows,
+    /// and with the index of the cascade being drawn as a `u32` push constant of the vertex stage.
+    /// Casters draw the components of the main pipelines, so they keep their own uniform buffer indices.
+    pub fn caster_pipeline_settings(&self) -> GraphicPipelineSettings {
+        GraphicPipelineSettings::default()
+            .depth_only()
+            .cull_mode(CullMode::None)
+            .depth_bias(self.depth_bias_constant, self.depth_bias_slope)
+            .push_constants(ShaderStage::Vertex, std::mem::size_of::<u32>() as u32)
+            .own_instance_indices()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GraphicPipelineSettings, ShadowSettings};
+
+    #[test]
+    fn casters_do_not_share_the_instance_indices() {
+        // the casters draw the components of the main pipelines, writing their indices would break the main draws.
+        assert!(ShadowSettings::default().caster_pipeline_settings().owns_instance_indices());
+        assert!(!GraphicPipelineSettings::default().owns_instance_indices());
+    }
+}
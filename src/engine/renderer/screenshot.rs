@@ -0,0 +1,565 @@
+use crate::engine::consts::PROPELLANT_DEBUG_FEATURES;
+use crate::engine::errors::{PResult, PropellantError};
+use crate::engine::window::vulkan::swapchain_interface::SwapchainInterface;
+use crate::engine::window::vulkan::vulkan_buffer::VulkanBuffer;
+use crate::VulkanInterface;
+
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+/// What to do with a screenshot once the frame is captured.
+/// Both are resolved on a background thread, so encoding does not stall the rendering.
+pub enum ScreenshotRequest {
+    /// Encode the frame as a png at the given path.
+    File(std::path::PathBuf),
+    /// Hand the srgb encoded pixels of the frame to the callback, or the error that prevented the capture.
+    Callback(Box<dyn FnOnce(PResult<image::RgbaImage>) + Send>),
+}
+
+impl ScreenshotRequest {
+    pub fn file<P: Into<std::path::PathBuf>>(path: P) -> ScreenshotRequest {
+        ScreenshotRequest::File(path.into())
+    }
+
+    pub fn callback<F: FnOnce(PResult<image::RgbaImage>) + Send + 'static>(callback: F) -> ScreenshotRequest {
+        ScreenshotRequest::Callback(Box::new(callback))
+    }
+
+    /// File requests have no one to report errors to, they are only printed with the debug features.
+    fn resolve(self, frame: PResult<image::RgbaImage>) {
+        match self {
+            ScreenshotRequest::File(path) => {
+                let result = frame.and_then(|frame| Ok(frame.save_with_format(&path, image::ImageFormat::Png)?));
+                if let Err(e) = result {
+                    if PROPELLANT_DEBUG_FEATURES {
+                        println!("[PROPELLANT DEBUG] [SCREENSHOT] Unable to save screenshot at {}: {e}", path.display());
+                    }
+                }
+            },
+            ScreenshotRequest::Callback(callback) => callback(frame),
+        }
+    }
+
+    /// Resolve the requests with the same frame: the last request takes it, the others get a copy.
+    fn resolve_all(mut requests: Vec<ScreenshotRequest>, frame: PResult<image::RgbaImage>) {
+        if let Some(last) = requests.pop() {
+            for request in requests.into_iter() {
+                let copy = match &frame {
+                    Ok(frame) => Ok(frame.clone()),
+                    Err(e) => Err(PropellantError::Custom(e.to_string())),
+                };
+                request.resolve(copy);
+            }
+            last.resolve(frame);
+        }
+    }
+}
+
+impl From<std::path::PathBuf> for ScreenshotRequest {
+    fn from(path: std::path::PathBuf) -> Self {
+        ScreenshotRequest::File(path)
+    }
+}
+
+impl From<&str> for ScreenshotRequest {
+    fn from(path: &str) -> Self {
+        ScreenshotRequest::File(path.into())
+    }
+}
+
+/// Copy of a presented swapchain image into host memory.
+/// The copy is recorded in its own command buffer, submitted right after the draw commands of the frame,
+/// so the image is read once rendered and before it is presented.
+/// The fence of the frame is polled on the next frames, and the pixels are read once it is signaled.
+pub(crate) struct ScreenshotCapture {
+    command_pool: vulkanalia::vk::CommandPool,
+    command_buffer: vulkanalia::vk::CommandBuffer,
+    buffer: VulkanBuffer,
+    /// Fence of the frame the copy is submitted with.
+    frame_fence: vulkanalia::vk::Fence,
+    format: vulkanalia::vk::Format,
+    color_space: vulkanalia::vk::ColorSpaceKHR,
+    /// Luminance of the sdr white in nits, to bring hdr images back to sdr.
+    paper_white: f32,
+    extent: vulkanalia::vk::Extent2D,
+    requests: Vec<ScreenshotRequest>,
+}
+
+impl ScreenshotCapture {
+    /// Record the copy of the given swapchain image, that will be submitted with the frame fence.
+    /// When the copy can't be recorded, the requests are resolved with the error.
+    pub fn record(
+        vk_interface: &VulkanInterface,
+        swapchain: &SwapchainInterface,
+        image_index: usize,
+        frame_fence: vulkanalia::vk::Fence,
+        paper_white: f32,
+        requests: Vec<ScreenshotRequest>,
+    ) -> Option<ScreenshotCapture> {
+        match Self::create(vk_interface, swapchain, image_index, frame_fence, paper_white) {
+            Ok(mut capture) => {
+                capture.requests = requests;
+                Some(capture)
+            },
+            Err(e) => {
+                std::thread::spawn(move || ScreenshotRequest::resolve_all(requests, Err(e)));
+                None
+            },
+        }
+    }
+
+    fn create(
+        vk_interface: &VulkanInterface,
+        swapchain: &SwapchainInterface,
+        image_index: usize,
+        frame_fence: vulkanalia::vk::Fence,
+        paper_white: f32,
+    ) -> PResult<ScreenshotCapture> {
+        if !swapchain.is_readable() {
+            return Err(PropellantError::Custom("The swapchain images can't be copied from on this surface.".to_string()));
+        }
+        // fail early, rather than copying pixels we can't read.
+        let format = swapchain.format();
+        let bytes_per_pixel = match Self::bytes_per_pixel(format) {
+            Some(bytes_per_pixel) => bytes_per_pixel,
+            None => return Err(PropellantError::Custom(format!("Screenshots of the swapchain format {format:?} are not supported."))),
+        };
+        let extent = swapchain.extent();
+        let image = swapchain.images()[image_index];
+
+        let buffer = VulkanBuffer::create(
+            &vk_interface.instance,
+            &vk_interface.device,
+            vk_interface.physical_device,
+            extent.width as u64 * extent.height as u64 * bytes_per_pixel,
+            vulkanalia::vk::BufferUsageFlags::TRANSFER_DST,
+            vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE | vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        let info = vulkanalia::vk::CommandPoolCreateInfo::builder()
+            .queue_family_index(vk_interface.indices.index())
+            .flags(vulkanalia::vk::CommandPoolCreateFlags::TRANSIENT);
+        let command_pool = unsafe { vk_interface.device.create_command_pool(&info, None)? };
+
+        let allocate_info = vulkanalia::vk::CommandBufferAllocateInfo::builder()
+            .command_pool(command_pool)
+            .level(vulkanalia::vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        let command_buffer = unsafe { vk_interface.device.allocate_command_buffers(&allocate_info)?[0] };
+
+        let capture = ScreenshotCapture {
+            command_pool,
+            command_buffer,
+            buffer,
+            frame_fence,
+            format,
+            color_space: swapchain.color_space(),
+            paper_white,
+            extent,
+            requests: Vec::with_capacity(0),
+        };
+        capture.register_commands(&vk_interface.device, image)?;
+
+        Ok(capture)
+    }
+
+    fn register_commands(
+        &self,
+        vk_device: &vulkanalia::Device,
+        image: vulkanalia::vk::Image,
+    ) -> PResult<()> {
+        let subresource_range = vulkanalia::vk::ImageSubresourceRange::builder()
+            .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1);
+        // the render pass leaves the image ready to present, wait for the color writes and move it to transfer source.
+        let to_transfer = vulkanalia::vk::ImageMemoryBarrier::builder()
+            .old_layout(vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR)
+            .new_layout(vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vulkanalia::vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_READ);
+        // and give it back to the presentation once copied.
+        let to_present = vulkanalia::vk::ImageMemoryBarrier::builder()
+            .old_layout(vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(vulkanalia::vk::ImageLayout::PRESENT_SRC_KHR)
+            .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_READ)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::empty());
+        let buffer_barrier = vulkanalia::vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vulkanalia::vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vulkanalia::vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vulkanalia::vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer.buffer())
+            .offset(0)
+            .size(self.buffer.size());
+
+        let subresource = vulkanalia::vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1);
+        let region = vulkanalia::vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(subresource)
+            .image_offset(vulkanalia::vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vulkanalia::vk::Extent3D { width: self.extent.width, height: self.extent.height, depth: 1 });
+
+        let info = vulkanalia::vk::CommandBufferBeginInfo::builder()
+            .flags(vulkanalia::vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        unsafe {
+            vk_device.begin_command_buffer(self.command_buffer, &info)?;
+            vk_device.cmd_pipeline_barrier(
+                self.command_buffer,
+                vulkanalia::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vulkanalia::vk::PipelineStageFlags::TRANSFER,
+                vulkanalia::vk::DependencyFlags::empty(),
+                &[] as &[vulkanalia::vk::MemoryBarrier],
+                &[] as &[vulkanalia::vk::BufferMemoryBarrier],
+                &[to_transfer],
+            );
+            vk_device.cmd_copy_image_to_buffer(
+                self.command_buffer,
+                image,
+                vulkanalia::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.buffer.buffer(),
+                &[region],
+            );
+            vk_device.cmd_pipeline_barrier(
+                self.command_buffer,
+                vulkanalia::vk::PipelineStageFlags::TRANSFER,
+                vulkanalia::vk::PipelineStageFlags::HOST | vulkanalia::vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vulkanalia::vk::DependencyFlags::empty(),
+                &[] as &[vulkanalia::vk::MemoryBarrier],
+                &[buffer_barrier],
+                &[to_present],
+            );
+            vk_device.end_command_buffer(self.command_buffer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn command_buffer(&self) -> vulkanalia::vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    /// Check whether the frame is done without waiting for it.
+    /// Once it is, read back the pixels, destroy the vulkan objects and resolve the requests on a background thread.
+    /// Returns whether the capture is finished, successfully or not.
+    pub fn try_finish(&mut self, vk_device: &vulkanalia::Device) -> bool {
+        let pixels = match self.read_pixels(vk_device) {
+            Ok(None) => return false,
+            Ok(Some(pixels)) => Ok(pixels),
+            Err(e) => Err(e),
+        };
+        self.destroy(vk_device);
+
+        let format = self.format;
+        let color_space = self.color_space;
+        let paper_white = self.paper_white;
+        let extent = self.extent;
+        let requests = std::mem::take(&mut self.requests);
+        std::thread::spawn(move || {
+            let frame = pixels.and_then(|pixels| Self::to_rgba(format, color_space, paper_white, extent, pixels));
+            ScreenshotRequest::resolve_all(requests, frame);
+        });
+        true
+    }
+
+    /// Give up on a capture that will never be finished, resolving the requests with an error.
+    pub fn abort(&mut self, vk_device: &vulkanalia::Device) {
+        self.destroy(vk_device);
+        let requests = std::mem::take(&mut self.requests);
+        std::thread::spawn(move || ScreenshotRequest::resolve_all(
+            requests,
+            Err(PropellantError::Custom("The frame of the screenshot was never rendered.".to_string())),
+        ));
+    }
+
+    /// The copied pixels, or None if the frame is still in flight.
+    fn read_pixels(&mut self, vk_device: &vulkanalia::Device) -> PResult<Option<Vec<u8>>> {
+        // the fence is only polled, the render thread never waits for a screenshot.
+        if unsafe { vk_device.get_fence_status(self.frame_fence)? } == vulkanalia::vk::SuccessCode::NOT_READY {
+            return Ok(None);
+        }
+        let mut pixels = vec![0u8; self.buffer.size() as usize];
+        let memory = self.buffer.map(vk_device)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(memory.cast::<u8>(), pixels.as_mut_ptr(), pixels.len());
+        }
+        self.buffer.unmap(vk_device);
+        Ok(Some(pixels))
+    }
+
+    /// Size of a pixel of the supported swapchain formats.
+    fn bytes_per_pixel(format: vulkanalia::vk::Format) -> Option<u64> {
+        match format {
+            vulkanalia::vk::Format::B8G8R8A8_SRGB |
+            vulkanalia::vk::Format::B8G8R8A8_UNORM |
+            vulkanalia::vk::Format::R8G8B8A8_SRGB |
+            vulkanalia::vk::Format::R8G8B8A8_UNORM |
+            vulkanalia::vk::Format::A2B10G10R10_UNORM_PACK32 |
+            vulkanalia::vk::Format::A2R10G10B10_UNORM_PACK32 => Some(4),
+            vulkanalia::vk::Format::R16G16B16A16_SFLOAT => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Convert the copied pixels to srgb encoded rgba.
+    /// Srgb images hold encoded values, and unorm images on a srgb surface are presented as is:
+    /// in both cases, the bytes are already what was on screen and only the channel order changes.
+    /// Hdr images are decoded to linear colors, brought back to sdr with the paper white and encoded in srgb.
+    fn to_rgba(
+        format: vulkanalia::vk::Format,
+        color_space: vulkanalia::vk::ColorSpaceKHR,
+        paper_white: f32,
+        extent: vulkanalia::vk::Extent2D,
+        pixels: Vec<u8>,
+    ) -> PResult<image::RgbaImage> {
+        // the surface is composited as opaque, whatever was written in the alpha channel.
+        let rgba = match format {
+            vulkanalia::vk::Format::B8G8R8A8_SRGB |
+            vulkanalia::vk::Format::B8G8R8A8_UNORM => pixels.chunks_exact(4).flat_map(|pixel| [pixel[2], pixel[1], pixel[0], u8::MAX]).collect(),
+            vulkanalia::vk::Format::R8G8B8A8_SRGB |
+            vulkanalia::vk::Format::R8G8B8A8_UNORM => pixels.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX]).collect(),
+            vulkanalia::vk::Format::R16G16B16A16_SFLOAT => pixels.chunks_exact(8).flat_map(|pixel| {
+                let color = [0, 2, 4].map(|offset| half_to_f32(u16::from_ne_bytes([pixel[offset], pixel[offset + 1]])));
+                encode_display_color(color, color_space, paper_white)
+            }).collect(),
+            // packed formats are read as a whole, the alpha takes the two high bits.
+            vulkanalia::vk::Format::A2B10G10R10_UNORM_PACK32 => pixels.chunks_exact(4).flat_map(|pixel| {
+                let packed = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let color = [0, 10, 20].map(|shift| ((packed >> shift) & 0x3ff) as f32 / 1023.0);
+                encode_display_color(color, color_space, paper_white)
+            }).collect(),
+            vulkanalia::vk::Format::A2R10G10B10_UNORM_PACK32 => pixels.chunks_exact(4).flat_map(|pixel| {
+                let packed = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let color = [20, 10, 0].map(|shift| ((packed >> shift) & 0x3ff) as f32 / 1023.0);
+                encode_display_color(color, color_space, paper_white)
+            }).collect(),
+            _ => return Err(PropellantError::Custom(format!("Screenshots of the swapchain format {format:?} are not supported."))),
+        };
+        match image::RgbaImage::from_raw(extent.width, extent.height, rgba) {
+            Some(frame) => Ok(frame),
+            None => Err(PropellantError::Custom("Screenshot buffer does not match the swapchain extent.".to_string())),
+        }
+    }
+
+    fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.buffer.destroy(vk_device);
+        unsafe {
+            vk_device.free_command_buffers(self.command_pool, &[self.command_buffer]);
+            vk_device.destroy_command_pool(self.command_pool, None);
+        }
+    }
+}
+
+/// Convert a color as the display reads it to srgb encoded rgba.
+/// Hdr color spaces hold absolute luminances: the paper white is brought back to the sdr white, and brighter colors are clipped.
+/// Other color spaces are presented as is, the values are already encoded.
+fn encode_display_color(color: [f32; 3], color_space: vulkanalia::vk::ColorSpaceKHR, paper_white: f32) -> [u8; 4] {
+    let linear = match color_space {
+        // extended srgb is linear, with 1.0 at 80 nits.
+        vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => glam::Vec3::from(color) * 80.0 / paper_white,
+        // hdr10 is pq encoded, with the rec 2020 primaries.
+        vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT => {
+            let rec2020_to_rec709 = glam::Mat3::from_cols(
+                glam::vec3(1.660491, -0.124550, -0.018151),
+                glam::vec3(-0.587641, 1.1329, -0.100579),
+                glam::vec3(-0.07285, -0.008349, 1.11873),
+            );
+            rec2020_to_rec709 * glam::Vec3::from(color.map(pq_to_nits)) / paper_white
+        },
+        _ => {
+            let [r, g, b] = color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+            return [r, g, b, u8::MAX];
+        },
+    };
+    let [r, g, b] = linear.to_array().map(linear_to_srgb);
+    [r, g, b, u8::MAX]
+}
+
+/// Luminance in nits of a pq encoded value, the st2084 eotf.
+fn pq_to_nits(encoded: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+    let power = encoded.clamp(0.0, 1.0).powf(1.0 / M2);
+    10000.0 * ((power - C1).max(0.0) / (C2 - C3 * power)).powf(1.0 / M1)
+}
+
+fn linear_to_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = match linear <= 0.0031308 {
+        true => linear * 12.92,
+        false => 1.055 * linear.powf(1.0 / 2.4) - 0.055,
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Decode an ieee half precision float.
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = match bits & 0x8000 {
+        0 => 1.0,
+        _ => -1.0,
+    };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f => match mantissa == 0.0 {
+            true => sign * f32::INFINITY,
+            false => f32::NAN,
+        },
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vulkanalia::vk::Extent2D = vulkanalia::vk::Extent2D { width: 2, height: 1 };
+
+    /// Inverse of the st2084 eotf, as the tonemapping shader writes it.
+    fn nits_to_pq(nits: f32) -> f32 {
+        let y = (nits / 10000.0).clamp(0.0, 1.0).powf(2610.0 / 16384.0);
+        ((3424.0 / 4096.0 + 2413.0 / 128.0 * y) / (1.0 + 2392.0 / 128.0 * y)).powf(2523.0 / 32.0)
+    }
+
+    fn pack_10_bits(high: f32, middle: f32, low: f32) -> [u8; 4] {
+        let [high, middle, low] = [high, middle, low].map(|channel| (channel * 1023.0).round() as u32);
+        (0b11 << 30 | high << 20 | middle << 10 | low).to_ne_bytes()
+    }
+
+    #[test]
+    fn half_floats_are_decoded() {
+        assert_eq!(half_to_f32(0x0000), 0.0);
+        assert_eq!(half_to_f32(0x3c00), 1.0);
+        assert_eq!(half_to_f32(0x3800), 0.5);
+        assert_eq!(half_to_f32(0x4100), 2.5);
+        assert_eq!(half_to_f32(0xc000), -2.0);
+        assert_eq!(half_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(half_to_f32(0x7bff), 65504.0);
+        assert_eq!(half_to_f32(0x7c00), f32::INFINITY);
+        assert!(half_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn pq_is_decoded_to_nits() {
+        assert_eq!(pq_to_nits(0.0), 0.0);
+        assert!((pq_to_nits(1.0) - 10000.0).abs() < 0.5);
+        for nits in [1.0, 80.0, 200.0, 1000.0] {
+            assert!((pq_to_nits(nits_to_pq(nits)) - nits).abs() < nits * 0.001);
+        }
+    }
+
+    #[test]
+    fn sdr_pixels_are_reordered_and_opaque() {
+        let pixels = vec![10, 20, 30, 0, 40, 50, 60, 128];
+        let bgra = ScreenshotCapture::to_rgba(
+            vulkanalia::vk::Format::B8G8R8A8_SRGB,
+            vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            200.0,
+            EXTENT,
+            pixels.clone(),
+        ).unwrap();
+        assert_eq!(bgra.into_raw(), vec![30, 20, 10, 255, 60, 50, 40, 255]);
+        let rgba = ScreenshotCapture::to_rgba(
+            vulkanalia::vk::Format::R8G8B8A8_UNORM,
+            vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            200.0,
+            EXTENT,
+            pixels,
+        ).unwrap();
+        assert_eq!(rgba.into_raw(), vec![10, 20, 30, 255, 40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn extended_srgb_pixels_are_brought_back_to_sdr() {
+        // the paper white at 200 nits is 2.5 in extended srgb, and the second pixel is twice as bright.
+        let pixels = [0x4100u16, 0x4100, 0x0000, 0x3c00, 0x4500, 0x4500, 0x4500, 0x3c00]
+            .iter()
+            .flat_map(|half| half.to_ne_bytes())
+            .collect();
+        let frame = ScreenshotCapture::to_rgba(
+            vulkanalia::vk::Format::R16G16B16A16_SFLOAT,
+            vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            200.0,
+            EXTENT,
+            pixels,
+        ).unwrap();
+        assert_eq!(frame.into_raw(), vec![255, 255, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn hdr10_pixels_are_brought_back_to_sdr() {
+        let white = nits_to_pq(200.0);
+        let grey = nits_to_pq(200.0 * 0.214);
+        // a grey at about half the srgb range, and the pure rec 2020 red, which is clipped in rec 709.
+        let pixels_abgr = [pack_10_bits(grey, grey, grey), pack_10_bits(0.0, 0.0, white)].concat();
+        let pixels_argb = [pack_10_bits(grey, grey, grey), pack_10_bits(white, 0.0, 0.0)].concat();
+        for (format, pixels) in [
+            (vulkanalia::vk::Format::A2B10G10R10_UNORM_PACK32, pixels_abgr),
+            (vulkanalia::vk::Format::A2R10G10B10_UNORM_PACK32, pixels_argb),
+        ] {
+            let frame = ScreenshotCapture::to_rgba(
+                format,
+                vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+                200.0,
+                EXTENT,
+                pixels,
+            ).unwrap().into_raw();
+            assert!(frame[..3].iter().all(|channel| channel.abs_diff(128) <= 2), "{format:?}: {frame:?}");
+            assert_eq!(frame[3], 255);
+            assert_eq!(frame[4..], [255, 0, 0, 255], "{format:?}");
+        }
+    }
+
+    #[test]
+    fn unsupported_formats_are_an_error() {
+        let result = ScreenshotCapture::to_rgba(
+            vulkanalia::vk::Format::R32G32B32A32_SFLOAT,
+            vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            200.0,
+            EXTENT,
+            vec![0; 32],
+        );
+        assert!(matches!(result, Err(PropellantError::Custom(_))));
+        assert_eq!(ScreenshotCapture::bytes_per_pixel(vulkanalia::vk::Format::R32G32B32A32_SFLOAT), None);
+    }
+
+    #[test]
+    fn every_callback_gets_the_result() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let requests = (0..2).map(|_| {
+            let sender = sender.clone();
+            ScreenshotRequest::callback(move |frame| sender.send(frame.map(|frame| frame.dimensions())).unwrap())
+        }).collect();
+        ScreenshotRequest::resolve_all(requests, Ok(image::RgbaImage::new(2, 1)));
+        assert_eq!(receiver.try_iter().map(|frame| frame.unwrap()).collect::<Vec<_>>(), vec![(2, 1), (2, 1)]);
+
+        let requests = (0..2).map(|_| {
+            let sender = sender.clone();
+            ScreenshotRequest::callback(move |frame| sender.send(frame.map(|frame| frame.dimensions())).unwrap())
+        }).collect();
+        ScreenshotRequest::resolve_all(requests, Err(PropellantError::Custom("lost".to_string())));
+        let errors = receiver.try_iter().map(|frame| frame.unwrap_err().to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, vec!["lost".to_string(), "lost".to_string()]);
+    }
+}
@@ -5,4 +5,27 @@ pub static DEFAULT_FRAG: &'static [u32] = include_glsl!("src/shaders/default.fra
 pub static UI_VERT: &'static [u32] = include_glsl!("src/shaders/ui.vert");
 pub static UI_FRAG: &'static [u32] = include_glsl!("src/shaders/ui.frag");
 pub static UI_TEXT_VERT: &'static [u32] = include_glsl!("src/shaders/text.vert");
-pub static UI_TEXT_FRAG: &'static [u32] = include_glsl!("src/shaders/text.frag");
\ No newline at end of file
+pub static UI_TEXT_FRAG: &'static [u32] = include_glsl!("src/shaders/text.frag");
+pub static SKINNED_VERT: &'static [u32] = include_glsl!("src/shaders/skinned.vert");
+pub static LINES_VERT: &'static [u32] = include_glsl!("src/shaders/lines.vert");
+pub static LINES_FRAG: &'static [u32] = include_glsl!("src/shaders/lines.frag");
+pub static POINTS_VERT: &'static [u32] = include_glsl!("src/shaders/points.vert");
+pub static PBR_FRAG: &'static [u32] = include_glsl!("src/shaders/pbr.frag");
+pub static UNLIT_VERT: &'static [u32] = include_glsl!("src/shaders/unlit.vert");
+pub static UNLIT_FRAG: &'static [u32] = include_glsl!("src/shaders/unlit.frag");
+pub static SHADOW_VERT: &'static [u32] = include_glsl!("src/shaders/shadow.vert");
+pub static SKINNED_SHADOW_VERT: &'static [u32] = include_glsl!("src/shaders/skinned_shadow.vert");
+pub static SKYBOX_VERT: &'static [u32] = include_glsl!("src/shaders/skybox.vert");
+pub static SKYBOX_FRAG: &'static [u32] = include_glsl!("src/shaders/skybox.frag");
+pub static FULLSCREEN_VERT: &'static [u32] = include_glsl!("src/shaders/fullscreen.vert");
+pub static TONEMAP_FRAG: &'static [u32] = include_glsl!("src/shaders/tonemap.frag");
+pub static FXAA_FRAG: &'static [u32] = include_glsl!("src/shaders/fxaa.frag");
+pub static VIGNETTE_FRAG: &'static [u32] = include_glsl!("src/shaders/vignette.frag");
+pub static COLOR_GRADING_COMP: &'static [u32] = include_glsl!("src/shaders/color_grading.comp");
+pub static BLOOM_PREFILTER_COMP: &'static [u32] = include_glsl!("src/shaders/bloom_prefilter.comp");
+pub static BLOOM_DOWNSAMPLE_COMP: &'static [u32] = include_glsl!("src/shaders/bloom_downsample.comp");
+pub static BLOOM_UPSAMPLE_COMP: &'static [u32] = include_glsl!("src/shaders/bloom_upsample.comp");
+pub static BLOOM_COMPOSITE_COMP: &'static [u32] = include_glsl!("src/shaders/bloom_composite.comp");
+pub static SSAO_COMP: &'static [u32] = include_glsl!("src/shaders/ssao.comp");
+pub static SSAO_BLUR_COMP: &'static [u32] = include_glsl!("src/shaders/ssao_blur.comp");
+pub static SSAO_COMPOSITE_COMP: &'static [u32] = include_glsl!("src/shaders/ssao_composite.comp");
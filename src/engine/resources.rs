@@ -1,6 +1,6 @@
 use crate::resource_loading::RequireResourcesLoadingFlag;
 
-use self::{mesh_library::MeshLibrary, texture_library::TextureLibrary, font_library::FontLibrary};
+use self::{mesh_library::MeshLibrary, texture_library::TextureLibrary, font_library::FontLibrary, release_queue::ReleaseQueue};
 use super::{
     window::vulkan::transfer_command_manager::TransferCommandManager,
     errors::PResult
@@ -9,12 +9,16 @@ use super::{
 pub(crate) mod mesh_library;
 pub(crate) mod texture_library;
 pub(crate) mod font_library;
+pub(crate) mod release_queue;
+pub(crate) mod resource_handle;
 
 /// Holds all the resources that are required by the user, 3D models, textures, etc.
 pub struct PropellantResources {
     meshes: MeshLibrary,
     textures: TextureLibrary,
     fonts: FontLibrary,
+    /// Unregistered resources waiting for the gpu to stop using them.
+    release_queue: ReleaseQueue,
 }
 
 #[cfg(feature = "ui")]
@@ -24,6 +28,7 @@ impl Default for PropellantResources {
             meshes: MeshLibrary::with_ui_quad(),
             textures: TextureLibrary::new(),
             fonts: FontLibrary::new(),
+            release_queue: ReleaseQueue::new(),
         }
     }
 }
@@ -35,6 +40,7 @@ impl Default for PropellantResources {
             meshes: MeshLibrary::new(),
             textures: TextureLibrary::new(),
             fonts: FontLibrary::new(),
+            release_queue: ReleaseQueue::new(),
         }
     }
 }
@@ -61,6 +67,30 @@ impl PropellantResources {
         Ok(())
     }
 
+    /// Unregister the resources that are no longer referenced by any handle,
+    /// and destroy the released resources that are not in use by any swapchain image anymore.
+    /// Returns true if resources have been released since the last call: the draw commands of all images
+    /// must then be recorded again, so they stop referencing them.
+    /// The given image must not be in use by the gpu.
+    pub fn sync_released_resources(
+        &mut self,
+        image_index: usize,
+        image_count: usize,
+        vk_device: &vulkanalia::Device,
+    ) -> bool {
+        self.meshes.unregister_unused_meshes();
+        self.textures.unregister_unused_textures();
+
+        let mut released = self.meshes.drain_released();
+        released.extend(self.textures.drain_released());
+        let has_released = !released.is_empty();
+        self.release_queue.push(released, image_count);
+
+        self.release_queue.sync_image(image_index, vk_device, &mut self.meshes, &mut self.textures);
+
+        has_released
+    }
+
     pub fn meshes(&self) -> &MeshLibrary {
         &self.meshes
     }
@@ -89,7 +119,15 @@ impl PropellantResources {
         self.fonts.load_font(id, bytes, &mut self.textures)
     }
 
+    /// Load a material file, and build the material with the textures it references.
+    /// Textures referenced by path are registered if they are not loaded yet.
+    #[cfg(feature = "material-assets")]
+    pub fn load_material<P: AsRef<std::path::Path>>(&mut self, path: P) -> PResult<crate::LoadedMaterial> {
+        crate::MaterialAsset::load(path)?.resolve(&mut self.textures)
+    }
+
     pub fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        self.release_queue.destroy(vk_device, &mut self.meshes, &mut self.textures);
         self.meshes.destroy(vk_device);
         self.textures.destroy(vk_device);
     }
@@ -6,106 +6,193 @@ use crate::{
             vulkan_buffer::VulkanBuffer,
             transfer_command_manager::TransferCommandManager
         },
-        errors::PResult, mesh::{vertex::StaticVertex, MeshType, StaticMeshVertexType}
+        errors::{PResult, PropellantError}, mesh::{MeshType, lod::MeshLodGroup},
+        resources::{
+            release_queue::ReleasedResource,
+            resource_handle::{HandleTracker, ResourceHandle},
+        }
     },
     id
 };
 
 use vulkanalia::vk::DeviceV1_0;
 
+use self::{
+    dynamic_mesh::DynamicMesh,
+    mesh_pool::{MeshPool, PoolAllocation},
+};
+
+pub(crate) mod dynamic_mesh;
+pub(crate) mod mesh_pool;
+
+/// Everything needed to record the draw of a mesh: the buffers to bind and the indices to draw.
+#[derive(Debug, Clone, Copy)]
+pub struct MeshDraw {
+    /// The buffer containing the vertex data
+    vertex_buffer: vulkanalia::vk::Buffer,
+    /// The buffer containing the index data
+    index_buffer: vulkanalia::vk::Buffer,
+    /// type of int used for this mesh
+    index_type: vulkanalia::vk::IndexType,
+    /// number of indices
+    index_count: usize,
+    /// position of the first index of the mesh in the index buffer
+    first_index: u32,
+    /// position of the first vertex of the mesh in the vertex buffer
+    vertex_offset: i32,
+}
+
+impl MeshDraw {
+    pub fn new(
+        vertex_buffer: vulkanalia::vk::Buffer,
+        index_buffer: vulkanalia::vk::Buffer,
+        index_type: vulkanalia::vk::IndexType,
+        index_count: usize,
+        first_index: u32,
+        vertex_offset: i32,
+    ) -> MeshDraw {
+        MeshDraw {
+            vertex_buffer,
+            index_buffer,
+            index_type,
+            index_count,
+            first_index,
+            vertex_offset,
+        }
+    }
+
+    pub fn bind_mesh(
+        &self,
+        vk_device: &vulkanalia::Device,
+        vk_command_buffer: vulkanalia::vk::CommandBuffer,
+    ) {
+        let buffers = [self.vertex_buffer];
+
+        unsafe {
+            vk_device.cmd_bind_vertex_buffers(vk_command_buffer, 0, &buffers, &[0]);
+            vk_device.cmd_bind_index_buffer(vk_command_buffer, self.index_buffer, 0, self.index_type);
+        }
+    }
+
+    /// Whether the other mesh uses the same bindings, so it can be drawn without binding it.
+    pub fn shares_bindings(&self, other: &MeshDraw) -> bool {
+        self.vertex_buffer == other.vertex_buffer
+            && self.index_buffer == other.index_buffer
+            && self.index_type == other.index_type
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
+
+    pub fn first_index(&self) -> u32 {
+        self.first_index
+    }
+
+    pub fn vertex_offset(&self) -> i32 {
+        self.vertex_offset
+    }
+}
 
 /// Instance of a mesh on the gpu.
+/// The data lives in the buffers of the mesh pool of the library.
 #[derive(Debug)]
 pub struct LoadedMesh {
-    /// The buffer containing the data
-    buffer: VulkanBuffer,
+    /// Region of the vertex data in the mesh pool
+    vertex_allocation: PoolAllocation,
+    /// Region of the index data in the mesh pool
+    index_allocation: PoolAllocation,
     /// number of indices
     index_count: usize,
     /// number of vertices
     vertex_count: usize,
     /// type of int used for this mesh
     index_type: vulkanalia::vk::IndexType,
+    /// position of the first index of the mesh in the index buffer
+    first_index: u32,
+    /// position of the first vertex of the mesh in the vertex buffer
+    vertex_offset: i32,
 }
 
 impl LoadedMesh {
     pub fn create(
         mesh: MeshType,
+        pool: &mut MeshPool,
         vk_instance: &vulkanalia::Instance,
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
         vk_transfer_manager: &mut TransferCommandManager,
     ) -> PResult<LoadedMesh> {
-        // we will use a single buffer for both vertex and index data.
-        // [ VERTEX BUFFER | INDEX BUFFER ]
-        // create a staging buffer for the buffer (on CPU / RAM)
-        let buffer_size = mesh.buffer_size() as u64;
-        let mut staging_buffer = VulkanBuffer::create(
+        let vertex_size = mesh.vertex_size() as u64;
+        let index_size = mesh.index_size() as u64;
+        // find room for the mesh in the pool
+        let vertex_allocation = pool.allocate_vertices(
+            mesh.vertex_data().len() as u64, vertex_size,
             vk_instance, vk_device, vk_physical_device,
-            buffer_size,
-            vulkanalia::vk::BufferUsageFlags::TRANSFER_SRC,
-            vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT | vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE,
         )?;
-        // copy the vertex data in the staging buffer
-        match &mesh {
-            MeshType::Static(mesh) => staging_buffer.map_data(
-                vk_device,
-                mesh.vertices(),
-                0,
-            )?,
-        };
-        // copy the index data in the staging buffer
-        match &mesh {
-            MeshType::Static(mesh) => staging_buffer.map_data(
-                vk_device,
-                mesh.triangles(),
-                mesh.vertices().len() * std::mem::size_of::<StaticMeshVertexType>(),
-            )?,
-        };
-        // create the buffer on the graphic card itself
-        let buffer = VulkanBuffer::create(
+        let index_allocation = pool.allocate_indices(
+            mesh.index_data().len() as u64, index_size,
             vk_instance, vk_device, vk_physical_device,
-            buffer_size,
-            // we need a target buffer that can be used as a vertex buffer, index buffer and transfer destination
-            vulkanalia::vk::BufferUsageFlags::TRANSFER_DST | vulkanalia::vk::BufferUsageFlags::VERTEX_BUFFER | vulkanalia::vk::BufferUsageFlags::INDEX_BUFFER,
-            vulkanalia::vk::MemoryPropertyFlags::DEVICE_LOCAL,
-        )?;
-        // set the buffer transfer on the queue
-        vk_transfer_manager.register_buffer_transfer(
-            vk_device,
-            staging_buffer,
-            buffer.buffer(),
-            buffer_size,
         )?;
-
-        let index_count = match &mesh {
-            MeshType::Static(mesh) => mesh.triangles().len(),
-        };
-        let vertex_count = match &mesh {
-            MeshType::Static(mesh) => mesh.vertices().len(),
+        let (vertex_buffer, index_buffer) = match (pool.vertex_buffer(&vertex_allocation), pool.index_buffer(&index_allocation)) {
+            (Some(vertex_buffer), Some(index_buffer)) => (vertex_buffer, index_buffer),
+            _ => return Err(PropellantError::Custom("Mesh pool allocation points to a released page".to_string())),
         };
-
-        let index_type = mesh.index_type();
+        // send the vertex and index data to their region of the pool through staging buffers
+        Self::register_transfer(mesh.vertex_data(), vertex_buffer, vertex_allocation.offset(), vk_instance, vk_device, vk_physical_device, vk_transfer_manager)?;
+        Self::register_transfer(mesh.index_data(), index_buffer, index_allocation.offset(), vk_instance, vk_device, vk_physical_device, vk_transfer_manager)?;
 
         Ok(LoadedMesh {
-            buffer,
-            index_count,
-            vertex_count,
-            index_type,
+            vertex_allocation,
+            index_allocation,
+            index_count: mesh.index_count(),
+            vertex_count: mesh.vertex_count(),
+            index_type: mesh.index_type(),
+            first_index: (index_allocation.offset() / index_size) as u32,
+            vertex_offset: (vertex_allocation.offset() / vertex_size) as i32,
         })
     }
 
-    pub fn bind_mesh(
-        &self,
+    fn register_transfer(
+        data: &[u8],
+        destination: vulkanalia::vk::Buffer,
+        destination_offset: u64,
+        vk_instance: &vulkanalia::Instance,
         vk_device: &vulkanalia::Device,
-        vk_command_buffer: vulkanalia::vk::CommandBuffer,
-    ) {
-        let buffers = [self.buffer.buffer()];
-        let offset = self.vertex_count as u64 * std::mem::size_of::<StaticVertex>() as u64;
-
-        unsafe {
-            vk_device.cmd_bind_vertex_buffers(vk_command_buffer, 0, &buffers, &[0]);
-            vk_device.cmd_bind_index_buffer(vk_command_buffer, self.buffer.buffer(), offset, self.index_type);
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        vk_transfer_manager: &mut TransferCommandManager,
+    ) -> PResult<()> {
+        if data.is_empty() {
+            return Ok(());
         }
+        // create a staging buffer for the data (on CPU / RAM)
+        let mut staging_buffer = VulkanBuffer::create(
+            vk_instance, vk_device, vk_physical_device,
+            data.len() as u64,
+            vulkanalia::vk::BufferUsageFlags::TRANSFER_SRC,
+            vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT | vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+        staging_buffer.map_data(vk_device, data, 0)?;
+        // set the buffer transfer on the queue
+        vk_transfer_manager.register_buffer_transfer(
+            vk_device,
+            staging_buffer,
+            destination,
+            destination_offset,
+            data.len() as u64,
+        )
+    }
+
+    /// The draw information of the mesh, or None if its pool pages have been released.
+    pub fn draw(&self, pool: &MeshPool) -> Option<MeshDraw> {
+        Some(MeshDraw {
+            vertex_buffer: pool.vertex_buffer(&self.vertex_allocation)?,
+            index_buffer: pool.index_buffer(&self.index_allocation)?,
+            index_type: self.index_type,
+            index_count: self.index_count,
+            first_index: self.first_index,
+            vertex_offset: self.vertex_offset,
+        })
     }
 
     pub fn index_count(&self) -> usize {
@@ -116,11 +203,14 @@ impl LoadedMesh {
         self.vertex_count
     }
 
+    /// Give back the regions of the mesh to the pool.
     pub fn destroy(
-        mut self,
+        self,
+        pool: &mut MeshPool,
         vk_device: &vulkanalia::Device
     ) {
-        self.buffer.destroy(vk_device);
+        pool.free_vertices(self.vertex_allocation, vk_device);
+        pool.free_indices(self.index_allocation, vk_device);
     }
 }
 
@@ -130,6 +220,21 @@ impl LoadedMesh {
 pub struct MeshLibrary {
     loading_queue: HashMap<u64, MeshType>,
     meshes: HashMap<u64, LoadedMesh>,
+    /// Large buffers the loaded meshes are allocated in.
+    pool: MeshPool,
+    /// Meshes that can be updated after being uploaded.
+    dynamic_meshes: HashMap<u64, DynamicMesh>,
+    /// Meshes with levels of detail. The key is the mesh id used by the renderers.
+    lod_groups: HashMap<u64, MeshLodGroup>,
+    /// Levels of detail waiting to be loaded, keyed by mesh id and level. The first level of each mesh is in the loading queue.
+    lod_loading_queue: HashMap<(u64, usize), MeshType>,
+    /// Loaded levels of detail, keyed by mesh id and level. They are apart from the meshes so they can not collide with their ids.
+    lod_meshes: HashMap<(u64, usize), LoadedMesh>,
+    /// Handles given on the meshes, to unload them once they are not used anymore.
+    handles: HandleTracker,
+    /// Unregistered meshes, waiting to be destroyed once the gpu is done with them.
+    released_meshes: Vec<LoadedMesh>,
+    released_dynamic_meshes: Vec<DynamicMesh>,
 }
 
 impl MeshLibrary {
@@ -137,6 +242,14 @@ impl MeshLibrary {
         MeshLibrary {
             loading_queue: HashMap::new(),
             meshes: HashMap::new(),
+            pool: MeshPool::new(),
+            dynamic_meshes: HashMap::new(),
+            lod_groups: HashMap::new(),
+            lod_loading_queue: HashMap::new(),
+            lod_meshes: HashMap::new(),
+            handles: HandleTracker::new(),
+            released_meshes: Vec::new(),
+            released_dynamic_meshes: Vec::new(),
         }
     }
 
@@ -151,6 +264,130 @@ impl MeshLibrary {
         self.loading_queue.insert(mesh_id, mesh);
     }
 
+    /// Register a dynamic mesh, whose data can be updated with `update_dynamic_mesh`.
+    /// Dynamic meshes are kept in host visible memory and rewritten by the renderer when they change,
+    /// so they are best suited for geometry that changes often, like procedural terrain or debug geometry.
+    pub fn register_dynamic_mesh(&mut self, mesh_id: u64, mesh: MeshType) {
+        self.dynamic_meshes.insert(mesh_id, DynamicMesh::new(mesh));
+    }
+
+    /// Replace the data of a dynamic mesh. If the mesh is not a registered dynamic mesh, it gets registered.
+    pub fn update_dynamic_mesh(&mut self, mesh_id: u64, mesh: MeshType) {
+        match self.dynamic_meshes.get_mut(&mesh_id) {
+            Some(dynamic_mesh) => dynamic_mesh.update(mesh),
+            None => self.register_dynamic_mesh(mesh_id, mesh),
+        }
+    }
+
+    pub fn dynamic_mesh(&self, mesh_id: u64) -> Option<&DynamicMesh> {
+        self.dynamic_meshes.get(&mesh_id)
+    }
+
+    /// Write the pending dynamic mesh data in the buffers of the given swapchain image.
+    /// Returns true if the draw commands of this image need to be recorded again.
+    /// The image must not be in use by the gpu.
+    pub fn sync_dynamic_meshes(
+        &mut self,
+        image_index: usize,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<bool> {
+        let mut requires_recording = false;
+        for (_, dynamic_mesh) in self.dynamic_meshes.iter_mut() {
+            requires_recording |= dynamic_mesh.sync(image_index, vk_instance, vk_device, vk_physical_device)?;
+        }
+        Ok(requires_recording)
+    }
+
+    /// Register a mesh with several levels of detail.
+    /// Each level is given with the minimum screen size (proportion of the screen height covered by the mesh)
+    /// at which it is used. The first level is the most detailed one, and is registered with the given id.
+    /// Below every threshold, the last level is used.
+    pub fn register_mesh_lods(&mut self, mesh_id: u64, levels: Vec<(MeshType, f32)>) {
+        let bounding_radius = levels.iter().map(|(mesh, _)| mesh.bounding_radius()).fold(0.0, f32::max);
+        let mut lod_levels = Vec::with_capacity(levels.len());
+        for (level, (mesh, threshold)) in levels.into_iter().enumerate() {
+            match level {
+                0 => self.register_mesh(mesh_id, mesh),
+                _ => {self.lod_loading_queue.insert((mesh_id, level), mesh);},
+            }
+            lod_levels.push((level, threshold));
+        }
+        self.lod_groups.insert(mesh_id, MeshLodGroup::new(bounding_radius, lod_levels));
+    }
+
+    /// Register a mesh, and generate its levels of detail by simplifying it.
+    /// Each level is given with the ratio of triangles to keep, and the minimum screen size to use it.
+    /// The original mesh is used when the screen size is above `full_detail_threshold`.
+    /// Fails without registering anything if the mesh can not be simplified.
+    pub fn register_mesh_with_generated_lods(&mut self, mesh_id: u64, mesh: MeshType, full_detail_threshold: f32, levels: &[(f32, f32)]) -> PResult<()> {
+        let mut lods = Vec::with_capacity(levels.len() + 1);
+        for (ratio, threshold) in levels.iter() {
+            lods.push((mesh.simplified(*ratio)?, *threshold));
+        }
+        lods.insert(0, (mesh, full_detail_threshold));
+        self.register_mesh_lods(mesh_id, lods);
+        Ok(())
+    }
+
+    pub fn lod_group(&self, mesh_id: u64) -> Option<&MeshLodGroup> {
+        self.lod_groups.get(&mesh_id)
+    }
+
+    /// Whether any mesh of the library has levels of detail.
+    pub fn has_lods(&self) -> bool {
+        !self.lod_groups.is_empty()
+    }
+
+    /// Get a reference counted handle on a mesh.
+    /// Once a handle have been created for a mesh, the mesh is unloaded when all its handles are dropped.
+    pub fn mesh_handle(&mut self, mesh_id: u64) -> ResourceHandle {
+        self.handles.handle(mesh_id)
+    }
+
+    /// Register a mesh, and get a reference counted handle on it.
+    pub fn register_mesh_handle(&mut self, mesh_id: u64, mesh: MeshType) -> ResourceHandle {
+        self.register_mesh(mesh_id, mesh);
+        self.mesh_handle(mesh_id)
+    }
+
+    /// Remove a mesh from the library, with all its levels of detail.
+    /// The gpu memory is freed once no frame in flight uses the mesh anymore.
+    /// Objects still using this mesh id will not be drawn.
+    pub fn unregister_mesh(&mut self, mesh_id: u64) {
+        self.handles.remove(mesh_id);
+        if let Some(group) = self.lod_groups.remove(&mesh_id) {
+            for (level, _) in group.levels().iter().filter(|(level, _)| *level > 0) {
+                self.lod_loading_queue.remove(&(mesh_id, *level));
+                if let Some(mesh) = self.lod_meshes.remove(&(mesh_id, *level)) {
+                    self.released_meshes.push(mesh);
+                }
+            }
+        }
+        self.loading_queue.remove(&mesh_id);
+        if let Some(mesh) = self.meshes.remove(&mesh_id) {
+            self.released_meshes.push(mesh);
+        }
+        if let Some(mesh) = self.dynamic_meshes.remove(&mesh_id) {
+            self.released_dynamic_meshes.push(mesh);
+        }
+    }
+
+    /// Unregister the meshes that had handles, but for which all handles have been dropped.
+    pub fn unregister_unused_meshes(&mut self) {
+        for mesh_id in self.handles.drain_unused().into_iter() {
+            self.unregister_mesh(mesh_id);
+        }
+    }
+
+    /// Take the meshes that have been unregistered since the last call.
+    pub fn drain_released(&mut self) -> Vec<ReleasedResource> {
+        self.released_meshes.drain(..).map(ReleasedResource::Mesh)
+            .chain(self.released_dynamic_meshes.drain(..).map(ReleasedResource::DynamicMesh))
+            .collect()
+    }
+
     pub fn load_meshes(
         &mut self,
         vk_instance: &vulkanalia::Instance,
@@ -161,6 +398,7 @@ impl MeshLibrary {
         for (mesh_id, mesh) in self.loading_queue.drain() {
             let loaded_mesh = LoadedMesh::create(
                 mesh,
+                &mut self.pool,
                 vk_instance,
                 vk_device,
                 vk_physical_device,
@@ -168,6 +406,17 @@ impl MeshLibrary {
             )?;
             self.meshes.insert(mesh_id, loaded_mesh);
         }
+        for (key, mesh) in self.lod_loading_queue.drain() {
+            let loaded_mesh = LoadedMesh::create(
+                mesh,
+                &mut self.pool,
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                vk_transfer_manager,
+            )?;
+            self.lod_meshes.insert(key, loaded_mesh);
+        }
         Ok(())
     }
 
@@ -175,12 +424,82 @@ impl MeshLibrary {
         self.meshes.get(mesh_id)
     }
 
+    /// Get the draw information of a mesh at the given level of detail for the given swapchain image,
+    /// looking in both static and dynamic meshes for the first level.
+    pub fn mesh_draw(&self, mesh_id: &u64, lod_level: usize, image_index: usize) -> Option<MeshDraw> {
+        if lod_level > 0 {
+            return self.lod_meshes.get(&(*mesh_id, lod_level)).and_then(|mesh| mesh.draw(&self.pool));
+        }
+        match self.meshes.get(mesh_id) {
+            Some(mesh) => mesh.draw(&self.pool),
+            None => self.dynamic_meshes.get(mesh_id).and_then(|mesh| mesh.draw(image_index)),
+        }
+    }
+
+    /// Free the pool regions of a mesh that was released.
+    pub(crate) fn destroy_released_mesh(
+        &mut self,
+        mesh: LoadedMesh,
+        vk_device: &vulkanalia::Device
+    ) {
+        mesh.destroy(&mut self.pool, vk_device);
+    }
+
     pub fn destroy(
         &mut self,
         vk_device: &vulkanalia::Device
     ) {
-        for (_, mesh) in self.meshes.drain() {
+        // loaded meshes only own regions of the pool, that is destroyed as a whole.
+        self.meshes.clear();
+        self.lod_meshes.clear();
+        self.released_meshes.clear();
+        for (_, mesh) in self.dynamic_meshes.drain() {
+            mesh.destroy(vk_device);
+        }
+        for mesh in self.released_dynamic_meshes.drain(..) {
             mesh.destroy(vk_device);
         }
+        self.pool.destroy(vk_device);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::{engine::mesh::MeshType, id};
+    use super::MeshLibrary;
+
+    #[test]
+    fn lod_levels_do_not_collide_with_registered_meshes() {
+        let mut library = MeshLibrary::new();
+        let mesh_id = id("rock");
+        library.register_mesh_lods(mesh_id, vec![
+            (MeshType::cube(1.0), 0.5),
+            (MeshType::cube(1.0), 0.2),
+            (MeshType::cube(1.0), 0.0),
+        ]);
+        // the ids the levels used to be registered with.
+        library.register_mesh(mesh_id ^ 0x9E37_79B9_7F4A_7C15, MeshType::cube(2.0));
+        library.register_mesh(mesh_id ^ 2u64.wrapping_mul(0x9E37_79B9_7F4A_7C15), MeshType::cube(2.0));
+
+        assert_eq!(library.loading_queue.len(), 3);
+        assert_eq!(library.lod_loading_queue.len(), 2);
+        assert!(library.lod_loading_queue.contains_key(&(mesh_id, 1)));
+        assert!(library.lod_loading_queue.contains_key(&(mesh_id, 2)));
+        let levels = library.lod_group(mesh_id).map(|group| group.levels().to_vec());
+        assert_eq!(levels, Some(vec![(0, 0.5), (1, 0.2), (2, 0.0)]));
+    }
+
+    #[test]
+    fn unregistering_a_mesh_removes_its_levels() {
+        let mut library = MeshLibrary::new();
+        library.register_mesh_lods(id("rock"), vec![
+            (MeshType::cube(1.0), 0.5),
+            (MeshType::cube(1.0), 0.0),
+        ]);
+        library.register_mesh(id("other"), MeshType::cube(1.0));
+        library.unregister_mesh(id("rock"));
+
+        assert!(library.lod_group(id("rock")).is_none());
+        assert!(library.lod_loading_queue.is_empty());
+        assert_eq!(library.loading_queue.keys().collect::<Vec<_>>(), vec![&id("other")]);
+    }
+}
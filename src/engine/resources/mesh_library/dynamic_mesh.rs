@@ -0,0 +1,245 @@
+use crate::engine::{
+    errors::{PResult, PropellantError},
+    mesh::MeshType,
+    window::vulkan::vulkan_buffer::VulkanBuffer,
+};
+
+use super::MeshDraw;
+
+/// Minimum size of the buffers of a dynamic mesh, in bytes.
+const DYNAMIC_MESH_MIN_CAPACITY: u64 = 1024;
+
+/// Storage of a dynamic mesh for a single swapchain image.
+#[derive(Debug)]
+struct DynamicMeshSlot {
+    /// host visible buffer containing the data, laid out as [ VERTEX BUFFER | INDEX BUFFER ]
+    buffer: VulkanBuffer,
+    /// layout of the mesh data written in the buffer
+    layout: SlotLayout,
+}
+
+/// Layout of the mesh data written in the buffer of a slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SlotLayout {
+    /// version of the mesh data written in the buffer
+    version: u64,
+    /// number of indices
+    index_count: usize,
+    /// position of the first index in the buffer, the index data being after the vertex data.
+    first_index: u32,
+    /// type of int used for this mesh
+    index_type: vulkanalia::vk::IndexType,
+}
+
+/// What syncing the slot of an image requires.
+#[derive(Debug, PartialEq)]
+enum SlotSync {
+    /// The slot already holds the latest mesh data.
+    UpToDate,
+    /// The mesh data must be written, in a new buffer of the given capacity when the slot has none or it is too small.
+    /// The draw commands must be recorded again when the buffer or the indices to draw change.
+    Write {
+        new_capacity: Option<u64>,
+        requires_recording: bool,
+    },
+}
+
+/// Decide how to sync a slot, from its current layout and buffer size, to hold the mesh data with the given layout and size.
+fn plan_slot_sync(current: Option<(SlotLayout, u64)>, layout: SlotLayout, required_size: u64) -> SlotSync {
+    match current {
+        Some((current_layout, _)) if current_layout.version == layout.version => SlotSync::UpToDate,
+        Some((current_layout, buffer_size)) if buffer_size >= required_size => SlotSync::Write {
+            new_capacity: None,
+            requires_recording: current_layout.index_count != layout.index_count
+                || current_layout.first_index != layout.first_index
+                || current_layout.index_type != layout.index_type,
+        },
+        // grow the buffer if the mesh does not fit in anymore
+        _ => SlotSync::Write {
+            new_capacity: Some(required_size.next_power_of_two().max(DYNAMIC_MESH_MIN_CAPACITY)),
+            requires_recording: true,
+        },
+    }
+}
+
+/// A mesh whose vertex and index data can be rewritten after being uploaded.
+/// The data lives in host visible memory, with one buffer per swapchain image:
+/// the buffer of an image is only rewritten once the gpu is done with that image.
+/// Buffers grow when the mesh does not fit in them anymore.
+#[derive(Debug)]
+pub struct DynamicMesh {
+    /// last mesh data given to the library
+    mesh: MeshType,
+    /// incremented each time the mesh data is updated
+    version: u64,
+    /// storage for each swapchain image, created the first time the image is synced.
+    slots: Vec<Option<DynamicMeshSlot>>,
+}
+
+impl DynamicMesh {
+    pub fn new(mesh: MeshType) -> DynamicMesh {
+        DynamicMesh {
+            mesh,
+            version: 1,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Replace the mesh data. It will be written in the buffers of each image as they get synced.
+    pub fn update(&mut self, mesh: MeshType) {
+        self.mesh = mesh;
+        self.version += 1;
+    }
+
+    pub fn mesh(&self) -> &MeshType {
+        &self.mesh
+    }
+
+    /// Write the latest mesh data in the buffer of the given image, if it is not up to date.
+    /// Returns true if the draw commands of this image need to be recorded again,
+    /// because the buffer was reallocated or the index count changed.
+    /// The image must not be in use by the gpu.
+    pub fn sync(
+        &mut self,
+        image_index: usize,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<bool> {
+        if self.slots.len() <= image_index {
+            self.slots.resize_with(image_index + 1, || None);
+        }
+
+        let required_size = self.mesh.buffer_size() as u64;
+        let index_offset = self.mesh.vertex_data().len() as u64;
+        let layout = SlotLayout {
+            version: self.version,
+            index_count: self.mesh.index_count(),
+            first_index: (index_offset / self.mesh.index_size() as u64) as u32,
+            index_type: self.mesh.index_type(),
+        };
+
+        let current = self.slots[image_index].as_ref().map(|slot| (slot.layout, slot.buffer.size()));
+        let (new_capacity, requires_recording) = match plan_slot_sync(current, layout, required_size) {
+            SlotSync::UpToDate => return Ok(false),
+            SlotSync::Write { new_capacity, requires_recording } => (new_capacity, requires_recording),
+        };
+
+        let slot = match (new_capacity, self.slots[image_index].take()) {
+            (Some(capacity), old_slot) => {
+                let buffer = VulkanBuffer::create(
+                    vk_instance, vk_device, vk_physical_device,
+                    capacity,
+                    vulkanalia::vk::BufferUsageFlags::VERTEX_BUFFER | vulkanalia::vk::BufferUsageFlags::INDEX_BUFFER,
+                    vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT | vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE,
+                );
+                if let Some(mut old_slot) = old_slot {
+                    old_slot.buffer.destroy(vk_device);
+                }
+                DynamicMeshSlot { buffer: buffer?, layout }
+            },
+            (None, Some(slot)) => slot,
+            (None, None) => return Err(PropellantError::Custom(format!("The dynamic mesh has no buffer for the image {image_index}."))),
+        };
+        // the slot is stored before being written, so its buffer is destroyed with the mesh even if the write fails.
+        let slot = self.slots[image_index].insert(slot);
+
+        // write the data in the buffer
+        let memory = slot.buffer.map(vk_device)?;
+        slot.buffer.write(memory, self.mesh.vertex_data());
+        slot.buffer.write(unsafe { memory.cast::<u8>().add(index_offset as usize).cast() }, self.mesh.index_data());
+        slot.buffer.unmap(vk_device);
+
+        slot.layout = layout;
+
+        Ok(requires_recording)
+    }
+
+    /// Get the draw information of the mesh for the given image, if the image have been synced.
+    pub fn draw(&self, image_index: usize) -> Option<MeshDraw> {
+        match self.slots.get(image_index) {
+            Some(Some(slot)) => Some(MeshDraw::new(
+                slot.buffer.buffer(),
+                slot.buffer.buffer(),
+                slot.layout.index_type,
+                slot.layout.index_count,
+                slot.layout.first_index,
+                0,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn destroy(
+        mut self,
+        vk_device: &vulkanalia::Device
+    ) {
+        for slot in self.slots.iter_mut().flatten() {
+            slot.buffer.destroy(vk_device);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_slot_sync, SlotLayout, SlotSync, DYNAMIC_MESH_MIN_CAPACITY};
+
+    fn layout(version: u64, index_count: usize) -> SlotLayout {
+        SlotLayout {
+            version,
+            index_count,
+            first_index: 24,
+            index_type: vulkanalia::vk::IndexType::UINT32,
+        }
+    }
+
+    #[test]
+    fn new_slots_get_a_buffer() {
+        assert_eq!(plan_slot_sync(None, layout(1, 36), 600), SlotSync::Write {
+            new_capacity: Some(DYNAMIC_MESH_MIN_CAPACITY),
+            requires_recording: true,
+        });
+        assert_eq!(plan_slot_sync(None, layout(1, 36), 3000), SlotSync::Write {
+            new_capacity: Some(4096),
+            requires_recording: true,
+        });
+    }
+
+    #[test]
+    fn up_to_date_slots_are_left_untouched() {
+        assert_eq!(plan_slot_sync(Some((layout(3, 36), 1024)), layout(3, 36), 600), SlotSync::UpToDate);
+    }
+
+    #[test]
+    fn outdated_slots_are_rewritten_in_place() {
+        assert_eq!(plan_slot_sync(Some((layout(1, 36), 1024)), layout(2, 36), 600), SlotSync::Write {
+            new_capacity: None,
+            requires_recording: false,
+        });
+        // drawing a different number of indices needs new draw commands.
+        assert_eq!(plan_slot_sync(Some((layout(1, 36), 1024)), layout(2, 24), 500), SlotSync::Write {
+            new_capacity: None,
+            requires_recording: true,
+        });
+    }
+
+    #[test]
+    fn slots_grow_when_the_mesh_does_not_fit() {
+        assert_eq!(plan_slot_sync(Some((layout(1, 36), 1024)), layout(2, 36), 1025), SlotSync::Write {
+            new_capacity: Some(2048),
+            requires_recording: true,
+        });
+    }
+
+    #[test]
+    fn each_image_slot_catches_up_with_the_mesh() {
+        // a mesh updated to version 2 while image 0 was synced with it, but image 1 still holds version 1.
+        let slots = [Some((layout(2, 36), 1024)), Some((layout(1, 36), 1024)), None];
+        let plans = slots.map(|slot| plan_slot_sync(slot, layout(2, 36), 600));
+        assert_eq!(plans, [
+            SlotSync::UpToDate,
+            SlotSync::Write { new_capacity: None, requires_recording: false },
+            SlotSync::Write { new_capacity: Some(DYNAMIC_MESH_MIN_CAPACITY), requires_recording: true },
+        ]);
+    }
+}
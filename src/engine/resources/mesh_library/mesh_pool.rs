@@ -0,0 +1,317 @@
+use crate::engine::{
+    errors::{PResult, PropellantError},
+    window::vulkan::vulkan_buffer::VulkanBuffer,
+};
+
+/// Size of the vertex buffers of the pool, in bytes.
+/// Meshes bigger than this get a page of their own.
+const MESH_POOL_VERTEX_PAGE_SIZE: u64 = 16 * 1024 * 1024;
+/// Size of the index buffers of the pool, in bytes.
+const MESH_POOL_INDEX_PAGE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// A large buffer meshes are sub allocated from.
+#[derive(Debug)]
+struct PoolPage {
+    buffer: VulkanBuffer,
+    /// size of the page, in bytes.
+    size: u64,
+    /// free ranges of the buffer, as (offset, size), sorted by offset.
+    free_ranges: Vec<(u64, u64)>,
+}
+
+impl PoolPage {
+    fn create(
+        size: u64,
+        usage: vulkanalia::vk::BufferUsageFlags,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<PoolPage> {
+        let buffer = VulkanBuffer::create(
+            vk_instance, vk_device, vk_physical_device,
+            size,
+            vulkanalia::vk::BufferUsageFlags::TRANSFER_DST | usage,
+            vulkanalia::vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+        Ok(PoolPage::new(buffer, size))
+    }
+
+    fn new(buffer: VulkanBuffer, size: u64) -> PoolPage {
+        PoolPage {
+            buffer,
+            size,
+            free_ranges: vec![(0, size)],
+        }
+    }
+
+    /// Find the first free range where the data fits, and return the aligned offset of the allocation.
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        for i in 0..self.free_ranges.len() {
+            let (range_offset, range_size) = self.free_ranges[i];
+            let offset = (range_offset + alignment - 1) / alignment * alignment;
+            let range_end = range_offset + range_size;
+            if offset + size > range_end {
+                continue;
+            }
+            // split the range around the allocation
+            self.free_ranges.remove(i);
+            if range_end > offset + size {
+                self.free_ranges.insert(i, (offset + size, range_end - offset - size));
+            }
+            if offset > range_offset {
+                self.free_ranges.insert(i, (range_offset, offset - range_offset));
+            }
+            return Some(offset);
+        }
+        None
+    }
+
+    /// Give back a range to the page, merging it with the neighbouring free ranges.
+    fn free(&mut self, offset: u64, size: u64) {
+        let index = self.free_ranges.partition_point(|(range_offset, _)| *range_offset < offset);
+        self.free_ranges.insert(index, (offset, size));
+        // merge with the next range
+        if index + 1 < self.free_ranges.len() && offset + size == self.free_ranges[index + 1].0 {
+            self.free_ranges[index].1 += self.free_ranges[index + 1].1;
+            self.free_ranges.remove(index + 1);
+        }
+        // merge with the previous range
+        if index > 0 && self.free_ranges[index - 1].0 + self.free_ranges[index - 1].1 == offset {
+            self.free_ranges[index - 1].1 += self.free_ranges[index].1;
+            self.free_ranges.remove(index);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.free_ranges.len() == 1 && self.free_ranges[0] == (0, self.size)
+    }
+
+    /// Dedicated pages of big meshes are released as soon as they are empty.
+    fn is_releasable(&self, page_size: u64) -> bool {
+        self.is_empty() && self.size > page_size
+    }
+}
+
+/// A region of a page of the mesh pool.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolAllocation {
+    page: usize,
+    offset: u64,
+    size: u64,
+}
+
+impl PoolAllocation {
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Sub allocates the static meshes out of a few large vertex and index buffers.
+/// Meshes sharing a page can be drawn with a single buffer bind, using offsets in the buffers.
+#[derive(Debug)]
+pub struct MeshPool {
+    vertex_pages: Vec<Option<PoolPage>>,
+    index_pages: Vec<Option<PoolPage>>,
+}
+
+impl MeshPool {
+    pub fn new() -> MeshPool {
+        MeshPool {
+            vertex_pages: Vec::new(),
+            index_pages: Vec::new(),
+        }
+    }
+
+    /// Allocate space for vertex data. The offset of the allocation is a multiple of the vertex size,
+    /// so the mesh can be drawn with a vertex offset.
+    pub fn allocate_vertices(
+        &mut self,
+        size: u64,
+        vertex_size: u64,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<PoolAllocation> {
+        Self::allocate(
+            &mut self.vertex_pages,
+            size, vertex_size,
+            MESH_POOL_VERTEX_PAGE_SIZE,
+            vulkanalia::vk::BufferUsageFlags::VERTEX_BUFFER,
+            vk_instance, vk_device, vk_physical_device,
+        )
+    }
+
+    /// Allocate space for index data. The offset of the allocation is a multiple of the index size,
+    /// so the mesh can be drawn with a first index.
+    pub fn allocate_indices(
+        &mut self,
+        size: u64,
+        index_size: u64,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<PoolAllocation> {
+        Self::allocate(
+            &mut self.index_pages,
+            size, index_size,
+            MESH_POOL_INDEX_PAGE_SIZE,
+            vulkanalia::vk::BufferUsageFlags::INDEX_BUFFER,
+            vk_instance, vk_device, vk_physical_device,
+        )
+    }
+
+    fn allocate(
+        pages: &mut Vec<Option<PoolPage>>,
+        size: u64,
+        alignment: u64,
+        page_size: u64,
+        usage: vulkanalia::vk::BufferUsageFlags,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+    ) -> PResult<PoolAllocation> {
+        // empty meshes still get a small allocation, buffers can't be empty.
+        let size = size.max(alignment);
+        for (page_index, page) in pages.iter_mut().enumerate() {
+            if let Some(page) = page {
+                if let Some(offset) = page.allocate(size, alignment) {
+                    return Ok(PoolAllocation { page: page_index, offset, size });
+                }
+            }
+        }
+        // no page have enough space, create a new one
+        let mut page = PoolPage::create(size.max(page_size), usage, vk_instance, vk_device, vk_physical_device)?;
+        let offset = match page.allocate(size, alignment) {
+            Some(offset) => offset,
+            None => {
+                page.buffer.destroy(vk_device);
+                return Err(PropellantError::Custom(format!(
+                    "Unable to allocate {size} bytes aligned on {alignment} in a new mesh pool page"
+                )));
+            }
+        };
+        let page_index = match pages.iter().position(|page| page.is_none()) {
+            Some(index) => {
+                pages[index] = Some(page);
+                index
+            },
+            None => {
+                pages.push(Some(page));
+                pages.len() - 1
+            }
+        };
+        Ok(PoolAllocation { page: page_index, offset, size })
+    }
+
+    pub fn free_vertices(&mut self, allocation: PoolAllocation, vk_device: &vulkanalia::Device) {
+        Self::free(&mut self.vertex_pages, allocation, MESH_POOL_VERTEX_PAGE_SIZE, vk_device);
+    }
+
+    pub fn free_indices(&mut self, allocation: PoolAllocation, vk_device: &vulkanalia::Device) {
+        Self::free(&mut self.index_pages, allocation, MESH_POOL_INDEX_PAGE_SIZE, vk_device);
+    }
+
+    fn free(pages: &mut [Option<PoolPage>], allocation: PoolAllocation, page_size: u64, vk_device: &vulkanalia::Device) {
+        if let Some(mut page) = Self::free_allocation(pages, allocation, page_size) {
+            page.buffer.destroy(vk_device);
+        }
+    }
+
+    /// Give back the allocation to its page, and take the page out of the pool if it can be released.
+    fn free_allocation(pages: &mut [Option<PoolPage>], allocation: PoolAllocation, page_size: u64) -> Option<PoolPage> {
+        let slot = pages.get_mut(allocation.page)?;
+        let page = slot.as_mut()?;
+        page.free(allocation.offset, allocation.size);
+        match page.is_releasable(page_size) {
+            true => slot.take(),
+            false => None,
+        }
+    }
+
+    /// The vertex buffer of the page of the allocation, if the page is still in the pool.
+    pub fn vertex_buffer(&self, allocation: &PoolAllocation) -> Option<vulkanalia::vk::Buffer> {
+        self.vertex_pages.get(allocation.page)
+            .and_then(Option::as_ref)
+            .map(|page| page.buffer.buffer())
+    }
+
+    /// The index buffer of the page of the allocation, if the page is still in the pool.
+    pub fn index_buffer(&self, allocation: &PoolAllocation) -> Option<vulkanalia::vk::Buffer> {
+        self.index_pages.get(allocation.page)
+            .and_then(Option::as_ref)
+            .map(|page| page.buffer.buffer())
+    }
+
+    pub fn destroy(
+        &mut self,
+        vk_device: &vulkanalia::Device
+    ) {
+        for mut page in self.vertex_pages.drain(..).flatten() {
+            page.buffer.destroy(vk_device);
+        }
+        for mut page in self.index_pages.drain(..).flatten() {
+            page.buffer.destroy(vk_device);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::window::vulkan::vulkan_buffer::VulkanBuffer;
+
+    use super::{MeshPool, PoolAllocation, PoolPage};
+
+    #[test]
+    fn allocations_are_aligned() {
+        let mut page = PoolPage::new(VulkanBuffer::empty(), 64);
+        assert_eq!(page.allocate(6, 4), Some(0));
+        // the allocation after the first one is pushed to the next multiple of the alignment
+        assert_eq!(page.allocate(12, 12), Some(12));
+        // the padding before the aligned offset stays free
+        assert_eq!(page.free_ranges, vec![(6, 6), (24, 40)]);
+        assert_eq!(page.allocate(4, 2), Some(6));
+        assert_eq!(page.allocate(48, 4), None);
+    }
+
+    #[test]
+    fn freed_ranges_are_merged() {
+        let mut page = PoolPage::new(VulkanBuffer::empty(), 30);
+        let first = page.allocate(10, 1).unwrap();
+        let second = page.allocate(10, 1).unwrap();
+        let third = page.allocate(10, 1).unwrap();
+        assert!(page.free_ranges.is_empty());
+
+        page.free(first, 10);
+        page.free(third, 10);
+        assert_eq!(page.free_ranges, vec![(0, 10), (20, 10)]);
+        // freeing the middle range merges it with both neighbours
+        page.free(second, 10);
+        assert_eq!(page.free_ranges, vec![(0, 30)]);
+        assert!(page.is_empty());
+    }
+
+    #[test]
+    fn only_empty_dedicated_pages_are_released() {
+        let mut pool = MeshPool::new();
+        pool.vertex_pages.push(Some(PoolPage::new(VulkanBuffer::empty(), 16)));
+        pool.vertex_pages.push(Some(PoolPage::new(VulkanBuffer::empty(), 64)));
+        let shared = PoolAllocation { page: 0, offset: 0, size: 8 };
+        let first = PoolAllocation { page: 1, offset: 0, size: 32 };
+        let second = PoolAllocation { page: 1, offset: 32, size: 32 };
+        for allocation in [shared, first, second] {
+            let page = pool.vertex_pages[allocation.page].as_mut().unwrap();
+            assert_eq!(page.allocate(allocation.size, 1), Some(allocation.offset));
+        }
+
+        // pages of the pool size are kept for the next meshes
+        assert!(MeshPool::free_allocation(&mut pool.vertex_pages, shared, 16).is_none());
+        assert!(pool.vertex_buffer(&shared).is_some());
+
+        // a dedicated page is released with its last allocation
+        assert!(MeshPool::free_allocation(&mut pool.vertex_pages, first, 16).is_none());
+        assert!(MeshPool::free_allocation(&mut pool.vertex_pages, second, 16).is_some());
+        assert!(pool.vertex_buffer(&second).is_none());
+        assert!(MeshPool::free_allocation(&mut pool.vertex_pages, second, 16).is_none());
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+
+use super::{
+    mesh_library::{LoadedMesh, MeshLibrary, dynamic_mesh::DynamicMesh},
+    texture_library::{LoadedTexture, TextureLibrary, cubemap::LoadedCubemap},
+};
+
+/// A gpu resource that have been unregistered from its library, but might still be in use by the gpu.
+pub enum ReleasedResource {
+    Mesh(LoadedMesh),
+    DynamicMesh(DynamicMesh),
+    /// A texture with the index it was using in the texture library.
+    Texture(u32, LoadedTexture),
+    Cubemap(LoadedCubemap),
+}
+
+impl ReleasedResource {
+    fn destroy(
+        self,
+        vk_device: &vulkanalia::Device,
+        meshes: &mut MeshLibrary,
+        textures: &mut TextureLibrary,
+    ) {
+        match self {
+            ReleasedResource::Mesh(mesh) => meshes.destroy_released_mesh(mesh, vk_device),
+            ReleasedResource::DynamicMesh(mesh) => mesh.destroy(vk_device),
+            ReleasedResource::Texture(index, mut texture) => {
+                texture.destroy(vk_device);
+                // the texture can't be referenced by the gpu anymore, the index can be given to another texture
+                textures.free_texture_index(index);
+            }
+            ReleasedResource::Cubemap(mut cubemap) => cubemap.destroy(vk_device),
+        }
+    }
+}
+
+/// Resources released at the same time.
+struct ReleaseBatch<R> {
+    resources: Vec<R>,
+    /// For each swapchain image, whether it stopped using the resources.
+    released_images: Vec<bool>,
+}
+
+/// Queue of released resources waiting to be destroyed.
+/// Command buffers of every swapchain image can reference released resources until they are recorded again,
+/// so resources are only destroyed once every image have been synced after the release.
+pub struct ReleaseQueue<R = ReleasedResource> {
+    batches: VecDeque<ReleaseBatch<R>>,
+}
+
+impl<R> ReleaseQueue<R> {
+    pub fn new() -> ReleaseQueue<R> {
+        ReleaseQueue {
+            batches: VecDeque::new(),
+        }
+    }
+
+    /// Add resources to destroy once every swapchain image stopped using them.
+    pub fn push(&mut self, resources: Vec<R>, image_count: usize) {
+        if resources.is_empty() {
+            return;
+        }
+        self.batches.push_back(ReleaseBatch {
+            resources,
+            released_images: vec![false; image_count],
+        });
+    }
+
+    /// Mark the given image as no longer using the released resources,
+    /// and take the resources that are not used by any image anymore.
+    fn release_image(&mut self, image_index: usize) -> Vec<R> {
+        for batch in self.batches.iter_mut() {
+            if let Some(released) = batch.released_images.get_mut(image_index) {
+                *released = true;
+            }
+        }
+        let mut unused = Vec::new();
+        while let Some(batch) = self.batches.front() {
+            if batch.released_images.iter().all(|released| *released) {
+                if let Some(batch) = self.batches.pop_front() {
+                    unused.extend(batch.resources);
+                }
+            } else {
+                break;
+            }
+        }
+        unused
+    }
+}
+
+impl ReleaseQueue {
+    /// Mark the given image as no longer using the released resources, and destroy the resources
+    /// that are not used by any image anymore.
+    /// The image must not be in use by the gpu, and its command buffer must be recorded again before being submitted.
+    pub fn sync_image(
+        &mut self,
+        image_index: usize,
+        vk_device: &vulkanalia::Device,
+        meshes: &mut MeshLibrary,
+        textures: &mut TextureLibrary,
+    ) {
+        for resource in self.release_image(image_index).into_iter() {
+            resource.destroy(vk_device, meshes, textures);
+        }
+    }
+
+    /// Destroy all the released resources, without waiting. The device must be idle.
+    pub fn destroy(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        meshes: &mut MeshLibrary,
+        textures: &mut TextureLibrary,
+    ) {
+        for batch in self.batches.drain(..) {
+            for resource in batch.resources.into_iter() {
+                resource.destroy(vk_device, meshes, textures);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::ReleaseQueue;
+
+    #[test]
+    fn resources_are_released_once_every_image_is_synced() {
+        let mut queue = ReleaseQueue::new();
+        queue.push(vec![1, 2], 3);
+        assert!(queue.release_image(0).is_empty());
+        // syncing the same image again does not count as another image
+        assert!(queue.release_image(0).is_empty());
+        assert!(queue.release_image(2).is_empty());
+        assert_eq!(queue.release_image(1), vec![1, 2]);
+        assert!(queue.release_image(1).is_empty());
+    }
+
+    #[test]
+    fn later_batches_wait_for_their_own_syncs() {
+        let mut queue = ReleaseQueue::new();
+        queue.push(vec![1], 2);
+        assert!(queue.release_image(0).is_empty());
+        // released after image 0 got synced, so it waits for image 0 again
+        queue.push(vec![2], 2);
+        assert_eq!(queue.release_image(1), vec![1]);
+        assert!(queue.release_image(1).is_empty());
+        assert_eq!(queue.release_image(0), vec![2]);
+    }
+
+    #[test]
+    fn batches_are_released_in_order() {
+        let mut queue = ReleaseQueue::new();
+        queue.push(vec![1], 2);
+        queue.push(vec![2], 2);
+        assert!(queue.release_image(0).is_empty());
+        assert_eq!(queue.release_image(1), vec![1, 2]);
+    }
+
+    #[test]
+    fn empty_releases_are_ignored() {
+        let mut queue = ReleaseQueue::<u32>::new();
+        queue.push(Vec::new(), 2);
+        assert!(queue.batches.is_empty());
+    }
+}
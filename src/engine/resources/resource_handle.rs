@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A reference counted handle on a resource of a library.
+/// While handles on a resource are alive, the resource stays loaded.
+/// Once every handle is dropped, the resource is unloaded by the library.
+/// Resources that never got a handle are only unloaded when explicitly unregistered.
+#[derive(Debug, Clone)]
+pub struct ResourceHandle {
+    id: u64,
+    references: Arc<()>,
+}
+
+impl ResourceHandle {
+    pub(crate) fn new(id: u64, references: Arc<()>) -> ResourceHandle {
+        ResourceHandle {
+            id,
+            references,
+        }
+    }
+
+    /// The id of the resource in its library.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Number of handles on this resource that are alive.
+    pub fn reference_count(&self) -> usize {
+        // the library is keeping a reference as well
+        Arc::strong_count(&self.references) - 1
+    }
+}
+
+/// Keep track of the handles given for the resources of a library.
+#[derive(Debug)]
+pub(crate) struct HandleTracker {
+    references: HashMap<u64, Arc<()>>,
+}
+
+impl HandleTracker {
+    pub fn new() -> HandleTracker {
+        HandleTracker {
+            references: HashMap::new(),
+        }
+    }
+
+    /// Create a new handle on the given resource.
+    pub fn handle(&mut self, id: u64) -> ResourceHandle {
+        let references = self.references.entry(id).or_insert_with(|| Arc::new(()));
+        ResourceHandle::new(id, references.clone())
+    }
+
+    /// Stop tracking a resource, usually because it got unregistered.
+    pub fn remove(&mut self, id: u64) {
+        self.references.remove(&id);
+    }
+
+    /// Remove and return the ids of the resources that no longer have any handle.
+    pub fn drain_unused(&mut self) -> Vec<u64> {
+        let unused = self.references.iter()
+            .filter(|(_, references)| Arc::strong_count(references) == 1)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in unused.iter() {
+            self.references.remove(id);
+        }
+        unused
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::HandleTracker;
+
+    #[test]
+    fn handles_count_their_references() {
+        let mut tracker = HandleTracker::new();
+        let first = tracker.handle(1);
+        assert_eq!(first.id(), 1);
+        assert_eq!(first.reference_count(), 1);
+        let second = tracker.handle(1);
+        let third = first.clone();
+        assert_eq!(first.reference_count(), 3);
+        drop(second);
+        drop(third);
+        assert_eq!(first.reference_count(), 1);
+    }
+
+    #[test]
+    fn resources_are_unused_once_every_handle_is_dropped() {
+        let mut tracker = HandleTracker::new();
+        let first = tracker.handle(1);
+        let second = tracker.handle(2);
+        let cloned = second.clone();
+        assert!(tracker.drain_unused().is_empty());
+        drop(first);
+        drop(second);
+        assert_eq!(tracker.drain_unused(), vec![1]);
+        // unused resources are only returned once
+        assert!(tracker.drain_unused().is_empty());
+        drop(cloned);
+        assert_eq!(tracker.drain_unused(), vec![2]);
+    }
+
+    #[test]
+    fn removed_resources_are_not_tracked() {
+        let mut tracker = HandleTracker::new();
+        let handle = tracker.handle(1);
+        tracker.remove(1);
+        assert_eq!(handle.reference_count(), 0);
+        drop(handle);
+        assert!(tracker.drain_unused().is_empty());
+    }
+}
@@ -2,9 +2,16 @@ use std::collections::{HashMap, BTreeMap};
 
 use image::{ImageBuffer, Rgba};
 
+use self::cubemap::{Cubemap, LoadedCubemap};
+
 use crate::{
     engine::{
         errors::PResult,
+        consts::PROPELLANT_DEBUG_FEATURES,
+        resources::{
+            release_queue::ReleasedResource,
+            resource_handle::{HandleTracker, ResourceHandle},
+        },
         window::vulkan::{
             transfer_command_manager::TransferCommandManager,
             vulkan_buffer::VulkanBuffer,
@@ -17,6 +24,26 @@ use crate::{
 use vulkanalia::vk::HasBuilder;
 use vulkanalia::vk::DeviceV1_0;
 
+pub(crate) mod cubemap;
+
+/// How the texture colors are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureColorSpace {
+    /// Colors are srgb encoded, and converted to linear when sampled. This is what color maps use.
+    Srgb,
+    /// Values are sampled as they are stored. This is what data maps (normals, roughness, occlusion) use.
+    Linear,
+}
+
+impl TextureColorSpace {
+    pub fn vk_format(&self) -> vulkanalia::vk::Format {
+        match self {
+            TextureColorSpace::Srgb => vulkanalia::vk::Format::R8G8B8A8_SRGB,
+            TextureColorSpace::Linear => vulkanalia::vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
 /// A texture allocated on the gpu for easy access.
 pub struct LoadedTexture {
     /// The texture buffer on the gpu.
@@ -30,6 +57,7 @@ pub struct LoadedTexture {
 impl LoadedTexture {
     pub fn create(
         from: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        color_space: TextureColorSpace,
         vk_instance: &vulkanalia::Instance,
         vk_device: &vulkanalia::Device,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
@@ -57,7 +85,7 @@ impl LoadedTexture {
             from.width(),
             from.height(),
             vulkanalia::vk::ImageUsageFlags::SAMPLED | vulkanalia::vk::ImageUsageFlags::TRANSFER_DST,
-            vulkanalia::vk::Format::R8G8B8A8_SRGB,
+            color_space.vk_format(),
         )?;
 
         // ask the transfer manager to send the buffer to the image object
@@ -74,7 +102,7 @@ impl LoadedTexture {
         let view = create_image_view(
             vk_device,
             &texture,
-            vulkanalia::vk::Format::R8G8B8A8_SRGB,
+            color_space.vk_format(),
             vulkanalia::vk::ImageAspectFlags::COLOR,
         )?;
 
@@ -132,23 +160,64 @@ impl LoadedTexture {
 
 
 pub struct TextureLibrary {
-    /// The texture hash id, mapped to the texture raw bytes and it's index in the texture buffer.
-    loading_queue: HashMap<u64, (ImageBuffer<Rgba<u8>, Vec<u8>>, u32)>,
-    /// The texture index, mapped to the texture id and object.
-    textures: BTreeMap<u32, (u64, LoadedTexture)>,
+    /// The texture hash id, mapped to the texture raw bytes, it's index in the texture buffer and its color space.
+    loading_queue: HashMap<u64, (ImageBuffer<Rgba<u8>, Vec<u8>>, u32, TextureColorSpace)>,
+    /// The texture index, mapped to the texture id, the generation of the index and the texture object.
+    /// The generation is changed every time a texture is loaded, so users of the index can know when it was recycled.
+    textures: BTreeMap<u32, (u64, u64, LoadedTexture)>,
     next_texture_index: u32,
+    /// Indices of unloaded textures, that can be given to new textures.
+    free_indices: Vec<u32>,
+    next_generation: u64,
+    /// Handles given on the textures, to unload them once they are not used anymore.
+    handles: HandleTracker,
+    /// Unregistered textures, waiting to be destroyed once the gpu is done with them.
+    released: Vec<(u32, LoadedTexture)>,
+    /// The cubemap hash id, mapped to the prefiltered cubemap waiting to be loaded.
+    cubemap_loading_queue: HashMap<u64, Cubemap>,
+    cubemaps: HashMap<u64, LoadedCubemap>,
+    /// Unregistered cubemaps, waiting to be destroyed once the gpu is done with them.
+    released_cubemaps: Vec<LoadedCubemap>,
+    /// The cubemap used as the environment of the scene.
+    environment: u64,
+    /// Whether the environment changed since the pipelines were last told about it.
+    environment_changed: bool,
 }
 
 impl TextureLibrary {
     pub fn new() -> TextureLibrary {
         
         let mut loading_queue = HashMap::new();
-        loading_queue.insert(id("white"), (Self::create_white_textures(), 0));
+        loading_queue.insert(id("white"), (Self::create_white_textures(), 0, TextureColorSpace::Srgb));
+
+        let mut cubemap_loading_queue = HashMap::new();
+        cubemap_loading_queue.insert(id("black-environment"), Cubemap::black());
 
         TextureLibrary {
             loading_queue,
             textures: BTreeMap::new(),
             next_texture_index: 1, // 0 is for the white texture
+            free_indices: Vec::new(),
+            next_generation: 0,
+            handles: HandleTracker::new(),
+            released: Vec::new(),
+            cubemap_loading_queue,
+            cubemaps: HashMap::new(),
+            released_cubemaps: Vec::new(),
+            environment: id("black-environment"),
+            environment_changed: false,
+        }
+    }
+
+    /// Get an index for a new texture, reusing the indices of unloaded textures first.
+    fn next_index(&mut self) -> u32 {
+        match self.free_indices.pop() {
+            Some(index) => index,
+            None => {
+                let index = self.next_texture_index;
+                self.next_texture_index += 1;
+                index
+            }
         }
     }
 
@@ -158,19 +227,180 @@ impl TextureLibrary {
     /// This operation might fail if the bytes are not a valid image.
     /// This will return the texture index, so it can then be used by a material to reference it.
     pub fn register_texture(&mut self, texture_id: u64, bytes: &[u8]) -> PResult<u32> {
-        let index = self.next_texture_index;
-        self.next_texture_index += 1;
-        self.loading_queue.insert(texture_id, (image::load_from_memory(bytes)?.to_rgba8(), index));
-        Ok(index)
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        self.register_built_texture_with_color_space(texture_id, image, TextureColorSpace::Srgb)
+    }
+
+    /// Register a texture that holds data rather than colors, such as normal, roughness or occlusion maps.
+    /// It is sampled as stored, without srgb conversion.
+    pub fn register_linear_texture(&mut self, texture_id: u64, bytes: &[u8]) -> PResult<u32> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        self.register_built_texture_with_color_space(texture_id, image, TextureColorSpace::Linear)
     }
 
     pub fn register_built_texture(&mut self, texture_id: u64, texture: ImageBuffer<Rgba<u8>, Vec<u8>>) -> PResult<u32> {
-        let index = self.next_texture_index;
-        self.next_texture_index += 1;
-        self.loading_queue.insert(texture_id, (texture, index));
+        self.register_built_texture_with_color_space(texture_id, texture, TextureColorSpace::Srgb)
+    }
+
+    pub fn register_built_texture_with_color_space(
+        &mut self,
+        texture_id: u64,
+        texture: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        color_space: TextureColorSpace,
+    ) -> PResult<u32> {
+        let index = self.next_index();
+        self.loading_queue.insert(texture_id, (texture, index, color_space));
         Ok(index)
     }
 
+    /// Register a cubemap from its six faces, in the order +X, -X, +Y, -Y, +Z, -Z.
+    /// The faces must be square images of the same size. Their colors are srgb encoded,
+    /// except for high dynamic range images (such as .hdr files) which are read as linear.
+    /// The cubemap is prefiltered for image based lighting when registered, which takes a moment for big faces.
+    /// It will be loaded in memory the next time the textures are loaded.
+    pub fn register_cubemap(&mut self, cubemap_id: u64, faces: [&[u8]; 6]) -> PResult<()> {
+        let faces = [
+            Self::decode_linear(faces[0])?,
+            Self::decode_linear(faces[1])?,
+            Self::decode_linear(faces[2])?,
+            Self::decode_linear(faces[3])?,
+            Self::decode_linear(faces[4])?,
+            Self::decode_linear(faces[5])?,
+        ];
+        self.cubemap_loading_queue.insert(cubemap_id, Cubemap::from_faces(faces)?);
+        Ok(())
+    }
+
+    /// Register a cubemap from a panorama in equirectangular projection, such as a .hdr environment.
+    /// The center of the panorama faces -Z. The faces of the cubemap are `face_size` texels wide.
+    pub fn register_equirectangular_cubemap(&mut self, cubemap_id: u64, bytes: &[u8], face_size: u32) -> PResult<()> {
+        let panorama = Self::decode_linear(bytes)?;
+        self.cubemap_loading_queue.insert(cubemap_id, Cubemap::from_equirectangular(&panorama, face_size)?);
+        Ok(())
+    }
+
+    /// Decode an image to linear colors. High dynamic range images are already linear, others are srgb encoded.
+    fn decode_linear(bytes: &[u8]) -> PResult<image::Rgb32FImage> {
+        let image = image::load_from_memory(bytes)?;
+        let linear = match image {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => image.to_rgb32f(),
+            _ => {
+                let mut linear = image.to_rgb32f();
+                for channel in linear.iter_mut() {
+                    *channel = match *channel <= 0.04045 {
+                        true => *channel / 12.92,
+                        false => ((*channel + 0.055) / 1.055).powf(2.4),
+                    };
+                }
+                linear
+            }
+        };
+        Ok(linear)
+    }
+
+    /// Use a cubemap as the environment of the scene: the skybox draws it, and the lit pipelines get their ambient light from it.
+    /// The environment is bound to the pipelines on the next frame, or once the cubemap is loaded if it is not yet.
+    pub fn set_environment(&mut self, cubemap_id: u64) {
+        self.environment = cubemap_id;
+        self.environment_changed = true;
+    }
+
+    /// Go back to a black environment, where the ambient light only comes from the ambiant color of the main light.
+    pub fn clear_environment(&mut self) {
+        self.environment = id("black-environment");
+        self.environment_changed = true;
+    }
+
+    /// Whether the environment changed since the last call, meaning the textures of the pipelines must be reloaded.
+    pub(crate) fn take_environment_changed(&mut self) -> bool {
+        std::mem::take(&mut self.environment_changed)
+    }
+
+    /// Remove a cubemap from the library. If it was the environment, the environment is cleared.
+    /// The gpu memory is freed once no frame in flight uses the cubemap anymore.
+    pub fn unregister_cubemap(&mut self, cubemap_id: u64) {
+        if cubemap_id == id("black-environment") {
+            if PROPELLANT_DEBUG_FEATURES {
+                println!("[PROPELLANT DEBUG] Attempt to unregister the black environment, which is used as the default environment.");
+            }
+            return;
+        }
+        if self.environment == cubemap_id {
+            self.clear_environment();
+        }
+        self.cubemap_loading_queue.remove(&cubemap_id);
+        if let Some(cubemap) = self.cubemaps.remove(&cubemap_id) {
+            self.released_cubemaps.push(cubemap);
+        }
+    }
+
+    /// The loaded cubemap of the environment, or the black environment if it is not loaded.
+    pub fn environment(&self) -> Option<&LoadedCubemap> {
+        self.cubemaps.get(&self.environment).or_else(|| self.cubemaps.get(&id("black-environment")))
+    }
+
+    /// Get a reference counted handle on a texture.
+    /// Once a handle have been created for a texture, the texture is unloaded when all its handles are dropped.
+    pub fn texture_handle(&mut self, texture_id: u64) -> ResourceHandle {
+        self.handles.handle(texture_id)
+    }
+
+    /// Get the index of a registered texture.
+    pub fn texture_index(&self, texture_id: u64) -> Option<u32> {
+        match self.loading_queue.get(&texture_id) {
+            Some((_, index, _)) => Some(*index),
+            None => self.textures.iter()
+                .find(|(_, (id, _, _))| *id == texture_id)
+                .map(|(index, _)| *index),
+        }
+    }
+
+    /// Remove a texture from the library.
+    /// The gpu memory is freed once no frame in flight uses the texture anymore,
+    /// and its index is then recycled for new textures.
+    /// Until the index is recycled, its slot in the pipelines samples the white texture.
+    /// Materials still using this texture index will sample whatever texture gets the index next.
+    pub fn unregister_texture(&mut self, texture_id: u64) {
+        if texture_id == id("white") {
+            if PROPELLANT_DEBUG_FEATURES {
+                println!("[PROPELLANT DEBUG] Attempt to unregister the white texture, which is used as the default texture.");
+            }
+            return;
+        }
+        self.handles.remove(texture_id);
+        // the texture was not loaded yet, the index can be reused right away
+        if let Some((_, index, _)) = self.loading_queue.remove(&texture_id) {
+            self.free_indices.push(index);
+        }
+        let index = self.textures.iter()
+            .find(|(_, (id, _, _))| *id == texture_id)
+            .map(|(index, _)| *index);
+        if let Some(index) = index {
+            if let Some((_, _, texture)) = self.textures.remove(&index) {
+                self.released.push((index, texture));
+            }
+        }
+    }
+
+    /// Unregister the textures that had handles, but for which all handles have been dropped.
+    pub fn unregister_unused_textures(&mut self) {
+        for texture_id in self.handles.drain_unused().into_iter() {
+            self.unregister_texture(texture_id);
+        }
+    }
+
+    /// Take the textures and cubemaps that have been unregistered since the last call.
+    pub fn drain_released(&mut self) -> Vec<ReleasedResource> {
+        self.released.drain(..).map(|(index, texture)| ReleasedResource::Texture(index, texture))
+            .chain(self.released_cubemaps.drain(..).map(ReleasedResource::Cubemap))
+            .collect()
+    }
+
+    /// Give back the index of a destroyed texture, so it can be used by new textures.
+    pub(crate) fn free_texture_index(&mut self, index: u32) {
+        self.free_indices.push(index);
+    }
+
     pub fn load_textures(
         &mut self,
         vk_instance: &vulkanalia::Instance,
@@ -178,15 +408,30 @@ impl TextureLibrary {
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
         vk_transfer_manager: &mut TransferCommandManager,
     ) -> PResult<()> {
-        for (id, (bytes, index)) in self.loading_queue.drain() {
+        for (id, (bytes, index, color_space)) in self.loading_queue.drain() {
             let loaded_texture = LoadedTexture::create(
                 bytes,
+                color_space,
                 vk_instance,
                 vk_device,
                 vk_physical_device,
                 vk_transfer_manager,
             )?;
-            self.textures.insert(index, (id, loaded_texture));
+            self.textures.insert(index, (id, self.next_generation, loaded_texture));
+            self.next_generation += 1;
+        }
+        for (id, cubemap) in self.cubemap_loading_queue.drain() {
+            let loaded_cubemap = LoadedCubemap::create(
+                cubemap,
+                vk_instance,
+                vk_device,
+                vk_physical_device,
+                vk_transfer_manager,
+            )?;
+            // a cubemap registered again replaces the previous one.
+            if let Some(previous) = self.cubemaps.insert(id, loaded_cubemap) {
+                self.released_cubemaps.push(previous);
+            }
         }
         Ok(())
     }
@@ -195,8 +440,14 @@ impl TextureLibrary {
         image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]))
     }
 
-    pub fn textures(&self) -> impl Iterator<Item = (u32, &LoadedTexture)> {
-        self.textures.iter().map(|(index, (_, texture))| (*index, texture))
+    /// Iterate over the loaded textures, with their index and the generation of the index.
+    pub fn textures(&self) -> impl Iterator<Item = (u32, u64, &LoadedTexture)> {
+        self.textures.iter().map(|(index, (_, generation, texture))| (*index, *generation, texture))
+    }
+
+    /// The white texture at index 0, written in the slots of unloaded textures.
+    pub fn fallback_texture(&self) -> Option<&LoadedTexture> {
+        self.textures.get(&0).map(|(_, _, texture)| texture)
     }
 
     pub fn max_index(&self) -> u32 {
@@ -207,9 +458,40 @@ impl TextureLibrary {
         &mut self,
         vk_device: &vulkanalia::Device
     ) {
-        for (_, (_, texture)) in self.textures.iter_mut() {
+        for (_, (_, _, texture)) in self.textures.iter_mut() {
             texture.destroy(vk_device);
         }
         self.textures.clear();
+        for (_, mut texture) in self.released.drain(..) {
+            texture.destroy(vk_device);
+        }
+        for (_, cubemap) in self.cubemaps.iter_mut() {
+            cubemap.destroy(vk_device);
+        }
+        self.cubemaps.clear();
+        for mut cubemap in self.released_cubemaps.drain(..) {
+            cubemap.destroy(vk_device);
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::id;
+
+    use super::TextureLibrary;
+
+    #[test]
+    fn environment_changes_are_reported_once() {
+        let mut textures = TextureLibrary::new();
+        assert!(!textures.take_environment_changed());
+        textures.set_environment(id("sky"));
+        assert!(textures.take_environment_changed());
+        assert!(!textures.take_environment_changed());
+        // unregistering the environment goes back to the black environment.
+        textures.unregister_cubemap(id("sky"));
+        assert!(textures.take_environment_changed());
+        textures.unregister_cubemap(id("sky"));
+        assert!(!textures.take_environment_changed());
+    }
+}
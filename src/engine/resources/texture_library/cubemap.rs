@@ -0,0 +1,611 @@
+use crate::engine::{
+    errors::{PResult, PropellantError, loading_errors::LoadingError},
+    window::vulkan::{
+        transfer_command_manager::TransferCommandManager,
+        vulkan_buffer::VulkanBuffer,
+        vulkan_image::{VulkanImage, vulkan_image_view::create_cube_image_view},
+    },
+};
+
+use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::DeviceV1_0;
+
+/// Cubemaps are stored in half floats, to keep the range of high dynamic range environments.
+const CUBEMAP_FORMAT: vulkanalia::vk::Format = vulkanalia::vk::Format::R16G16B16A16_SFLOAT;
+/// Number of levels of the specular map, from a mirror reflection to a fully rough one.
+const SPECULAR_LEVEL_COUNT: u32 = 6;
+/// Samples taken for each texel of the specular levels.
+const SPECULAR_SAMPLE_COUNT: u32 = 32;
+/// Irradiance varies slowly with the normal, small faces are enough.
+const IRRADIANCE_SIZE: u32 = 16;
+/// Faces size of the level the irradiance is projected from.
+const IRRADIANCE_SOURCE_SIZE: u32 = 64;
+
+/// One level of a cubemap: the six faces in the order of the image layers, +X, -X, +Y, -Y, +Z, -Z.
+#[derive(Debug, Clone)]
+pub struct CubemapLevel {
+    size: u32,
+    /// linear colors, face after face, row after row.
+    texels: Vec<glam::Vec3>,
+}
+
+impl CubemapLevel {
+    /// Build a level from the color seen in each direction.
+    fn from_fn<F: Fn(glam::Vec3) -> glam::Vec3>(size: u32, color: F) -> CubemapLevel {
+        let mut texels = Vec::with_capacity(6 * (size * size) as usize);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let s = (x as f32 + 0.5) / size as f32;
+                    let t = (y as f32 + 0.5) / size as f32;
+                    texels.push(color(Self::direction(face, s, t)));
+                }
+            }
+        }
+        CubemapLevel { size, texels }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Direction pointed by coordinates in [0, 1] on a face, as vulkan samples cubemaps.
+    fn direction(face: usize, s: f32, t: f32) -> glam::Vec3 {
+        let u = 2.0 * s - 1.0;
+        let v = 2.0 * t - 1.0;
+        match face {
+            0 => glam::Vec3::new(1.0, -v, -u),
+            1 => glam::Vec3::new(-1.0, -v, u),
+            2 => glam::Vec3::new(u, 1.0, v),
+            3 => glam::Vec3::new(u, -1.0, -v),
+            4 => glam::Vec3::new(u, -v, 1.0),
+            _ => glam::Vec3::new(-u, -v, -1.0),
+        }.normalize()
+    }
+
+    /// Face and coordinates in [0, 1] on the face seen in a direction.
+    fn face_coordinates(direction: glam::Vec3) -> (usize, f32, f32) {
+        let abs = direction.abs();
+        let (face, sc, tc, major) = if abs.x >= abs.y && abs.x >= abs.z {
+            match direction.x > 0.0 {
+                true => (0, -direction.z, -direction.y, abs.x),
+                false => (1, direction.z, -direction.y, abs.x),
+            }
+        } else if abs.y >= abs.z {
+            match direction.y > 0.0 {
+                true => (2, direction.x, direction.z, abs.y),
+                false => (3, direction.x, -direction.z, abs.y),
+            }
+        } else {
+            match direction.z > 0.0 {
+                true => (4, direction.x, -direction.y, abs.z),
+                false => (5, -direction.x, -direction.y, abs.z),
+            }
+        };
+        (face, 0.5 * (sc / major + 1.0), 0.5 * (tc / major + 1.0))
+    }
+
+    fn texel(&self, face: usize, x: u32, y: u32) -> glam::Vec3 {
+        self.texels[face * (self.size * self.size) as usize + (y * self.size + x) as usize]
+    }
+
+    /// Bilinear sample of the color seen in a direction. The filtering is clamped to the edges of the faces.
+    fn sample(&self, direction: glam::Vec3) -> glam::Vec3 {
+        let (face, s, t) = Self::face_coordinates(direction);
+        let max = (self.size - 1) as f32;
+        let x = (s * self.size as f32 - 0.5).clamp(0.0, max);
+        let y = (t * self.size as f32 - 0.5).clamp(0.0, max);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let top = self.texel(face, x0, y0).lerp(self.texel(face, x1, y0), fx);
+        let bottom = self.texel(face, x0, y1).lerp(self.texel(face, x1, y1), fx);
+        top.lerp(bottom, fy)
+    }
+
+    /// Level of half the size, averaging the texels four by four.
+    fn downsample(&self) -> CubemapLevel {
+        let size = (self.size / 2).max(1);
+        let mut texels = Vec::with_capacity(6 * (size * size) as usize);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let (x0, y0) = ((2 * x).min(self.size - 1), (2 * y).min(self.size - 1));
+                    let (x1, y1) = ((2 * x + 1).min(self.size - 1), (2 * y + 1).min(self.size - 1));
+                    texels.push((
+                        self.texel(face, x0, y0) + self.texel(face, x1, y0) +
+                        self.texel(face, x0, y1) + self.texel(face, x1, y1)
+                    ) * 0.25);
+                }
+            }
+        }
+        CubemapLevel { size, texels }
+    }
+
+    /// Solid angle covered by a texel of a face.
+    fn texel_solid_angle(size: u32, x: u32, y: u32) -> f32 {
+        fn area(x: f32, y: f32) -> f32 {
+            (x * y).atan2((x * x + y * y + 1.0).sqrt())
+        }
+        let half_texel = 1.0 / size as f32;
+        let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+        let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+        let (u0, u1) = (u - half_texel, u + half_texel);
+        let (v0, v1) = (v - half_texel, v + half_texel);
+        area(u0, v0) - area(u0, v1) - area(u1, v0) + area(u1, v1)
+    }
+}
+
+/// Environment map, prefiltered for image based lighting.
+/// The specular levels hold the light reflected by surfaces of increasing roughness, the first level being the environment itself.
+/// The irradiance holds the light diffused by a white surface facing each direction.
+#[derive(Debug, Clone)]
+pub struct Cubemap {
+    specular: Vec<CubemapLevel>,
+    irradiance: CubemapLevel,
+}
+
+impl Cubemap {
+    /// Build a cubemap from six square faces of the same size, in linear colors.
+    pub fn from_faces(faces: [image::Rgb32FImage; 6]) -> PResult<Cubemap> {
+        let size = faces[0].width();
+        if size == 0 || faces.iter().any(|face| face.width() != size || face.height() != size) {
+            return Err(PropellantError::Loading(LoadingError::InvalidCubemap(
+                "the six faces must be square images of the same size".to_string()
+            )));
+        }
+        let mut texels = Vec::with_capacity(6 * (size * size) as usize);
+        for face in faces.iter() {
+            texels.extend(face.pixels().map(|pixel| glam::Vec3::from_array(pixel.0)));
+        }
+        Ok(Self::prefilter(CubemapLevel { size, texels }))
+    }
+
+    /// Build a cubemap from a panorama in equirectangular projection, in linear colors.
+    /// The center of the panorama faces -Z, and its top is +Y.
+    pub fn from_equirectangular(panorama: &image::Rgb32FImage, face_size: u32) -> PResult<Cubemap> {
+        if panorama.width() == 0 || panorama.height() == 0 || face_size == 0 {
+            return Err(PropellantError::Loading(LoadingError::InvalidCubemap(
+                "the panorama and the faces can't be empty".to_string()
+            )));
+        }
+        let (width, height) = (panorama.width(), panorama.height());
+        let texel = |x: i64, y: i64| {
+            // wrap around horizontally, clamp vertically.
+            let x = x.rem_euclid(width as i64) as u32;
+            let y = y.clamp(0, height as i64 - 1) as u32;
+            glam::Vec3::from_array(panorama.get_pixel(x, y).0)
+        };
+        let level = CubemapLevel::from_fn(face_size, |direction| {
+            let s = 0.5 + direction.x.atan2(-direction.z) / (2.0 * std::f32::consts::PI);
+            let t = direction.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+            let x = s * width as f32 - 0.5;
+            let y = t * height as f32 - 0.5;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+            let top = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+            let bottom = texel(x0, y0 + 1).lerp(texel(x0 + 1, y0 + 1), fx);
+            top.lerp(bottom, fy)
+        });
+        Ok(Self::prefilter(level))
+    }
+
+    /// Cubemap of a black environment, used when no environment is set.
+    pub fn black() -> Cubemap {
+        let black = CubemapLevel { size: 1, texels: vec![glam::Vec3::ZERO; 6] };
+        Cubemap {
+            specular: vec![black.clone()],
+            irradiance: black,
+        }
+    }
+
+    /// Compute the specular levels and the irradiance of an environment.
+    fn prefilter(source: CubemapLevel) -> Cubemap {
+        // the full mip chain of the source, to sample it without aliasing.
+        let mut chain = vec![source];
+        while chain[chain.len() - 1].size > 1 {
+            let next = chain[chain.len() - 1].downsample();
+            chain.push(next);
+        }
+
+        let level_count = SPECULAR_LEVEL_COUNT.min(chain.len() as u32);
+        let mut specular = vec![chain[0].clone()];
+        for level in 1..level_count {
+            let roughness = level as f32 / (level_count - 1) as f32;
+            specular.push(Self::prefilter_specular(&chain, chain[level as usize].size, roughness));
+        }
+
+        let irradiance = Self::prefilter_irradiance(&chain);
+
+        Cubemap { specular, irradiance }
+    }
+
+    /// Integrate the environment over the GGX lobe of the given roughness, assuming the view direction is the normal.
+    /// The samples are importance sampled, and read from the level of the source matching their footprint.
+    fn prefilter_specular(chain: &[CubemapLevel], size: u32, roughness: f32) -> CubemapLevel {
+        let alpha = roughness * roughness;
+        let alpha_squared = alpha * alpha;
+        let source_texel_solid_angle = 4.0 * std::f32::consts::PI / (6.0 * (chain[0].size * chain[0].size) as f32);
+        let max_level = (chain.len() - 1) as f32;
+
+        CubemapLevel::from_fn(size, |normal| {
+            let up = match normal.z.abs() < 0.999 {
+                true => glam::Vec3::Z,
+                false => glam::Vec3::X,
+            };
+            let tangent = up.cross(normal).normalize();
+            let bitangent = normal.cross(tangent);
+
+            let mut color = glam::Vec3::ZERO;
+            let mut total_weight = 0.0;
+            for i in 0..SPECULAR_SAMPLE_COUNT {
+                // hammersley sequence
+                let xi = glam::Vec2::new(
+                    i as f32 / SPECULAR_SAMPLE_COUNT as f32,
+                    i.reverse_bits() as f32 / 4294967296.0,
+                );
+                let phi = 2.0 * std::f32::consts::PI * xi.x;
+                let cos_theta = ((1.0 - xi.y) / (1.0 + (alpha_squared - 1.0) * xi.y)).sqrt();
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let half = (tangent * phi.cos() * sin_theta + bitangent * phi.sin() * sin_theta + normal * cos_theta).normalize();
+                let light = 2.0 * normal.dot(half) * half - normal;
+                let n_dot_l = normal.dot(light);
+                if n_dot_l <= 0.0 {
+                    continue;
+                }
+                // with the view along the normal, the pdf of the sample is D / 4.
+                let n_dot_h = normal.dot(half).max(0.0);
+                let d = n_dot_h * n_dot_h * (alpha_squared - 1.0) + 1.0;
+                let distribution = alpha_squared / (std::f32::consts::PI * d * d);
+                let sample_solid_angle = 4.0 / (SPECULAR_SAMPLE_COUNT as f32 * distribution + 0.0001);
+                let level = (0.5 * (sample_solid_angle / source_texel_solid_angle).log2() + 1.0).clamp(0.0, max_level);
+                let lower = level.floor();
+                let sample = match lower < max_level {
+                    true => chain[lower as usize].sample(light).lerp(chain[lower as usize + 1].sample(light), level - lower),
+                    false => chain[lower as usize].sample(light),
+                };
+                color += sample * n_dot_l;
+                total_weight += n_dot_l;
+            }
+            match total_weight > 0.0 {
+                true => color / total_weight,
+                false => chain[0].sample(normal),
+            }
+        })
+    }
+
+    /// Light diffused by a white lambertian surface facing each direction,
+    /// from the projection of the environment on the first nine spherical harmonics.
+    fn prefilter_irradiance(chain: &[CubemapLevel]) -> CubemapLevel {
+        let source = chain.iter()
+            .find(|level| level.size <= IRRADIANCE_SOURCE_SIZE)
+            .unwrap_or(&chain[chain.len() - 1]);
+
+        let mut coefficients = [glam::Vec3::ZERO; 9];
+        for face in 0..6 {
+            for y in 0..source.size {
+                for x in 0..source.size {
+                    let s = (x as f32 + 0.5) / source.size as f32;
+                    let t = (y as f32 + 0.5) / source.size as f32;
+                    let direction = CubemapLevel::direction(face, s, t);
+                    let weight = CubemapLevel::texel_solid_angle(source.size, x, y);
+                    let color = source.texel(face, x, y);
+                    for (coefficient, basis) in coefficients.iter_mut().zip(Self::sh_basis(direction)) {
+                        *coefficient += color * basis * weight;
+                    }
+                }
+            }
+        }
+
+        // convolution with the clamped cosine, divided by pi so a white surface under a uniform environment gets its color.
+        let bands = [1.0, 2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 0.25, 0.25, 0.25, 0.25, 0.25];
+        CubemapLevel::from_fn(IRRADIANCE_SIZE, |normal| {
+            coefficients.iter()
+                .zip(Self::sh_basis(normal))
+                .zip(bands)
+                .fold(glam::Vec3::ZERO, |sum, ((coefficient, basis), band)| sum + *coefficient * basis * band)
+                .max(glam::Vec3::ZERO)
+        })
+    }
+
+    /// The first nine real spherical harmonics, evaluated in a direction.
+    fn sh_basis(direction: glam::Vec3) -> [f32; 9] {
+        let glam::Vec3 { x, y, z } = direction;
+        [
+            0.282095,
+            0.488603 * y,
+            0.488603 * z,
+            0.488603 * x,
+            1.092548 * x * y,
+            1.092548 * y * z,
+            0.315392 * (3.0 * z * z - 1.0),
+            1.092548 * x * z,
+            0.546274 * (x * x - y * y),
+        ]
+    }
+
+    pub fn specular_levels(&self) -> &[CubemapLevel] {
+        &self.specular
+    }
+
+    pub fn irradiance(&self) -> &CubemapLevel {
+        &self.irradiance
+    }
+}
+
+/// Convert a float to the bits of a half float, rounding to the nearest.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // infinity or nan
+        return sign | 0x7c00 | match mantissa {
+            0 => 0,
+            _ => 0x200,
+        };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        // too big, infinity
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // subnormal half, or zero
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let round = (mantissa >> (shift - 1)) & 1;
+        sign | (half + round) as u16
+    } else {
+        let half = ((exponent as u32) << 10) | (mantissa >> 13);
+        let round = (mantissa >> 12) & 1;
+        // the rounding carry can overflow into the exponent, which is still the right result.
+        sign | (half + round) as u16
+    }
+}
+
+/// Cube image on the gpu, with a view on all its levels.
+struct CubemapImage {
+    image: VulkanImage,
+    view: vulkanalia::vk::ImageView,
+}
+
+impl CubemapImage {
+    fn create(
+        levels: &[CubemapLevel],
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        vk_transfer_manager: &mut TransferCommandManager,
+    ) -> PResult<CubemapImage> {
+        // levels are packed one after the other, each with its six faces.
+        let bytes = levels.iter()
+            .flat_map(|level| level.texels.iter())
+            .flat_map(|texel| [texel.x, texel.y, texel.z, 1.0])
+            .flat_map(|value| f32_to_f16(value).to_ne_bytes())
+            .collect::<Vec<u8>>();
+
+        let mut staging_buffer = VulkanBuffer::create(
+            vk_instance, vk_device, vk_physical_device,
+            bytes.len() as u64,
+            vulkanalia::vk::BufferUsageFlags::TRANSFER_SRC,
+            vulkanalia::vk::MemoryPropertyFlags::HOST_COHERENT | vulkanalia::vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+        staging_buffer.map_data(vk_device, &bytes, 0)?;
+
+        let size = levels[0].size;
+        let image = VulkanImage::create_cubemap(
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            size,
+            levels.len() as u32,
+            vulkanalia::vk::ImageUsageFlags::SAMPLED | vulkanalia::vk::ImageUsageFlags::TRANSFER_DST,
+            CUBEMAP_FORMAT,
+        )?;
+
+        vk_transfer_manager.register_cubemap_transfer(
+            vk_device,
+            staging_buffer,
+            image.image(),
+            size,
+            levels.len() as u32,
+        )?;
+
+        let view = create_cube_image_view(vk_device, &image, CUBEMAP_FORMAT, levels.len() as u32)?;
+
+        Ok(CubemapImage { image, view })
+    }
+
+    fn destroy(&mut self, vk_device: &vulkanalia::Device) {
+        unsafe {
+            vk_device.destroy_image_view(self.view, None);
+        }
+        self.image.destroy(vk_device);
+    }
+}
+
+/// A cubemap allocated on the gpu, with its specular and irradiance maps.
+pub struct LoadedCubemap {
+    specular: CubemapImage,
+    irradiance: CubemapImage,
+    /// Trilinear sampler, shared by both maps.
+    sampler: vulkanalia::vk::Sampler,
+}
+
+impl LoadedCubemap {
+    pub fn create(
+        from: Cubemap,
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        vk_transfer_manager: &mut TransferCommandManager,
+    ) -> PResult<LoadedCubemap> {
+        let specular = CubemapImage::create(
+            from.specular_levels(),
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            vk_transfer_manager,
+        )?;
+        let irradiance = CubemapImage::create(
+            std::slice::from_ref(from.irradiance()),
+            vk_instance,
+            vk_device,
+            vk_physical_device,
+            vk_transfer_manager,
+        )?;
+
+        // the roughness selects the level of the specular map, so the levels are blended.
+        let info = vulkanalia::vk::SamplerCreateInfo::builder()
+            .mag_filter(vulkanalia::vk::Filter::LINEAR)
+            .min_filter(vulkanalia::vk::Filter::LINEAR)
+            .address_mode_u(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vulkanalia::vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(vulkanalia::vk::BorderColor::FLOAT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vulkanalia::vk::CompareOp::ALWAYS)
+            .mipmap_mode(vulkanalia::vk::SamplerMipmapMode::LINEAR)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(SPECULAR_LEVEL_COUNT as f32);
+
+        let sampler = unsafe { vk_device.create_sampler(&info, None)? };
+
+        Ok(LoadedCubemap {
+            specular,
+            irradiance,
+            sampler,
+        })
+    }
+
+    pub fn specular_view(&self) -> vulkanalia::vk::ImageView {
+        self.specular.view
+    }
+
+    pub fn irradiance_view(&self) -> vulkanalia::vk::ImageView {
+        self.irradiance.view
+    }
+
+    pub fn sampler(&self) -> vulkanalia::vk::Sampler {
+        self.sampler
+    }
+
+    pub fn destroy(
+        &mut self,
+        vk_device: &vulkanalia::Device
+    ) {
+        unsafe {
+            vk_device.destroy_sampler(self.sampler, None);
+        }
+        self.specular.destroy(vk_device);
+        self.irradiance.destroy(vk_device);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Cubemap, CubemapLevel, f32_to_f16};
+
+    /// Decode the bits of a half float.
+    fn f16_to_f32(half: u16) -> f32 {
+        let sign = match half & 0x8000 {
+            0 => 1.0,
+            _ => -1.0,
+        };
+        let exponent = ((half >> 10) & 0x1f) as i32;
+        let mantissa = (half & 0x3ff) as f32;
+        match exponent {
+            0 => sign * mantissa * 2f32.powi(-24),
+            0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+            0x1f => f32::NAN,
+            _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    #[test]
+    fn half_floats_round_trip() {
+        // every half that is a number, including zeros, subnormals and infinities, is converted back to itself.
+        for half in 0..=u16::MAX {
+            let value = f16_to_f32(half);
+            if value.is_nan() {
+                continue;
+            }
+            assert_eq!(f32_to_f16(value), half, "{value} was converted to {:#06x} instead of {half:#06x}", f32_to_f16(value));
+        }
+        // nan stays nan.
+        let nan = f32_to_f16(f32::NAN);
+        assert_eq!(nan & 0x7c00, 0x7c00);
+        assert_ne!(nan & 0x3ff, 0);
+    }
+
+    #[test]
+    fn half_floats_are_rounded_and_clamped() {
+        // the smallest subnormal half is 2^-24, anything under half of it is zero.
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(2f32.powi(-26)), 0x0000);
+        assert_eq!(f32_to_f16(-2f32.powi(-26)), 0x8000);
+        // values are rounded to the nearest half.
+        assert_eq!(f32_to_f16(1.0 + 0.75 * 2f32.powi(-10)), 0x3c01);
+        assert_eq!(f32_to_f16(1.0 + 0.25 * 2f32.powi(-10)), 0x3c00);
+        // the biggest half is 65504, bigger values are infinite.
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(-1e6), 0xfc00);
+    }
+
+    #[test]
+    fn face_coordinates_match_the_directions() {
+        for face in 0..6 {
+            for (s, t) in [(0.5, 0.5), (0.1, 0.2), (0.9, 0.3), (0.25, 0.95), (0.7, 0.05)] {
+                let direction = CubemapLevel::direction(face, s, t);
+                let (found_face, found_s, found_t) = CubemapLevel::face_coordinates(direction);
+                assert_eq!(found_face, face, "face of {direction:?}");
+                assert!((found_s - s).abs() < 1e-5 && (found_t - t).abs() < 1e-5, "face {face}: ({s}, {t}) became ({found_s}, {found_t})");
+            }
+        }
+        // the faces are looked along their axis.
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::X), (0, 0.5, 0.5));
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::NEG_X), (1, 0.5, 0.5));
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::Y), (2, 0.5, 0.5));
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::NEG_Y), (3, 0.5, 0.5));
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::Z), (4, 0.5, 0.5));
+        assert_eq!(CubemapLevel::face_coordinates(glam::Vec3::NEG_Z), (5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn uniform_environment_irradiance_is_its_color() {
+        let color = [0.2, 0.5, 1.5];
+        let cubemap = Cubemap::from_faces(std::array::from_fn(|_| image::Rgb32FImage::from_pixel(8, 8, image::Rgb(color)))).unwrap();
+        let color = glam::Vec3::from_array(color);
+        for texel in cubemap.irradiance().texels.iter() {
+            assert!(texel.abs_diff_eq(color, 1e-2), "irradiance {texel:?}");
+        }
+        // every roughness reflects the same color.
+        assert_eq!(cubemap.specular_levels().len(), 4);
+        for level in cubemap.specular_levels() {
+            for texel in level.texels.iter() {
+                assert!(texel.abs_diff_eq(color, 1e-3), "specular {texel:?} of level of size {}", level.size());
+            }
+        }
+    }
+
+    #[test]
+    fn faces_must_be_squares_of_the_same_size() {
+        let face = |width, height| image::Rgb32FImage::from_pixel(width, height, image::Rgb([1.0, 1.0, 1.0]));
+        assert!(Cubemap::from_faces(std::array::from_fn(|_| face(4, 4))).is_ok());
+        assert!(Cubemap::from_faces(std::array::from_fn(|i| if i == 3 { face(2, 2) } else { face(4, 4) })).is_err());
+        assert!(Cubemap::from_faces(std::array::from_fn(|_| face(4, 2))).is_err());
+        assert!(Cubemap::from_faces(std::array::from_fn(|_| face(0, 0))).is_err());
+    }
+}
@@ -1,3 +1,10 @@
+use foundry::ComponentTable;
+
+use crate::{PropellantResources, UiTextRenderer};
+use crate::engine::consts::PROPELLANT_DEBUG_FEATURES;
+
+use super::ui_transform::UiTransform;
+
 
 #[derive(Debug, Clone, Copy)]
 pub struct UiResolution {
@@ -34,4 +41,33 @@ impl UiResolution {
     pub fn set_window_size(&mut self, new_size: glam::Vec2) {
         self.screen_size = new_size;
     }
+
+    /// Give the screen resolution to all the ui transforms, and lay out the texts again.
+    pub(crate) fn propagate(components: &mut ComponentTable) {
+        let resolution = match components.get_singleton::<UiResolution>() {
+            Some(res) => *res,
+            None => {
+                if PROPELLANT_DEBUG_FEATURES {
+                    println!("[PROPELLANT DEBUG] [UI] Ui require screen resolution flag set, but no existing screen resolution.");
+                }
+                UiResolution::default()
+            }
+        };
+        for (_, ui_tf) in components.query1d_mut::<UiTransform>() {
+            ui_tf.set_ui_resolution(resolution);
+        }
+        // temp: rebuild text here
+        let fonts = match components.get_singleton::<PropellantResources>() {
+            Some(resources) => resources.fonts().clone(),
+            None => return,
+        };
+        for (_, tf, tr) in components.query2d_mut::<UiTransform, UiTextRenderer>() {
+            match fonts.font(tr.font()) {
+                Some(font) => tr.rebuild_text(tf, font, resolution),
+                None => if PROPELLANT_DEBUG_FEATURES {
+                    println!("[PROPELLANT DEBUG] [UI] Text renderer font {} not found.", tr.font());
+                },
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use crate::{
 
 use self::vulkan::vulkan_interface::VulkanInterface;
 
-use super::{errors::PResult, renderer::VulkanRenderer};
+use super::{errors::PResult, renderer::{VulkanRenderer, screenshot::ScreenshotRequest}};
 
 
 #[derive(AsAny)]
@@ -16,6 +16,8 @@ pub struct PropellantWindow {
     window: winit::window::Window,
     #[cfg(feature = "vulkan-renderer")]
     renderer: Box<dyn VulkanRenderer>,
+    /// Key taking a screenshot, and the directory to save it in.
+    screenshot_hotkey: Option<(winit::event::VirtualKeyCode, std::path::PathBuf)>,
 }
 
 impl PropellantWindow {
@@ -24,6 +26,35 @@ impl PropellantWindow {
         self.window.request_redraw();
     }
 
+    /// Capture the next presented frame. The request is either a path to save a png at, or a callback receiving the pixels.
+    /// The frame is encoded on a background thread. Callbacks receive the error that prevented the capture,
+    /// while errors of file requests are only printed with the debug features.
+    pub fn request_screenshot(&mut self, request: impl Into<ScreenshotRequest>) {
+        self.renderer.request_screenshot(request.into());
+    }
+
+    /// Take a screenshot when the hotkey is pressed, named after the current time.
+    fn handle_screenshot_hotkey(&mut self, event: &winit::event::WindowEvent) {
+        let (hotkey, directory) = match &self.screenshot_hotkey {
+            Some(hotkey) => hotkey,
+            None => return,
+        };
+        match event {
+            winit::event::WindowEvent::KeyboardInput { input: winit::event::KeyboardInput {
+                virtual_keycode: Some(key),
+                state: winit::event::ElementState::Pressed,
+                ..
+            }, .. } if key == hotkey => {
+                let time = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                let path = directory.join(format!("screenshot_{}_{:03}.png", time.as_secs(), time.subsec_millis()));
+                self.request_screenshot(path);
+            },
+            _ => {},
+        }
+    }
+
     pub fn render(&mut self, components: &mut ComponentTable) {
         #[cfg(feature = "vulkan-renderer")]
         {
@@ -42,6 +73,8 @@ impl PropellantWindow {
     pub fn handle_event(&mut self, event: winit::event::WindowEvent, control_flow: &mut winit::event_loop::ControlFlow, components: &mut ComponentTable) {
         use crate::PropellantFlag;
 
+        self.handle_screenshot_hotkey(&event);
+
         match event {
             winit::event::WindowEvent::CloseRequested => control_flow.set_exit(),
             winit::event::WindowEvent::Resized(new_size) => {
@@ -73,6 +106,8 @@ impl PropellantWindow {
 
         use super::engine_events::PropellantEventSenderExt;
 
+        self.handle_screenshot_hotkey(&event);
+
         match event {
             winit::event::WindowEvent::CloseRequested => control_flow.set_exit(),
             winit::event::WindowEvent::Resized(new_size) => {
@@ -41,4 +41,17 @@ impl PhysicalDevicePreferences for DefaultPhysicalDevicePreferences {
         // could not sort them up, return equal
         std::cmp::Ordering::Equal
     }
-}
\ No newline at end of file
+}
+/// Only accepts software implementations of vulkan, such as lavapipe or swiftshader.
+/// Their rasterization does not depend on the gpu of the machine, which makes rendered images reproducible.
+pub struct SoftwarePhysicalDevicePreferences;
+
+impl PhysicalDevicePreferences for SoftwarePhysicalDevicePreferences {
+    fn is_device_compatible(&self, prop: PhysicalDeviceProperties, feat: PhysicalDeviceFeatures) -> bool {
+        prop.device_type == PhysicalDeviceType::CPU && DefaultPhysicalDevicePreferences.is_device_compatible(prop, feat)
+    }
+
+    fn order_devices(&self, _device1: (PhysicalDeviceProperties, PhysicalDeviceFeatures), _device2: (PhysicalDeviceProperties, PhysicalDeviceFeatures)) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
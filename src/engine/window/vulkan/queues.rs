@@ -1,6 +1,6 @@
 use crate::engine::errors::{PropellantError, PResult};
 use crate::engine::errors::rendering_error::RenderingError;
-use vulkanalia::vk::{InstanceV1_0, KhrSurfaceExtension};
+use vulkanalia::vk::{Handle, InstanceV1_0, KhrSurfaceExtension};
 
 /// This represent the index of a the queue family that we will be using.
 #[derive(Copy, Clone, Debug)]
@@ -12,6 +12,7 @@ pub struct QueueFamilyIndices(u32);
 impl QueueFamilyIndices {
     /// Finds a queue family that meet our needs, and return it's index under the form of a queue family.
     /// It can be done to look for different indices for graphics or presentation, but it does not change a lot and adds overhead.
+    /// Without surface (null handle), the engine is headless and any graphics queue will do.
     pub unsafe fn get(
         instance: &vulkanalia::Instance,
         physical_device: vulkanalia::vk::PhysicalDevice,
@@ -28,7 +29,7 @@ impl QueueFamilyIndices {
             // all our requiremenets here
             if
                 properties.queue_flags.contains(vulkanalia::vk::QueueFlags::GRAPHICS) &&
-                instance.get_physical_device_surface_support_khr(physical_device, index, surface)?
+                (surface.is_null() || instance.get_physical_device_surface_support_khr(physical_device, index, surface)?)
             {
                 return Ok(QueueFamilyIndices(index))
             }
@@ -12,13 +12,17 @@ use vulkanalia::vk::HasBuilder;
 pub struct SwapchainInterface {
     swapchain: vulkanalia::vk::SwapchainKHR,
     format: vulkanalia::vk::Format,
+    /// Color space the presentation engine reads the images in, hdr ones need their own encoding.
+    color_space: vulkanalia::vk::ColorSpaceKHR,
+    /// Whether the images can be copied from, to take screenshots.
+    readable: bool,
     extent: vulkanalia::vk::Extent2D,
     images: Vec<vulkanalia::vk::Image>,
     image_views: Vec<vulkanalia::vk::ImageView>,
 }
 
 impl SwapchainInterface {
-    /// Creates a new swapchain interface.
+    /// Creates a new swapchain interface, in a hdr color space when it is asked for and supported by the surface.
     pub fn create(
         vk_instance: &vulkanalia::Instance,
         window: &winit::window::Window,
@@ -26,10 +30,11 @@ impl SwapchainInterface {
         physical_device: vulkanalia::vk::PhysicalDevice,
         device: &vulkanalia::Device,
         indices: QueueFamilyIndices,
+        hdr_output: bool,
     ) -> PResult<SwapchainInterface> {
         let support = SwapchainSupport::get(vk_instance, physical_device, surface)?;
 
-        let format = support.format();
+        let format = support.format(hdr_output);
         let present_mode = support.present_mode();
         let extent = support.extent(window);
 
@@ -49,6 +54,13 @@ impl SwapchainInterface {
         let image_sharing_mode = vulkanalia::vk::SharingMode::EXCLUSIVE;
         let queue_family_indices = vec![indices.index()]; // todo ? is this correct ?
 
+        // screenshots copy the swapchain images, when the surface allows it.
+        let readable = support.capabilities().supported_usage_flags.contains(vulkanalia::vk::ImageUsageFlags::TRANSFER_SRC);
+        let image_usage = match readable {
+            true => vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT | vulkanalia::vk::ImageUsageFlags::TRANSFER_SRC,
+            false => vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        };
+
         let info = vulkanalia::vk::SwapchainCreateInfoKHR::builder()
             .surface(surface)
             .min_image_count(image_count)
@@ -56,7 +68,7 @@ impl SwapchainInterface {
             .image_color_space(format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(support.capabilities().current_transform)
@@ -76,6 +88,8 @@ impl SwapchainInterface {
         Ok(SwapchainInterface {
             swapchain,
             format: format.format,
+            color_space: format.color_space,
+            readable,
             extent,
             images,
             image_views,
@@ -92,11 +106,12 @@ impl SwapchainInterface {
         physical_device: vulkanalia::vk::PhysicalDevice,
         device: &vulkanalia::Device,
         indices: QueueFamilyIndices,
+        hdr_output: bool,
     ) -> PResult<()> {
         // create the swapchain again.
         let support = SwapchainSupport::get(vk_instance, physical_device, surface)?;
 
-        let format = support.format();
+        let format = support.format(hdr_output);
         let present_mode = support.present_mode();
         let extent = support.extent(window);
 
@@ -116,6 +131,13 @@ impl SwapchainInterface {
         let image_sharing_mode = vulkanalia::vk::SharingMode::EXCLUSIVE;
         let queue_family_indices = vec![indices.index()]; // todo ? is this correct ?
 
+        // screenshots copy the swapchain images, when the surface allows it.
+        let readable = support.capabilities().supported_usage_flags.contains(vulkanalia::vk::ImageUsageFlags::TRANSFER_SRC);
+        let image_usage = match readable {
+            true => vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT | vulkanalia::vk::ImageUsageFlags::TRANSFER_SRC,
+            false => vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        };
+
         let info = vulkanalia::vk::SwapchainCreateInfoKHR::builder()
             .surface(surface)
             .min_image_count(image_count)
@@ -123,7 +145,7 @@ impl SwapchainInterface {
             .image_color_space(format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vulkanalia::vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(support.capabilities().current_transform)
@@ -146,6 +168,8 @@ impl SwapchainInterface {
         // assign every new field.
         self.swapchain = swapchain;
         self.format = format.format;
+        self.color_space = format.color_space;
+        self.readable = readable;
         self.extent = extent;
         self.images = images;
         self.image_views = image_views;
@@ -189,6 +213,15 @@ impl SwapchainInterface {
         self.format
     }
 
+    pub fn color_space(&self) -> vulkanalia::vk::ColorSpaceKHR {
+        self.color_space
+    }
+
+    /// Whether the swapchain images can be used as transfer sources.
+    pub fn is_readable(&self) -> bool {
+        self.readable
+    }
+
     pub fn extent(&self) -> vulkanalia::vk::Extent2D {
         self.extent
     }
@@ -31,7 +31,30 @@ impl SwapchainSupport {
         self.capabilities
     } 
 
-    pub fn format(&self) -> vulkanalia::vk::SurfaceFormatKHR {
+    /// The format of the swapchain images: a hdr one when it is asked for and the surface supports one, srgb otherwise.
+    pub fn format(&self, hdr_output: bool) -> vulkanalia::vk::SurfaceFormatKHR {
+        match self.hdr_format() {
+            Some(format) if hdr_output => format,
+            _ => self.sdr_format(),
+        }
+    }
+
+    /// The hdr color spaces the tonemapping can write, when the surface reports them.
+    /// They are only reported when the swapchain color space instance extension is enabled.
+    pub fn hdr_format(&self) -> Option<vulkanalia::vk::SurfaceFormatKHR> {
+        let preferences = [
+            (vulkanalia::vk::Format::R16G16B16A16_SFLOAT, vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT),
+            (vulkanalia::vk::Format::A2B10G10R10_UNORM_PACK32, vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT),
+            (vulkanalia::vk::Format::A2R10G10B10_UNORM_PACK32, vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT),
+        ];
+        preferences.iter().find_map(|(format, color_space)| self.formats
+            .iter()
+            .cloned()
+            .find(|f| f.format == *format && f.color_space == *color_space)
+        )
+    }
+
+    fn sdr_format(&self) -> vulkanalia::vk::SurfaceFormatKHR {
         self.formats
             .iter()
             .cloned()
@@ -71,4 +94,51 @@ impl SwapchainSupport {
                 .build()
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwapchainSupport;
+
+    fn support(formats: &[(vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR)]) -> SwapchainSupport {
+        SwapchainSupport {
+            capabilities: vulkanalia::vk::SurfaceCapabilitiesKHR::default(),
+            formats: formats.iter().map(|(format, color_space)| vulkanalia::vk::SurfaceFormatKHR {
+                format: *format,
+                color_space: *color_space,
+            }).collect(),
+            present_modes: vec![vulkanalia::vk::PresentModeKHR::FIFO],
+        }
+    }
+
+    const SRGB: (vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR) = (vulkanalia::vk::Format::B8G8R8A8_SRGB, vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR);
+    const SCRGB: (vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR) = (vulkanalia::vk::Format::R16G16B16A16_SFLOAT, vulkanalia::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT);
+    const HDR10_ABGR: (vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR) = (vulkanalia::vk::Format::A2B10G10R10_UNORM_PACK32, vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+    const HDR10_ARGB: (vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR) = (vulkanalia::vk::Format::A2R10G10B10_UNORM_PACK32, vulkanalia::vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+
+    fn pair(format: vulkanalia::vk::SurfaceFormatKHR) -> (vulkanalia::vk::Format, vulkanalia::vk::ColorSpaceKHR) {
+        (format.format, format.color_space)
+    }
+
+    #[test]
+    fn hdr_formats_are_picked_by_preference() {
+        assert_eq!(support(&[SRGB, HDR10_ARGB, HDR10_ABGR, SCRGB]).hdr_format().map(pair), Some(SCRGB));
+        assert_eq!(support(&[SRGB, HDR10_ARGB, HDR10_ABGR]).hdr_format().map(pair), Some(HDR10_ABGR));
+        assert_eq!(support(&[SRGB, HDR10_ARGB]).hdr_format().map(pair), Some(HDR10_ARGB));
+        assert_eq!(support(&[SRGB]).hdr_format().map(pair), None);
+        // a hdr format in a srgb color space can't be used for hdr.
+        let linear_srgb = (vulkanalia::vk::Format::R16G16B16A16_SFLOAT, vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        assert_eq!(support(&[SRGB, linear_srgb]).hdr_format().map(pair), None);
+    }
+
+    #[test]
+    fn swapchains_are_srgb_unless_hdr_is_asked_and_supported() {
+        let hdr_display = support(&[HDR10_ABGR, SRGB, SCRGB]);
+        assert_eq!(pair(hdr_display.format(true)), SCRGB);
+        assert_eq!(pair(hdr_display.format(false)), SRGB);
+        assert_eq!(pair(support(&[SRGB]).format(true)), SRGB);
+        // without the preferred srgb format, the first format of the surface is used.
+        let unorm = (vulkanalia::vk::Format::B8G8R8A8_UNORM, vulkanalia::vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        assert_eq!(pair(support(&[unorm, HDR10_ABGR]).format(false)), unorm);
+    }
+}
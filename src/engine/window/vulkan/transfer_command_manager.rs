@@ -10,10 +10,12 @@ use vulkanalia::vk::Handle;
 use super::vulkan_buffer::VulkanBuffer;
 
 pub enum TransferCommand {
-    /// staging buffer, destination buffer, size
-    CopyBuffer(VulkanBuffer, vulkanalia::vk::Buffer, u64),
+    /// staging buffer, destination buffer, offset in the destination buffer, size
+    CopyBuffer(VulkanBuffer, vulkanalia::vk::Buffer, u64, u64),
     /// staging buffer, destination image, width, height
     CopyImage(VulkanBuffer, vulkanalia::vk::Image, u32, u32),
+    /// staging buffer with all the levels of the faces, destination cube image, size of the faces, mip levels
+    CopyCubemap(VulkanBuffer, vulkanalia::vk::Image, u32, u32),
     /// Transition the image to the given layout.
     TransitionImageLayout(vulkanalia::vk::Image, vulkanalia::vk::Format, vulkanalia::vk::ImageLayout, vulkanalia::vk::ImageLayout),
 }
@@ -23,9 +25,10 @@ impl TransferCommand {
     pub fn buffer_transfer(
         staging: VulkanBuffer,
         destination: vulkanalia::vk::Buffer,
+        destination_offset: u64,
         size: u64,
     ) -> TransferCommand {
-        TransferCommand::CopyBuffer(staging, destination, size)
+        TransferCommand::CopyBuffer(staging, destination, destination_offset, size)
     }
 
     pub fn destroy(
@@ -33,8 +36,9 @@ impl TransferCommand {
         vk_device: &vulkanalia::Device
     ) {
         match self {
-            TransferCommand::CopyBuffer(staging_buffer, _, _) => staging_buffer.destroy(vk_device),
+            TransferCommand::CopyBuffer(staging_buffer, _, _, _) => staging_buffer.destroy(vk_device),
             TransferCommand::CopyImage(staging_buffer, _, _, _) => staging_buffer.destroy(vk_device),
+            TransferCommand::CopyCubemap(staging_buffer, _, _, _) => staging_buffer.destroy(vk_device),
             TransferCommand::TransitionImageLayout(_, _, _, _) => {}
         }
     }
@@ -92,8 +96,9 @@ impl TransferCommandManager {
 
         for transfer in self.transfer_queue.iter() {
             match transfer {
-                TransferCommand::CopyBuffer(staging, destination, size) => Self::record_buffer_transfer(vk_device, command_buffer, staging, *destination, *size)?,
+                TransferCommand::CopyBuffer(staging, destination, destination_offset, size) => Self::record_buffer_transfer(vk_device, command_buffer, staging, *destination, *destination_offset, *size)?,
                 TransferCommand::CopyImage(staging, destination, width, height) => Self::record_image_transfer(vk_device, command_buffer, staging, *destination, *width, *height)?,
+                TransferCommand::CopyCubemap(staging, destination, size, mip_levels) => Self::record_cubemap_transfer(vk_device, command_buffer, staging, *destination, *size, *mip_levels)?,
                 TransferCommand::TransitionImageLayout(image, format, old_layout, new_layout) => Self::record_pipeline_barrier(vk_device, command_buffer, *image, *format,  *old_layout, *new_layout)?,
             }
         }
@@ -126,10 +131,11 @@ impl TransferCommandManager {
         command_buffer: vulkanalia::vk::CommandBuffer,
         staging: &VulkanBuffer,
         destination: vulkanalia::vk::Buffer,
+        destination_offset: vulkanalia::vk::DeviceSize,
         size: vulkanalia::vk::DeviceSize,
     ) -> PResult<()> {        
 
-        let regions = vulkanalia::vk::BufferCopy::builder().size(size);
+        let regions = vulkanalia::vk::BufferCopy::builder().dst_offset(destination_offset).size(size);
         unsafe { vk_device.cmd_copy_buffer(command_buffer, staging.buffer(), destination, &[regions]) };
 
         Ok(())
@@ -186,6 +192,68 @@ impl TransferCommandManager {
         Ok(())
     }
 
+    fn record_cubemap_transfer(
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        staging: &VulkanBuffer,
+        destination: vulkanalia::vk::Image,
+        size: u32,
+        mip_levels: u32,
+    ) -> PResult<()> {
+
+        // switch all the faces and levels to transfer destination.
+        Self::record_subresource_barrier(vk_device, command_buffer, destination,
+            vulkanalia::vk::Format::R16G16B16A16_SFLOAT,
+            vulkanalia::vk::ImageLayout::UNDEFINED,
+            vulkanalia::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            mip_levels,
+            6,
+        )?;
+
+        // the levels are packed one after the other in the staging buffer, each with its six faces.
+        let mut offset = 0;
+        let regions = (0..mip_levels).map(|level| {
+            let level_size = (size >> level).max(1);
+            let subresource = vulkanalia::vk::ImageSubresourceLayers::builder()
+                .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+                .mip_level(level)
+                .base_array_layer(0)
+                .layer_count(6);
+
+            let region = vulkanalia::vk::BufferImageCopy::builder()
+                .buffer_offset(offset)
+                .buffer_row_length(0)
+                .buffer_image_height(0)
+                .image_subresource(subresource)
+                .image_offset(vulkanalia::vk::Offset3D { x: 0, y: 0, z: 0 })
+                .image_extent(vulkanalia::vk::Extent3D { width: level_size, height: level_size, depth: 1 })
+                .build();
+            // four half floats per texel
+            offset += 6 * (level_size * level_size) as u64 * 8;
+            region
+        }).collect::<Vec<_>>();
+
+        unsafe {
+            vk_device.cmd_copy_buffer_to_image(
+                command_buffer,
+                staging.buffer(),
+                destination,
+                vulkanalia::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            );
+        }
+
+        // switch the whole cubemap to shader read.
+        Self::record_subresource_barrier(vk_device, command_buffer, destination,
+            vulkanalia::vk::Format::R16G16B16A16_SFLOAT,
+            vulkanalia::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vulkanalia::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            mip_levels,
+            6,
+        )?;
+
+        Ok(())
+    }
 
     fn record_pipeline_barrier(
         vk_device: &vulkanalia::Device,
@@ -194,6 +262,20 @@ impl TransferCommandManager {
         format: vulkanalia::vk::Format,
         old_layout: vulkanalia::vk::ImageLayout,
         new_layout: vulkanalia::vk::ImageLayout,
+    ) -> PResult<()> {
+        Self::record_subresource_barrier(vk_device, command_buffer, destination, format, old_layout, new_layout, 1, 1)
+    }
+
+    /// Transition the first levels and layers of the image.
+    fn record_subresource_barrier(
+        vk_device: &vulkanalia::Device,
+        command_buffer: vulkanalia::vk::CommandBuffer,
+        destination: vulkanalia::vk::Image,
+        format: vulkanalia::vk::Format,
+        old_layout: vulkanalia::vk::ImageLayout,
+        new_layout: vulkanalia::vk::ImageLayout,
+        level_count: u32,
+        layer_count: u32,
     ) -> PResult<()> {
         // create the access masks from the layouts.
         let (
@@ -236,9 +318,9 @@ impl TransferCommandManager {
         let subresource = vulkanalia::vk::ImageSubresourceRange::builder()
             .aspect_mask(aspect_mask)
             .base_mip_level(0)
-            .level_count(1)
+            .level_count(level_count)
             .base_array_layer(0)
-            .layer_count(1);
+            .layer_count(layer_count);
 
         let barrier = vulkanalia::vk::ImageMemoryBarrier::builder()
             .old_layout(old_layout)
@@ -271,9 +353,10 @@ impl TransferCommandManager {
         vk_device: &vulkanalia::Device,
         staging: VulkanBuffer, // take ownership to destroy it when transfer is done.
         destination: vulkanalia::vk::Buffer,
+        destination_offset: vulkanalia::vk::DeviceSize,
         size: vulkanalia::vk::DeviceSize,
     ) -> PResult<()> {
-        self.transfer_queue.push(TransferCommand::buffer_transfer(staging, destination, size));
+        self.transfer_queue.push(TransferCommand::buffer_transfer(staging, destination, destination_offset, size));
         let fence_info = vulkanalia::vk::FenceCreateInfo::default();
 
         // complete the fence list.
@@ -307,6 +390,27 @@ impl TransferCommandManager {
         Ok(())
     }
 
+    pub fn register_cubemap_transfer(
+        &mut self,
+        vk_device: &vulkanalia::Device,
+        staging: VulkanBuffer, // take ownership to destroy it when transfer is done.
+        destination: vulkanalia::vk::Image,
+        size: u32,
+        mip_levels: u32,
+    ) -> PResult<()> {
+        self.transfer_queue.push(TransferCommand::CopyCubemap(staging, destination, size, mip_levels));
+        let fence_info = vulkanalia::vk::FenceCreateInfo::default();
+
+        // complete the fence list.
+        while self.transfer_fences.len() < self.transfer_queue.len() {
+            self.transfer_fences.push(unsafe {
+                vk_device.create_fence(&fence_info, None)?
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn register_transition_image_layout(
         &mut self,
         vk_device: &vulkanalia::Device,
@@ -25,8 +25,48 @@ impl VulkanImage {
         height: u32,
         usage: vulkanalia::vk::ImageUsageFlags,
         format: vulkanalia::vk::Format,
+    ) -> PResult<VulkanImage> {
+        Self::create_layered(vk_instance, vk_device, vk_physical_device, width, height, 1, usage, format)
+    }
+
+    /// Create an image with several array layers, like the cascades of a shadow map.
+    pub fn create_layered(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        width: u32,
+        height: u32,
+        layer_count: u32,
+        usage: vulkanalia::vk::ImageUsageFlags,
+        format: vulkanalia::vk::Format,
     ) -> PResult<VulkanImage> {
         // image create info
+        let info = vulkanalia::vk::ImageCreateInfo::builder()
+            .image_type(vulkanalia::vk::ImageType::_2D)
+            .extent(vulkanalia::vk::Extent3D { width, height, depth: 1 })
+            .mip_levels(1)
+            .array_layers(layer_count)
+            .format(format)
+            .tiling(vulkanalia::vk::ImageTiling::OPTIMAL)
+            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+            .usage(usage)
+            .sharing_mode(vulkanalia::vk::SharingMode::EXCLUSIVE)
+            .samples(vulkanalia::vk::SampleCountFlags::_1);
+
+        Self::allocate(vk_instance, vk_device, vk_physical_device, &info, width, height, format)
+    }
+
+    /// Create an attachment image with several samples per pixel, drawn by multisampled render passes.
+    pub fn create_multisampled(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        width: u32,
+        height: u32,
+        samples: vulkanalia::vk::SampleCountFlags,
+        usage: vulkanalia::vk::ImageUsageFlags,
+        format: vulkanalia::vk::Format,
+    ) -> PResult<VulkanImage> {
         let info = vulkanalia::vk::ImageCreateInfo::builder()
             .image_type(vulkanalia::vk::ImageType::_2D)
             .extent(vulkanalia::vk::Extent3D { width, height, depth: 1 })
@@ -37,9 +77,48 @@ impl VulkanImage {
             .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
             .usage(usage)
             .sharing_mode(vulkanalia::vk::SharingMode::EXCLUSIVE)
+            .samples(samples);
+
+        Self::allocate(vk_instance, vk_device, vk_physical_device, &info, width, height, format)
+    }
+
+    /// Create a cube compatible image, with its six faces as layers and the given number of mip levels.
+    pub fn create_cubemap(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        size: u32,
+        mip_levels: u32,
+        usage: vulkanalia::vk::ImageUsageFlags,
+        format: vulkanalia::vk::Format,
+    ) -> PResult<VulkanImage> {
+        let info = vulkanalia::vk::ImageCreateInfo::builder()
+            .flags(vulkanalia::vk::ImageCreateFlags::CUBE_COMPATIBLE)
+            .image_type(vulkanalia::vk::ImageType::_2D)
+            .extent(vulkanalia::vk::Extent3D { width: size, height: size, depth: 1 })
+            .mip_levels(mip_levels)
+            .array_layers(6)
+            .format(format)
+            .tiling(vulkanalia::vk::ImageTiling::OPTIMAL)
+            .initial_layout(vulkanalia::vk::ImageLayout::UNDEFINED)
+            .usage(usage)
+            .sharing_mode(vulkanalia::vk::SharingMode::EXCLUSIVE)
             .samples(vulkanalia::vk::SampleCountFlags::_1);
 
-        let image = unsafe { vk_device.create_image(&info, None)? };
+        Self::allocate(vk_instance, vk_device, vk_physical_device, &info, size, size, format)
+    }
+
+    /// Create the image and bind it to device local memory.
+    fn allocate(
+        vk_instance: &vulkanalia::Instance,
+        vk_device: &vulkanalia::Device,
+        vk_physical_device: vulkanalia::vk::PhysicalDevice,
+        info: &vulkanalia::vk::ImageCreateInfo,
+        width: u32,
+        height: u32,
+        format: vulkanalia::vk::Format,
+    ) -> PResult<VulkanImage> {
+        let image = unsafe { vk_device.create_image(info, None)? };
 
         let requirements = unsafe { vk_device.get_image_memory_requirements(image) };
         
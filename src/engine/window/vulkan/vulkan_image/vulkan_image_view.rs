@@ -11,17 +11,53 @@ pub fn create_image_view(
     image: &VulkanImage,
     format: vulkanalia::vk::Format,
     aspects: vulkanalia::vk::ImageAspectFlags,
+) -> PResult<vulkanalia::vk::ImageView> {
+    create_layered_image_view(vk_device, image, format, aspects, vulkanalia::vk::ImageViewType::_2D, 0, 1)
+}
+
+/// Create a view on some array layers of an image, either a single layer or a 2D array.
+pub fn create_layered_image_view(
+    vk_device: &vulkanalia::Device,
+    image: &VulkanImage,
+    format: vulkanalia::vk::Format,
+    aspects: vulkanalia::vk::ImageAspectFlags,
+    view_type: vulkanalia::vk::ImageViewType,
+    base_layer: u32,
+    layer_count: u32,
 ) -> PResult<vulkanalia::vk::ImageView> {
     let subresource_range = vulkanalia::vk::ImageSubresourceRange::builder()
         .aspect_mask(aspects)
         .base_mip_level(0)
         .level_count(1)
+        .base_array_layer(base_layer)
+        .layer_count(layer_count);
+
+    let info = vulkanalia::vk::ImageViewCreateInfo::builder()
+        .image(image.image())
+        .view_type(view_type)
+        .format(format)
+        .subresource_range(subresource_range);
+
+    Ok(unsafe { vk_device.create_image_view(&info, None)? })
+}
+
+/// Create a cube view on the six layers of a cube compatible image, with all its mip levels.
+pub fn create_cube_image_view(
+    vk_device: &vulkanalia::Device,
+    image: &VulkanImage,
+    format: vulkanalia::vk::Format,
+    mip_levels: u32,
+) -> PResult<vulkanalia::vk::ImageView> {
+    let subresource_range = vulkanalia::vk::ImageSubresourceRange::builder()
+        .aspect_mask(vulkanalia::vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(mip_levels)
         .base_array_layer(0)
-        .layer_count(1);
+        .layer_count(6);
 
     let info = vulkanalia::vk::ImageViewCreateInfo::builder()
         .image(image.image())
-        .view_type(vulkanalia::vk::ImageViewType::_2D)
+        .view_type(vulkanalia::vk::ImageViewType::CUBE)
         .format(format)
         .subresource_range(subresource_range);
 
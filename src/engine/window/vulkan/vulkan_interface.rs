@@ -18,6 +18,7 @@ use vulkanalia::vk::EntryV1_0;
 use vulkanalia::vk::InstanceV1_0;
 use vulkanalia::vk::DeviceV1_0;
 use vulkanalia::vk::HasBuilder;
+use vulkanalia::vk::Handle;
 use vulkanalia::vk::KhrSurfaceExtension;
 
 /// Extensions that are required to run the propellant engine, if we are using the window and vulkan.
@@ -26,6 +27,11 @@ pub(crate) const REQUIRED_DEVICE_EXTENSIONS: &[vulkanalia::vk::ExtensionName] =
     vulkanalia::vk::EXT_DESCRIPTOR_INDEXING_EXTENSION.name,
 ];
 
+/// Extensions that are required to render offscreen, without window nor surface.
+pub(crate) const HEADLESS_DEVICE_EXTENSIONS: &[vulkanalia::vk::ExtensionName] = &[
+    vulkanalia::vk::EXT_DESCRIPTOR_INDEXING_EXTENSION.name,
+];
+
 pub struct VulkanInterface {
     pub entry: vulkanalia::Entry,
     pub instance: vulkanalia::Instance,
@@ -43,6 +49,29 @@ impl VulkanInterface {
         device_prefs: &Box<dyn PhysicalDevicePreferences>,
         app_name: String,
     ) -> PResult<VulkanInterface>{
+        // get the required extensions from the winit window
+        let extensions = vulkanalia::window::get_required_instance_extensions(&window).iter().map(|e| e.as_ptr())
+            .collect::<Vec<_>>();
+        let (entry, instance) = Self::create_instance(app_name, &extensions)?;
+        // create the surface : interface between vulkan and winit window.
+        let surface = unsafe {vulkanalia::window::create_surface(&instance, &window, &window)?};
+        Self::create_with_surface(entry, instance, surface, device_prefs)
+    }
+
+    /// Create a vulkan interface without window nor surface, to render offscreen.
+    /// It does not need any presentation support, so it also runs on software drivers such as lavapipe.
+    pub fn create_headless(
+        device_prefs: &Box<dyn PhysicalDevicePreferences>,
+        app_name: String,
+    ) -> PResult<VulkanInterface> {
+        let (entry, instance) = Self::create_instance(app_name, &[])?;
+        Self::create_with_surface(entry, instance, vulkanalia::vk::SurfaceKHR::null(), device_prefs)
+    }
+
+    fn create_instance(
+        app_name: String,
+        extensions: &[*const std::ffi::c_char],
+    ) -> PResult<(vulkanalia::Entry, vulkanalia::Instance)> {
         // create the app info as a builder 
         let application_info = vulkanalia::vk::ApplicationInfo::builder()
             .application_name(app_name.as_bytes())
@@ -50,9 +79,6 @@ impl VulkanInterface {
             .engine_name(b"ProppelantEngine\0")
             .engine_version(vulkanalia::vk::make_version(ENGINE_VERSION.0, ENGINE_VERSION.1, ENGINE_VERSION.2))
             .api_version(vulkanalia::vk::make_version(1, 2, 0));
-        // get the required extensions from the winit window
-        let extensions = vulkanalia::window::get_required_instance_extensions(&window).iter().map(|e| e.as_ptr())
-            .collect::<Vec<_>>();
         // create the vulkan loader and entry
         let loader = unsafe {
             // lib loading module error is private, so we have to go with a match here
@@ -85,6 +111,20 @@ impl VulkanInterface {
             Vec::with_capacity(0)
         };
 
+        // the surfaces only report the hdr color spaces with this extension, it is enabled when presenting and available.
+        let colorspace_extension = vulkanalia::vk::EXT_SWAPCHAIN_COLORSPACE_EXTENSION.name;
+        let available_extensions = unsafe {
+            entry
+                .enumerate_instance_extension_properties(None)?
+                .iter()
+                .map(|e| e.extension_name)
+                .collect::<HashSet<_>>()
+        };
+        let mut extensions = extensions.to_vec();
+        if !extensions.is_empty() && available_extensions.contains(&colorspace_extension) {
+            extensions.push(colorspace_extension.as_ptr());
+        }
+
         // create the vk instance info
         let info = vulkanalia::vk::InstanceCreateInfo::builder()
             .application_info(&application_info)
@@ -93,14 +133,23 @@ impl VulkanInterface {
 
         // create the vk instance
         let instance = unsafe {entry.create_instance(&info, None)?};
-        // create the surface : interface between vulkan and winit window.
-        let surface = unsafe {vulkanalia::window::create_surface(&instance, &window, &window)?};
+
+        Ok((entry, instance))
+    }
+
+    /// Pick a device and create the logical device, presenting to the surface unless it is null.
+    fn create_with_surface(
+        entry: vulkanalia::Entry,
+        instance: vulkanalia::Instance,
+        surface: vulkanalia::vk::SurfaceKHR,
+        device_prefs: &Box<dyn PhysicalDevicePreferences>,
+    ) -> PResult<VulkanInterface> {
         // pick a physical device that match our needs
         let physical_device = Self::pick_physical_device(&instance, device_prefs, surface)?;
         // get the queue indices. 
         let indices = unsafe { QueueFamilyIndices::get(&instance, physical_device, surface)? };
         // create the actual device with the info
-        let (device, queue) = Self::create_logical_device(&instance, physical_device, indices)?;
+        let (device, queue) = Self::create_logical_device(&instance, physical_device, indices, Self::device_extensions(surface))?;
 
         // the transfer manager is able to send data to the gpu.
         let transfer_manager = TransferCommandManager::create(&device, indices)?;
@@ -115,7 +164,19 @@ impl VulkanInterface {
             surface,
             transfer_manager,
         })
+    }
 
+    /// Device extensions to enable: the swapchain is only needed when presenting to a surface.
+    fn device_extensions(surface: vulkanalia::vk::SurfaceKHR) -> &'static [vulkanalia::vk::ExtensionName] {
+        match surface.is_null() {
+            true => HEADLESS_DEVICE_EXTENSIONS,
+            false => REQUIRED_DEVICE_EXTENSIONS,
+        }
+    }
+
+    /// Whether this interface was created without surface, and can only render offscreen.
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_null()
     }
 
     fn pick_physical_device(
@@ -128,12 +189,15 @@ impl VulkanInterface {
             vk_instance.enumerate_physical_devices()?.into_iter().filter(|device| {
                 let properties = vk_instance.get_physical_device_properties(*device);
                 let features = vk_instance.get_physical_device_features(*device);
-                match SwapchainSupport::get(vk_instance, *device, surface) {
-                    Ok(support) => if !support.is_sufficient() { return false; },
-                    Err(_) => return false,
-                };
+                // headless devices do not present, they do not need swapchain support.
+                if !surface.is_null() {
+                    match SwapchainSupport::get(vk_instance, *device, surface) {
+                        Ok(support) => if !support.is_sufficient() { return false; },
+                        Err(_) => return false,
+                    };
+                }
                 device_prefs.is_device_compatible(properties, features) &&
-                Self::check_physical_device_extensions(vk_instance, *device)
+                Self::check_physical_device_extensions(vk_instance, *device, Self::device_extensions(surface))
             }).map(|device| {
                 // todo : add swapchain support prefs.
                 (device, vk_instance.get_physical_device_properties(device), vk_instance.get_physical_device_features(device))
@@ -146,6 +210,7 @@ impl VulkanInterface {
     fn check_physical_device_extensions(
         instance: &vulkanalia::Instance,
         physical_device: vulkanalia::vk::PhysicalDevice,
+        required_extensions: &[vulkanalia::vk::ExtensionName],
     ) -> bool {
         let extensions = unsafe {
                 match instance.enumerate_device_extension_properties(physical_device, None) {
@@ -156,22 +221,28 @@ impl VulkanInterface {
             .iter()
             .map(|e| e.extension_name)
             .collect::<HashSet<_>>();
-        REQUIRED_DEVICE_EXTENSIONS.iter().all(|e| extensions.contains(e))
+        required_extensions.iter().all(|e| extensions.contains(e))
     }
 
     fn create_logical_device(
         vk_instance: &vulkanalia::Instance,
         vk_physical_device: vulkanalia::vk::PhysicalDevice,
         indices: QueueFamilyIndices,
+        device_extensions: &[vulkanalia::vk::ExtensionName],
     ) -> PResult<(vulkanalia::Device, vulkanalia::vk::Queue)> {
         let queue_priorities = &[1.0];
         let queue_info = vulkanalia::vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(indices.index())
             .queue_priorities(queue_priorities);
         
+        // wireframe, wide lines and large points are optional, enable them when the device supports them.
+        let supported_features = unsafe { vk_instance.get_physical_device_features(vk_physical_device) };
         let features = vulkanalia::vk::PhysicalDeviceFeatures::builder()
             .sampler_anisotropy(true)
-            .shader_sampled_image_array_dynamic_indexing(true);
+            .shader_sampled_image_array_dynamic_indexing(true)
+            .fill_mode_non_solid(supported_features.fill_mode_non_solid == vulkanalia::vk::TRUE)
+            .wide_lines(supported_features.wide_lines == vulkanalia::vk::TRUE)
+            .large_points(supported_features.large_points == vulkanalia::vk::TRUE);
 
         // allows to use non uniform indexing in shaders, that is required for our texture lib.
         let mut vk12_device_features = vulkanalia::vk::PhysicalDeviceVulkan12Features::builder()
@@ -183,7 +254,7 @@ impl VulkanInterface {
             .descriptor_binding_variable_descriptor_count(true);
     
         let queue_infos = &[queue_info];
-        let extensions = REQUIRED_DEVICE_EXTENSIONS.iter().map(|e| e.as_ptr()).collect::<Vec<_>>();
+        let extensions = device_extensions.iter().map(|e| e.as_ptr()).collect::<Vec<_>>();
         let info = vulkanalia::vk::DeviceCreateInfo::builder()
             .queue_create_infos(queue_infos)
             .enabled_features(&features)
@@ -244,7 +315,9 @@ impl Drop for VulkanInterface {
             self.device.device_wait_idle().unwrap(); // todo : handle failure here ?
             self.transfer_manager.destroy(&self.device);
             self.device.destroy_device(None);
-            self.instance.destroy_surface_khr(self.surface, None);
+            if !self.surface.is_null() {
+                self.instance.destroy_surface_khr(self.surface, None);
+            }
             self.instance.destroy_instance(None);
 
         }
@@ -17,6 +17,7 @@ pub struct PropellantWindowBuilder {
     device_prefs: Box<dyn PhysicalDevicePreferences>,
     renderer: Box<dyn VulkanRendererBuilder>,
     inner_size: (usize, usize),
+    screenshot_hotkey: Option<(winit::event::VirtualKeyCode, std::path::PathBuf)>,
 }
 
 impl HasBuilder for PropellantWindow {
@@ -28,6 +29,7 @@ impl HasBuilder for PropellantWindow {
             device_prefs: Box::new(DefaultPhysicalDevicePreferences),
             renderer: DefaultVulkanRendererBuilder::default(),
             inner_size: (800, 450),
+            screenshot_hotkey: None,
         }
     }
 }
@@ -50,6 +52,7 @@ impl PropellantWindowBuilder {
             vk_interface,
             window,
             renderer,
+            screenshot_hotkey: self.screenshot_hotkey,
         })
     }
 
@@ -65,4 +68,9 @@ impl PropellantWindowBuilder {
         PropellantWindowBuilder { renderer, ..self }
     }
 
+    /// Save a screenshot in the given directory each time the key is pressed.
+    pub fn with_screenshot_hotkey(self, key: winit::event::VirtualKeyCode, directory: std::path::PathBuf) -> PropellantWindowBuilder {
+        PropellantWindowBuilder { screenshot_hotkey: Some((key, directory)), ..self }
+    }
+
 }
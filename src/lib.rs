@@ -1,3 +1,5 @@
+extern crate self as propellant;
+
 pub(crate) mod engine;
 pub(crate) mod utils;
 
@@ -6,42 +8,129 @@ pub use engine::{
     PropellantEngine,
     common_components::camera::Camera,
     common_systems::fps_limiter::FpsLimiter,
+    animations::{
+        skeleton::{Skeleton, Joint},
+        skinning_system::SkinningSystem,
+    },
     mesh::{
         MeshType,
         mesh_renderer::InstancedMeshRenderer,
+        mesh_renderer::skinned_mesh_renderer::SkinnedMeshRenderer,
         StaticMesh,
+        CompactStaticMesh,
+        SkinnedMesh,
+        LineMesh,
+        PointMesh,
+        ColorMesh,
+        StaticVertex,
+        SkeletalVertex,
+        LineVertex,
+        PointVertex,
+        ColorVertex,
+        VulkanVertex,
+        VertexAttribute,
+        CustomMesh,
     },
     material::{
         phong_material::PhongMaterial,
+        pbr_material::PbrMaterial,
+        skybox_material::SkyboxMaterial,
+        tint_material::TintMaterial,
+        transparent::{Transparent, TransparentPbrMaterial, TransparentUnlitMaterial},
+        unlit_material::UnlitMaterial,
+        vertex_color_material::VertexColorMaterial,
         colored_texture::ColoredTexture,
     },
     engine_events::{
         PropellantEvent,
         PropellantEventSenderExt,
     },
-    resources::PropellantResources,
+    resources::{
+        PropellantResources,
+        resource_handle::ResourceHandle,
+        texture_library::TextureColorSpace,
+    },
     window::{
         PropellantWindow,
         window_builder::PropellantWindowBuilder,
         vulkan::vulkan_interface::VulkanInterface,
+        vulkan::physical_device_prefs::{PhysicalDevicePreferences, DefaultPhysicalDevicePreferences, SoftwarePhysicalDevicePreferences},
     },
     transform::transform::Transform,
     lights::directionnal_light::DirectionnalLight,
+    lights::point_light::PointLight,
+    lights::spot_light::SpotLight,
     flags::*,
     renderer::{
         renderer_builder::default_vulkan_renderer_builder::DefaultVulkanRendererBuilder,
+        offscreen_renderer::OffscreenRenderer,
+        golden_images::{GoldenImageHarness, GoldenImageTolerance, GoldenImageComparison, GoldenImageOutcome},
+        screenshot::ScreenshotRequest,
         rendering_pipeline::rendering_pipeline_builder::RenderingPipelineBuilder,
         graphic_pipeline::{
             uniform::frame_uniform::{
                 camera_uniform::CameraUniformObject,
                 main_directionnal_light::MainDirectionnalLight,
+                local_lights::{LocalLights, ClusteredLights},
             },
             uniform::object_uniform::model_uniform::ModelMatrixUniformObject,
+            uniform::object_uniform::joint_palette_uniform::JointPaletteUniformObject,
             graphic_pipeline_builder::default_phong_pipeline,
+            graphic_pipeline_builder::default_skinned_phong_pipeline,
+            graphic_pipeline_builder::default_unlit_pipeline,
+            graphic_pipeline_builder::default_transparent_unlit_pipeline,
+            graphic_pipeline_builder::default_vertex_color_pipeline,
+            graphic_pipeline_builder::default_pbr_pipeline,
+            graphic_pipeline_builder::default_skinned_pbr_pipeline,
+            graphic_pipeline_builder::default_transparent_pbr_pipeline,
+            graphic_pipeline_builder::default_line_pipeline,
+            graphic_pipeline_builder::default_point_pipeline,
+            graphic_pipeline_builder::default_skybox_pipeline,
+            graphic_pipeline_builder::default_phong_shadow_caster_pipeline,
+            graphic_pipeline_builder::default_skinned_phong_shadow_caster_pipeline,
+            graphic_pipeline_builder::default_pbr_shadow_caster_pipeline,
+            graphic_pipeline_builder::default_skinned_pbr_shadow_caster_pipeline,
+            graphic_pipeline_settings::{
+                GraphicPipelineSettings,
+                PrimitiveTopology,
+                PolygonMode,
+                DepthSetting,
+                CompareOp,
+                CullMode,
+                FrontFace,
+                BlendMode,
+            },
             graphic_pipeline_gen::ShaderStage,
         },
+        compute_pipeline::compute_pipeline_builder::{
+            ComputePipelineBuilder,
+            ComputeShader,
+            Tonemapping,
+            ColorGrading,
+            tonemap_pipeline,
+            tonemap_pipeline_with_settings,
+            fxaa_pipeline,
+            vignette_pipeline,
+            color_grading_pipeline,
+            bloom_pipeline,
+            ssao_pipeline,
+        },
+        compute_pipeline::post_process_effects::{
+            PostProcessEffect,
+            BloomSettings,
+            SsaoSettings,
+            TonemapSettings,
+        },
         rendering_pipeline::final_render_target::FinalRenderTargetBuilder,
-        rendering_pipeline::intermediate_render_targets::IntermediateRenderTargetBuilder,
+        rendering_pipeline::intermediate_render_targets::{
+            IntermediateRenderTargetBuilder,
+            HDR_FORMAT,
+            LDR_FORMAT,
+        },
+        rendering_pipeline::shadow_render_pass::shadow_settings::{
+            ShadowSettings,
+            MAX_SHADOW_CASCADES,
+        },
     },
 
 };
@@ -56,6 +145,17 @@ pub use engine::{
     ui::text::text_renderer::UiTextRenderer,
 };
 
+#[cfg(feature = "material-assets")]
+pub use engine::material::material_asset::{
+    MaterialAsset,
+    LoadedMaterial,
+    TextureSource,
+    PhongMaterialAsset,
+    PbrMaterialAsset,
+    UnlitMaterialAsset,
+    texture_file_id,
+};
+
 #[cfg(feature = "inputs")]
 pub use engine::inputs::{
     input_context::InputContext,
@@ -80,6 +180,7 @@ pub use glam;
 pub use winit;
 pub use vulkanalia;
 pub use vk_shader_macros;
+pub use propellant_derive::VulkanVertex;
 
 pub use utils::{
     id::{
@@ -9,13 +9,14 @@ struct TexturedColor {
 struct PhongMaterial {
     TexturedColor albedo;
     TexturedColor metalic;
+    TexturedColor normal;
 };
 
 layout(set = 1, binding = 0) uniform MainDirectionnalLight {
     vec3 direction;
     float _padd_0;
     vec3 ambiant_color;
-    float _padd_1;
+    float environment_intensity;
     vec3 direct_color;
     float _padd_2;
 } mainLight;
@@ -26,15 +27,144 @@ layout(set = 2, binding = 0) readonly buffer MaterialProperties {
 
 layout(set = 4, binding = 0) uniform sampler2D all_textures[];
 
+const uint CLUSTER_X = 16;
+const uint CLUSTER_Y = 9;
+const uint CLUSTER_Z = 16;
+const uint MAX_LOCAL_LIGHTS = 256;
+
+struct LocalLight {
+    vec3 position;
+    float range;
+    vec3 color;
+    float intensity;
+    vec3 direction;
+    float cosOuterCone;
+    float cosInnerCone;
+    float _padd_0;
+    float _padd_1;
+    float _padd_2;
+};
+
+layout(set = 5, binding = 0) readonly buffer LocalLights {
+    mat4 view;
+    mat4 viewProjection;
+    float zNear;
+    float zFar;
+    uint lightCount;
+    uint _padd;
+    LocalLight lights[MAX_LOCAL_LIGHTS];
+    // offset in the light indices and light count of each cluster
+    uvec2 clusters[CLUSTER_X * CLUSTER_Y * CLUSTER_Z];
+    uint lightIndices[];
+} localLights;
+
+const uint MAX_SHADOW_CASCADES = 4;
+
+layout(set = 6, binding = 0) uniform ShadowCascades {
+    mat4 view;
+    mat4 viewProjections[MAX_SHADOW_CASCADES];
+    vec4 splitDepths;
+    vec4 texelSizes;
+    uint cascadeCount;
+    float normalBias;
+    int pcfRadius;
+    float _padd;
+} shadows;
+
+layout(set = 6, binding = 1) uniform sampler2DArrayShadow shadowMap;
+
+// prefiltered environment: the specular mip chain goes from sharp to rough, the irradiance is already convolved.
+layout(set = 7, binding = 0) uniform samplerCube environmentSpecular;
+layout(set = 7, binding = 1) uniform samplerCube environmentIrradiance;
+
 layout (location = 0) in flat int instanceIndex;
 layout (location = 1) in smooth vec3 inPosition;
 layout (location = 2) in smooth vec3 inNormal;
 layout (location = 3) in smooth vec2 inUv;
 layout (location = 4) in smooth vec3 inCamPos;
+layout (location = 5) in smooth vec4 inTangent;
 
 layout (location = 0) out vec4 outColor;
 
 
+// index of the cluster of the view frustum containing the position.
+// tiles are split evenly on screen, depth slices exponentially between the near and far planes.
+uint clusterIndex(vec3 position) {
+    vec4 clip = localLights.viewProjection * vec4(position, 1.0);
+    vec2 ndc = clip.xy / clip.w;
+    float depth = max(-(localLights.view * vec4(position, 1.0)).z, localLights.zNear);
+    uvec2 tile = uvec2(clamp((ndc * 0.5 + 0.5) * vec2(CLUSTER_X, CLUSTER_Y), vec2(0.0), vec2(CLUSTER_X - 1, CLUSTER_Y - 1)));
+    uint slice = uint(clamp(log(depth / localLights.zNear) / log(localLights.zFar / localLights.zNear) * float(CLUSTER_Z), 0.0, float(CLUSTER_Z - 1)));
+    return tile.x + tile.y * CLUSTER_X + slice * CLUSTER_X * CLUSTER_Y;
+}
+
+// fraction of the main light reaching the position, from the shadow map cascade covering it.
+// the position is pushed along the normal by a few texels to avoid shadow acne, and the lookup is averaged over the pcf kernel.
+float mainLightShadow(vec3 position, vec3 normal) {
+    float depth = -(shadows.view * vec4(position, 1.0)).z;
+    uint cascade = 0u;
+    while (cascade < shadows.cascadeCount && depth > shadows.splitDepths[cascade]) {
+        cascade++;
+    }
+    // no shadows, or further than the shadow distance.
+    if (cascade >= shadows.cascadeCount) {
+        return 1.0;
+    }
+    vec3 offsetPosition = position + normal * shadows.normalBias * shadows.texelSizes[cascade];
+    vec4 lightClip = shadows.viewProjections[cascade] * vec4(offsetPosition, 1.0);
+    vec3 coords = lightClip.xyz / lightClip.w;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+    vec2 uv = coords.xy * 0.5 + 0.5;
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -shadows.pcfRadius; x <= shadows.pcfRadius; x++) {
+        for (int y = -shadows.pcfRadius; y <= shadows.pcfRadius; y++) {
+            lit += texture(shadowMap, vec4(uv + vec2(x, y) * texelSize, float(cascade), coords.z));
+        }
+    }
+    float kernelSize = float(2 * shadows.pcfRadius + 1);
+    return lit / (kernelSize * kernelSize);
+}
+
+// light of a point or spot light reaching the position, and the direction towards the light.
+vec3 localLightRadiance(LocalLight light, vec3 position, out vec3 L) {
+    vec3 toLight = light.position - position;
+    float distanceSquared = max(dot(toLight, toLight), 0.0001);
+    L = toLight * inversesqrt(distanceSquared);
+    // inverse square falloff, smoothly brought to zero at the range.
+    float ratio = distanceSquared / (light.range * light.range);
+    float window = clamp(1.0 - ratio * ratio, 0.0, 1.0);
+    float attenuation = window * window / distanceSquared;
+    // spot lights fade out between their inner and outer cones, point lights cones cover everything.
+    float cone = smoothstep(light.cosOuterCone, light.cosInnerCone, dot(-L, light.direction));
+    return light.color * light.intensity * attenuation * cone;
+}
+
+// tangent frame from the screen space derivatives, for meshes without tangents.
+mat3 cotangentFrame(vec3 normal, vec3 position, vec2 uv) {
+    vec3 dp1 = dFdx(position);
+    vec3 dp2 = dFdy(position);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(tangent, tangent), dot(bitangent, bitangent)));
+    return mat3(tangent * invmax, bitangent * invmax, normal);
+}
+
+mat3 tangentFrame(vec3 normal) {
+    if (inTangent.w == 0.0) {
+        return cotangentFrame(normal, inPosition, inUv);
+    }
+    vec3 tangent = normalize(inTangent.xyz - normal * dot(normal, inTangent.xyz));
+    vec3 bitangent = inTangent.w * cross(normal, tangent);
+    return mat3(tangent, bitangent, normal);
+}
+
 void main() {
 
     vec4 albedo_tex = texture(all_textures[nonuniformEXT(materialsProperties.materials[instanceIndex].albedo.textureId)], inUv);
@@ -42,14 +172,40 @@ void main() {
     vec4 metalic_tex = texture(all_textures[nonuniformEXT(materialsProperties.materials[instanceIndex].metalic.textureId)], inUv);
     float metalic = metalic_tex.r * materialsProperties.materials[instanceIndex].metalic.color.r;
 
-    vec3 ambiant = mainLight.ambiant_color * albedo;
-    vec3 diffuse = mainLight.direct_color * albedo * max(0.0, dot(inNormal, -mainLight.direction));
+    vec3 normal = normalize(inNormal);
+    // texture 0 is the white texture, meaning there is no normal map. The normal color holds the map strength.
+    uint normalTextureId = materialsProperties.materials[instanceIndex].normal.textureId;
+    if (normalTextureId != 0u) {
+        vec3 mapNormal = texture(all_textures[nonuniformEXT(normalTextureId)], inUv).xyz * 2.0 - 1.0;
+        mapNormal *= materialsProperties.materials[instanceIndex].normal.color;
+        normal = normalize(tangentFrame(normal) * mapNormal);
+    }
+
+    // the main light does not reach shadowed fragments, only its ambiant part does.
+    float shadow = mainLightShadow(inPosition, normalize(inNormal));
+    vec3 irradiance = mainLight.environment_intensity * texture(environmentIrradiance, normal).rgb;
+    vec3 ambiant = (mainLight.ambiant_color + irradiance) * albedo;
+    vec3 diffuse = shadow * mainLight.direct_color * albedo * max(0.0, dot(normal, -mainLight.direction));
 
     vec3 viewDir = normalize(inPosition - inCamPos);
-    vec3 reflectDir = reflect(-mainLight.direction, inNormal);
+    vec3 reflectDir = reflect(-mainLight.direction, normal);
     float spec = pow(max(dot(viewDir, reflectDir), 0.0), 128);
 
-    vec3 specular = 0.5 * spec * mainLight.direct_color * metalic; 
+    vec3 specular = shadow * 0.5 * spec * mainLight.direct_color * metalic; 
+    // metalic surfaces also reflect the environment, slightly blurred.
+    vec3 reflected = textureLod(environmentSpecular, reflect(viewDir, normal), 1.0).rgb;
+    specular += 0.5 * mainLight.environment_intensity * reflected * metalic;
+
+    // point and spot lights of the cluster of the fragment
+    uvec2 cluster = localLights.clusters[clusterIndex(inPosition)];
+    for (uint i = 0u; i < cluster.y; i++) {
+        LocalLight light = localLights.lights[localLights.lightIndices[cluster.x + i]];
+        vec3 L;
+        vec3 radiance = localLightRadiance(light, inPosition, L);
+        diffuse += radiance * albedo * max(0.0, dot(normal, L));
+        float localSpec = pow(max(dot(viewDir, reflect(L, normal)), 0.0), 128);
+        specular += 0.5 * localSpec * radiance * metalic;
+    }
       
     vec3 result = ambiant + diffuse + specular;
     outColor = vec4(result, 1.0);
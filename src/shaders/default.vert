@@ -12,18 +12,21 @@ layout(set = 3, binding = 0) readonly buffer UniformModel {
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 layout(location = 2) in vec2 inUv;
+layout(location = 3) in vec4 inTangent;
 
 layout (location = 0) out int instanceIndex;
 layout (location = 1) out vec3 outPosition;
 layout (location = 2) out vec3 outNormal;
 layout (location = 3) out vec2 outUv;
 layout (location = 4) out vec3 outCamPos;
+layout (location = 5) out vec4 outTangent;
 
 void main() {
     instanceIndex = gl_InstanceIndex;
     outPosition = (models.world_pos[gl_InstanceIndex] * vec4(inPosition, 1.0)).xyz;
     outNormal = transpose(inverse(mat3(models.world_pos[gl_InstanceIndex]))) * inNormal;
     outUv = inUv;
+    outTangent = vec4(mat3(models.world_pos[gl_InstanceIndex]) * inTangent.xyz, inTangent.w);
     outCamPos = (inverse(cam.view) * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
     gl_Position = cam.proj * cam.view * models.world_pos[gl_InstanceIndex] * vec4(inPosition, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,9 @@
+#version 450
+
+layout (location = 0) out vec2 outUv;
+
+void main() {
+    // a single triangle covering the screen, the uvs go from 0 to 1 on the visible part.
+    outUv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(outUv * 2.0 - 1.0, 0.0, 1.0);
+}
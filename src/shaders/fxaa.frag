@@ -0,0 +1,69 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D inputColor;
+
+layout(push_constant) uniform PostProcess {
+    vec2 texelSize;
+    vec2 padding;
+    // x: edge threshold, y: minimum edge threshold, z: subpixel blending.
+    vec4 parameters[2];
+} postProcess;
+
+layout (location = 0) in vec2 inUv;
+
+layout (location = 0) out vec4 outColor;
+
+float luma(vec3 color) {
+    // perceived luma, the square root approximates the gamma encoding.
+    return sqrt(dot(color, vec3(0.299, 0.587, 0.114)));
+}
+
+void main() {
+    vec2 texel = postProcess.texelSize;
+    float edgeThreshold = postProcess.parameters[0].x;
+    float minThreshold = postProcess.parameters[0].y;
+    float subpixel = postProcess.parameters[0].z;
+
+    vec3 center = texture(inputColor, inUv).rgb;
+    float lumaCenter = luma(center);
+    float lumaN = luma(texture(inputColor, inUv + vec2(0.0, -texel.y)).rgb);
+    float lumaS = luma(texture(inputColor, inUv + vec2(0.0, texel.y)).rgb);
+    float lumaW = luma(texture(inputColor, inUv + vec2(-texel.x, 0.0)).rgb);
+    float lumaE = luma(texture(inputColor, inUv + vec2(texel.x, 0.0)).rgb);
+
+    float lumaMin = min(lumaCenter, min(min(lumaN, lumaS), min(lumaW, lumaE)));
+    float lumaMax = max(lumaCenter, max(max(lumaN, lumaS), max(lumaW, lumaE)));
+    float range = lumaMax - lumaMin;
+    // no visible edge, keep the pixel as is.
+    if (range < max(minThreshold, lumaMax * edgeThreshold)) {
+        outColor = vec4(center, 1.0);
+        return;
+    }
+
+    float lumaNW = luma(texture(inputColor, inUv + vec2(-texel.x, -texel.y)).rgb);
+    float lumaNE = luma(texture(inputColor, inUv + vec2(texel.x, -texel.y)).rgb);
+    float lumaSW = luma(texture(inputColor, inUv + vec2(-texel.x, texel.y)).rgb);
+    float lumaSE = luma(texture(inputColor, inUv + vec2(texel.x, texel.y)).rgb);
+
+    // the edge direction is across the strongest luma gradient.
+    vec2 direction = vec2(
+        -((lumaNW + lumaNE) - (lumaSW + lumaSE)),
+        (lumaNW + lumaSW) - (lumaNE + lumaSE)
+    );
+    float reduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * (1.0 / 8.0), 1.0 / 128.0);
+    float scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2(-8.0), vec2(8.0)) * texel;
+
+    vec3 near = 0.5 * (
+        texture(inputColor, inUv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(inputColor, inUv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 far = near * 0.5 + 0.25 * (
+        texture(inputColor, inUv + direction * -0.5).rgb +
+        texture(inputColor, inUv + direction * 0.5).rgb
+    );
+    // the far samples may cross another edge, fall back to the near ones then.
+    float lumaFar = luma(far);
+    vec3 blended = (lumaFar < lumaMin || lumaFar > lumaMax) ? near : far;
+    outColor = vec4(mix(center, blended, subpixel), 1.0);
+}
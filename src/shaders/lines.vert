@@ -0,0 +1,24 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform UniformCamera {
+    mat4 proj;
+    mat4 view;
+} cam;
+
+layout(set = 1, binding = 0) readonly buffer TintMaterials {
+    vec4 tints[];
+} materials;
+
+layout(set = 2, binding = 0) readonly buffer UniformModel {
+    mat4 world_pos[];
+} models;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    outColor = inColor * materials.tints[gl_InstanceIndex];
+    gl_Position = cam.proj * cam.view * models.world_pos[gl_InstanceIndex] * vec4(inPosition, 1.0);
+}
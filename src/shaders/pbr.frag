@@ -0,0 +1,290 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier : enable
+
+const float PI = 3.14159265359;
+
+struct PbrMaterial {
+    vec4 baseColor;
+    vec3 emissive;
+    uint baseColorTexture;
+    float metallic;
+    float roughness;
+    float normalScale;
+    float occlusionStrength;
+    uint metallicRoughnessTexture;
+    uint normalTexture;
+    uint occlusionTexture;
+    uint emissiveTexture;
+    float alphaCutoff;
+    float padding0;
+    float padding1;
+    float padding2;
+};
+
+layout(set = 1, binding = 0) uniform MainDirectionnalLight {
+    vec3 direction;
+    float _padd_0;
+    vec3 ambiant_color;
+    float environment_intensity;
+    vec3 direct_color;
+    float _padd_2;
+} mainLight;
+
+layout(set = 2, binding = 0) readonly buffer MaterialProperties {
+    PbrMaterial materials[];
+} materialsProperties;
+
+layout(set = 4, binding = 0) uniform sampler2D all_textures[];
+
+const uint CLUSTER_X = 16;
+const uint CLUSTER_Y = 9;
+const uint CLUSTER_Z = 16;
+const uint MAX_LOCAL_LIGHTS = 256;
+
+struct LocalLight {
+    vec3 position;
+    float range;
+    vec3 color;
+    float intensity;
+    vec3 direction;
+    float cosOuterCone;
+    float cosInnerCone;
+    float _padd_0;
+    float _padd_1;
+    float _padd_2;
+};
+
+layout(set = 5, binding = 0) readonly buffer LocalLights {
+    mat4 view;
+    mat4 viewProjection;
+    float zNear;
+    float zFar;
+    uint lightCount;
+    uint _padd;
+    LocalLight lights[MAX_LOCAL_LIGHTS];
+    // offset in the light indices and light count of each cluster
+    uvec2 clusters[CLUSTER_X * CLUSTER_Y * CLUSTER_Z];
+    uint lightIndices[];
+} localLights;
+
+const uint MAX_SHADOW_CASCADES = 4;
+
+layout(set = 6, binding = 0) uniform ShadowCascades {
+    mat4 view;
+    mat4 viewProjections[MAX_SHADOW_CASCADES];
+    vec4 splitDepths;
+    vec4 texelSizes;
+    uint cascadeCount;
+    float normalBias;
+    int pcfRadius;
+    float _padd;
+} shadows;
+
+layout(set = 6, binding = 1) uniform sampler2DArrayShadow shadowMap;
+
+// prefiltered environment: the specular mip chain goes from sharp to rough, the irradiance is already convolved.
+layout(set = 7, binding = 0) uniform samplerCube environmentSpecular;
+layout(set = 7, binding = 1) uniform samplerCube environmentIrradiance;
+
+layout (location = 0) in flat int instanceIndex;
+layout (location = 1) in smooth vec3 inPosition;
+layout (location = 2) in smooth vec3 inNormal;
+layout (location = 3) in smooth vec2 inUv;
+layout (location = 4) in smooth vec3 inCamPos;
+layout (location = 5) in smooth vec4 inTangent;
+
+layout (location = 0) out vec4 outColor;
+
+vec4 sampleTexture(uint textureId) {
+    return texture(all_textures[nonuniformEXT(textureId)], inUv);
+}
+
+// index of the cluster of the view frustum containing the position.
+// tiles are split evenly on screen, depth slices exponentially between the near and far planes.
+uint clusterIndex(vec3 position) {
+    vec4 clip = localLights.viewProjection * vec4(position, 1.0);
+    vec2 ndc = clip.xy / clip.w;
+    float depth = max(-(localLights.view * vec4(position, 1.0)).z, localLights.zNear);
+    uvec2 tile = uvec2(clamp((ndc * 0.5 + 0.5) * vec2(CLUSTER_X, CLUSTER_Y), vec2(0.0), vec2(CLUSTER_X - 1, CLUSTER_Y - 1)));
+    uint slice = uint(clamp(log(depth / localLights.zNear) / log(localLights.zFar / localLights.zNear) * float(CLUSTER_Z), 0.0, float(CLUSTER_Z - 1)));
+    return tile.x + tile.y * CLUSTER_X + slice * CLUSTER_X * CLUSTER_Y;
+}
+
+// fraction of the main light reaching the position, from the shadow map cascade covering it.
+// the position is pushed along the normal by a few texels to avoid shadow acne, and the lookup is averaged over the pcf kernel.
+float mainLightShadow(vec3 position, vec3 normal) {
+    float depth = -(shadows.view * vec4(position, 1.0)).z;
+    uint cascade = 0u;
+    while (cascade < shadows.cascadeCount && depth > shadows.splitDepths[cascade]) {
+        cascade++;
+    }
+    // no shadows, or further than the shadow distance.
+    if (cascade >= shadows.cascadeCount) {
+        return 1.0;
+    }
+    vec3 offsetPosition = position + normal * shadows.normalBias * shadows.texelSizes[cascade];
+    vec4 lightClip = shadows.viewProjections[cascade] * vec4(offsetPosition, 1.0);
+    vec3 coords = lightClip.xyz / lightClip.w;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+    vec2 uv = coords.xy * 0.5 + 0.5;
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -shadows.pcfRadius; x <= shadows.pcfRadius; x++) {
+        for (int y = -shadows.pcfRadius; y <= shadows.pcfRadius; y++) {
+            lit += texture(shadowMap, vec4(uv + vec2(x, y) * texelSize, float(cascade), coords.z));
+        }
+    }
+    float kernelSize = float(2 * shadows.pcfRadius + 1);
+    return lit / (kernelSize * kernelSize);
+}
+
+// light of a point or spot light reaching the position, and the direction towards the light.
+vec3 localLightRadiance(LocalLight light, vec3 position, out vec3 L) {
+    vec3 toLight = light.position - position;
+    float distanceSquared = max(dot(toLight, toLight), 0.0001);
+    L = toLight * inversesqrt(distanceSquared);
+    // inverse square falloff, smoothly brought to zero at the range.
+    float ratio = distanceSquared / (light.range * light.range);
+    float window = clamp(1.0 - ratio * ratio, 0.0, 1.0);
+    float attenuation = window * window / distanceSquared;
+    // spot lights fade out between their inner and outer cones, point lights cones cover everything.
+    float cone = smoothstep(light.cosOuterCone, light.cosInnerCone, dot(-L, light.direction));
+    return light.color * light.intensity * attenuation * cone;
+}
+
+// tangent frame from the screen space derivatives, for meshes without tangents.
+mat3 cotangentFrame(vec3 normal, vec3 position, vec2 uv) {
+    vec3 dp1 = dFdx(position);
+    vec3 dp2 = dFdy(position);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(tangent, tangent), dot(bitangent, bitangent)));
+    return mat3(tangent * invmax, bitangent * invmax, normal);
+}
+
+mat3 tangentFrame(vec3 normal) {
+    if (inTangent.w == 0.0) {
+        return cotangentFrame(normal, inPosition, inUv);
+    }
+    vec3 tangent = normalize(inTangent.xyz - normal * dot(normal, inTangent.xyz));
+    vec3 bitangent = inTangent.w * cross(normal, tangent);
+    return mat3(tangent, bitangent, normal);
+}
+
+// Trowbridge-Reitz GGX normal distribution
+float distributionGGX(float NdotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith geometry term, with the Schlick-GGX approximation for each direction
+float geometrySmith(float NdotV, float NdotL, float roughness) {
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    float ggxV = NdotV / (NdotV * (1.0 - k) + k);
+    float ggxL = NdotL / (NdotL * (1.0 - k) + k);
+    return ggxV * ggxL;
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 F0) {
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// light reflected towards V, for a light of the given color coming from the direction L.
+// light colors are the brightness of a white diffuse surface facing the light, as in the phong pipeline, hence the PI factor.
+vec3 shade(vec3 N, vec3 V, vec3 L, vec3 lightColor, vec3 baseColor, float metallic, float roughness) {
+    vec3 H = normalize(V + L);
+
+    float NdotV = max(dot(N, V), 0.0001);
+    float NdotL = max(dot(N, L), 0.0);
+    float NdotH = max(dot(N, H), 0.0);
+    float HdotV = max(dot(H, V), 0.0);
+
+    // dielectrics reflect about 4% of the light, metals reflect their base color.
+    vec3 F0 = mix(vec3(0.04), baseColor, metallic);
+    vec3 F = fresnelSchlick(HdotV, F0);
+    float D = distributionGGX(NdotH, roughness);
+    float G = geometrySmith(NdotV, NdotL, roughness);
+    vec3 specular = (D * G * F) / (4.0 * NdotV * max(NdotL, 0.0001));
+
+    // the light that is not reflected is diffused, except by metals.
+    vec3 kD = (vec3(1.0) - F) * (1.0 - metallic);
+    vec3 diffuse = kD * baseColor / PI;
+
+    return (diffuse + specular) * PI * lightColor * NdotL;
+}
+
+// analytic approximation of the environment brdf integral (Karis), instead of a lookup texture.
+vec2 environmentBrdf(float NdotV, float roughness) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+// light of the environment reflected towards V, with the split sum approximation.
+// the rougher the surface, the blurrier the mip level of the prefiltered specular map.
+vec3 environmentLight(vec3 N, vec3 V, vec3 baseColor, float metallic, float roughness) {
+    float NdotV = max(dot(N, V), 0.0001);
+    vec3 F0 = mix(vec3(0.04), baseColor, metallic);
+    vec2 brdf = environmentBrdf(NdotV, roughness);
+    vec3 specularColor = F0 * brdf.x + brdf.y;
+
+    float lod = roughness * float(textureQueryLevels(environmentSpecular) - 1);
+    vec3 prefiltered = textureLod(environmentSpecular, reflect(-V, N), lod).rgb;
+    vec3 irradiance = texture(environmentIrradiance, N).rgb;
+
+    vec3 kD = (vec3(1.0) - specularColor) * (1.0 - metallic);
+    return kD * baseColor * irradiance + specularColor * prefiltered;
+}
+
+void main() {
+    PbrMaterial material = materialsProperties.materials[instanceIndex];
+
+    // color textures are stored in srgb and data textures in linear, so they are all sampled in linear space.
+    vec4 baseColor = sampleTexture(material.baseColorTexture) * material.baseColor;
+    // alpha cutout: fragments under the cutoff are not drawn at all, for foliage or fences.
+    if (baseColor.a < material.alphaCutoff) {
+        discard;
+    }
+    vec4 metallicRoughness = sampleTexture(material.metallicRoughnessTexture);
+    float metallic = clamp(metallicRoughness.b * material.metallic, 0.0, 1.0);
+    float roughness = clamp(metallicRoughness.g * material.roughness, 0.04, 1.0);
+    float occlusion = mix(1.0, sampleTexture(material.occlusionTexture).r, material.occlusionStrength);
+    vec3 emissive = sampleTexture(material.emissiveTexture).rgb * material.emissive;
+
+    vec3 N = normalize(inNormal);
+    // texture 0 is the white texture, meaning there is no normal map.
+    if (material.normalTexture != 0u) {
+        vec3 mapNormal = sampleTexture(material.normalTexture).xyz * 2.0 - 1.0;
+        mapNormal.xy *= material.normalScale;
+        N = normalize(tangentFrame(N) * mapNormal);
+    }
+    vec3 V = normalize(inCamPos - inPosition);
+    // the main light does not reach shadowed fragments, only its ambiant part does.
+    float shadow = mainLightShadow(inPosition, normalize(inNormal));
+    vec3 direct = shadow * shade(N, V, normalize(-mainLight.direction), mainLight.direct_color, baseColor.rgb, metallic, roughness);
+
+    // point and spot lights of the cluster of the fragment
+    uvec2 cluster = localLights.clusters[clusterIndex(inPosition)];
+    for (uint i = 0u; i < cluster.y; i++) {
+        LocalLight light = localLights.lights[localLights.lightIndices[cluster.x + i]];
+        vec3 L;
+        vec3 radiance = localLightRadiance(light, inPosition, L);
+        direct += shade(N, V, L, radiance, baseColor.rgb, metallic, roughness);
+    }
+    vec3 ambiant = mainLight.ambiant_color * baseColor.rgb * occlusion;
+    ambiant += mainLight.environment_intensity * environmentLight(N, V, baseColor.rgb, metallic, roughness) * occlusion;
+
+    // the swapchain is srgb, so the linear result is encoded when written.
+    outColor = vec4(direct + ambiant + emissive, baseColor.a);
+}
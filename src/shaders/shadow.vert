@@ -0,0 +1,29 @@
+#version 450
+
+const uint MAX_SHADOW_CASCADES = 4;
+
+layout(set = 0, binding = 0) uniform ShadowCascades {
+    mat4 view;
+    mat4 viewProjections[MAX_SHADOW_CASCADES];
+    vec4 splitDepths;
+    vec4 texelSizes;
+    uint cascadeCount;
+    float normalBias;
+    int pcfRadius;
+    float _padd;
+} shadows;
+
+layout(set = 2, binding = 0) readonly buffer UniformModel {
+    mat4 world_pos[];
+} models;
+
+// cascade being drawn, the shadow map layer of the framebuffer.
+layout(push_constant) uniform Cascade {
+    uint index;
+} cascade;
+
+layout(location = 0) in vec3 inPosition;
+
+void main() {
+    gl_Position = shadows.viewProjections[cascade.index] * models.world_pos[gl_InstanceIndex] * vec4(inPosition, 1.0);
+}
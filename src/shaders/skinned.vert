@@ -0,0 +1,46 @@
+#version 450
+
+const int MAX_JOINTS = 64;
+
+layout(set = 0, binding = 0) uniform UniformCamera {
+    mat4 proj;
+    mat4 view;
+} cam;
+
+struct JointPalette {
+    mat4 joints[MAX_JOINTS];
+};
+
+// joint palettes already contains the world transform of the model
+layout(set = 3, binding = 0) readonly buffer UniformJointPalettes {
+    JointPalette palettes[];
+} skins;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 2) in vec2 inUv;
+layout(location = 3) in uvec4 inJointIds;
+layout(location = 4) in vec4 inJointWeights;
+
+layout (location = 0) out int instanceIndex;
+layout (location = 1) out vec3 outPosition;
+layout (location = 2) out vec3 outNormal;
+layout (location = 3) out vec2 outUv;
+layout (location = 4) out vec3 outCamPos;
+layout (location = 5) out vec4 outTangent;
+
+void main() {
+    mat4 skin = inJointWeights.x * skins.palettes[gl_InstanceIndex].joints[inJointIds.x]
+              + inJointWeights.y * skins.palettes[gl_InstanceIndex].joints[inJointIds.y]
+              + inJointWeights.z * skins.palettes[gl_InstanceIndex].joints[inJointIds.z]
+              + inJointWeights.w * skins.palettes[gl_InstanceIndex].joints[inJointIds.w];
+
+    instanceIndex = gl_InstanceIndex;
+    outPosition = (skin * vec4(inPosition, 1.0)).xyz;
+    outNormal = transpose(inverse(mat3(skin))) * inNormal;
+    outUv = inUv;
+    // skeletal vertices have no tangents, the fragment shader derives them from the uvs.
+    outTangent = vec4(0.0);
+    outCamPos = (inverse(cam.view) * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
+    gl_Position = cam.proj * cam.view * skin * vec4(inPosition, 1.0);
+}
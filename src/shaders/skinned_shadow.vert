@@ -0,0 +1,42 @@
+#version 450
+
+const uint MAX_SHADOW_CASCADES = 4;
+const int MAX_JOINTS = 64;
+
+layout(set = 0, binding = 0) uniform ShadowCascades {
+    mat4 view;
+    mat4 viewProjections[MAX_SHADOW_CASCADES];
+    vec4 splitDepths;
+    vec4 texelSizes;
+    uint cascadeCount;
+    float normalBias;
+    int pcfRadius;
+    float _padd;
+} shadows;
+
+struct JointPalette {
+    mat4 joints[MAX_JOINTS];
+};
+
+// joint palettes already contains the world transform of the model
+layout(set = 2, binding = 0) readonly buffer UniformJointPalettes {
+    JointPalette palettes[];
+} skins;
+
+// cascade being drawn, the shadow map layer of the framebuffer.
+layout(push_constant) uniform Cascade {
+    uint index;
+} cascade;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 3) in uvec4 inJointIds;
+layout(location = 4) in vec4 inJointWeights;
+
+void main() {
+    mat4 skin = inJointWeights.x * skins.palettes[gl_InstanceIndex].joints[inJointIds.x]
+              + inJointWeights.y * skins.palettes[gl_InstanceIndex].joints[inJointIds.y]
+              + inJointWeights.z * skins.palettes[gl_InstanceIndex].joints[inJointIds.z]
+              + inJointWeights.w * skins.palettes[gl_InstanceIndex].joints[inJointIds.w];
+
+    gl_Position = shadows.viewProjections[cascade.index] * skin * vec4(inPosition, 1.0);
+}
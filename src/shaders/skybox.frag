@@ -0,0 +1,30 @@
+#version 450
+
+struct SkyboxMaterial {
+    vec3 tint;
+    float intensity;
+    float blur;
+    float padding0;
+    float padding1;
+    float padding2;
+};
+
+layout(set = 1, binding = 0) readonly buffer MaterialProperties {
+    SkyboxMaterial materials[];
+} materialsProperties;
+
+layout(set = 3, binding = 0) uniform samplerCube environmentSpecular;
+layout(set = 3, binding = 1) uniform samplerCube environmentIrradiance;
+
+layout (location = 0) in flat int instanceIndex;
+layout (location = 1) in smooth vec3 inDirection;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    SkyboxMaterial material = materialsProperties.materials[instanceIndex];
+    // the blur picks a rougher level of the specular mip chain.
+    float lod = clamp(material.blur, 0.0, 1.0) * float(textureQueryLevels(environmentSpecular) - 1);
+    vec3 color = textureLod(environmentSpecular, normalize(inDirection), lod).rgb;
+    outColor = vec4(color * material.tint * material.intensity, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform UniformCamera {
+    mat4 proj;
+    mat4 view;
+} cam;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 2) in vec2 inUv;
+layout(location = 3) in vec4 inTangent;
+
+layout (location = 0) out int instanceIndex;
+layout (location = 1) out vec3 outDirection;
+
+void main() {
+    instanceIndex = gl_InstanceIndex;
+    // the cube follows the camera: only the rotation of the view is applied.
+    outDirection = inPosition;
+    vec4 position = cam.proj * vec4(mat3(cam.view) * inPosition, 1.0);
+    // the depth is forced to the far plane, so the skybox is behind everything else.
+    gl_Position = position.xyww;
+}
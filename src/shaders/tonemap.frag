@@ -0,0 +1,104 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D inputColor;
+
+layout(push_constant) uniform PostProcess {
+    vec2 texelSize;
+    vec2 padding;
+    // [0].x: operator, 0 is reinhard, 1 is aces and 2 is agx. [0].y: exposure in stops.
+    // [0].z: output encoding, 0 is sdr, 1 is extended linear srgb and 2 is hdr10 pq.
+    // [1].x: luminance of the sdr white in nits, [1].y: peak luminance in nits, for the hdr encodings.
+    vec4 parameters[2];
+} postProcess;
+
+layout (location = 0) in vec2 inUv;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 reinhard(vec3 color) {
+    // on the luminance, so saturated colors keep their hue.
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color / (1.0 + luminance);
+}
+
+vec3 aces(vec3 color) {
+    // fitted curve of Krzysztof Narkowicz.
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return (color * (a * color + b)) / (color * (c * color + d) + e);
+}
+
+vec3 agxContrast(vec3 x) {
+    // polynomial fit of the default agx contrast curve, by Benjamin Wrensch.
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+vec3 agx(vec3 color) {
+    const mat3 inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    const mat3 outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    const float minEv = -12.47393;
+    const float maxEv = 4.026069;
+    // the curve works on the log encoded colors, and gives display encoded ones.
+    vec3 encoded = clamp(log2(max(inset * color, vec3(1e-10))), minEv, maxEv);
+    encoded = agxContrast((encoded - minEv) / (maxEv - minEv));
+    return pow(max(outset * encoded, vec3(0.0)), vec3(2.2));
+}
+
+vec3 tonemap(vec3 color, int operator) {
+    if (operator == 2) {
+        return agx(color);
+    }
+    return operator == 1 ? aces(color) : reinhard(color);
+}
+
+vec3 pq(vec3 nits) {
+    // inverse of the st2084 eotf, from the absolute luminance.
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+void main() {
+    vec3 color = max(texture(inputColor, inUv).rgb, vec3(0.0)) * exp2(postProcess.parameters[0].y);
+    int operator = int(postProcess.parameters[0].x);
+    int encoding = int(postProcess.parameters[0].z);
+
+    if (encoding == 0) {
+        // the srgb swapchain encodes the linear colors.
+        outColor = vec4(clamp(tonemap(color, operator), 0.0, 1.0), 1.0);
+        return;
+    }
+
+    // hdr displays go above the sdr white: the curve is stretched up to the peak luminance.
+    float paperWhite = postProcess.parameters[1].x;
+    float headroom = postProcess.parameters[1].y / paperWhite;
+    vec3 nits = clamp(tonemap(color / headroom, operator), 0.0, 1.0) * headroom * paperWhite;
+    if (encoding == 1) {
+        // extended srgb is linear, with 1.0 at 80 nits.
+        outColor = vec4(nits / 80.0, 1.0);
+    } else {
+        const mat3 rec709ToRec2020 = mat3(
+            0.6274040, 0.0690970, 0.0163916,
+            0.3292820, 0.9195400, 0.0880132,
+            0.0433136, 0.0113612, 0.8955950
+        );
+        outColor = vec4(pq(rec709ToRec2020 * nits), 1.0);
+    }
+}
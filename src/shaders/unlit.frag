@@ -0,0 +1,30 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier : enable
+
+struct UnlitMaterial {
+    vec4 color;
+    uint texture;
+    float alphaCutoff;
+    float padding0;
+    float padding1;
+};
+
+layout(set = 1, binding = 0) readonly buffer MaterialProperties {
+    UnlitMaterial materials[];
+} materialsProperties;
+
+layout(set = 3, binding = 0) uniform sampler2D all_textures[];
+
+layout (location = 0) in flat int instanceIndex;
+layout (location = 1) in smooth vec2 inUv;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    UnlitMaterial material = materialsProperties.materials[instanceIndex];
+    vec4 color = texture(all_textures[nonuniformEXT(material.texture)], inUv) * material.color;
+    if (color.a < material.alphaCutoff) {
+        discard;
+    }
+    outColor = color;
+}
@@ -0,0 +1,25 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D inputColor;
+
+layout(push_constant) uniform PostProcess {
+    vec2 texelSize;
+    vec2 padding;
+    // x: intensity, y: smoothness.
+    vec4 parameters[2];
+} postProcess;
+
+layout (location = 0) in vec2 inUv;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    vec3 color = texture(inputColor, inUv).rgb;
+    float intensity = postProcess.parameters[0].x;
+    float smoothness = max(postProcess.parameters[0].y, 0.001);
+    // distance to the center, corrected by the aspect ratio so the vignette is round, and 1 in the corners.
+    vec2 aspect = vec2(postProcess.texelSize.y / postProcess.texelSize.x, 1.0);
+    float distance = length((inUv - 0.5) * aspect) / length(0.5 * aspect);
+    float vignette = 1.0 - intensity * smoothstep(1.0 - smoothness, 1.0, distance);
+    outColor = vec4(color * vignette, 1.0);
+}
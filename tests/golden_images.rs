@@ -0,0 +1,130 @@
+// the scenes draw ui panels and text.
+#![cfg(feature = "ui")]
+
+use foundry::*;
+use glam::Vec3;
+use propellant::*;
+
+/// Render reference scenes offscreen and compare them against the pngs of `tests/golden`.
+/// This needs a software vulkan implementation, such as lavapipe, and is skipped on machines without vulkan.
+/// Missing references fail: run with `PROPELLANT_BLESS_GOLDEN_IMAGES` set to create them, or to overwrite them after an intended change.
+/// Failures write the rendered image and a diff in `target/golden_images`.
+#[test]
+fn golden_images() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = GoldenImageHarness::create_if_available(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden").into(),
+        concat!(env!("CARGO_MANIFEST_DIR"), "/target/golden_images").into(),
+    )?;
+    let mut harness = match harness {
+        Some(harness) => harness.with_frame_count(3),
+        None => {
+            println!("No vulkan implementation found, the golden images are skipped.");
+            return Ok(());
+        },
+    };
+    let (width, height) = (harness.width() as f32, harness.height() as f32);
+
+    harness.check("phong", RenderingPipelineBuilder::default(), |world| {
+        let mut resources = PropellantResources::default();
+        resources.meshes_mut().register_mesh(id("cube"), MeshType::cube(1.0));
+        resources.meshes_mut().register_mesh(id("quad"), MeshType::flat_quad(10.0));
+        world.add_singleton(resources);
+        world.add_singleton(DirectionnalLight::new(
+            glam::vec3(0.1, 0.1, 0.1),
+            glam::vec3(0.8, 0.7, 0.75),
+            glam::vec3(-1., -1., -1.).normalize(),
+        ));
+        let _cam = create_entity!(world;
+            Transform::origin().translated(glam::vec3(0., -1.5, -5.)),
+            Camera::main_perspective(height, width, 0.1, 100., 1.2)
+        );
+        let _quad = create_entity!(world;
+            Transform::origin().translated(glam::vec3(0., -0.5, 0.)),
+            InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+                id("quad"),
+                PhongMaterial::default().colored(glam::vec3(0.5, 0.5, 0.5))
+            )
+        );
+        let _cube = create_entity!(world;
+            Transform::origin().rotated(glam::Quat::from_rotation_y(0.6)),
+            InstancedMeshRenderer::<PhongMaterial, StaticMesh>::new(
+                id("cube"),
+                PhongMaterial::default().colored(glam::vec3(0.6, 0., 0.))
+            )
+        );
+        Ok(())
+    })?;
+
+    harness.check("ui", RenderingPipelineBuilder::default(), |world| {
+        world.add_singleton(PropellantResources::default());
+        let _cam = create_entity!(world;
+            Transform::origin(),
+            Camera::main_perspective(height, width, 0.1, 100., 1.2)
+        );
+        let panel_tf = UiTransform::new(
+            glam::vec2(0., -10.),
+            glam::vec2(0.5, 1.),
+            glam::vec2(-20., 50.),
+            glam::vec2(1., 0.),
+            UiAnchor::Bottom,
+            0,
+        );
+        let button_tf = UiTransform::new(
+            glam::vec2(-10., 0.),
+            glam::vec2(1., 0.5),
+            glam::vec2(100., -20.),
+            glam::vec2(0., 1.),
+            UiAnchor::Right,
+            1,
+        ).child_of(Some(&panel_tf));
+        let _panel = create_entity!(world;
+            panel_tf,
+            UiMaterial::colored(Vec3::new(0.5, 1.0, 0.8), 20.).to_mesh_renderer()
+        );
+        let _button = create_entity!(world;
+            button_tf,
+            UiMaterial::colored(Vec3::new(0.2, 0.5, 0.3), 10.).to_mesh_renderer()
+        );
+        let _corner = create_entity!(world;
+            UiTransform::new(
+                glam::vec2(-5., 10.),
+                glam::vec2(1., 0.),
+                glam::vec2(120., 40.),
+                glam::vec2(0., 0.),
+                UiAnchor::TopRight,
+                0,
+            ),
+            UiMaterial::colored(Vec3::new(1.0, 0.5, 0.8), 0.).to_mesh_renderer()
+        );
+        Ok(())
+    })?;
+
+    harness.check("text", RenderingPipelineBuilder::default(), |world| {
+        let mut resources = PropellantResources::default();
+        let font = resources.load_font(id("font"), include_bytes!("../examples/text/noto-serif.ttf"))?;
+        world.add_singleton(resources);
+        let _cam = create_entity!(world;
+            Transform::origin(),
+            Camera::main_perspective(height, width, 0.1, 100., 1.2)
+        );
+        let _text = create_entity!(world;
+            UiTransform::new(
+                glam::vec2(20., 20.),
+                glam::vec2(0., 0.),
+                glam::vec2(0., 0.),
+                glam::vec2(1., 1.),
+                UiAnchor::TopLeft,
+                0,
+            ),
+            UiTextRenderer::new("Golden images\nThe quick brown fox.".to_string(), font, glam::vec3(1., 1., 1.))
+        );
+        Ok(())
+    })?;
+
+    for (name, outcome) in harness.outcomes() {
+        println!("{name}: {outcome:?}");
+    }
+    assert!(harness.all_passed(), "golden images failed: {:?}", harness.outcomes());
+
+    Ok(())
+}